@@ -6,6 +6,7 @@ use crate::{
     accept_type::AcceptType,
     context::{api_spawn_blocking, Context},
     failpoint::fail_point_poem,
+    field_selection::parse_field_selection,
     page::determine_limit,
     response::{
         account_not_found, resource_not_found, struct_field_not_found, BadRequestError,
@@ -59,6 +60,12 @@ impl AccountsApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the account at
+        ///
+        /// The account is read at the last version committed before this timestamp, e.g., to
+        /// look up its sequence number at a point in time. It can't be provided along with
+        /// `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
     ) -> BasicResultWith404<AccountData> {
         fail_point_poem("endpoint_get_account")?;
         self.context
@@ -66,7 +73,9 @@ impl AccountsApi {
 
         let context = self.context.clone();
         api_spawn_blocking(move || {
-            let account = Account::new(context, address.0, ledger_version.0, None, None, false)?;
+            let ledger_version =
+                context.resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
+            let account = Account::new(context, address.0, ledger_version, None, None, false)?;
             account.account(&accept_type)
         })
         .await
@@ -94,6 +103,12 @@ impl AccountsApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the account resources at
+        ///
+        /// The resources are read at the last version committed before this timestamp, and all
+        /// pages requested with the same timestamp are read at that version. It can't be
+        /// provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
         /// Cursor specifying where to start for pagination
         ///
         /// This cursor cannot be derived manually client-side. Instead, you must
@@ -105,22 +120,34 @@ impl AccountsApi {
         ///
         /// If not provided, defaults to default page size.
         limit: Query<Option<u16>>,
+        /// Comma separated list of the fields to include in each resource, e.g.
+        /// `type,data.coin.value` to only get the balances of the coin stores
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Only the selected
+        /// fields are serialized, but the resources are still read in full.
+        fields: Query<Option<String>>,
     ) -> BasicResultWith404<Vec<MoveResource>> {
         fail_point_poem("endpoint_get_account_resources")?;
         self.context
             .check_api_output_enabled("Get account resources", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
 
         let context = self.context.clone();
         api_spawn_blocking(move || {
+            let ledger_version =
+                context.resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
             let account = Account::new(
                 context,
                 address.0,
-                ledger_version.0,
+                ledger_version,
                 start.0.map(StateKey::from),
                 limit.0,
                 true,
             )?;
-            account.resources(&accept_type)
+            account
+                .resources(&accept_type)
+                .and_then(|response| response.with_field_selection(field_selection))
         })
         .await
     }
@@ -147,6 +174,12 @@ impl AccountsApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the account modules at
+        ///
+        /// The modules are read at the last version committed before this timestamp, e.g., to
+        /// get the code of a package before an upgrade. It can't be provided along with
+        /// `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
         /// Cursor specifying where to start for pagination
         ///
         /// This cursor cannot be derived manually client-side. Instead, you must
@@ -165,10 +198,12 @@ impl AccountsApi {
 
         let context = self.context.clone();
         api_spawn_blocking(move || {
+            let ledger_version =
+                context.resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
             let account = Account::new(
                 context,
                 address.0,
-                ledger_version.0,
+                ledger_version,
                 start.0.map(StateKey::from),
                 limit.0,
                 true,
@@ -8,7 +8,7 @@ use crate::{
     response::{BasicResponse, BasicResponseStatus, BasicResultWith404},
     ApiTags,
 };
-use aptos_api_types::{BcsBlock, Block, LedgerInfo};
+use aptos_api_types::{BcsBlock, Block, LedgerInfo, VersionByTimestamp};
 use poem_openapi::{
     param::{Path, Query},
     OpenApi,
@@ -102,6 +102,36 @@ impl BlocksApi {
         })
         .await
     }
+
+    /// Get version by timestamp
+    ///
+    /// This endpoint allows you to get the version of the last transaction committed before
+    /// a timestamp, by searching the block metadata. The version can then be used to read
+    /// the state of the ledger at that time.
+    ///
+    /// The timestamp can't be after the latest ledger timestamp, as blocks committed later
+    /// could still have an earlier timestamp.
+    ///
+    /// To use this endpoint with BCS, the version is returned as a BCS encoded u64.
+    #[oai(
+        path = "/versions/by_timestamp/:timestamp",
+        method = "get",
+        operation_id = "get_version_by_timestamp",
+        tag = "ApiTags::Blocks"
+    )]
+    async fn get_version_by_timestamp(
+        &self,
+        accept_type: AcceptType,
+        /// Timestamp in microseconds to lookup the version for
+        timestamp: Path<u64>,
+    ) -> BasicResultWith404<VersionByTimestamp> {
+        fail_point_poem("endpoint_get_version_by_timestamp")?;
+        self.context
+            .check_api_output_enabled("Get version by timestamp", &accept_type)?;
+        let api = self.clone();
+        api_spawn_blocking(move || api.get_version_by_timestamp_inner(accept_type, timestamp.0))
+            .await
+    }
 }
 
 impl BlocksApi {
@@ -135,6 +165,31 @@ impl BlocksApi {
         self.render_bcs_block(&accept_type, latest_ledger_info, bcs_block)
     }
 
+    fn get_version_by_timestamp_inner(
+        &self,
+        accept_type: AcceptType,
+        timestamp: u64,
+    ) -> BasicResultWith404<VersionByTimestamp> {
+        let latest_ledger_info = self.context.get_latest_ledger_info()?;
+        let version = self
+            .context
+            .get_version_by_timestamp(timestamp, &latest_ledger_info)?;
+
+        match accept_type {
+            AcceptType::Json => BasicResponse::try_from_json((
+                VersionByTimestamp {
+                    timestamp: timestamp.into(),
+                    version: version.into(),
+                },
+                &latest_ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+            AcceptType::Bcs => {
+                BasicResponse::try_from_bcs((version, &latest_ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
+
     /// Renders a [`BcsBlock`] into a [`Block`] if it's a JSON accept type
     fn render_bcs_block(
         &self,
@@ -8,19 +8,22 @@ use crate::{
     response::{
         bcs_api_disabled, block_not_found_by_height, block_not_found_by_version,
         block_pruned_by_height, json_api_disabled, version_not_found, version_pruned,
-        ForbiddenError, InternalError, NotFoundError, ServiceUnavailableError, StdApiError,
+        BadRequestError, ForbiddenError, InternalError, NotFoundError, ServiceUnavailableError,
+        StdApiError,
     },
 };
 use anyhow::{anyhow, bail, ensure, format_err, Context as AnyhowContext, Result};
 use aptos_api_types::{
     AptosErrorCode, AsConverter, BcsBlock, GasEstimation, LedgerInfo, ResourceGroup,
-    TransactionOnChainData,
+    TransactionOnChainData, U64,
 };
 use aptos_config::config::{NodeConfig, RoleType};
 use aptos_crypto::HashValue;
 use aptos_gas_schedule::{AptosGasParameters, FromOnChainGasSchedule};
 use aptos_logger::{error, info, Schema};
-use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
+use aptos_mempool::{
+    AccountMempoolTransactions, MempoolClientRequest, MempoolClientSender, SubmissionStatus,
+};
 use aptos_storage_interface::{
     state_view::{DbStateView, DbStateViewAtVersion, LatestDbStateCheckpointView},
     AptosDbError, DbReader, Order, MAX_REQUEST_LIMIT,
@@ -39,7 +42,7 @@ use aptos_types::{
     state_store::{
         state_key::{inner::StateKeyInner, prefix::StateKeyPrefix, StateKey},
         state_value::StateValue,
-        TStateView,
+        StateView, TStateView,
     },
     transaction::{
         block_epilogue::BlockEndInfo, SignedTransaction, Transaction, TransactionWithProof, Version,
@@ -78,6 +81,7 @@ pub struct Context {
     simulate_txn_stats: Arc<FunctionStats>,
     pub indexer_reader: Option<Arc<dyn IndexerReader>>,
     pub wait_for_hash_active_connections: Arc<AtomicUsize>,
+    pub transaction_stream_active_connections: Arc<AtomicUsize>,
 }
 
 impl std::fmt::Debug for Context {
@@ -131,6 +135,7 @@ impl Context {
             simulate_txn_stats,
             indexer_reader,
             wait_for_hash_active_connections: Arc::new(AtomicUsize::new(0)),
+            transaction_stream_active_connections: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -214,6 +219,16 @@ impl Context {
         callback.await?
     }
 
+    pub async fn cancel_transaction(&self, txn: SignedTransaction) -> Result<SubmissionStatus> {
+        let (req_sender, callback) = oneshot::channel();
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::CancelTransaction(txn, req_sender))
+            .await?;
+
+        callback.await?
+    }
+
     // For use from external crates where they don't want to handle
     // the API response error types.
     pub fn get_latest_ledger_info_wrapped(&self) -> anyhow::Result<LedgerInfo> {
@@ -306,6 +321,66 @@ impl Context {
         Ok((latest_ledger_info, requested_ledger_version))
     }
 
+    /// Resolves the ledger version to read state at from the `ledger_version` and
+    /// `ledger_timestamp` query parameters, at most one of which can be provided.
+    /// Returns `None` if neither is provided, i.e., the latest version should be used.
+    pub fn resolve_ledger_version<E: StdApiError + BadRequestError>(
+        &self,
+        ledger_version: Option<U64>,
+        ledger_timestamp: Option<U64>,
+    ) -> Result<Option<U64>, E> {
+        match (ledger_version, ledger_timestamp) {
+            (Some(_), Some(_)) => Err(E::bad_request_with_code_no_info(
+                "Only one of 'ledger_version' and 'ledger_timestamp' can be provided",
+                AptosErrorCode::InvalidInput,
+            )),
+            (None, Some(ledger_timestamp)) => {
+                let latest_ledger_info = self.get_latest_ledger_info()?;
+                let version =
+                    self.get_version_by_timestamp(ledger_timestamp.0, &latest_ledger_info)?;
+                Ok(Some(version.into()))
+            },
+            (ledger_version, None) => Ok(ledger_version),
+        }
+    }
+
+    /// Returns the version of the last transaction committed before the given timestamp
+    /// (in microseconds), found by searching the block metadata in storage.
+    pub fn get_version_by_timestamp<E: StdApiError + BadRequestError>(
+        &self,
+        timestamp: u64,
+        latest_ledger_info: &LedgerInfo,
+    ) -> Result<Version, E> {
+        // Blocks committed after the latest one could still have an earlier timestamp
+        if timestamp > latest_ledger_info.timestamp() {
+            return Err(E::bad_request_with_code(
+                format!(
+                    "Timestamp({}) is after the latest ledger timestamp({})",
+                    timestamp,
+                    latest_ledger_info.timestamp()
+                ),
+                AptosErrorCode::InvalidInput,
+                latest_ledger_info,
+            ));
+        }
+
+        self.db
+            .get_last_version_before_timestamp(timestamp, latest_ledger_info.version())
+            .map_err(|err| {
+                let not_found = matches!(err, AptosDbError::NotFound(_));
+                let err = anyhow::Error::from(err).context(format!(
+                    "Failed to find the last version before timestamp({})",
+                    timestamp
+                ));
+                // E.g. the timestamp is before the first block
+                if not_found {
+                    E::not_found_with_code(err, AptosErrorCode::VersionNotFound, latest_ledger_info)
+                } else {
+                    E::internal_with_code(err, AptosErrorCode::InternalError, latest_ledger_info)
+                }
+            })
+    }
+
     pub fn get_latest_internal_indexer_ledger_version_and_main_db_info<E: StdApiError>(
         &self,
     ) -> Result<(LedgerInfo, Version), E> {
@@ -735,6 +810,17 @@ impl Context {
         }
 
         let state_view = self.latest_state_view_poem(ledger_info)?;
+        self.render_transactions_non_sequential_with_state_view(ledger_info, &state_view, data)
+    }
+
+    /// Same as `render_transactions_non_sequential`, but resolves the Move types of the
+    /// transactions with the given state view instead of the latest one.
+    pub fn render_transactions_non_sequential_with_state_view<E: InternalError, S: StateView>(
+        &self,
+        ledger_info: &LedgerInfo,
+        state_view: &S,
+        data: Vec<TransactionOnChainData>,
+    ) -> Result<Vec<aptos_api_types::Transaction>, E> {
         let converter = state_view.as_converter(self.db.clone(), self.indexer_reader.clone());
         let txns: Vec<aptos_api_types::Transaction> = data
             .into_iter()
@@ -878,6 +964,23 @@ impl Context {
         callback.await.map_err(anyhow::Error::from)
     }
 
+    pub async fn get_pending_transactions_by_account(
+        &self,
+        address: AccountAddress,
+    ) -> Result<AccountMempoolTransactions> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mp_sender
+            .clone()
+            .send(MempoolClientRequest::GetAccountTransactions(
+                address, req_sender,
+            ))
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        callback.await.map_err(anyhow::Error::from)
+    }
+
     pub fn get_transaction_by_version(
         &self,
         version: u64,
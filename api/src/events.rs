@@ -12,19 +12,22 @@ use crate::{
         BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
         InternalError,
     },
+    transaction_stream::{event_stream, start_stream, STREAM_KEEP_ALIVE_SECS},
     ApiTags,
 };
 use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
     verify_field_identifier, Address, AptosErrorCode, AsConverter, IdentifierWrapper, LedgerInfo,
-    MoveStructTag, VerifyInputWithRecursion, VersionedEvent, U64,
+    MoveModuleId, MoveStructTag, VerifyInputWithRecursion, VersionedEvent, U64,
 };
 use aptos_types::event::EventKey;
+use futures::stream::BoxStream;
 use poem_openapi::{
     param::{Path, Query},
+    payload::EventStream,
     OpenApi,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 #[derive(Clone)]
 pub struct EventsApi {
@@ -150,6 +153,52 @@ impl EventsApi {
         })
         .await
     }
+
+    /// Stream committed events
+    ///
+    /// Streams the events emitted by committed transactions to the client as server-sent
+    /// events, starting at the given ledger version. Each event contains a single JSON
+    /// encoded event, and new events are pushed to the client as they are committed.
+    ///
+    /// The stream can optionally be filtered by the event type, the sender of the emitting
+    /// transaction, or the module of the entry function called by the emitting transaction.
+    /// If multiple filters are provided, an event must match all of them.
+    ///
+    /// If the stream ends (e.g., due to an error on the node), clients can resume it
+    /// from the version after the last event they received.
+    #[oai(
+        path = "/events/stream",
+        method = "get",
+        operation_id = "stream_events",
+        tag = "ApiTags::Events"
+    )]
+    async fn stream_events(
+        &self,
+        /// Ledger version to start streaming events from
+        ///
+        /// If not provided, defaults to the next version to be committed
+        start: Query<Option<U64>>,
+        /// Only stream events emitted by user transactions sent by this account
+        sender: Query<Option<Address>>,
+        /// Only stream events of this type e.g. `0x1::coin::DepositEvent`
+        event_type: Query<Option<MoveStructTag>>,
+        /// Only stream events emitted by transactions calling an entry function in this
+        /// module e.g. `0x1::coin`
+        module: Query<Option<MoveModuleId>>,
+    ) -> poem::Result<EventStream<BoxStream<'static, VersionedEvent>>, BasicErrorWith404> {
+        fail_point_poem("endpoint_stream_events")?;
+        let (filter, start_version) = start_stream(
+            &self.context,
+            "Stream events",
+            start.0,
+            sender.0,
+            event_type.0,
+            module.0,
+        )?;
+
+        let stream = event_stream(self.context.clone(), filter, start_version);
+        Ok(EventStream::new(stream).keep_alive(Duration::from_secs(STREAM_KEEP_ALIVE_SECS)))
+    }
 }
 
 impl EventsApi {
@@ -0,0 +1,753 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the field selection that can be applied to JSON responses, so that
+//! only the fields requested by the client are returned, along with a Poem payload type
+//! that applies it. The selection is applied while serializing the response, so the
+//! fields that aren't selected are never serialized.
+
+use crate::response::BadRequestError;
+use anyhow::{ensure, Context};
+use aptos_api_types::AptosErrorCode;
+use poem::{IntoResponse, Response};
+use poem_openapi::{
+    payload::{Json, Payload},
+    registry::{MetaMediaType, MetaResponse, MetaResponses, MetaSchemaRef, Registry},
+    types::{ToJSON, Type},
+    ApiResponse,
+};
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+use serde_json::Value;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// The maximum number of fields that can be selected in a single request
+const MAX_SELECTED_FIELDS: usize = 100;
+
+/// A selection of fields of a JSON value, parsed from a comma separated list of dot
+/// separated paths, e.g. `type,data.coin.value`. Selecting a field selects all of its
+/// nested fields.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    /// Selected fields, along with the selection of their nested fields (`None` if all of
+    /// the nested fields are selected)
+    fields: BTreeMap<String, Option<FieldSelection>>,
+}
+
+impl FieldSelection {
+    fn insert(&mut self, path: &[&str]) {
+        let Some((field, nested_path)) = path.split_first() else {
+            return;
+        };
+
+        if nested_path.is_empty() {
+            self.fields.insert(field.to_string(), None);
+            return;
+        }
+
+        // If the field is already selected as a whole, there's nothing to narrow down
+        if let Some(nested_selection) = self
+            .fields
+            .entry(field.to_string())
+            .or_insert_with(|| Some(FieldSelection::default()))
+        {
+            nested_selection.insert(nested_path);
+        }
+    }
+
+    /// Returns `None` if the field isn't selected, otherwise the selection of its nested
+    /// fields (`Some(None)` if all of them are selected)
+    fn get(&self, field: &str) -> Option<Option<&FieldSelection>> {
+        self.fields.get(field).map(Option::as_ref)
+    }
+
+    /// Serializes the value to JSON, only including its selected fields. The selection is
+    /// applied to each element of an array, and values other than objects and arrays are
+    /// serialized as is.
+    pub fn to_json_bytes<T: Serialize + ?Sized>(&self, value: &T) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(&Selected {
+            value,
+            selection: self,
+        })
+    }
+}
+
+impl FromStr for FieldSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let paths: Vec<&str> = s.split(',').map(str::trim).collect();
+        ensure!(
+            paths.len() <= MAX_SELECTED_FIELDS,
+            "Too many fields selected: {}, the maximum is {}",
+            paths.len(),
+            MAX_SELECTED_FIELDS
+        );
+
+        let mut selection = FieldSelection::default();
+        for path in paths {
+            let path: Vec<&str> = path.split('.').collect();
+            ensure!(
+                path.iter().all(|field| !field.is_empty()),
+                "Invalid field path '{}'",
+                path.join(".")
+            );
+            selection.insert(&path);
+        }
+        Ok(selection)
+    }
+}
+
+/// Parses the `fields` query parameter of an endpoint
+pub fn parse_field_selection<E: BadRequestError>(
+    fields: Option<String>,
+) -> Result<Option<FieldSelection>, E> {
+    fields
+        .map(|fields| {
+            fields
+                .parse::<FieldSelection>()
+                .context("'fields' invalid")
+                .map_err(|err| E::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput))
+        })
+        .transpose()
+}
+
+/// An empty selection, used for the variants of enums that aren't selected
+static EMPTY_SELECTION: FieldSelection = FieldSelection {
+    fields: BTreeMap::new(),
+};
+
+/// Serializes a value with only the selected fields of its objects
+struct Selected<'a, T: ?Sized> {
+    value: &'a T,
+    selection: &'a FieldSelection,
+}
+
+impl<'a, T: Serialize + ?Sized> Serialize for Selected<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(SelectingSerializer {
+            inner: serializer,
+            selection: self.selection,
+        })
+    }
+}
+
+/// Serializes a value with the given nested selection, if any
+struct MaybeSelected<'a, T: ?Sized> {
+    value: &'a T,
+    selection: Option<&'a FieldSelection>,
+}
+
+impl<'a, T: Serialize + ?Sized> Serialize for MaybeSelected<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.selection {
+            Some(selection) => Selected {
+                value: self.value,
+                selection,
+            }
+            .serialize(serializer),
+            None => self.value.serialize(serializer),
+        }
+    }
+}
+
+/// A serializer that skips the fields of structs and maps that aren't selected, and
+/// applies the selection of the selected fields to their values. Sequences and options
+/// are serialized with the same selection applied to their elements.
+struct SelectingSerializer<'a, S> {
+    inner: S,
+    selection: &'a FieldSelection,
+}
+
+/// Returns the key of a map entry as a string, so that it can be matched against the
+/// selected fields
+fn map_key<K: Serialize + ?Sized, E: serde::ser::Error>(key: &K) -> Result<String, E> {
+    match serde_json::to_value(key).map_err(E::custom)? {
+        Value::String(key) => Ok(key),
+        key => Ok(key.to_string()),
+    }
+}
+
+impl<'a, S: Serializer> Serializer for SelectingSerializer<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+    type SerializeMap = SelectingMap<'a, S::SerializeMap>;
+    type SerializeSeq = SelectingSeq<'a, S::SerializeSeq>;
+    type SerializeStruct = SelectingStruct<'a, S::SerializeStruct>;
+    type SerializeStructVariant = SelectingStruct<'a, S::SerializeStructVariant>;
+    type SerializeTuple = SelectingSeq<'a, S::SerializeTuple>;
+    type SerializeTupleStruct = SelectingSeq<'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = SelectingSeq<'a, S::SerializeTupleVariant>;
+
+    fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&Selected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_newtype_struct(name, &Selected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        // The variant is serialized as an object with a single field, named by the variant
+        match self.selection.get(variant) {
+            Some(selection) => {
+                self.inner
+                    .serialize_newtype_variant(name, variant_index, variant, &MaybeSelected {
+                        value,
+                        selection,
+                    })
+            },
+            None => self.inner.serialize_map(Some(0))?.end(),
+        }
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Ok(SelectingSeq {
+            inner: self.inner.serialize_seq(len)?,
+            selection: Some(self.selection),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Ok(SelectingSeq {
+            inner: self.inner.serialize_tuple(len)?,
+            selection: Some(self.selection),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        Ok(SelectingSeq {
+            inner: self.inner.serialize_tuple_struct(name, len)?,
+            selection: Some(self.selection),
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        // The variant is serialized as an object with a single field, named by the variant,
+        // which is always kept. The selection of that field is applied to the elements.
+        Ok(SelectingSeq {
+            inner: self
+                .inner
+                .serialize_tuple_variant(name, variant_index, variant, len)?,
+            selection: self
+                .selection
+                .get(variant)
+                .unwrap_or(Some(&EMPTY_SELECTION)),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        // The number of entries isn't known until the keys are matched to the selection
+        Ok(SelectingMap {
+            inner: self.inner.serialize_map(None)?,
+            selection: self.selection,
+            value_selection: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        Ok(SelectingStruct {
+            inner: self.inner.serialize_struct(name, len)?,
+            selection: Some(self.selection),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        // The variant is serialized as an object with a single field, named by the variant,
+        // which is always kept. The selection of that field is applied to the fields.
+        Ok(SelectingStruct {
+            inner: self
+                .inner
+                .serialize_struct_variant(name, variant_index, variant, len)?,
+            selection: self
+                .selection
+                .get(variant)
+                .unwrap_or(Some(&EMPTY_SELECTION)),
+        })
+    }
+}
+
+/// Serializes the elements of a sequence with the given selection, if any
+struct SelectingSeq<'a, S> {
+    inner: S,
+    selection: Option<&'a FieldSelection>,
+}
+
+impl<'a, S: SerializeSeq> SerializeSeq for SelectingSeq<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_element(&MaybeSelected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, S: SerializeTuple> SerializeTuple for SelectingSeq<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_element(&MaybeSelected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, S: SerializeTupleStruct> SerializeTupleStruct for SelectingSeq<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_field(&MaybeSelected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, S: SerializeTupleVariant> SerializeTupleVariant for SelectingSeq<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.inner.serialize_field(&MaybeSelected {
+            value,
+            selection: self.selection,
+        })
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// Serializes the selected fields of a struct, or all of them if there is no selection
+struct SelectingStruct<'a, S> {
+    inner: S,
+    selection: Option<&'a FieldSelection>,
+}
+
+impl<'a, S> SelectingStruct<'a, S> {
+    /// Returns `None` if the field isn't selected, otherwise the selection of its nested
+    /// fields
+    fn field_selection(&self, key: &str) -> Option<Option<&'a FieldSelection>> {
+        match self.selection {
+            Some(selection) => selection.get(key),
+            None => Some(None),
+        }
+    }
+}
+
+impl<'a, S: SerializeStruct> SerializeStruct for SelectingStruct<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        match self.field_selection(key) {
+            Some(selection) => self
+                .inner
+                .serialize_field(key, &MaybeSelected { value, selection }),
+            None => self.inner.skip_field(key),
+        }
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, S: SerializeStructVariant> SerializeStructVariant for SelectingStruct<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        match self.field_selection(key) {
+            Some(selection) => self
+                .inner
+                .serialize_field(key, &MaybeSelected { value, selection }),
+            None => self.inner.skip_field(key),
+        }
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// Serializes the selected entries of a map. This also covers structs with flattened
+/// fields and internally tagged enums, which serde serializes as maps.
+struct SelectingMap<'a, S> {
+    inner: S,
+    selection: &'a FieldSelection,
+    /// The selection of the value of the current entry, if its key is selected
+    value_selection: Option<Option<&'a FieldSelection>>,
+}
+
+impl<'a, S: SerializeMap> SerializeMap for SelectingMap<'a, S> {
+    type Error = S::Error;
+    type Ok = S::Ok;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), S::Error> {
+        self.value_selection = self.selection.get(&map_key(key)?);
+        if self.value_selection.is_some() {
+            self.inner.serialize_key(key)?;
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), S::Error> {
+        match self.value_selection.take() {
+            Some(selection) => self
+                .inner
+                .serialize_value(&MaybeSelected { value, selection }),
+            None => Ok(()),
+        }
+    }
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), S::Error> {
+        match self.selection.get(&map_key(key)?) {
+            Some(selection) => self
+                .inner
+                .serialize_entry(key, &MaybeSelected { value, selection }),
+            None => Ok(()),
+        }
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// A JSON payload that only includes the selected fields of the value, if there is a
+/// field selection. Otherwise, it is the same as [`poem_openapi::payload::Json`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldSelectedJson<T> {
+    pub value: T,
+    /// The JSON of the selected fields of the value, if there is a field selection
+    pub selected: Option<Vec<u8>>,
+}
+
+impl<T> FieldSelectedJson<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            selected: None,
+        }
+    }
+}
+
+impl<T: Type> Payload for FieldSelectedJson<T> {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn schema_ref() -> MetaSchemaRef {
+        T::schema_ref()
+    }
+
+    fn register(registry: &mut Registry) {
+        T::register(registry);
+    }
+}
+
+impl<T: ToJSON> IntoResponse for FieldSelectedJson<T> {
+    fn into_response(self) -> Response {
+        match self.selected {
+            Some(selected) => Response::builder()
+                .content_type(Self::CONTENT_TYPE)
+                .body(selected),
+            None => Json(self.value).into_response(),
+        }
+    }
+}
+
+impl<T: ToJSON> ApiResponse for FieldSelectedJson<T> {
+    fn meta() -> MetaResponses {
+        MetaResponses {
+            responses: vec![MetaResponse {
+                description: "",
+                status: Some(200),
+                content: vec![MetaMediaType {
+                    content_type: Self::CONTENT_TYPE,
+                    schema: Self::schema_ref(),
+                }],
+                headers: vec![],
+            }],
+        }
+    }
+
+    fn register(registry: &mut Registry) {
+        T::register(registry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FieldSelection;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    fn select<T: Serialize>(selection: &str, value: &T) -> Value {
+        let selection: FieldSelection = selection.parse().unwrap();
+        serde_json::from_slice(&selection.to_json_bytes(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_field_selection() {
+        let value = json!([
+            {
+                "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                "data": {
+                    "coin": {"value": "100"},
+                    "frozen": false,
+                },
+            },
+            {
+                "type": "0x1::account::Account",
+                "data": {"sequence_number": "1"},
+            },
+        ]);
+        assert_eq!(
+            select("type, data.coin.value,data.coin", &value),
+            json!([
+                {
+                    "type": "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+                    "data": {"coin": {"value": "100"}},
+                },
+                {
+                    "type": "0x1::account::Account",
+                    "data": {},
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_field_selection_of_structs() {
+        #[derive(Serialize)]
+        struct Info {
+            version: u64,
+            hash: String,
+        }
+
+        #[derive(Serialize)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum Transaction {
+            UserTransaction {
+                #[serde(flatten)]
+                info: Info,
+                sender: String,
+                signature: Option<Vec<u8>>,
+            },
+            BlockMetadataTransaction {
+                #[serde(flatten)]
+                info: Info,
+                epoch: u64,
+            },
+        }
+
+        let transactions = vec![
+            Transaction::UserTransaction {
+                info: Info {
+                    version: 1,
+                    hash: "0x1".to_string(),
+                },
+                sender: "0xa".to_string(),
+                signature: Some(vec![1, 2]),
+            },
+            Transaction::BlockMetadataTransaction {
+                info: Info {
+                    version: 2,
+                    hash: "0x2".to_string(),
+                },
+                epoch: 3,
+            },
+        ];
+        assert_eq!(
+            select("type,version,sender,signature", &transactions),
+            json!([
+                {
+                    "type": "user_transaction",
+                    "version": 1,
+                    "sender": "0xa",
+                    "signature": [1, 2],
+                },
+                {
+                    "type": "block_metadata_transaction",
+                    "version": 2,
+                },
+            ])
+        );
+        assert_eq!(
+            select("hash", &Some(&transactions[1])),
+            json!({"hash": "0x2"})
+        );
+    }
+
+    #[test]
+    fn test_invalid_field_selection() {
+        assert!("type,".parse::<FieldSelection>().is_err());
+        assert!("data..coin".parse::<FieldSelection>().is_err());
+    }
+}
@@ -14,17 +14,22 @@ pub mod context;
 mod error_converter;
 mod events;
 mod failpoint;
+mod field_selection;
 mod index;
 mod log;
 pub mod metrics;
 mod page;
+mod rate_limit;
 mod response;
 mod runtime;
 mod set_failpoints;
+mod simulation;
 pub mod spec;
 mod state;
+mod state_override;
 #[cfg(test)]
 pub mod tests;
+mod transaction_stream;
 mod transactions;
 mod view_function;
 
@@ -96,6 +96,14 @@ pub static WAIT_TRANSACTION_GAUGE: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static TRANSACTION_STREAM_GAUGE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_api_transaction_stream",
+        "Number of active committed transaction and event streams"
+    )
+    .unwrap()
+});
+
 pub static WAIT_TRANSACTION_POLL_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "aptos_api_wait_transaction_poll_time",
@@ -105,3 +113,21 @@ pub static WAIT_TRANSACTION_POLL_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static RATE_LIMITED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_api_rate_limited_requests",
+        "API requests rejected by the rate limiter grouped by endpoint group and client type",
+        &["endpoint_group", "client_type"]
+    )
+    .unwrap()
+});
+
+pub static RATE_LIMITER_BUCKET_REQUESTS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_api_rate_limiter_bucket_requests",
+        "Requests allowed and throttled per client per second grouped by rate limiter",
+        &["rate_limiter", "result"]
+    )
+    .unwrap()
+});
@@ -0,0 +1,282 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics::{RATE_LIMITED_REQUESTS, RATE_LIMITER_BUCKET_REQUESTS};
+use aptos_api_types::{AptosError, AptosErrorCode};
+use aptos_config::config::{ApiRateLimitConfig, ApiTokenBucketConfig, EndpointGroupRateLimits};
+use aptos_rate_limiter::rate_limit::TokenBucketRateLimiter;
+use poem::{
+    http::{
+        header::{HeaderValue, RETRY_AFTER},
+        Method, StatusCode,
+    },
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::payload::Json;
+use std::{collections::HashSet, net::IpAddr, sync::Arc, time::Instant};
+
+const CLIENT_TYPE_API_KEY: &str = "api_key";
+const CLIENT_TYPE_IP: &str = "ip";
+
+/// A group of endpoints that is rate limited separately from the others
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndpointGroup {
+    Submit,
+    Simulate,
+    View,
+    Read,
+}
+
+impl EndpointGroup {
+    /// Determines the group of the endpoint a request is made to
+    pub fn of_request(request: &Request) -> Self {
+        if request.method() != Method::POST {
+            return EndpointGroup::Read;
+        }
+
+        let path = request.uri().path().trim_end_matches('/');
+        match path {
+            "/v1/transactions" | "/v1/transactions/batch" | "/v1/transactions/cancel" => {
+                EndpointGroup::Submit
+            },
+            "/v1/view" | "/v1/view/batch" => EndpointGroup::View,
+            _ if path.starts_with("/v1/transactions/simulate") => EndpointGroup::Simulate,
+            _ => EndpointGroup::Read,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointGroup::Submit => "submit",
+            EndpointGroup::Simulate => "simulate",
+            EndpointGroup::View => "view",
+            EndpointGroup::Read => "read",
+        }
+    }
+}
+
+/// Rate limiters of an endpoint group, with a bucket per client
+struct EndpointGroupRateLimiters {
+    api_key: TokenBucketRateLimiter<String>,
+    ip: TokenBucketRateLimiter<IpAddr>,
+}
+
+impl EndpointGroupRateLimiters {
+    fn new(
+        api_key_label: &'static str,
+        api_key_bucket: &ApiTokenBucketConfig,
+        ip_label: &'static str,
+        ip_bucket: &ApiTokenBucketConfig,
+        initial_bucket_fill_percentage: u8,
+    ) -> Self {
+        Self {
+            api_key: TokenBucketRateLimiter::new(
+                api_key_label,
+                String::new(),
+                initial_bucket_fill_percentage,
+                api_key_bucket.bucket_size,
+                api_key_bucket.fill_rate,
+                Some(RATE_LIMITER_BUCKET_REQUESTS.clone()),
+            ),
+            ip: TokenBucketRateLimiter::new(
+                ip_label,
+                String::new(),
+                initial_bucket_fill_percentage,
+                ip_bucket.bucket_size,
+                ip_bucket.fill_rate,
+                Some(RATE_LIMITER_BUCKET_REQUESTS.clone()),
+            ),
+        }
+    }
+}
+
+struct RateLimiters {
+    api_key_header: String,
+    api_keys: HashSet<String>,
+    submit: EndpointGroupRateLimiters,
+    simulate: EndpointGroupRateLimiters,
+    view: EndpointGroupRateLimiters,
+    read: EndpointGroupRateLimiters,
+}
+
+impl RateLimiters {
+    fn new(config: &ApiRateLimitConfig) -> Self {
+        let EndpointGroupRateLimits {
+            submit: api_key_submit,
+            simulate: api_key_simulate,
+            view: api_key_view,
+            read: api_key_read,
+        } = &config.api_key_limits;
+        let EndpointGroupRateLimits {
+            submit: ip_submit,
+            simulate: ip_simulate,
+            view: ip_view,
+            read: ip_read,
+        } = &config.ip_limits;
+        let initial = config.initial_bucket_fill_percentage;
+
+        Self {
+            api_key_header: config.api_key_header.clone(),
+            api_keys: config.api_keys.iter().cloned().collect(),
+            submit: EndpointGroupRateLimiters::new(
+                "api_submit_api_key",
+                api_key_submit,
+                "api_submit_ip",
+                ip_submit,
+                initial,
+            ),
+            simulate: EndpointGroupRateLimiters::new(
+                "api_simulate_api_key",
+                api_key_simulate,
+                "api_simulate_ip",
+                ip_simulate,
+                initial,
+            ),
+            view: EndpointGroupRateLimiters::new(
+                "api_view_api_key",
+                api_key_view,
+                "api_view_ip",
+                ip_view,
+                initial,
+            ),
+            read: EndpointGroupRateLimiters::new(
+                "api_read_api_key",
+                api_key_read,
+                "api_read_ip",
+                ip_read,
+                initial,
+            ),
+        }
+    }
+
+    fn group(&self, group: EndpointGroup) -> &EndpointGroupRateLimiters {
+        match group {
+            EndpointGroup::Submit => &self.submit,
+            EndpointGroup::Simulate => &self.simulate,
+            EndpointGroup::View => &self.view,
+            EndpointGroup::Read => &self.read,
+        }
+    }
+
+    /// Takes a token for the request from the bucket of its client. If there are no
+    /// tokens left, returns the client type and the time at which a token is available.
+    fn acquire(
+        &self,
+        group: EndpointGroup,
+        request: &Request,
+    ) -> Result<(), (&'static str, Option<Instant>)> {
+        let limiters = self.group(group);
+
+        let api_key = request
+            .headers()
+            .get(self.api_key_header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .filter(|api_key| self.api_keys.contains(*api_key));
+        let (client_type, bucket) = match api_key {
+            Some(api_key) => (
+                CLIENT_TYPE_API_KEY,
+                limiters.api_key.bucket(api_key.to_string()),
+            ),
+            None => match request.remote_addr().as_socket_addr() {
+                Some(address) => (CLIENT_TYPE_IP, limiters.ip.bucket(address.ip())),
+                // There's no way to identify the client, so it can't be rate limited
+                None => return Ok(()),
+            },
+        };
+
+        let result = bucket.lock().acquire_all_tokens(1);
+        result.map_err(|available_at| (client_type, available_at))
+    }
+
+    fn garbage_collect(&self) {
+        for limiters in [&self.submit, &self.simulate, &self.view, &self.read] {
+            limiters.api_key.garbage_collect_full_buckets();
+            limiters.ip.garbage_collect_full_buckets();
+        }
+    }
+}
+
+/// This middleware rate limits requests per client and endpoint group, using token buckets.
+/// Clients are identified by their API key if they send a known one, and by their IP
+/// address otherwise. Throttled requests are rejected with a 429 and a `Retry-After` header.
+#[derive(Clone)]
+pub struct RateLimit {
+    /// The rate limiters, if rate limiting is enabled
+    limiters: Option<Arc<RateLimiters>>,
+}
+
+impl RateLimit {
+    pub fn new(config: &ApiRateLimitConfig) -> Self {
+        Self {
+            limiters: config.enabled.then(|| Arc::new(RateLimiters::new(config))),
+        }
+    }
+
+    /// Removes the buckets of clients that haven't made requests recently
+    pub fn garbage_collect(&self) {
+        if let Some(limiters) = &self.limiters {
+            limiters.garbage_collect();
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimit {
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            inner: ep,
+            limiters: self.limiters.clone(),
+        }
+    }
+}
+
+/// Endpoint for RateLimit middleware.
+pub struct RateLimitEndpoint<E> {
+    inner: E,
+    limiters: Option<Arc<RateLimiters>>,
+}
+
+impl<E: Endpoint> Endpoint for RateLimitEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if let Some(limiters) = &self.limiters {
+            let group = EndpointGroup::of_request(&req);
+            if let Err((client_type, available_at)) = limiters.acquire(group, &req) {
+                RATE_LIMITED_REQUESTS
+                    .with_label_values(&[group.as_str(), client_type])
+                    .inc();
+                return Ok(too_many_requests(group, available_at));
+            }
+        }
+
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+fn too_many_requests(group: EndpointGroup, available_at: Option<Instant>) -> Response {
+    // Retry-After is in whole seconds, so round up to not have clients retry too early
+    let retry_after_secs = available_at
+        .map(|available_at| {
+            let wait = available_at.saturating_duration_since(Instant::now());
+            wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+        })
+        .unwrap_or(0)
+        .max(1);
+
+    let mut response = Json(AptosError::new_with_error_code(
+        format!(
+            "Too many {} requests, retry after {} seconds",
+            group.as_str(),
+            retry_after_secs
+        ),
+        AptosErrorCode::RateLimited,
+    ))
+    .into_response();
+    response.set_status(StatusCode::TOO_MANY_REQUESTS);
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
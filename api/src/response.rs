@@ -29,13 +29,13 @@
 
 // TODO: https://github.com/aptos-labs/aptos-core/issues/2279
 
-use super::{accept_type::AcceptType, bcs_payload::Bcs};
+use super::{accept_type::AcceptType, bcs_payload::Bcs, field_selection::FieldSelectedJson};
 use aptos_api_types::{Address, AptosError, AptosErrorCode, HashValue, LedgerInfo};
 use move_core_types::{
     identifier::{IdentStr, Identifier},
     language_storage::StructTag,
 };
-use poem_openapi::{payload::Json, types::ToJSON, ResponseContent};
+use poem_openapi::{types::ToJSON, ResponseContent};
 use serde_json::Value;
 use std::fmt::Display;
 
@@ -43,8 +43,9 @@ use std::fmt::Display;
 #[derive(ResponseContent)]
 pub enum AptosResponseContent<T: ToJSON + Send + Sync> {
     /// When returning data as JSON, we take in T and then serialize to JSON as
-    /// part of the response.
-    Json(Json<T>),
+    /// part of the response. Only the selected fields are serialized, if the
+    /// client requested a field selection.
+    Json(FieldSelectedJson<T>),
 
     /// Return the data as BCS, which is just Vec<u8>. This data could have come
     /// from either an internal Rust type being serialized into bytes, or just
@@ -369,7 +370,9 @@ macro_rules! generate_success_response {
             fn from(
                 (value, ledger_info, status): (poem_openapi::payload::Json<T>, &aptos_api_types::LedgerInfo, [<$enum_name Status>]),
             ) -> Self {
-                let content = $crate::response::AptosResponseContent::Json(value);
+                let content = $crate::response::AptosResponseContent::Json(
+                    $crate::field_selection::FieldSelectedJson::new(value.0),
+                );
                 Self::from((content, ledger_info, status))
             }
         }
@@ -486,6 +489,32 @@ macro_rules! generate_success_response {
                 self
             }
 
+            pub fn with_field_selection<E: $crate::response::InternalError>(
+                mut self,
+                field_selection: Option<$crate::field_selection::FieldSelection>,
+            ) -> Result<Self, E> {
+                let Some(field_selection) = field_selection else {
+                    return Ok(self);
+                };
+                match self {
+                    $(
+                    [<$enum_name>]::$name(ref mut content, _, _, _, _, _, _, _, _, _) => {
+                        if let $crate::response::AptosResponseContent::Json(json) = content {
+                            json.selected = Some(
+                                field_selection
+                                    .to_json_bytes(&json.value)
+                                    .map_err(|e| E::internal_with_code_no_info(
+                                        e,
+                                        aptos_api_types::AptosErrorCode::InternalError,
+                                    ))?
+                            );
+                        }
+                    }
+                    )*
+                }
+                Ok(self)
+            }
+
             pub fn with_gas_used(mut self, new_gas_used: Option<u64>) -> Self {
                 match self {
                     $(
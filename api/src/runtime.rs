@@ -12,6 +12,7 @@ use crate::{
     events::EventsApi,
     index::IndexApi,
     log::middleware_log,
+    rate_limit::RateLimit,
     set_failpoints,
     spec::{spec_endpoint_json, spec_endpoint_yaml},
     state::StateApi,
@@ -172,6 +173,20 @@ pub fn attach_poem_to_runtime(
 
     let size_limit = context.content_length_limit();
 
+    let rate_limit = RateLimit::new(&config.api.rate_limit);
+    if config.api.rate_limit.enabled {
+        let rate_limit = rate_limit.clone();
+        let interval_secs = config.api.rate_limit.garbage_collection_interval_secs;
+        runtime_handle.spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                rate_limit.garbage_collect();
+            }
+        });
+    }
+
     let api_service = get_api_service(context.clone());
 
     let spec_json = spec_endpoint_json(&api_service);
@@ -240,6 +255,8 @@ pub fn attach_poem_to_runtime(
                         poem::get(set_failpoints::set_failpoint_poem).data(context.clone()),
                     ),
             )
+            // Rate limit within CORS, so that throttled responses have CORS headers too.
+            .with(rate_limit)
             .with(cors)
             .with(PostSizeLimit::new(size_limit))
             // NOTE: Make sure to keep this after all the `with` middleware.
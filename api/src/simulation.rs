@@ -0,0 +1,163 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for rendering the gas profile and the state diff of simulated transactions.
+
+use aptos_api_types::{
+    GasProfile, GasProfileDependency, GasProfileFrame, GasProfileInstruction, MoveConverter,
+    StateChangeType, StateDiff,
+};
+use aptos_gas_profiling::{CallFrame, ExecutionGasEvent, FrameName, TransactionGasLog};
+use aptos_types::{
+    state_store::StateView,
+    write_set::{WriteOp, WriteSet},
+};
+use move_core_types::{
+    identifier::IdentStr,
+    language_storage::{ModuleId, TypeTag},
+};
+use std::collections::BTreeMap;
+
+/// Converts the gas log of a simulated transaction into a [`GasProfile`].
+pub fn gas_profile(log: &TransactionGasLog) -> GasProfile {
+    let exec_io = &log.exec_io;
+
+    let io_gas = exec_io.transaction_transient.map(u64::from).unwrap_or(0)
+        + exec_io
+            .events_transient
+            .iter()
+            .map(|event| u64::from(event.cost))
+            .sum::<u64>()
+        + exec_io
+            .write_set_transient
+            .iter()
+            .map(|write| u64::from(write.cost))
+            .sum::<u64>();
+
+    GasProfile {
+        gas_scaling_factor: u64::from(exec_io.gas_scaling_factor).into(),
+        total_execution_and_io_gas: u64::from(exec_io.total).into(),
+        intrinsic_gas: u64::from(exec_io.intrinsic_cost).into(),
+        keyless_gas: u64::from(exec_io.keyless_cost).into(),
+        dependencies: exec_io
+            .dependencies
+            .iter()
+            .map(|dependency| GasProfileDependency {
+                module: (&dependency.id).into(),
+                size: u64::from(dependency.size).into(),
+                gas: u64::from(dependency.cost).into(),
+            })
+            .collect(),
+        call_graph: gas_profile_frame(&exec_io.call_graph),
+        io_gas: io_gas.into(),
+        total_storage_fee: u64::from(log.storage.total).into(),
+        total_storage_refund: u64::from(log.storage.total_refund).into(),
+    }
+}
+
+fn gas_profile_frame(frame: &CallFrame) -> GasProfileFrame {
+    let mut total_gas = 0;
+    let mut instructions: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    let mut calls = vec![];
+
+    for event in &frame.events {
+        let (name, cost) = match event {
+            ExecutionGasEvent::Loc(_) => continue,
+            ExecutionGasEvent::Call(child_frame) => {
+                let child_frame = gas_profile_frame(child_frame);
+                total_gas += child_frame.total_gas.0;
+                calls.push(child_frame);
+                continue;
+            },
+            ExecutionGasEvent::Bytecode { op, cost } => {
+                (format!("{:?}", op).to_ascii_lowercase(), *cost)
+            },
+            ExecutionGasEvent::CallNative {
+                module_id,
+                fn_name,
+                ty_args,
+                cost,
+            } => (function_name(module_id, fn_name, ty_args), *cost),
+            ExecutionGasEvent::LoadResource { ty, cost, .. } => {
+                (format!("load_resource<{}>", ty), *cost)
+            },
+            ExecutionGasEvent::CreateTy { cost } => ("create_ty".to_string(), *cost),
+        };
+
+        let cost = u64::from(cost);
+        total_gas += cost;
+        let (count, gas) = instructions.entry(name).or_default();
+        *count += 1;
+        *gas += cost;
+    }
+
+    let mut instructions: Vec<_> = instructions
+        .into_iter()
+        .map(|(name, (count, gas))| GasProfileInstruction {
+            name,
+            count: count.into(),
+            gas: gas.into(),
+        })
+        .collect();
+    instructions.sort_by(|a, b| b.gas.0.cmp(&a.gas.0));
+
+    let name = match &frame.name {
+        FrameName::Script => "<script>".to_string(),
+        FrameName::Function {
+            module_id,
+            name,
+            ty_args,
+        } => function_name(module_id, name, ty_args),
+    };
+
+    GasProfileFrame {
+        name,
+        total_gas: total_gas.into(),
+        instructions,
+        calls,
+    }
+}
+
+fn function_name(module_id: &ModuleId, name: &IdentStr, ty_args: &[TypeTag]) -> String {
+    let mut function_name = format!("{}::{}", module_id.short_str_lossless(), name);
+    if !ty_args.is_empty() {
+        let ty_args: Vec<_> = ty_args.iter().map(|ty| ty.to_string()).collect();
+        function_name.push_str(&format!("<{}>", ty_args.join(", ")));
+    }
+    function_name
+}
+
+/// Renders the changes in the write set of a simulated transaction, along with the values
+/// of the changed state items in `state_view` (i.e., before the transaction).
+///
+/// Like the `changes` of a transaction, state items that can't be rendered are skipped.
+pub fn state_diff<S: StateView>(
+    converter: &MoveConverter<S>,
+    state_view: &S,
+    write_set: &WriteSet,
+) -> anyhow::Result<Vec<StateDiff>> {
+    let mut diff = vec![];
+    for (state_key, op) in write_set.iter() {
+        let change_type = match op {
+            WriteOp::Creation { .. } => StateChangeType::Created,
+            WriteOp::Modification { .. } => StateChangeType::Modified,
+            WriteOp::Deletion { .. } => StateChangeType::Deleted,
+        };
+        let Ok(after) = converter.try_into_write_set_changes(state_key.clone(), op.clone()) else {
+            continue;
+        };
+        let before = match state_view.get_state_value_bytes(state_key)? {
+            Some(bytes) => converter.try_into_write_set_changes(
+                state_key.clone(),
+                WriteOp::legacy_modification(bytes),
+            )?,
+            None => vec![],
+        };
+        diff.push(StateDiff {
+            change_type,
+            before,
+            after,
+        });
+    }
+    Ok(diff)
+}
@@ -5,6 +5,7 @@ use crate::{
     accept_type::AcceptType,
     context::api_spawn_blocking,
     failpoint::fail_point_poem,
+    field_selection::parse_field_selection,
     response::{
         api_forbidden, build_not_found, module_not_found, resource_not_found, table_item_not_found,
         BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
@@ -59,6 +60,18 @@ impl StateApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the resource at
+        ///
+        /// The resource is read at the last version committed before this timestamp, e.g., to
+        /// get a balance at a point in time. It can't be provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
+        /// Comma separated list of the fields of the resource to include, e.g.
+        /// `data.coin.value` for the balance of a `0x1::coin::CoinStore`
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Only the selected
+        /// fields are serialized, but the resource is still read in full.
+        fields: Query<Option<String>>,
     ) -> BasicResultWith404<MoveResource> {
         resource_type
             .0
@@ -70,15 +83,20 @@ impl StateApi {
         fail_point_poem("endpoint_get_account_resource")?;
         self.context
             .check_api_output_enabled("Get account resource", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
 
         let api = self.clone();
         api_spawn_blocking(move || {
+            let ledger_version = api
+                .context
+                .resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
             api.resource(
                 &accept_type,
                 address.0,
                 resource_type.0,
-                ledger_version.0.map(|inner| inner.0),
+                ledger_version.map(|inner| inner.0),
             )
+            .and_then(|response| response.with_field_selection(field_selection))
         })
         .await
     }
@@ -107,6 +125,11 @@ impl StateApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the module at
+        ///
+        /// The module is read at the last version committed before this timestamp. It can't be
+        /// provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
     ) -> BasicResultWith404<MoveModuleBytecode> {
         verify_module_identifier(module_name.0.as_str())
             .context("'module_name' invalid")
@@ -118,7 +141,10 @@ impl StateApi {
             .check_api_output_enabled("Get account module", &accept_type)?;
         let api = self.clone();
         api_spawn_blocking(move || {
-            api.module(&accept_type, address.0, module_name.0, ledger_version.0)
+            let ledger_version = api
+                .context
+                .resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
+            api.module(&accept_type, address.0, module_name.0, ledger_version)
         })
         .await
     }
@@ -152,6 +178,11 @@ impl StateApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the table item at
+        ///
+        /// The item is read at the last version committed before this timestamp. It can't be
+        /// provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
     ) -> BasicResultWith404<MoveValue> {
         table_item_request
             .0
@@ -165,11 +196,14 @@ impl StateApi {
             .check_api_output_enabled("Get table item", &accept_type)?;
         let api = self.clone();
         api_spawn_blocking(move || {
+            let ledger_version = api
+                .context
+                .resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
             api.table_item(
                 &accept_type,
                 table_handle.0,
                 table_item_request.0,
-                ledger_version.0,
+                ledger_version,
             )
         })
         .await
@@ -202,6 +236,11 @@ impl StateApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) to get the raw table item at
+        ///
+        /// The item is read at the last version committed before this timestamp. It can't be
+        /// provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
     ) -> BasicResultWith404<MoveValue> {
         fail_point_poem("endpoint_get_table_item")?;
 
@@ -216,11 +255,14 @@ impl StateApi {
 
         let api = self.clone();
         api_spawn_blocking(move || {
+            let ledger_version = api
+                .context
+                .resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
             api.raw_table_item(
                 &accept_type,
                 table_handle.0,
                 table_item_request.0,
-                ledger_version.0,
+                ledger_version,
             )
         })
         .await
@@ -249,6 +291,11 @@ impl StateApi {
         ///
         /// If not provided, it will be the latest version
         ledger_version: Query<Option<U64>>,
+        /// Ledger timestamp (in microseconds) at which the value is got.
+        ///
+        /// The value is read at the last version committed before this timestamp. It can't be
+        /// provided along with `ledger_version`.
+        ledger_timestamp: Query<Option<U64>>,
     ) -> BasicResultWith404<MoveValue> {
         fail_point_poem("endpoint_get_raw_state_value")?;
 
@@ -262,7 +309,13 @@ impl StateApi {
             .check_api_output_enabled("Get raw state value", &accept_type)?;
 
         let api = self.clone();
-        api_spawn_blocking(move || api.raw_value(&accept_type, request.0, ledger_version.0)).await
+        api_spawn_blocking(move || {
+            let ledger_version = api
+                .context
+                .resolve_ledger_version(ledger_version.0, ledger_timestamp.0)?;
+            api.raw_value(&accept_type, request.0, ledger_version)
+        })
+        .await
     }
 }
 
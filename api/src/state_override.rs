@@ -0,0 +1,391 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, ensure, format_err, Context as AnyhowContext};
+use aptos_api_types::{MoveModuleBytecode, StateOverrides};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::{
+        fungible_store::{primary_store, FungibleStoreResource},
+        AccountResource, CoinStoreResource, ObjectGroupResource,
+    },
+    on_chain_config::CurrentTimeMicroseconds,
+    state_store::{
+        errors::StateviewError, state_key::StateKey, state_storage_usage::StateStorageUsage,
+        state_value::StateValue, StateView, StateViewId, TStateView,
+    },
+};
+use move_core_types::{
+    ident_str,
+    language_storage::StructTag,
+    move_resource::{MoveResource, MoveStructType},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A state view that applies [`StateOverrides`] on top of a base state view.
+/// It is used to simulate transactions against a modified ledger state.
+///
+/// As the overrides may replace modules, transactions must be simulated on this view with a
+/// Move VM that is not shared with other VMs, see
+/// [`aptos_vm::AptosSimulationVM::new_with_uncached_move_vm`].
+pub struct OverriddenStateView<'a, S> {
+    base_view: &'a S,
+    overrides: HashMap<StateKey, StateValue>,
+}
+
+impl<'a, S: StateView> OverriddenStateView<'a, S> {
+    /// Applies the overrides on top of `base_view`. There can be at most `max_overrides`
+    /// resource, module, balance and sequence number overrides, and the resources and modules
+    /// can be at most `max_override_bytes` in total.
+    pub fn new(
+        base_view: &'a S,
+        state_overrides: StateOverrides,
+        max_overrides: usize,
+        max_override_bytes: usize,
+    ) -> anyhow::Result<Self> {
+        let num_overrides = state_overrides.resources.len()
+            + state_overrides.modules.len()
+            + state_overrides.balances.len()
+            + state_overrides.sequence_numbers.len();
+        ensure!(
+            num_overrides <= max_overrides,
+            "Too many state overrides: {} (max {})",
+            num_overrides,
+            max_overrides
+        );
+        let override_bytes = state_overrides
+            .resources
+            .iter()
+            .map(|resource| resource.data.inner().len())
+            .chain(
+                state_overrides
+                    .modules
+                    .iter()
+                    .map(|module| module.bytecode.inner().len()),
+            )
+            .sum::<usize>();
+        ensure!(
+            override_bytes <= max_override_bytes,
+            "State overrides are too large: {} bytes (max {})",
+            override_bytes,
+            max_override_bytes
+        );
+
+        let mut view = Self {
+            base_view,
+            overrides: HashMap::new(),
+        };
+
+        for resource in state_overrides.resources {
+            let address: AccountAddress = resource.address.into();
+            let struct_tag: StructTag = resource
+                .resource_type
+                .try_into()
+                .context("Invalid resource type")?;
+            view.insert(
+                StateKey::resource(&address, &struct_tag)?,
+                resource.data.into(),
+            );
+        }
+
+        for module in state_overrides.modules {
+            let bytecode: Vec<u8> = module.bytecode.into();
+            let module = MoveModuleBytecode::new(bytecode.clone())
+                .try_parse_abi()?
+                .abi
+                .ok_or_else(|| format_err!("Failed to deserialize module bytecode"))?;
+            let address: AccountAddress = module.address.into();
+            view.insert(StateKey::module(&address, &module.name), bytecode);
+        }
+
+        for balance in state_overrides.balances {
+            view.override_balance(balance.address.into(), balance.amount.0)?;
+        }
+
+        for sequence_number in state_overrides.sequence_numbers {
+            let address: AccountAddress = sequence_number.address.into();
+            let mut account: AccountResource = view
+                .read_resource(&address)?
+                .ok_or_else(|| format_err!("Account {} does not exist", address))?;
+            account.set_sequence_number(sequence_number.sequence_number.0);
+            view.write_resource(&address, &account)?;
+        }
+
+        if let Some(timestamp_usecs) = state_overrides.timestamp_usecs {
+            let timestamp = CurrentTimeMicroseconds {
+                microseconds: timestamp_usecs.0,
+            };
+            view.insert(
+                StateKey::on_chain_config::<CurrentTimeMicroseconds>()?,
+                bcs::to_bytes(&timestamp)?,
+            );
+        }
+
+        Ok(view)
+    }
+
+    /// Sets the APT balance of an account. If the account has a primary fungible store for APT,
+    /// the balance is set there and the coin store (if any) is emptied, so that the total APT
+    /// balance of the account is `amount`.
+    fn override_balance(&mut self, address: AccountAddress, amount: u64) -> anyhow::Result<()> {
+        let coin_store: Option<CoinStoreResource> = self.read_resource(&address)?;
+
+        let group_key =
+            StateKey::resource_group(&primary_store(&address), &ObjectGroupResource::struct_tag());
+        let mut group: BTreeMap<StructTag, Vec<u8>> = self
+            .get_state_value_bytes(&group_key)?
+            .map(|bytes| bcs::from_bytes(&bytes))
+            .transpose()?
+            .unwrap_or_default();
+        let coin_amount = match group.get(&FungibleStoreResource::struct_tag()) {
+            Some(bytes) => {
+                // With a concurrent balance, the balance of the store itself isn't used
+                let concurrent_balance_tag = StructTag {
+                    name: ident_str!("ConcurrentFungibleBalance").to_owned(),
+                    ..FungibleStoreResource::struct_tag()
+                };
+                if group.contains_key(&concurrent_balance_tag) {
+                    bail!(
+                        "Overriding a concurrent fungible balance is not supported: {}",
+                        address
+                    );
+                }
+
+                let fungible_store: FungibleStoreResource = bcs::from_bytes(bytes)?;
+                let fungible_store = FungibleStoreResource::new(
+                    fungible_store.metadata(),
+                    amount,
+                    fungible_store.frozen(),
+                );
+                group.insert(
+                    FungibleStoreResource::struct_tag(),
+                    bcs::to_bytes(&fungible_store)?,
+                );
+                self.insert(group_key, bcs::to_bytes(&group)?);
+                0
+            },
+            None if coin_store.is_some() => amount,
+            None => bail!(
+                "Account {} has neither an APT coin store nor a primary APT fungible store",
+                address
+            ),
+        };
+
+        if let Some(coin_store) = coin_store {
+            let coin_store = CoinStoreResource::new(
+                coin_amount,
+                coin_store.frozen(),
+                coin_store.deposit_events().clone(),
+                coin_store.withdraw_events().clone(),
+            );
+            self.write_resource(&address, &coin_store)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, state_key: StateKey, bytes: Vec<u8>) {
+        self.overrides
+            .insert(state_key, StateValue::new_legacy(bytes.into()));
+    }
+
+    fn read_resource<T: MoveResource + DeserializeOwned>(
+        &self,
+        address: &AccountAddress,
+    ) -> anyhow::Result<Option<T>> {
+        self.get_state_value_bytes(&StateKey::resource_typed::<T>(address)?)?
+            .map(|bytes| bcs::from_bytes(&bytes))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    fn write_resource<T: MoveResource + Serialize>(
+        &mut self,
+        address: &AccountAddress,
+        resource: &T,
+    ) -> anyhow::Result<()> {
+        self.insert(
+            StateKey::resource_typed::<T>(address)?,
+            bcs::to_bytes(resource)?,
+        );
+        Ok(())
+    }
+}
+
+impl<'a, S: StateView> TStateView for OverriddenStateView<'a, S> {
+    type Key = StateKey;
+
+    fn id(&self) -> StateViewId {
+        self.base_view.id()
+    }
+
+    fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>, StateviewError> {
+        match self.overrides.get(state_key) {
+            Some(state_value) => Ok(Some(state_value.clone())),
+            None => self.base_view.get_state_value(state_key),
+        }
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage, StateviewError> {
+        self.base_view.get_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OverriddenStateView;
+    use aptos_api_types::{
+        Address, BalanceOverride, HexEncodedBytes, MoveStructTag, ResourceOverride, StateOverrides,
+        U64,
+    };
+    use aptos_types::{
+        account_address::AccountAddress,
+        account_config::{
+            fungible_store::{primary_store, FungibleStoreResource},
+            CoinStoreResource, ObjectGroupResource,
+        },
+        event::{EventHandle, EventKey},
+        state_store::{
+            in_memory_state_view::InMemoryStateView, state_key::StateKey, state_value::StateValue,
+            TStateView,
+        },
+    };
+    use move_core_types::{language_storage::StructTag, move_resource::MoveStructType};
+    use std::collections::{BTreeMap, HashMap};
+
+    const BALANCE: u64 = 100;
+    const OVERRIDDEN_BALANCE: u64 = 1_000;
+    const MAX_OVERRIDES: usize = 2;
+    const MAX_OVERRIDE_BYTES: usize = 100;
+
+    fn new_view(
+        base_view: &InMemoryStateView,
+        state_overrides: StateOverrides,
+    ) -> anyhow::Result<OverriddenStateView<InMemoryStateView>> {
+        OverriddenStateView::new(
+            base_view,
+            state_overrides,
+            MAX_OVERRIDES,
+            MAX_OVERRIDE_BYTES,
+        )
+    }
+
+    fn coin_store(address: AccountAddress, coin: u64) -> (StateKey, StateValue) {
+        let coin_store = CoinStoreResource::new(
+            coin,
+            false,
+            EventHandle::new(EventKey::new(0, address), 0),
+            EventHandle::new(EventKey::new(1, address), 0),
+        );
+        (
+            StateKey::resource_typed::<CoinStoreResource>(&address).unwrap(),
+            StateValue::new_legacy(bcs::to_bytes(&coin_store).unwrap().into()),
+        )
+    }
+
+    fn fungible_store(address: AccountAddress, balance: u64) -> (StateKey, StateValue) {
+        let fungible_store = FungibleStoreResource::new(AccountAddress::TEN, balance, false);
+        let group: BTreeMap<StructTag, Vec<u8>> = [(
+            FungibleStoreResource::struct_tag(),
+            bcs::to_bytes(&fungible_store).unwrap(),
+        )]
+        .into_iter()
+        .collect();
+        (
+            StateKey::resource_group(&primary_store(&address), &ObjectGroupResource::struct_tag()),
+            StateValue::new_legacy(bcs::to_bytes(&group).unwrap().into()),
+        )
+    }
+
+    fn balance_overrides(address: AccountAddress) -> StateOverrides {
+        StateOverrides {
+            balances: vec![BalanceOverride {
+                address: Address::from(address),
+                amount: U64(OVERRIDDEN_BALANCE),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn apt_balance(view: &OverriddenStateView<InMemoryStateView>, address: AccountAddress) -> u64 {
+        let coin = view
+            .read_resource::<CoinStoreResource>(&address)
+            .unwrap()
+            .map_or(0, |coin_store| coin_store.coin());
+        let group_key =
+            StateKey::resource_group(&primary_store(&address), &ObjectGroupResource::struct_tag());
+        let fungible = view
+            .get_state_value_bytes(&group_key)
+            .unwrap()
+            .map_or(0, |bytes| {
+                let group: BTreeMap<StructTag, Vec<u8>> = bcs::from_bytes(&bytes).unwrap();
+                let store: FungibleStoreResource =
+                    bcs::from_bytes(&group[&FungibleStoreResource::struct_tag()]).unwrap();
+                store.balance()
+            });
+        coin + fungible
+    }
+
+    #[test]
+    fn test_balance_override_coin_store() {
+        let address = AccountAddress::random();
+        let base_view = InMemoryStateView::new(HashMap::from([coin_store(address, BALANCE)]));
+        let view = new_view(&base_view, balance_overrides(address)).unwrap();
+        assert_eq!(apt_balance(&view, address), OVERRIDDEN_BALANCE);
+    }
+
+    #[test]
+    fn test_balance_override_fungible_store() {
+        // The balance is split between the coin store and the primary fungible store
+        let address = AccountAddress::random();
+        let base_view = InMemoryStateView::new(HashMap::from([
+            coin_store(address, BALANCE),
+            fungible_store(address, BALANCE),
+        ]));
+        let view = new_view(&base_view, balance_overrides(address)).unwrap();
+        assert_eq!(apt_balance(&view, address), OVERRIDDEN_BALANCE);
+
+        // An account may only have a primary fungible store
+        let base_view = InMemoryStateView::new(HashMap::from([fungible_store(address, BALANCE)]));
+        let view = new_view(&base_view, balance_overrides(address)).unwrap();
+        assert_eq!(apt_balance(&view, address), OVERRIDDEN_BALANCE);
+    }
+
+    #[test]
+    fn test_balance_override_without_store() {
+        let address = AccountAddress::random();
+        let base_view = InMemoryStateView::new(HashMap::new());
+        assert!(new_view(&base_view, balance_overrides(address)).is_err());
+    }
+
+    #[test]
+    fn test_override_limits() {
+        let address = AccountAddress::random();
+        let base_view = InMemoryStateView::new(HashMap::from([coin_store(address, BALANCE)]));
+
+        // Too many overrides
+        let mut state_overrides = balance_overrides(address);
+        state_overrides.balances = vec![state_overrides.balances[0].clone(); MAX_OVERRIDES + 1];
+        assert!(new_view(&base_view, state_overrides).is_err());
+
+        // Overrides that are too large
+        let resource_override = |size| ResourceOverride {
+            address: Address::from(address),
+            resource_type: MoveStructTag::from(CoinStoreResource::struct_tag()),
+            data: HexEncodedBytes(vec![0; size]),
+        };
+        let state_overrides = StateOverrides {
+            resources: vec![resource_override(MAX_OVERRIDE_BYTES / 2); 2],
+            ..Default::default()
+        };
+        new_view(&base_view, state_overrides).unwrap();
+        let state_overrides = StateOverrides {
+            resources: vec![
+                resource_override(MAX_OVERRIDE_BYTES / 2),
+                resource_override(MAX_OVERRIDE_BYTES / 2 + 1),
+            ],
+            ..Default::default()
+        };
+        assert!(new_view(&base_view, state_overrides).is_err());
+    }
+}
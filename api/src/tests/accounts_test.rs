@@ -147,6 +147,82 @@ async fn test_get_account_resources_by_ledger_version() {
     test_account_resources_by_ledger_version_with_context(shard_context).await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_resources_by_ledger_timestamp() {
+    let mut context = new_test_context(current_function_name!());
+    let root_address = context.root_account().await.address().to_hex_literal();
+
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&vec![txn]).await;
+    let first_block_timestamp = context.get_latest_ledger_info().timestamp();
+
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&vec![txn]).await;
+    let second_block_timestamp = context.get_latest_ledger_info().timestamp();
+
+    // The state is read before the block with the given timestamp
+    for (timestamp, sequence_number) in
+        [(first_block_timestamp, "0"), (second_block_timestamp, "1")]
+    {
+        let resources = context
+            .get(&account_resources_with_ledger_timestamp(
+                &root_address,
+                timestamp,
+            ))
+            .await;
+        let root_account = find_value(&resources, |f| f["type"] == "0x1::account::Account");
+        assert_eq!(root_account["data"]["sequence_number"], sequence_number);
+    }
+
+    // Timestamps after the latest ledger timestamp are rejected
+    context
+        .expect_status_code(400)
+        .get(&account_resources_with_ledger_timestamp(
+            &root_address,
+            second_block_timestamp + 1,
+        ))
+        .await;
+
+    // The ledger version and timestamp can't both be provided
+    context
+        .expect_status_code(400)
+        .get(&format!(
+            "{}&ledger_version=0",
+            account_resources_with_ledger_timestamp(&root_address, first_block_timestamp)
+        ))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_resources_with_field_selection() {
+    let mut context = new_test_context(current_function_name!());
+    let root_address = context.root_account().await.address().to_hex_literal();
+
+    let resources = context
+        .get(&format!(
+            "{}?fields=type,data.sequence_number",
+            account_resources(&root_address)
+        ))
+        .await;
+    for resource in resources.as_array().unwrap() {
+        let resource = resource.as_object().unwrap();
+        assert!(resource.contains_key("type"));
+        assert!(resource.keys().all(|key| key == "type" || key == "data"));
+    }
+    let root_account = find_value(&resources, |f| f["type"] == "0x1::account::Account");
+    assert_eq!(root_account["data"], json!({"sequence_number": "0"}));
+
+    context
+        .expect_status_code(400)
+        .get(&format!(
+            "{}?fields=type,",
+            account_resources(&root_address)
+        ))
+        .await;
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_get_account_resources_by_too_large_ledger_version() {
     let mut context = new_test_context(current_function_name!());
@@ -414,6 +490,14 @@ fn account_resources_with_ledger_version(address: &str, ledger_version: i128) ->
     )
 }
 
+fn account_resources_with_ledger_timestamp(address: &str, ledger_timestamp: u64) -> String {
+    format!(
+        "{}?ledger_timestamp={}",
+        account_resources(address),
+        ledger_timestamp
+    )
+}
+
 fn account_modules(address: &str) -> String {
     format!("/accounts/{}/modules", address)
 }
@@ -23,6 +23,30 @@ async fn test_get_unknown_block_by_height() {
     context.check_golden_output(resp);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_version_by_timestamp() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&vec![txn]).await;
+    let ledger_info = context.get_latest_ledger_info();
+
+    // The last version before the block is the genesis transaction
+    let resp = context
+        .get(&version_by_timestamp(ledger_info.timestamp()))
+        .await;
+    assert_eq!(resp["version"], "0");
+
+    context
+        .expect_status_code(400)
+        .get(&version_by_timestamp(ledger_info.timestamp() + 1))
+        .await;
+}
+
 fn blocks_by_height(height: u64) -> String {
     format!("/blocks/by_height/{}", height)
 }
+
+fn version_by_timestamp(timestamp: u64) -> String {
+    format!("/versions/by_timestamp/{}", timestamp)
+}
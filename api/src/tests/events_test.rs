@@ -3,8 +3,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::new_test_context;
-use crate::tests::new_test_context_with_db_sharding_and_internal_indexer;
+use crate::tests::{
+    new_test_context_with_db_sharding_and_internal_indexer,
+    new_test_context_with_transaction_stream, StreamClient,
+};
 use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_types::account_config::aptos_test_root_address;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde_json::json;
 use std::path::PathBuf;
@@ -230,3 +234,70 @@ async fn test_get_events_by_struct_type_has_generic_type_parameter() {
     let resp = context.expect_status_code(404).get(path.as_str()).await;
     context.check_golden_output(resp);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_events() {
+    let mut context = new_test_context_with_transaction_stream(current_function_name!());
+    let mut stream = StreamClient::open(
+        &context,
+        &format!("/events/stream?sender={}", aptos_test_root_address()),
+    )
+    .await;
+
+    // Commit a transaction and verify its events are pushed to the stream
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&[txn.clone()]).await;
+    let committed_txn = context
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            txn.committed_hash().to_hex_literal()
+        ))
+        .await;
+    let events = committed_txn["events"].as_array().unwrap();
+    assert!(!events.is_empty());
+    for event in events {
+        let streamed_event = stream.next_item().await;
+        assert_eq!(streamed_event["version"], committed_txn["version"]);
+        assert_eq!(streamed_event["guid"], event["guid"]);
+        assert_eq!(streamed_event["sequence_number"], event["sequence_number"]);
+        assert_eq!(streamed_event["type"], event["type"]);
+        assert_eq!(streamed_event["data"], event["data"]);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_events_by_type() {
+    let mut context = new_test_context_with_transaction_stream(current_function_name!());
+
+    // Commit a transaction and identify the type of its last event
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&[txn.clone()]).await;
+    let committed_txn = context
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            txn.committed_hash().to_hex_literal()
+        ))
+        .await;
+    let events = committed_txn["events"].as_array().unwrap();
+    let event_type = events.last().unwrap()["type"].as_str().unwrap();
+
+    // Verify a stream starting at the transaction only returns events of the type
+    let mut stream = StreamClient::open(
+        &context,
+        &format!(
+            "/events/stream?start={}&event_type={}",
+            committed_txn["version"].as_str().unwrap(),
+            utf8_percent_encode(event_type, NON_ALPHANUMERIC)
+        ),
+    )
+    .await;
+    for event in events.iter().filter(|event| event["type"] == event_type) {
+        let streamed_event = stream.next_item().await;
+        assert_eq!(streamed_event["version"], committed_txn["version"]);
+        assert_eq!(streamed_event["guid"], event["guid"]);
+        assert_eq!(streamed_event["type"], event["type"]);
+        assert_eq!(streamed_event["data"], event["data"]);
+    }
+}
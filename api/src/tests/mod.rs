@@ -11,6 +11,7 @@ mod invalid_post_request_test;
 mod modules;
 mod multisig_transactions_test;
 mod objects;
+mod rate_limit_test;
 mod resource_groups;
 mod secp256k1_ecdsa;
 mod simulation_test;
@@ -21,8 +22,15 @@ mod transactions_test;
 mod view_function;
 mod webauthn_secp256r1_ecdsa;
 
-use aptos_api_test_context::{new_test_context as super_new_test_context, TestContext};
+use aptos_api_test_context::{
+    new_test_context as super_new_test_context, ApiSpecificConfig, TestContext,
+};
 use aptos_config::config::{internal_indexer_db_config::InternalIndexerDBConfig, NodeConfig};
+use serde_json::Value;
+use std::time::Duration;
+
+// The maximum time to wait for the next item of a stream
+const STREAM_ITEM_TIMEOUT: Duration = Duration::from_secs(30);
 
 fn new_test_context(test_name: String) -> TestContext {
     new_test_context_with_config(test_name, NodeConfig::default())
@@ -32,9 +40,63 @@ fn new_test_context_with_config(test_name: String, node_config: NodeConfig) -> T
     super_new_test_context(test_name, node_config, false)
 }
 
+fn new_test_context_with_transaction_stream(test_name: String) -> TestContext {
+    let mut node_config = NodeConfig::default();
+    node_config.api.transaction_stream_enabled = true;
+    new_test_context_with_config(test_name, node_config)
+}
+
 fn new_test_context_with_db_sharding_and_internal_indexer(test_name: String) -> TestContext {
     let mut node_config = NodeConfig::default();
     node_config.storage.rocksdb_configs.enable_storage_sharding = true;
     node_config.indexer_db_config = InternalIndexerDBConfig::new(true, true, true, 10_000);
     super_new_test_context(test_name, node_config, true)
 }
+
+/// A client reading the items of a server-sent event stream served by the API
+struct StreamClient {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl StreamClient {
+    /// Opens the stream at the given path of the API
+    async fn open(context: &TestContext, path: &str) -> Self {
+        let ApiSpecificConfig::V1(address) = context.api_specific_config;
+        let url = format!("http://{}{}", address, context.prepend_path(path));
+        let response = reqwest::get(url).await.unwrap();
+        assert_eq!(response.status(), 200);
+        Self {
+            response,
+            buffer: vec![],
+        }
+    }
+
+    /// Returns the next item of the stream (skipping keep alive messages)
+    async fn next_item(&mut self) -> Value {
+        loop {
+            // Parse the next complete message in the buffer (if any)
+            if let Some(index) = self.buffer.windows(2).position(|bytes| bytes == b"\n\n") {
+                let message: Vec<u8> = self.buffer.drain(..index + 2).collect();
+                let data: String = std::str::from_utf8(&message)
+                    .unwrap()
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .map(str::trim_start)
+                    .collect();
+                if !data.is_empty() {
+                    return serde_json::from_str(&data).unwrap();
+                }
+                continue;
+            }
+
+            // Otherwise, read more data from the stream
+            let chunk = tokio::time::timeout(STREAM_ITEM_TIMEOUT, self.response.chunk())
+                .await
+                .expect("Timed out waiting for the next stream item")
+                .unwrap()
+                .expect("The stream ended unexpectedly");
+            self.buffer.extend_from_slice(&chunk);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+module addr::counter {
+    use 0x1::aggregator_v2::Aggregator;
+
+    struct Counter has key {
+        counter: Aggregator<u64>,
+    }
+
+    public entry fun increment_counter() {
+        abort 42
+    }
+
+    #[view]
+    public fun add_and_get_counter_value(): u64 {
+        abort 42
+    }
+}
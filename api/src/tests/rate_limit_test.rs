@@ -0,0 +1,51 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::new_test_context_with_config;
+use aptos_api_test_context::current_function_name;
+use aptos_config::config::{ApiTokenBucketConfig, NodeConfig};
+
+const API_KEY: &str = "test-api-key";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_rate_limit_by_ip_and_api_key() {
+    let mut node_config = NodeConfig::default();
+    let rate_limit = &mut node_config.api.rate_limit;
+    rate_limit.enabled = true;
+    rate_limit.api_keys = vec![API_KEY.to_string()];
+    rate_limit.ip_limits.read = ApiTokenBucketConfig::new(2, 1);
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    // Requests are throttled once the bucket of the IP address is empty
+    let mut throttled_resp = None;
+    for _ in 0..10 {
+        let resp = context
+            .reply(warp::test::request().method("GET").path("/v1/-/healthy"))
+            .await;
+        if resp.status() == 429 {
+            throttled_resp = Some(resp);
+            break;
+        }
+        assert_eq!(resp.status(), 200);
+    }
+    let resp = throttled_resp.expect("requests should be rate limited");
+    let retry_after: u64 = resp.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after >= 1);
+    let err: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(err["error_code"], "rate_limited");
+
+    // Requests with a known API key have their own bucket
+    let resp = context
+        .reply(
+            warp::test::request()
+                .method("GET")
+                .path("/v1/-/healthy")
+                .header("x-api-key", API_KEY),
+        )
+        .await;
+    assert_eq!(resp.status(), 200);
+}
@@ -1,9 +1,13 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use super::new_test_context;
+use super::{new_test_context, new_test_context_with_config};
 use aptos_api_test_context::{current_function_name, TestContext};
+use aptos_api_types::HexEncodedBytes;
+use aptos_config::config::NodeConfig;
 use aptos_crypto::ed25519::Ed25519Signature;
+use aptos_framework::{BuildOptions, BuiltPackage};
+use aptos_sdk::types::LocalAccount;
 use aptos_types::transaction::{
     authenticator::TransactionAuthenticator, EntryFunction, TransactionPayload,
 };
@@ -100,6 +104,149 @@ async fn test_simulate_transaction_with_insufficient_balance() {
     assert!(!resp[0]["success"].as_bool().is_some_and(|v| v));
 }
 
+/// Simulates an APT transfer with a dummy signature, with the given balance of the sender (if
+/// any), and with the gas profile and state diff if `profile` is set
+async fn simulate_aptos_transfer_with_options(
+    context: &mut TestContext,
+    transfer_amount: u64,
+    balance_override: Option<u64>,
+    profile: bool,
+    expected_status: u16,
+) -> serde_json::Value {
+    let alice = &mut context.gen_account();
+    let bob = &mut context.gen_account();
+    let txn = context.mint_user_account(alice).await;
+    context.commit_block(&vec![txn]).await;
+
+    let txn = context.account_transfer_to(alice, bob.address(), transfer_amount);
+
+    if let TransactionAuthenticator::Ed25519 {
+        public_key,
+        signature: _,
+    } = txn.authenticator_ref()
+    {
+        let txn_request = json!({
+            "sender": txn.sender().to_string(),
+            "sequence_number": txn.sequence_number().to_string(),
+            "max_gas_amount": txn.max_gas_amount().to_string(),
+            "gas_unit_price": txn.gas_unit_price().to_string(),
+            "expiration_timestamp_secs": txn.expiration_timestamp_secs().to_string(),
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [
+                    bob.address().to_standard_string(), transfer_amount.to_string(),
+                ]
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": public_key.to_string(),
+                "signature": Ed25519Signature::dummy_signature().to_string(),
+            }
+        });
+        let context = context.expect_status_code(expected_status);
+        match balance_override {
+            Some(balance) => {
+                context
+                    .post(
+                        &format!("/transactions/simulate_with_overrides?profile={}", profile),
+                        json!({
+                            "transaction": txn_request,
+                            "state_overrides": {
+                                "balances": [{
+                                    "address": alice.address().to_string(),
+                                    "amount": balance.to_string(),
+                                }],
+                            },
+                        }),
+                    )
+                    .await
+            },
+            None => {
+                context
+                    .post(
+                        &format!("/transactions/simulate?profile={}", profile),
+                        txn_request,
+                    )
+                    .await
+            },
+        }
+    } else {
+        unreachable!("Simulation uses Ed25519 authenticator.");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_balance_override() {
+    let mut context = new_test_context(current_function_name!());
+    let resp = simulate_aptos_transfer_with_options(
+        &mut context,
+        LARGE_TRANSFER_AMOUNT,
+        Some(10 * LARGE_TRANSFER_AMOUNT),
+        false,
+        200,
+    )
+    .await;
+    assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_too_many_overrides() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.max_state_overrides = 0;
+    let mut context = new_test_context_with_config(current_function_name!(), node_config);
+    let resp = simulate_aptos_transfer_with_options(
+        &mut context,
+        SMALL_TRANSFER_AMOUNT,
+        Some(10 * LARGE_TRANSFER_AMOUNT),
+        false,
+        400,
+    )
+    .await;
+    assert_eq!(resp["error_code"], "invalid_input");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_profile() {
+    let mut context = new_test_context(current_function_name!());
+    let resp =
+        simulate_aptos_transfer_with_options(&mut context, SMALL_TRANSFER_AMOUNT, None, true, 200)
+            .await;
+    assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
+
+    let gas_profile = &resp[0]["gas_profile"];
+    assert_eq!(
+        gas_profile["call_graph"]["name"].as_str(),
+        Some("0x1::aptos_account::transfer")
+    );
+    assert!(!gas_profile["call_graph"]["calls"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    // The balance of the sender is modified, and the account of the receiver is created
+    let state_diff = resp[0]["state_diff"].as_array().unwrap();
+    assert!(state_diff
+        .iter()
+        .any(|diff| diff["change_type"] == "modified"
+            && !diff["before"].as_array().unwrap().is_empty()));
+    assert!(state_diff
+        .iter()
+        .any(|diff| diff["change_type"] == "created"
+            && diff["before"].as_array().unwrap().is_empty()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_without_profile() {
+    let mut context = new_test_context(current_function_name!());
+    let resp =
+        simulate_aptos_transfer_with_options(&mut context, SMALL_TRANSFER_AMOUNT, None, false, 200)
+            .await;
+    assert!(resp[0].get("gas_profile").is_none());
+    assert!(resp[0].get("state_diff").is_none());
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_simulate_txn_with_aggregator() {
     let mut context = new_test_context(current_function_name!());
@@ -153,3 +300,110 @@ async fn test_simulate_txn_with_aggregator() {
         unreachable!("Simulation uses Ed25519 authenticator.");
     }
 }
+
+/// Publishes the counter package under the root account, and returns a transaction calling
+/// `increment_counter` to simulate, with a dummy signature
+async fn publish_counter_package(context: &mut TestContext) -> (LocalAccount, serde_json::Value) {
+    let account = context.root_account().await;
+
+    let named_addresses = vec![("addr".to_string(), account.address())];
+    let path = PathBuf::from(std::env!("CARGO_MANIFEST_DIR")).join("src/tests/move/pack_counter");
+    let payload = TestContext::build_package(path, named_addresses);
+    let txn = account.sign_with_transaction_builder(context.transaction_factory().payload(payload));
+    context.commit_block(&vec![txn]).await;
+
+    let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(account.address(), ident_str!("counter").to_owned()),
+        ident_str!("increment_counter").to_owned(),
+        vec![],
+        vec![],
+    ));
+    let txn = account.sign_with_transaction_builder(context.transaction_factory().payload(payload));
+    if let TransactionAuthenticator::Ed25519 {
+        public_key,
+        signature: _,
+    } = txn.authenticator_ref()
+    {
+        let function = format!("{}::counter::increment_counter", account.address());
+        let txn_request = json!({
+            "sender": txn.sender().to_string(),
+            "sequence_number": txn.sequence_number().to_string(),
+            "max_gas_amount": txn.max_gas_amount().to_string(),
+            "gas_unit_price": txn.gas_unit_price().to_string(),
+            "expiration_timestamp_secs": txn.expiration_timestamp_secs().to_string(),
+            "payload": {
+                "type": "entry_function_payload",
+                "function": function,
+                "type_arguments": [],
+                "arguments": []
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": public_key.to_string(),
+                "signature": Ed25519Signature::dummy_signature().to_string(),
+            }
+        });
+        (account, txn_request)
+    } else {
+        unreachable!("Simulation uses Ed25519 authenticator.");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_module_override() {
+    let mut context = new_test_context(current_function_name!());
+    let (account, txn_request) = publish_counter_package(&mut context).await;
+
+    // The overridden counter module aborts in all of its functions
+    let mut build_options = BuildOptions::default();
+    build_options
+        .named_addresses
+        .insert("addr".to_string(), account.address());
+    let path =
+        PathBuf::from(std::env!("CARGO_MANIFEST_DIR")).join("src/tests/move/pack_counter_override");
+    let code = BuiltPackage::build(path, build_options)
+        .unwrap()
+        .extract_code();
+    let module_overrides: Vec<_> = code
+        .iter()
+        .map(|bytecode| json!({ "bytecode": HexEncodedBytes(bytecode.clone()).to_string() }))
+        .collect();
+
+    let view_request = json!({
+        "function": format!("{}::counter::add_and_get_counter_value", account.address()),
+        "type_arguments": [],
+        "arguments": [],
+    });
+
+    // Load the published module into the VMs shared by the node
+    let resp = context
+        .expect_status_code(200)
+        .post("/transactions/simulate", txn_request.clone())
+        .await;
+    assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
+
+    // The overridden module is used, even though the published module was loaded before
+    let resp = context
+        .expect_status_code(200)
+        .post(
+            "/transactions/simulate_with_overrides",
+            json!({
+                "transaction": txn_request.clone(),
+                "state_overrides": { "modules": module_overrides },
+            }),
+        )
+        .await;
+    assert!(!resp[0]["success"].as_bool().is_some_and(|v| v));
+
+    // The published module is still used by simulations and view functions without overrides
+    let resp = context
+        .expect_status_code(200)
+        .post("/transactions/simulate", txn_request)
+        .await;
+    assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
+    let resp = context
+        .expect_status_code(200)
+        .post("/view", view_request)
+        .await;
+    assert_eq!(resp, json!(["10"]));
+}
@@ -5,8 +5,10 @@
 use super::new_test_context;
 use crate::tests::{
     new_test_context_with_config, new_test_context_with_db_sharding_and_internal_indexer,
+    new_test_context_with_transaction_stream, StreamClient,
 };
 use aptos_api_test_context::{assert_json, current_function_name, pretty, TestContext};
+use aptos_api_types::{mime_types, MempoolAccountTransactions};
 use aptos_config::config::{GasEstimationStaticOverride, NodeConfig};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519Signature},
@@ -30,7 +32,7 @@ use move_core_types::{
 use poem_openapi::types::ParseFromJSON;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serde_json::json;
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::atomic::Ordering, time::Duration};
 use tokio::time::sleep;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -189,6 +191,113 @@ async fn test_post_transaction_rejected_by_mempool() {
     context.check_golden_output(resp);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cancel_transaction() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    let cancellation = create_cancellation(&context).await;
+
+    context
+        .expect_status_code(202)
+        .post_bcs_txn("/transactions", &bcs::to_bytes(&txn).unwrap())
+        .await;
+    let pending_txn = context
+        .expect_status_code(202)
+        .post_bcs_txn(
+            "/transactions/cancel",
+            &bcs::to_bytes(&cancellation).unwrap(),
+        )
+        .await;
+    assert_eq!(
+        pending_txn["hash"],
+        cancellation.committed_hash().to_hex_literal()
+    );
+
+    // The cancellation replaces the pending transaction in mempool
+    context
+        .expect_status_code(404)
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            txn.committed_hash().to_hex_literal()
+        ))
+        .await;
+    let resp = context
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            cancellation.committed_hash().to_hex_literal()
+        ))
+        .await;
+    assert_eq!(resp["type"], "pending_transaction");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cancel_transaction_rejected() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    let cancellation = create_cancellation(&context).await;
+
+    // Once the transaction is committed, its sequence number is used up
+    context
+        .expect_status_code(202)
+        .post_bcs_txn("/transactions", &bcs::to_bytes(&txn).unwrap())
+        .await;
+    context.commit_mempool_txns(1).await;
+
+    let resp = context
+        .expect_status_code(400)
+        .post_bcs_txn(
+            "/transactions/cancel",
+            &bcs::to_bytes(&cancellation).unwrap(),
+        )
+        .await;
+    assert_eq!(resp["error_code"], "vm_error");
+    context
+        .expect_status_code(404)
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            cancellation.committed_hash().to_hex_literal()
+        ))
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cancel_unknown_transaction() {
+    let mut context = new_test_context(current_function_name!());
+    let cancellation = create_cancellation(&context).await;
+
+    // Without a pending transaction to replace, the cancellation is submitted as is
+    let pending_txn = context
+        .expect_status_code(202)
+        .post_bcs_txn(
+            "/transactions/cancel",
+            &bcs::to_bytes(&cancellation).unwrap(),
+        )
+        .await;
+    let resp = context
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            cancellation.committed_hash().to_hex_literal()
+        ))
+        .await;
+    assert_eq!(resp["type"], "pending_transaction");
+    assert_eq!(resp["hash"], pending_txn["hash"]);
+}
+
+/// A transfer of 0 from the root account to itself, with a higher gas unit price than the other
+/// transactions of the root account, so that it can cancel them.
+async fn create_cancellation(context: &TestContext) -> SignedTransaction {
+    let root_account = context.root_account().await;
+    let factory = context.transaction_factory();
+    root_account.sign_with_transaction_builder(
+        factory
+            .account_transfer(root_account.address(), 0)
+            .gas_unit_price(factory.get_gas_unit_price() + 1)
+            .expiration_timestamp_secs(u64::MAX),
+    )
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_multi_agent_signed_transaction() {
     let mut context = new_test_context(current_function_name!());
@@ -573,6 +682,61 @@ async fn test_get_pending_transaction_by_hash() {
     context.check_golden_output(not_found);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_account_mempool_transactions() {
+    let mut context = new_test_context(current_function_name!());
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    let body = bcs::to_bytes(&txn).unwrap();
+    let pending_txn = context
+        .expect_status_code(202)
+        .post_bcs_txn("/transactions", body)
+        .await;
+
+    let resp = context
+        .get(&format!(
+            "/accounts/{}/mempool_transactions",
+            txn.sender().to_hex_literal()
+        ))
+        .await;
+    assert_eq!(
+        resp["account_sequence_number"],
+        pending_txn["sequence_number"]
+    );
+    assert_eq!(resp["sequence_number_gap"], json!(null));
+    let txns = resp["transactions"].as_array().unwrap();
+    assert_eq!(txns.len(), 1);
+    assert_eq!(txns[0]["hash"], pending_txn["hash"]);
+    assert_eq!(txns[0]["sequence_number"], pending_txn["sequence_number"]);
+    assert_eq!(txns[0]["state"], "ready");
+    assert_eq!(txns[0]["broadcast_state"], "ready");
+
+    // BCS returns the same transactions
+    let req = warp::test::request()
+        .method("GET")
+        .path(&context.prepend_path(&format!(
+            "/accounts/{}/mempool_transactions",
+            txn.sender().to_hex_literal()
+        )))
+        .header("Accept", mime_types::BCS);
+    let bcs_resp = context.reply(req).await;
+    assert_eq!(bcs_resp.status(), 200);
+    assert_eq!(
+        bcs::from_bytes::<MempoolAccountTransactions>(bcs_resp.body()).unwrap(),
+        serde_json::from_value::<MempoolAccountTransactions>(resp).unwrap()
+    );
+
+    // Accounts without pending transactions have nothing in mempool
+    let resp = context
+        .get(&format!(
+            "/accounts/{}/mempool_transactions",
+            account.address().to_hex_literal()
+        ))
+        .await;
+    assert_eq!(resp["account_sequence_number"], json!(null));
+    assert_eq!(resp["transactions"], json!([]));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_wait_transaction_by_hash() {
     let mut node_config = NodeConfig::default();
@@ -1690,6 +1854,154 @@ async fn test_simulation_filter_allow_sender() {
     context.check_golden_output(resp);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_submission_filter_deny_sender() {
+    let mut node_config = NodeConfig::default();
+
+    // Deny the root sender only.
+    let mut filter = node_config.api.transaction_submission_filter.clone();
+    filter = filter.add_deny_sender(aptos_test_root_address());
+    node_config.api.transaction_submission_filter = filter;
+
+    let mut context = new_test_context_with_config(current_function_name!(), node_config);
+
+    // Transactions from the root account should be rejected
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    let resp = context
+        .expect_status_code(403)
+        .post_bcs_txn("/transactions", bcs::to_bytes(&txn).unwrap())
+        .await;
+    assert_eq!(resp["error_code"], "rejected_by_filter");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_disabled() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.transaction_stream_enabled = false;
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    let resp = context
+        .expect_status_code(403)
+        .get("/transactions/stream")
+        .await;
+    assert_eq!(resp["error_code"], "api_disabled");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions() {
+    let mut context = new_test_context_with_transaction_stream(current_function_name!());
+    let mut stream = StreamClient::open(
+        &context,
+        &format!("/transactions/stream?sender={}", aptos_test_root_address()),
+    )
+    .await;
+
+    // Commit a transaction and verify it's pushed to the stream
+    let account = context.gen_account();
+    let txn = context.create_user_account(&account).await;
+    context.commit_block(&[txn.clone()]).await;
+    let streamed_txn = stream.next_item().await;
+    assert_eq!(
+        streamed_txn["hash"],
+        txn.committed_hash().to_hex_literal().as_str()
+    );
+
+    // Verify the streamed transaction matches the committed transaction
+    let committed_txn = context
+        .get(&format!(
+            "/transactions/by_version/{}",
+            streamed_txn["version"].as_str().unwrap()
+        ))
+        .await;
+    assert_eq!(streamed_txn, committed_txn);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_resume() {
+    let mut context = new_test_context_with_transaction_stream(current_function_name!());
+    let mut root_account = context.root_account().await;
+
+    // Commit two transactions in separate blocks
+    let account1 = context.gen_account();
+    let txn1 = context.create_user_account_by(&mut root_account, &account1);
+    context.commit_block(&[txn1.clone()]).await;
+    let account2 = context.gen_account();
+    let txn2 = context.create_user_account_by(&mut root_account, &account2);
+    context.commit_block(&[txn2.clone()]).await;
+    let txn1_version: u64 = context
+        .get(&format!(
+            "/transactions/by_hash/{}",
+            txn1.committed_hash().to_hex_literal()
+        ))
+        .await["version"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    // Verify a stream starting at the first transaction returns both transactions
+    let mut stream = StreamClient::open(
+        &context,
+        &format!(
+            "/transactions/stream?sender={}&start={}",
+            aptos_test_root_address(),
+            txn1_version
+        ),
+    )
+    .await;
+    assert_eq!(
+        stream.next_item().await["hash"],
+        txn1.committed_hash().to_hex_literal().as_str()
+    );
+    assert_eq!(
+        stream.next_item().await["hash"],
+        txn2.committed_hash().to_hex_literal().as_str()
+    );
+
+    // Verify a stream resumed after the first transaction only returns the second one
+    let mut stream = StreamClient::open(
+        &context,
+        &format!(
+            "/transactions/stream?sender={}&start={}",
+            aptos_test_root_address(),
+            txn1_version + 1
+        ),
+    )
+    .await;
+    assert_eq!(
+        stream.next_item().await["hash"],
+        txn2.committed_hash().to_hex_literal().as_str()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_stream_transactions_client_disconnect() {
+    let mut context = new_test_context_with_transaction_stream(current_function_name!());
+    let active_connections = context
+        .context
+        .transaction_stream_active_connections
+        .clone();
+
+    // Open a stream and wait for the next committed block
+    let mut stream = StreamClient::open(&context, "/transactions/stream").await;
+    context.commit_block(&[]).await;
+    stream.next_item().await;
+    assert_eq!(active_connections.load(Ordering::Relaxed), 1);
+
+    // Disconnect the client, and verify the server drops the stream once it
+    // tries to push new transactions to the client.
+    drop(stream);
+    for _ in 0..100 {
+        if active_connections.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        context.commit_block(&[]).await;
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The stream wasn't dropped after the client disconnected!");
+}
+
 fn gen_string(len: u64) -> String {
     let mut rng = thread_rng();
     std::iter::repeat(())
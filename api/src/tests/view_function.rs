@@ -252,3 +252,79 @@ async fn test_view_aggregator() {
         .await;
     context.check_golden_output_no_prune(resp);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch() {
+    let mut context = new_test_context(current_function_name!());
+    let creator = &mut context.gen_account();
+    let owner = &mut context.gen_account();
+    let txn1 = context.mint_user_account(creator).await;
+    let txn2 = context.account_transfer(creator, owner, 100_000);
+
+    context.commit_block(&vec![txn1, txn2]).await;
+
+    let resp = context
+        .post(
+            "/view/batch",
+            json!(vec![
+                build_coin_balance_request(&owner.address()),
+                build_coin_decimals_request(),
+                json!({
+                    "function":"0x1::aptos_account::assert_account_exists",
+                    "arguments": vec![owner.address().to_string()],
+                    "type_arguments": [],
+                }),
+            ]),
+        )
+        .await;
+
+    // A failing view function only fails its own result
+    assert_eq!(resp[0]["values"], json!(["100000"]));
+    assert!(resp[0]["error"].is_null());
+    assert_eq!(resp[1]["values"], json!([8]));
+    assert!(resp[1]["error"].is_null());
+    assert!(resp[2]["values"].is_null());
+    assert_eq!(resp[2]["error"]["error_code"], "invalid_input");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch_too_large() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.max_view_function_batch_size = 1;
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    let resp = context
+        .expect_status_code(400)
+        .post(
+            "/view/batch",
+            json!(vec![
+                build_coin_decimals_request(),
+                build_coin_decimals_request()
+            ]),
+        )
+        .await;
+    assert_eq!(resp["error_code"], "invalid_input");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_view_batch_too_large_bcs() {
+    let mut node_config = NodeConfig::default();
+    node_config.api.max_view_function_batch_size = 1;
+    let context = new_test_context_with_config(current_function_name!(), node_config);
+
+    // The batch is rejected based on its length prefix, before the
+    // (invalid) view functions are decoded.
+    let req = warp::test::request()
+        .method("POST")
+        .path("/v1/view/batch")
+        .header("Content-Type", "application/x.aptos.view_function+bcs")
+        .body(vec![2, 0xFF, 0xFF]);
+    let resp = context.reply(req).await;
+    assert_eq!(resp.status(), 400);
+    let resp: Value = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(resp["error_code"], "invalid_input");
+    assert!(resp["message"]
+        .as_str()
+        .unwrap()
+        .contains("Requested too many view functions"));
+}
@@ -0,0 +1,384 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for streaming committed transactions and events to clients as server-sent events.
+
+use crate::{
+    accept_type::AcceptType,
+    context::Context,
+    metrics::TRANSACTION_STREAM_GAUGE,
+    response::{api_disabled, version_pruned, BasicError, BasicErrorWith404},
+};
+use anyhow::Context as AnyhowContext;
+use aptos_api_types::{
+    Address, AptosErrorCode, AsConverter, LedgerInfo, MoveModuleId, MoveStructTag, Transaction,
+    TransactionOnChainData, VerifyInput, VerifyInputWithRecursion, VersionedEvent, U64,
+};
+use aptos_logger::debug;
+use aptos_types::{
+    account_address::AccountAddress,
+    contract_event::{ContractEvent, EventWithVersion},
+    transaction::{
+        MultisigTransactionPayload, Transaction as ChainTransaction, TransactionPayload,
+    },
+};
+use futures::{stream::BoxStream, StreamExt};
+use move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+use std::{
+    collections::VecDeque,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+/// Interval at which keep alive messages are sent on idle streams
+pub const STREAM_KEEP_ALIVE_SECS: u64 = 15;
+
+/// Filter applied to the committed transactions before they are streamed.
+/// A transaction must match all of the provided criteria.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionStreamFilter {
+    pub sender: Option<AccountAddress>,
+    pub event_type: Option<StructTag>,
+    pub module: Option<ModuleId>,
+}
+
+impl TransactionStreamFilter {
+    fn is_empty(&self) -> bool {
+        self.sender.is_none() && self.event_type.is_none() && self.module.is_none()
+    }
+
+    fn matches(&self, txn: &TransactionOnChainData) -> bool {
+        if let Some(sender) = &self.sender {
+            let matches_sender = matches!(
+                &txn.transaction,
+                ChainTransaction::UserTransaction(signed_txn) if signed_txn.sender() == *sender
+            );
+            if !matches_sender {
+                return false;
+            }
+        }
+
+        if self.event_type.is_some() && !txn.events.iter().any(|event| self.matches_event(event)) {
+            return false;
+        }
+
+        if let Some(module) = &self.module {
+            let called_module = match &txn.transaction {
+                ChainTransaction::UserTransaction(signed_txn) => match signed_txn.payload() {
+                    TransactionPayload::EntryFunction(entry_function) => {
+                        Some(entry_function.module())
+                    },
+                    TransactionPayload::Multisig(multisig) => multisig
+                        .transaction_payload
+                        .as_ref()
+                        .map(|payload| match payload {
+                            MultisigTransactionPayload::EntryFunction(entry_function) => {
+                                entry_function.module()
+                            },
+                        }),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if called_module != Some(module) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Returns true iff the event is of the type in the filter (if any)
+    fn matches_event(&self, event: &ContractEvent) -> bool {
+        match &self.event_type {
+            Some(event_type) => match event.type_tag() {
+                TypeTag::Struct(struct_tag) => struct_tag.as_ref() == event_type,
+                _ => false,
+            },
+            None => true,
+        }
+    }
+}
+
+/// Validates the parameters of a stream request and reserves one of the active stream
+/// slots in the context. Returns the stream filter and the version to start streaming at.
+/// The slot is released once the stream returned by [`transaction_stream`] or
+/// [`event_stream`] is dropped.
+pub fn start_stream(
+    context: &Context,
+    operation: &'static str,
+    start: Option<U64>,
+    sender: Option<Address>,
+    event_type: Option<MoveStructTag>,
+    module: Option<MoveModuleId>,
+) -> Result<(TransactionStreamFilter, u64), BasicErrorWith404> {
+    if !context.node_config.api.transaction_stream_enabled {
+        return Err(api_disabled(operation));
+    }
+    context.check_api_output_enabled(operation, &AcceptType::Json)?;
+
+    let event_type = event_type
+        .map(|event_type| {
+            event_type.verify(0)?;
+            StructTag::try_from(event_type)
+        })
+        .transpose()
+        .context("'event_type' invalid")
+        .map_err(|err| {
+            BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+        })?;
+    if let Some(module) = &module {
+        module.verify().context("'module' invalid").map_err(|err| {
+            BasicErrorWith404::bad_request_with_code_no_info(err, AptosErrorCode::InvalidInput)
+        })?;
+    }
+    let filter = TransactionStreamFilter {
+        sender: sender.map(Into::into),
+        event_type,
+        module: module.map(Into::into),
+    };
+
+    let ledger_info = context.get_latest_ledger_info()?;
+    let start_version = match start {
+        Some(start) => start.0,
+        None => ledger_info.version() + 1,
+    };
+    if start_version < ledger_info.oldest_version() {
+        return Err(version_pruned(start_version, &ledger_info));
+    }
+
+    // Reject the stream if there are too many active streams
+    if context
+        .transaction_stream_active_connections
+        .fetch_add(1, Ordering::Relaxed)
+        >= context
+            .node_config
+            .api
+            .transaction_stream_max_active_connections
+    {
+        context
+            .transaction_stream_active_connections
+            .fetch_sub(1, Ordering::Relaxed);
+        return Err(BasicErrorWith404::service_unavailable_with_code(
+            "Too many active streams, please try again later",
+            AptosErrorCode::RateLimited,
+            &ledger_info,
+        ));
+    }
+
+    Ok((filter, start_version))
+}
+
+/// Decrements the number of active transaction streams once the stream is dropped
+/// (i.e., when the client disconnects).
+struct ActiveStreamGuard {
+    context: Arc<Context>,
+}
+
+impl Drop for ActiveStreamGuard {
+    fn drop(&mut self) {
+        TRANSACTION_STREAM_GAUGE.dec();
+        self.context
+            .transaction_stream_active_connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Reads a page of committed versions starting at the given version, and returns the
+/// number of versions read along with the items rendered from them
+type FetchFn<T> = fn(&Context, &TransactionStreamFilter, u64) -> anyhow::Result<(u64, Vec<T>)>;
+
+struct StreamState<T> {
+    context: Arc<Context>,
+    filter: TransactionStreamFilter,
+    fetch: FetchFn<T>,
+    next_version: u64,
+    pending: VecDeque<T>,
+    _guard: ActiveStreamGuard,
+}
+
+impl<T: Send + 'static> StreamState<T> {
+    /// Fetches the next page of committed versions (if any) starting at `next_version`,
+    /// and appends the items that match the filter to the pending queue. Returns the number
+    /// of versions that were processed.
+    async fn fetch_next_page(&mut self) -> anyhow::Result<u64> {
+        let context = self.context.clone();
+        let filter = self.filter.clone();
+        let fetch = self.fetch;
+        let start_version = self.next_version;
+        let (num_versions, items) =
+            tokio::task::spawn_blocking(move || fetch(&context, &filter, start_version)).await??;
+
+        self.next_version += num_versions;
+        self.pending.extend(items);
+        Ok(num_versions)
+    }
+}
+
+/// Reads a page of committed transactions starting at `start_version` (if any)
+fn read_transactions(
+    context: &Context,
+    start_version: u64,
+) -> anyhow::Result<Option<(LedgerInfo, Vec<TransactionOnChainData>)>> {
+    let ledger_info = context.get_latest_ledger_info_wrapped()?;
+    let ledger_version = ledger_info.version();
+    if start_version > ledger_version {
+        return Ok(None);
+    }
+
+    let limit = std::cmp::min(
+        context.max_transactions_page_size() as u64,
+        ledger_version - start_version + 1,
+    ) as u16;
+    let data = context
+        .get_transactions(start_version, limit, ledger_version)
+        .context("Failed to read raw transactions from storage")?;
+    Ok(Some((ledger_info, data)))
+}
+
+/// Reads a page of committed transactions starting at `start_version`, and returns the
+/// number of versions read along with the rendered transactions that match the filter.
+fn fetch_transactions(
+    context: &Context,
+    filter: &TransactionStreamFilter,
+    start_version: u64,
+) -> anyhow::Result<(u64, Vec<Transaction>)> {
+    let (ledger_info, data) = match read_transactions(context, start_version)? {
+        Some(page) => page,
+        None => return Ok((0, vec![])),
+    };
+    let num_versions = data.len() as u64;
+
+    let mut timestamp = context
+        .get_block_timestamp::<BasicError>(&ledger_info, start_version)
+        .map_err(anyhow::Error::from)?;
+    let state_view = context
+        .latest_state_view_poem::<BasicError>(&ledger_info)
+        .map_err(anyhow::Error::from)?;
+    let converter = state_view.as_converter(context.db.clone(), context.indexer_reader.clone());
+
+    let mut txns = vec![];
+    for txn in data {
+        // Update the timestamp if the next block occurs
+        if let Some(block_metadata) = txn.transaction.try_as_block_metadata_ext() {
+            timestamp = block_metadata.timestamp_usecs();
+        } else if let Some(block_metadata) = txn.transaction.try_as_block_metadata() {
+            timestamp = block_metadata.timestamp_usecs();
+        }
+        if filter.is_empty() || filter.matches(&txn) {
+            txns.push(
+                converter
+                    .try_into_onchain_transaction(timestamp, txn)
+                    .context("Failed to convert transaction data from storage")?,
+            );
+        }
+    }
+
+    Ok((num_versions, txns))
+}
+
+/// Reads a page of committed transactions starting at `start_version`, and returns the
+/// number of versions read along with the rendered events of the transactions that match
+/// the filter. If the filter has an event type, only the events of that type are returned.
+fn fetch_events(
+    context: &Context,
+    filter: &TransactionStreamFilter,
+    start_version: u64,
+) -> anyhow::Result<(u64, Vec<VersionedEvent>)> {
+    let (ledger_info, data) = match read_transactions(context, start_version)? {
+        Some(page) => page,
+        None => return Ok((0, vec![])),
+    };
+    let num_versions = data.len() as u64;
+
+    let state_view = context
+        .latest_state_view_poem::<BasicError>(&ledger_info)
+        .map_err(anyhow::Error::from)?;
+    let converter = state_view.as_converter(context.db.clone(), context.indexer_reader.clone());
+
+    let mut events = vec![];
+    for txn in data {
+        if !filter.is_empty() && !filter.matches(&txn) {
+            continue;
+        }
+        let txn_events: Vec<_> = txn
+            .events
+            .into_iter()
+            .filter(|event| filter.matches_event(event))
+            .map(|event| EventWithVersion::new(txn.version, event))
+            .collect();
+        events.extend(
+            converter
+                .try_into_versioned_events(&txn_events)
+                .context("Failed to convert events from storage")?,
+        );
+    }
+
+    Ok((num_versions, events))
+}
+
+/// Creates a stream of the committed transactions starting at `start_version` that match
+/// the given filter. The caller is expected to have already reserved a stream slot (see
+/// [`start_stream`]); it is released when the stream is dropped.
+pub fn transaction_stream(
+    context: Arc<Context>,
+    filter: TransactionStreamFilter,
+    start_version: u64,
+) -> BoxStream<'static, Transaction> {
+    stream_committed(context, filter, fetch_transactions, start_version)
+}
+
+/// Creates a stream of the events emitted by the committed transactions starting at
+/// `start_version` that match the given filter. The caller is expected to have already
+/// reserved a stream slot (see [`start_stream`]); it is released when the stream is dropped.
+pub fn event_stream(
+    context: Arc<Context>,
+    filter: TransactionStreamFilter,
+    start_version: u64,
+) -> BoxStream<'static, VersionedEvent> {
+    stream_committed(context, filter, fetch_events, start_version)
+}
+
+/// Creates a stream of the items fetched from the committed versions starting at
+/// `start_version`, polling the storage for new versions once it's caught up.
+fn stream_committed<T: Send + 'static>(
+    context: Arc<Context>,
+    filter: TransactionStreamFilter,
+    fetch: FetchFn<T>,
+    start_version: u64,
+) -> BoxStream<'static, T> {
+    TRANSACTION_STREAM_GAUGE.inc();
+    let poll_interval =
+        Duration::from_millis(context.node_config.api.transaction_stream_poll_interval_ms);
+    let state = StreamState {
+        context: context.clone(),
+        filter,
+        fetch,
+        next_version: start_version,
+        pending: VecDeque::new(),
+        _guard: ActiveStreamGuard { context },
+    };
+
+    futures::stream::unfold(state, move |mut state| async move {
+        loop {
+            if let Some(txn) = state.pending.pop_front() {
+                return Some((txn, state));
+            }
+
+            match state.fetch_next_page().await {
+                // There are no new transactions, wait before polling storage again
+                Ok(0) => tokio::time::sleep(poll_interval).await,
+                Ok(_) => {},
+                Err(error) => {
+                    // End the stream. Clients can resume from the last version they received.
+                    debug!(
+                        "Failed to fetch committed data for stream at version {}: {:?}",
+                        state.next_version, error
+                    );
+                    return None;
+                },
+            }
+        }
+    })
+    .boxed()
+}
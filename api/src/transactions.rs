@@ -8,6 +8,7 @@ use crate::{
     bcs_payload::Bcs,
     context::{api_spawn_blocking, Context, FunctionStats},
     failpoint::fail_point_poem,
+    field_selection::parse_field_selection,
     generate_error_response, generate_success_response, metrics,
     metrics::WAIT_TRANSACTION_GAUGE,
     page::Page,
@@ -17,21 +18,29 @@ use crate::{
         BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResult, BasicResultWith404,
         ForbiddenError, InsufficientStorageError, InternalError,
     },
+    simulation,
+    state_override::OverriddenStateView,
+    transaction_stream::{start_stream, transaction_stream, STREAM_KEEP_ALIVE_SECS},
     ApiTags,
 };
 use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
     verify_function_identifier, verify_module_identifier, Address, AptosError, AptosErrorCode,
     AsConverter, EncodeSubmissionRequest, GasEstimation, GasEstimationBcs, HashValue,
-    HexEncodedBytes, LedgerInfo, MoveType, PendingTransaction, SubmitTransactionRequest,
-    Transaction, TransactionData, TransactionOnChainData, TransactionsBatchSingleSubmissionFailure,
-    TransactionsBatchSubmissionResult, UserTransaction, VerifyInput, VerifyInputWithRecursion,
-    MAX_RECURSIVE_TYPES_ALLOWED, U64,
+    HexEncodedBytes, LedgerInfo, MempoolAccountTransactions, MempoolBroadcastState,
+    MempoolTransactionInfo, MempoolTransactionState, MoveModuleId, MoveStructTag, MoveType,
+    PendingTransaction, SequenceNumberGap, SimulateTransactionWithOverridesRequest,
+    SubmitTransactionRequest, Transaction, TransactionData, TransactionOnChainData,
+    TransactionsBatchSingleSubmissionFailure, TransactionsBatchSubmissionResult, UserTransaction,
+    VerifyInput, VerifyInputWithRecursion, MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
 use aptos_crypto::{hash::CryptoHash, signing_message};
+use aptos_gas_profiling::GasProfiler;
+use aptos_mempool::{AccountMempoolTransactions, BroadcastState, PendingTransactionState};
 use aptos_types::{
     account_address::AccountAddress,
     mempool_status::MempoolStatusCode,
+    state_store::StateView,
     transaction::{
         EntryFunction, ExecutionStatus, MultisigTransactionPayload, RawTransaction,
         RawTransactionWithData, SignedTransaction, TransactionPayload,
@@ -40,13 +49,17 @@ use aptos_types::{
     APTOS_COIN_TYPE,
 };
 use aptos_vm::{AptosSimulationVM, AptosVM};
+use futures::stream::BoxStream;
 use move_core_types::{ident_str, language_storage::ModuleId, vm_status::VMStatus};
 use poem_openapi::{
     param::{Path, Query},
-    payload::Json,
+    payload::{EventStream, Json},
     ApiRequest, OpenApi,
 };
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
 
 generate_success_response!(SubmitTransactionResponse, (202, Accepted));
 
@@ -163,10 +176,18 @@ impl TransactionsApi {
         ///
         /// If not provided, defaults to default page size
         limit: Query<Option<u16>>,
+        /// Comma separated list of the fields to include in each transaction, e.g.
+        /// `version,hash,success,payload.function` to list the entry functions called
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Only the selected
+        /// fields are serialized.
+        fields: Query<Option<String>>,
     ) -> BasicResultWith404<Vec<Transaction>> {
         fail_point_poem("endpoint_get_transactions")?;
         self.context
             .check_api_output_enabled("Get transactions", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
         let page = Page::new(
             start.0.map(|v| v.0),
             limit.0,
@@ -174,7 +195,9 @@ impl TransactionsApi {
         );
 
         let api = self.clone();
-        api_spawn_blocking(move || api.list(&accept_type, page)).await
+        api_spawn_blocking(move || api.list(&accept_type, page))
+            .await
+            .and_then(|response| response.with_field_selection(field_selection))
     }
 
     /// Get transaction by hash
@@ -202,13 +225,24 @@ impl TransactionsApi {
         accept_type: AcceptType,
         /// Hash of transaction to retrieve
         txn_hash: Path<HashValue>,
+        /// Comma separated list of the fields of the transaction to include, e.g.
+        /// `type,version,success,vm_status` to check whether a submitted transaction was
+        /// committed successfully
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Pending
+        /// transactions don't have the fields of committed ones (e.g., `version`). Only the
+        /// selected fields are serialized.
+        fields: Query<Option<String>>,
         // TODO: Use a new request type that can't return 507.
     ) -> BasicResultWith404<Transaction> {
         fail_point_poem("endpoint_transaction_by_hash")?;
         self.context
             .check_api_output_enabled("Get transactions by hash", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
         self.get_transaction_by_hash_inner(&accept_type, txn_hash.0)
             .await
+            .and_then(|response| response.with_field_selection(field_selection))
     }
 
     /// Wait for transaction by hash
@@ -278,6 +312,51 @@ impl TransactionsApi {
         result
     }
 
+    /// Stream committed transactions
+    ///
+    /// Streams committed transactions to the client as server-sent events, starting
+    /// at the given ledger version. Each event contains a single JSON encoded transaction,
+    /// and new transactions are pushed to the client as they are committed.
+    ///
+    /// The stream can optionally be filtered by the transaction sender, the type of
+    /// an emitted event, or the module of the called entry function. If multiple
+    /// filters are provided, a transaction must match all of them.
+    ///
+    /// If the stream ends (e.g., due to an error on the node), clients can resume it
+    /// from the version after the last transaction they received.
+    #[oai(
+        path = "/transactions/stream",
+        method = "get",
+        operation_id = "stream_transactions",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn stream_transactions(
+        &self,
+        /// Ledger version to start streaming transactions from
+        ///
+        /// If not provided, defaults to the next version to be committed
+        start: Query<Option<U64>>,
+        /// Only stream user transactions sent by this account
+        sender: Query<Option<Address>>,
+        /// Only stream transactions that emitted an event of this type e.g. `0x1::coin::DepositEvent`
+        event_type: Query<Option<MoveStructTag>>,
+        /// Only stream transactions calling an entry function in this module e.g. `0x1::coin`
+        module: Query<Option<MoveModuleId>>,
+    ) -> poem::Result<EventStream<BoxStream<'static, Transaction>>, BasicErrorWith404> {
+        fail_point_poem("endpoint_stream_transactions")?;
+        let (filter, start_version) = start_stream(
+            &self.context,
+            "Stream transactions",
+            start.0,
+            sender.0,
+            event_type.0,
+            module.0,
+        )?;
+
+        let stream = transaction_stream(self.context.clone(), filter, start_version);
+        Ok(EventStream::new(stream).keep_alive(Duration::from_secs(STREAM_KEEP_ALIVE_SECS)))
+    }
+
     /// Get transaction by version
     ///
     /// Retrieves a transaction by a given version. If the version has been
@@ -293,15 +372,24 @@ impl TransactionsApi {
         accept_type: AcceptType,
         /// Version of transaction to retrieve
         txn_version: Path<U64>,
+        /// Comma separated list of the fields of the transaction to include, e.g.
+        /// `hash,events` to only get the events emitted by the transaction
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Only the selected
+        /// fields are serialized.
+        fields: Query<Option<String>>,
     ) -> BasicResultWith404<Transaction> {
         fail_point_poem("endpoint_transaction_by_version")?;
         self.context
             .check_api_output_enabled("Get transactions by version", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
         let api = self.clone();
         api_spawn_blocking(move || {
             api.get_transaction_by_version_inner(&accept_type, txn_version.0)
         })
         .await
+        .and_then(|response| response.with_field_selection(field_selection))
     }
 
     /// Get account transactions
@@ -331,17 +419,54 @@ impl TransactionsApi {
         ///
         /// If not provided, defaults to default page size
         limit: Query<Option<u16>>,
+        /// Comma separated list of the fields to include in each transaction, e.g.
+        /// `sequence_number,version,gas_used` to track the gas spent by the account
+        ///
+        /// Nested fields are selected with dot separated paths, and selecting a field selects
+        /// all of its nested fields. If not provided, all fields are included. Only the selected
+        /// fields are serialized.
+        fields: Query<Option<String>>,
     ) -> BasicResultWith404<Vec<Transaction>> {
         fail_point_poem("endpoint_get_accounts_transactions")?;
         self.context
             .check_api_output_enabled("Get account transactions", &accept_type)?;
+        let field_selection = parse_field_selection(fields.0)?;
         let page = Page::new(
             start.0.map(|v| v.0),
             limit.0,
             self.context.max_transactions_page_size(),
         );
         let api = self.clone();
-        api_spawn_blocking(move || api.list_by_account(&accept_type, page, address.0)).await
+        api_spawn_blocking(move || api.list_by_account(&accept_type, page, address.0))
+            .await
+            .and_then(|response| response.with_field_selection(field_selection))
+    }
+
+    /// Get account mempool transactions
+    ///
+    /// Retrieves the transactions of an account that are pending in the mempool of
+    /// this node, along with their state. Ready transactions can be included in the
+    /// next block, while parked transactions are waiting for the transactions in
+    /// `sequence_number_gap` to be submitted.
+    ///
+    /// Mempool is local to each node, so other nodes may have a different view of the
+    /// pending transactions of the account.
+    #[oai(
+        path = "/accounts/:address/mempool_transactions",
+        method = "get",
+        operation_id = "get_account_mempool_transactions",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn get_account_mempool_transactions(
+        &self,
+        accept_type: AcceptType,
+        /// Address of account with or without a `0x` prefix
+        address: Path<Address>,
+    ) -> BasicResult<MempoolAccountTransactions> {
+        fail_point_poem("endpoint_get_account_mempool_transactions")?;
+        self.context
+            .check_api_output_enabled("Get account mempool transactions", &accept_type)?;
+        self.list_mempool_by_account(&accept_type, address.0).await
     }
 
     /// Submit transaction
@@ -390,7 +515,51 @@ impl TransactionsApi {
             .check_api_output_enabled("Submit transaction", &accept_type)?;
         let ledger_info = self.context.get_latest_ledger_info()?;
         let signed_transaction = self.get_signed_transaction(&ledger_info, data)?;
-        self.create(&accept_type, &ledger_info, signed_transaction)
+        self.create(&accept_type, &ledger_info, signed_transaction, false)
+            .await
+    }
+
+    /// Cancel transaction
+    ///
+    /// Cancels a pending transaction, by replacing it in mempool with a no-op from the same
+    /// sender with the same sequence number. The no-op must be either a script with no code and
+    /// no arguments or a transfer of 0 coins to the sender (`0x1::aptos_account::transfer`), and
+    /// it must have a higher gas unit price than the pending transaction. Unlike a regular
+    /// submission, it may have a different max gas amount. Once the replacement is committed,
+    /// the pending transaction can no longer be committed, since its sequence number is used up.
+    ///
+    /// The cancellation is accepted in the same formats as /transactions. It is also sent to
+    /// the peers of this node, if enabled, so that they evict the pending transaction too.
+    /// Cancellation is best effort: if the pending transaction is already on its way into a
+    /// block, it may still be committed, in which case the replacement is discarded.
+    #[oai(
+        path = "/transactions/cancel",
+        method = "post",
+        operation_id = "cancel_transaction",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn cancel_transaction(
+        &self,
+        accept_type: AcceptType,
+        data: SubmitTransactionPost,
+    ) -> SubmitTransactionResult<PendingTransaction> {
+        data.verify()
+            .context("Submitted transaction invalid'")
+            .map_err(|err| {
+                SubmitTransactionError::bad_request_with_code_no_info(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                )
+            })?;
+        fail_point_poem("endpoint_cancel_transaction")?;
+        if !self.context.node_config.api.transaction_submission_enabled {
+            return Err(api_disabled("Cancel transaction"));
+        }
+        self.context
+            .check_api_output_enabled("Cancel transaction", &accept_type)?;
+        let ledger_info = self.context.get_latest_ledger_info()?;
+        let signed_transaction = self.get_signed_transaction(&ledger_info, data)?;
+        self.create(&accept_type, &ledger_info, signed_transaction, true)
             .await
     }
 
@@ -471,6 +640,10 @@ impl TransactionsApi {
     ///
     /// To use this endpoint with BCS, you must submit a SignedTransaction
     /// encoded as BCS. See SignedTransaction in types/src/transaction/mod.rs.
+    ///
+    /// If `profile` is set, the JSON response also includes a breakdown of the gas used per
+    /// call and instruction, and the changes made to the state along with their previous
+    /// values. Profiling is not supported for BCS responses.
     #[oai(
         path = "/transactions/simulate",
         method = "post",
@@ -489,6 +662,9 @@ impl TransactionsApi {
         /// If set to true, the transaction will use a higher price than the original
         /// estimate.
         estimate_prioritized_gas_unit_price: Query<Option<bool>>,
+        /// If set to true, the gas profile and the state diff of the transaction will be
+        /// included in the response
+        profile: Query<Option<bool>>,
         data: SubmitTransactionPost,
     ) -> SimulateTransactionResult<Vec<UserTransaction>> {
         data.verify()
@@ -511,21 +687,7 @@ impl TransactionsApi {
         api_spawn_blocking(move || {
             let ledger_info = context.get_latest_ledger_info()?;
             let mut signed_transaction = api.get_signed_transaction(&ledger_info, data)?;
-
-            // Confirm the simulation filter allows the transaction. We use HashValue::zero()
-            // here for the block ID because we don't allow filtering by block ID for the
-            // simulation filters. See the ConfigSanitizer for ApiConfig.
-            if !context.node_config.api.simulation_filter.allows(
-                aptos_crypto::HashValue::zero(),
-                ledger_info.timestamp(),
-                &signed_transaction,
-            ) {
-                return Err(SubmitTransactionError::forbidden_with_code(
-                    "Transaction not allowed by simulation filter",
-                    AptosErrorCode::InvalidInput,
-                    &ledger_info,
-                ));
-            }
+            api.check_simulation_filter(&ledger_info, &signed_transaction)?;
 
             let estimated_gas_unit_price = match (
                 estimate_gas_unit_price.0.unwrap_or_default(),
@@ -617,7 +779,109 @@ impl TransactionsApi {
                 );
             }
 
-            api.simulate(&accept_type, ledger_info, signed_transaction)
+            api.simulate(
+                &accept_type,
+                ledger_info,
+                signed_transaction,
+                profile.0.unwrap_or_default(),
+            )
+        })
+        .await
+    }
+
+    /// Simulate transaction with state overrides
+    ///
+    /// Same as /transactions/simulate, but the transaction is simulated on top of the given
+    /// state overrides rather than the current ledger state alone. The overrides can replace
+    /// resource values, publish modules, set the APT balance and sequence number of accounts,
+    /// and set the block timestamp. This can be used to test module upgrades and other
+    /// "what if" scenarios without deploying them to a network.
+    ///
+    /// The state overrides are only used for the simulation, and are never persisted. The
+    /// number of overrides and the total size of the resources and modules are limited by the
+    /// node, and requests above the limits are rejected.
+    #[oai(
+        path = "/transactions/simulate_with_overrides",
+        method = "post",
+        operation_id = "simulate_transaction_with_overrides",
+        tag = "ApiTags::Transactions"
+    )]
+    async fn simulate_transaction_with_overrides(
+        &self,
+        accept_type: AcceptType,
+        /// If set to true, the gas profile and the state diff of the transaction will be
+        /// included in the response
+        profile: Query<Option<bool>>,
+        data: Json<SimulateTransactionWithOverridesRequest>,
+    ) -> SimulateTransactionResult<Vec<UserTransaction>> {
+        data.0
+            .verify()
+            .context("Simulated transaction invalid")
+            .map_err(|err| {
+                SubmitTransactionError::bad_request_with_code_no_info(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                )
+            })?;
+        fail_point_poem("endpoint_simulate_transaction_with_overrides")?;
+        if !self.context.node_config.api.transaction_simulation_enabled {
+            return Err(api_disabled("Simulate transaction"));
+        }
+        self.context
+            .check_api_output_enabled("Simulate transaction", &accept_type)?;
+
+        let api = self.clone();
+        let context = self.context.clone();
+        api_spawn_blocking(move || {
+            let ledger_info = context.get_latest_ledger_info()?;
+            let SimulateTransactionWithOverridesRequest {
+                transaction,
+                state_overrides,
+            } = data.0;
+
+            let state_view = context.latest_state_view_poem(&ledger_info)?;
+            let api_config = &context.node_config.api;
+            let state_view = OverriddenStateView::new(
+                &state_view,
+                state_overrides,
+                api_config.max_state_overrides,
+                api_config.max_state_override_bytes,
+            )
+            .context("Invalid state overrides")
+            .map_err(|err| {
+                SubmitTransactionError::bad_request_with_code(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                    &ledger_info,
+                )
+            })?;
+
+            // The transaction is converted with the overridden state, as it may call
+            // functions of the overridden modules
+            let signed_transaction = state_view
+                .as_converter(context.db.clone(), context.indexer_reader.clone())
+                .try_into_signed_transaction_poem(transaction, context.chain_id())
+                .context("Failed to create SignedTransaction from SubmitTransactionRequest")
+                .map_err(|err| {
+                    SubmitTransactionError::bad_request_with_code(
+                        err,
+                        AptosErrorCode::InvalidInput,
+                        &ledger_info,
+                    )
+                })?;
+            api.check_simulation_filter(&ledger_info, &signed_transaction)?;
+
+            // The overridden modules must not be loaded into the VMs shared with block
+            // execution and other requests, so the simulation uses its own Move VM
+            let vm = AptosSimulationVM::new_with_uncached_move_vm(&state_view);
+            api.simulate_with_state_view(
+                &accept_type,
+                ledger_info,
+                signed_transaction,
+                &vm,
+                &state_view,
+                profile.0.unwrap_or_default(),
+            )
         })
         .await
     }
@@ -1011,6 +1275,38 @@ impl TransactionsApi {
         }
     }
 
+    /// List the transactions of an account that are in mempool
+    async fn list_mempool_by_account(
+        &self,
+        accept_type: &AcceptType,
+        address: Address,
+    ) -> BasicResult<MempoolAccountTransactions> {
+        let context = self.context.clone();
+        let ledger_info = api_spawn_blocking(move || context.get_latest_ledger_info()).await?;
+
+        let txns = self
+            .context
+            .get_pending_transactions_by_account(address.into())
+            .await
+            .context(format!(
+                "Failed to get mempool transactions of account {}",
+                address
+            ))
+            .map_err(|err| {
+                BasicError::internal_with_code(err, AptosErrorCode::InternalError, &ledger_info)
+            })?;
+
+        let txns = render_mempool_transactions(txns);
+        match accept_type {
+            AcceptType::Json => {
+                BasicResponse::try_from_json((txns, &ledger_info, BasicResponseStatus::Ok))
+            },
+            AcceptType::Bcs => {
+                BasicResponse::try_from_bcs((txns, &ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
+
     /// Parses a single signed transaction
     fn get_signed_transaction(
         &self,
@@ -1183,11 +1479,39 @@ impl TransactionsApi {
     }
 
     /// Submits a single transaction, and converts mempool codes to errors
-    async fn create_internal(&self, txn: SignedTransaction) -> Result<(), AptosError> {
-        let (mempool_status, vm_status_opt) = self
+    async fn create_internal(
+        &self,
+        ledger_info: &LedgerInfo,
+        txn: SignedTransaction,
+        // Whether the transaction cancels the pending transaction with the same sequence number
+        cancellation: bool,
+    ) -> Result<(), AptosError> {
+        // Confirm the transaction submission filter allows the transaction. We use
+        // HashValue::zero() here for the block ID because we don't allow filtering by
+        // block ID for the submission filters. See the ConfigSanitizer for ApiConfig.
+        if !self
             .context
-            .submit_transaction(txn)
-            .await
+            .node_config
+            .api
+            .transaction_submission_filter
+            .allows(
+                aptos_crypto::HashValue::zero(),
+                ledger_info.timestamp(),
+                &txn,
+            )
+        {
+            return Err(AptosError::new_with_error_code(
+                "Transaction not allowed by transaction submission filter",
+                AptosErrorCode::RejectedByFilter,
+            ));
+        }
+
+        let submission_status = if cancellation {
+            self.context.cancel_transaction(txn).await
+        } else {
+            self.context.submit_transaction(txn).await
+        };
+        let (mempool_status, vm_status_opt) = submission_status
             .context("Mempool failed to initially evaluate submitted transaction")
             .map_err(|err| {
                 aptos_api_types::AptosError::new_with_error_code(err, AptosErrorCode::InternalError)
@@ -1227,6 +1551,10 @@ impl TransactionsApi {
                 mempool_status.message,
                 AptosErrorCode::InvalidTransactionUpdate,
             )),
+            MempoolStatusCode::RejectedByFilter => Err(AptosError::new_with_error_code(
+                mempool_status.message,
+                AptosErrorCode::RejectedByFilter,
+            )),
             MempoolStatusCode::UnknownStatus => Err(AptosError::new_with_error_code(
                 format!("Transaction was rejected with status {}", mempool_status,),
                 AptosErrorCode::InternalError,
@@ -1240,8 +1568,12 @@ impl TransactionsApi {
         accept_type: &AcceptType,
         ledger_info: &LedgerInfo,
         txn: SignedTransaction,
+        cancellation: bool,
     ) -> SubmitTransactionResult<PendingTransaction> {
-        match self.create_internal(txn.clone()).await {
+        match self
+            .create_internal(ledger_info, txn.clone(), cancellation)
+            .await
+        {
             Ok(()) => match accept_type {
                 AcceptType::Json => {
                     let state_view = self
@@ -1290,6 +1622,9 @@ impl TransactionsApi {
                 | AptosErrorCode::InvalidTransactionUpdate => Err(
                     SubmitTransactionError::bad_request_from_aptos_error(error, ledger_info),
                 ),
+                AptosErrorCode::RejectedByFilter => Err(
+                    SubmitTransactionError::forbidden_from_aptos_error(error, ledger_info),
+                ),
                 AptosErrorCode::MempoolIsFull => Err(
                     SubmitTransactionError::insufficient_storage_from_aptos_error(
                         error,
@@ -1314,7 +1649,7 @@ impl TransactionsApi {
         // Iterate through transactions keeping track of failures
         let mut txn_failures = Vec::new();
         for (idx, txn) in txns.iter().enumerate() {
-            if let Err(error) = self.create_internal(txn.clone()).await {
+            if let Err(error) = self.create_internal(ledger_info, txn.clone(), false).await {
                 txn_failures.push(TransactionsBatchSingleSubmissionFailure {
                     error,
                     transaction_index: idx,
@@ -1352,6 +1687,25 @@ impl TransactionsApi {
         accept_type: &AcceptType,
         ledger_info: LedgerInfo,
         txn: SignedTransaction,
+        profile: bool,
+    ) -> SimulateTransactionResult<Vec<UserTransaction>> {
+        let state_view = self.context.latest_state_view_poem(&ledger_info)?;
+        let vm = AptosSimulationVM::new(&state_view);
+        self.simulate_with_state_view(accept_type, ledger_info, txn, &vm, &state_view, profile)
+    }
+
+    /// Simulate a transaction with the given VM against the given state view
+    ///
+    /// If `profile` is set, the transaction is executed with the gas profiler, and the gas
+    /// profile and state diff are added to the JSON response.
+    fn simulate_with_state_view<S: StateView>(
+        &self,
+        accept_type: &AcceptType,
+        ledger_info: LedgerInfo,
+        txn: SignedTransaction,
+        vm: &AptosSimulationVM,
+        state_view: &S,
+        profile: bool,
     ) -> SimulateTransactionResult<Vec<UserTransaction>> {
         // The caller must ensure that the signature is not valid, as otherwise
         // a malicious actor could execute the transaction without their knowledge
@@ -1363,10 +1717,51 @@ impl TransactionsApi {
             ));
         }
 
+        if profile && accept_type == &AcceptType::Bcs {
+            return Err(SubmitTransactionError::bad_request_with_code(
+                "Profiling simulated transactions is only supported for JSON responses",
+                AptosErrorCode::InvalidInput,
+                &ledger_info,
+            ));
+        }
+
         // Simulate transaction
-        let state_view = self.context.latest_state_view_poem(&ledger_info)?;
-        let (vm_status, output) =
-            AptosSimulationVM::create_vm_and_simulate_signed_transaction(&txn, &state_view);
+        let (vm_status, output, gas_log) = if profile {
+            let entry_function = match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => Some(entry_function),
+                TransactionPayload::Multisig(multisig) => match &multisig.transaction_payload {
+                    Some(MultisigTransactionPayload::EntryFunction(entry_function)) => {
+                        Some(entry_function)
+                    },
+                    None => {
+                        return Err(SubmitTransactionError::bad_request_with_code(
+                            "Profiling multisig transactions requires the transaction payload",
+                            AptosErrorCode::InvalidInput,
+                            &ledger_info,
+                        ))
+                    },
+                },
+                TransactionPayload::Script(_) | TransactionPayload::ModuleBundle(_) => None,
+            };
+            let (vm_status, output, gas_profiler) = vm
+                .simulate_signed_transaction_with_modified_gas_meter(
+                    &txn,
+                    state_view,
+                    |gas_meter| match entry_function {
+                        Some(entry_function) => GasProfiler::new_function(
+                            gas_meter,
+                            entry_function.module().clone(),
+                            entry_function.function().to_owned(),
+                            entry_function.ty_args().to_vec(),
+                        ),
+                        None => GasProfiler::new_script(gas_meter),
+                    },
+                );
+            (vm_status, output, gas_profiler.map(GasProfiler::finish))
+        } else {
+            let (vm_status, output) = vm.simulate_signed_transaction(&txn, state_view);
+            (vm_status, output, None)
+        };
         let version = ledger_info.version();
 
         // Ensure that all known statuses return their values in the output (even if they aren't supposed to)
@@ -1426,9 +1821,32 @@ impl TransactionsApi {
 
         let result = match accept_type {
             AcceptType::Json => {
+                let mut gas_profile = gas_log.as_ref().map(simulation::gas_profile);
+                let mut state_diff = if profile {
+                    let converter = state_view
+                        .as_converter(self.context.db.clone(), self.context.indexer_reader.clone());
+                    let state_diff =
+                        simulation::state_diff(&converter, state_view, output.write_set())
+                            .context("Failed to render the state diff of the transaction")
+                            .map_err(|err| {
+                                SubmitTransactionError::internal_with_code(
+                                    err,
+                                    AptosErrorCode::InternalError,
+                                    &ledger_info,
+                                )
+                            })?;
+                    Some(state_diff)
+                } else {
+                    None
+                };
+
                 let transactions = self
                     .context
-                    .render_transactions_non_sequential(&ledger_info, vec![simulated_txn])?;
+                    .render_transactions_non_sequential_with_state_view(
+                        &ledger_info,
+                        state_view,
+                        vec![simulated_txn],
+                    )?;
 
                 // Users can only make requests to simulate UserTransactions, so unpack
                 // the Vec<Transaction> into Vec<UserTransaction>.
@@ -1449,6 +1867,8 @@ impl TransactionsApi {
                                 },
                                 _ => (),
                             }
+                            user_txn.gas_profile = gas_profile.take();
+                            user_txn.state_diff = state_diff.take();
                             user_transactions.push(user_txn);
                         },
                         _ => {
@@ -1474,6 +1894,28 @@ impl TransactionsApi {
         result.map(|r| r.with_gas_used(Some(output.gas_used())))
     }
 
+    /// Confirm the simulation filter allows the transaction
+    fn check_simulation_filter(
+        &self,
+        ledger_info: &LedgerInfo,
+        txn: &SignedTransaction,
+    ) -> Result<(), SubmitTransactionError> {
+        // We use HashValue::zero() here for the block ID because we don't allow filtering by
+        // block ID for the simulation filters. See the ConfigSanitizer for ApiConfig.
+        if !self.context.node_config.api.simulation_filter.allows(
+            aptos_crypto::HashValue::zero(),
+            ledger_info.timestamp(),
+            txn,
+        ) {
+            return Err(SubmitTransactionError::forbidden_with_code(
+                "Transaction not allowed by simulation filter",
+                AptosErrorCode::InvalidInput,
+                ledger_info,
+            ));
+        }
+        Ok(())
+    }
+
     /// Encode message as BCS
     pub fn get_signing_message(
         &self,
@@ -1553,6 +1995,42 @@ fn override_gas_parameters(
     SignedTransaction::new_signed_transaction(raw_txn, signed_txn.authenticator())
 }
 
+fn render_mempool_transactions(txns: AccountMempoolTransactions) -> MempoolAccountTransactions {
+    MempoolAccountTransactions {
+        account_sequence_number: txns.account_sequence_number.map(U64::from),
+        sequence_number_gap: txns.sequence_number_gap.map(|gap| SequenceNumberGap {
+            start: gap.start.into(),
+            end: gap.end.into(),
+        }),
+        transactions: txns
+            .transactions
+            .into_iter()
+            .map(|txn| MempoolTransactionInfo {
+                hash: txn.hash.into(),
+                sequence_number: txn.sequence_number.into(),
+                gas_unit_price: txn.gas_unit_price.into(),
+                ranking_score: txn.ranking_score.into(),
+                insertion_timestamp_usecs: (txn
+                    .insertion_time
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_micros() as u64)
+                    .into(),
+                expiration_timestamp_secs: txn.expiration_timestamp_secs.into(),
+                state: match txn.state {
+                    PendingTransactionState::Ready => MempoolTransactionState::Ready,
+                    PendingTransactionState::Parked => MempoolTransactionState::Parked,
+                },
+                broadcast_state: match txn.broadcast_state {
+                    BroadcastState::Ready => MempoolBroadcastState::Ready,
+                    BroadcastState::NotReady => MempoolBroadcastState::NotReady,
+                    BroadcastState::NonQualified => MempoolBroadcastState::NonQualified,
+                },
+            })
+            .collect(),
+    }
+}
+
 enum GetByVersionResponse {
     VersionTooNew,
     VersionTooOld,
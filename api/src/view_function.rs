@@ -12,12 +12,13 @@ use crate::{
     },
     ApiTags, Context,
 };
-use anyhow::Context as anyhowContext;
+use anyhow::{bail, Context as anyhowContext};
 use aptos_api_types::{
-    AptosErrorCode, AsConverter, MoveValue, ViewFunction, ViewRequest, MAX_RECURSIVE_TYPES_ALLOWED,
-    U64,
+    AptosError, AptosErrorCode, AsConverter, MoveValue, ViewFunction, ViewFunctionBcsResult,
+    ViewFunctionResult, ViewRequest, MAX_RECURSIVE_TYPES_ALLOWED, U64,
 };
-use aptos_bcs_utils::serialize_uleb128;
+use aptos_bcs_utils::{deserialize_uleb128, serialize_uleb128};
+use aptos_storage_interface::state_view::DbStateView;
 use aptos_vm::AptosVM;
 use itertools::Itertools;
 use move_core_types::language_storage::TypeTag;
@@ -39,6 +40,15 @@ pub enum ViewFunctionRequest {
     Bcs(Bcs),
 }
 
+#[derive(ApiRequest, Debug)]
+pub enum ViewFunctionBatchRequest {
+    #[oai(content_type = "application/json")]
+    Json(Json<Vec<ViewRequest>>),
+
+    #[oai(content_type = "application/x.aptos.view_function+bcs")]
+    Bcs(Bcs),
+}
+
 #[OpenApi]
 impl ViewFunctionApi {
     /// Execute view function of a module
@@ -71,6 +81,44 @@ impl ViewFunctionApi {
         api_spawn_blocking(move || view_request(context, accept_type, request, ledger_version))
             .await
     }
+
+    /// Execute a batch of view functions
+    ///
+    /// Execute multiple Move view functions against the same ledger version and return the
+    /// result of each of them, in the order they were provided. A view function that fails
+    /// does not fail the whole batch, instead its error is returned as its result.
+    ///
+    /// The total gas used by all view functions in the batch is limited to the gas limit of a
+    /// single view function. Once the limit is exhausted, the remaining view functions fail.
+    ///
+    /// The Aptos nodes prune account state history, via a configurable time window.
+    /// If the requested ledger version has been pruned, the server responds with a 410.
+    #[oai(
+        path = "/view/batch",
+        method = "post",
+        operation_id = "view_batch",
+        tag = "ApiTags::View"
+    )]
+    async fn view_function_batch(
+        &self,
+        accept_type: AcceptType,
+        /// List of view function requests with type and position arguments
+        request: ViewFunctionBatchRequest,
+        /// Ledger version to get state of account
+        ///
+        /// If not provided, it will be the latest version
+        ledger_version: Query<Option<U64>>,
+    ) -> BasicResultWith404<Vec<ViewFunctionResult>> {
+        fail_point_poem("endpoint_view_function_batch")?;
+        self.context
+            .check_api_output_enabled("View function batch", &accept_type)?;
+
+        let context = self.context.clone();
+        api_spawn_blocking(move || {
+            view_batch_request(context, accept_type, request, ledger_version)
+        })
+        .await
+    }
 }
 
 fn view_request(
@@ -118,19 +166,9 @@ fn view_request(
     };
 
     // Reject the request if it's not allowed by the filter.
-    if !context.node_config.api.view_filter.allows(
-        view_function.module.address(),
-        view_function.module.name().as_str(),
-        view_function.function.as_str(),
-    ) {
-        return Err(BasicErrorWith404::forbidden_with_code_no_info(
-            format!(
-                "Function {}::{} is not allowed",
-                view_function.module, view_function.function
-            ),
-            AptosErrorCode::InvalidInput,
-        ));
-    }
+    check_view_filter(&context, &view_function).map_err(|err| {
+        BasicErrorWith404::forbidden_with_code_no_info(err, AptosErrorCode::InvalidInput)
+    })?;
 
     let output = AptosVM::execute_view_function(
         &state_view,
@@ -166,31 +204,7 @@ fn view_request(
             BasicResponse::try_from_encoded((ret, &ledger_info, BasicResponseStatus::Ok))
         },
         AcceptType::Json => {
-            let return_types = state_view
-                .as_converter(context.db.clone(), context.indexer_reader.clone())
-                .function_return_types(&view_function)
-                .and_then(|tys| {
-                    tys.into_iter()
-                        .map(TypeTag::try_from)
-                        .collect::<anyhow::Result<Vec<_>>>()
-                })
-                .map_err(|err| {
-                    BasicErrorWith404::bad_request_with_code(
-                        err,
-                        AptosErrorCode::InternalError,
-                        &ledger_info,
-                    )
-                })?;
-
-            let move_vals = values
-                .into_iter()
-                .zip(return_types.into_iter())
-                .map(|(v, ty)| {
-                    state_view
-                        .as_converter(context.db.clone(), context.indexer_reader.clone())
-                        .try_into_move_value(&ty, &v)
-                })
-                .collect::<anyhow::Result<Vec<_>>>()
+            let move_vals = convert_return_values(&context, &state_view, &view_function, values)
                 .map_err(|err| {
                     BasicErrorWith404::bad_request_with_code(
                         err,
@@ -208,3 +222,236 @@ fn view_request(
     );
     result.map(|r| r.with_gas_used(Some(output.gas_used)))
 }
+
+fn view_batch_request(
+    context: Arc<Context>,
+    accept_type: AcceptType,
+    request: ViewFunctionBatchRequest,
+    ledger_version: Query<Option<U64>>,
+) -> BasicResultWith404<Vec<ViewFunctionResult>> {
+    // Retrieve the current state of the chain
+    let (ledger_info, requested_version) = context
+        .get_latest_ledger_info_and_verify_lookup_version(ledger_version.map(|inner| inner.0))?;
+
+    // Check the batch size before doing any work. For BCS, the size is the length
+    // prefix of the encoded vector, so the requests don't need to be decoded.
+    let batch_size = match &request {
+        ViewFunctionBatchRequest::Json(data) => data.0.len() as u64,
+        ViewFunctionBatchRequest::Bcs(data) => {
+            deserialize_uleb128(data.0.as_slice())
+                .context("Failed to deserialize the length of the list of ViewRequest")
+                .map_err(|err| {
+                    BasicErrorWith404::bad_request_with_code(
+                        err,
+                        AptosErrorCode::InvalidInput,
+                        &ledger_info,
+                    )
+                })?
+                .0
+        },
+    };
+    let max_batch_size = context.node_config.api.max_view_function_batch_size;
+    if batch_size > max_batch_size as u64 {
+        return Err(BasicErrorWith404::bad_request_with_code(
+            format!(
+                "Requested too many view functions: {}, while limit is {}",
+                batch_size, max_batch_size,
+            ),
+            AptosErrorCode::InvalidInput,
+            &ledger_info,
+        ));
+    }
+
+    let state_view = context
+        .state_view_at_version(requested_version)
+        .map_err(|err| {
+            BasicErrorWith404::bad_request_with_code(
+                err,
+                AptosErrorCode::InternalError,
+                &ledger_info,
+            )
+        })?;
+
+    // Requests that can't be converted only fail their own result, not the whole batch
+    let view_functions: Vec<Result<ViewFunction, AptosError>> = match request {
+        ViewFunctionBatchRequest::Json(data) => {
+            let converter =
+                state_view.as_converter(context.db.clone(), context.indexer_reader.clone());
+            data.0
+                .into_iter()
+                .map(|request| {
+                    converter.convert_view_function(request).map_err(|err| {
+                        AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput)
+                    })
+                })
+                .collect()
+        },
+        ViewFunctionBatchRequest::Bcs(data) => bcs::from_bytes_with_limit::<Vec<ViewFunction>>(
+            data.0.as_slice(),
+            MAX_RECURSIVE_TYPES_ALLOWED as usize,
+        )
+        .context("Failed to deserialize input into a list of ViewRequest")
+        .map_err(|err| {
+            BasicErrorWith404::bad_request_with_code(
+                err,
+                AptosErrorCode::InvalidInput,
+                &ledger_info,
+            )
+        })?
+        .into_iter()
+        .map(Ok)
+        .collect(),
+    };
+
+    // Execute the view functions in order, with the gas limit shared by the whole batch
+    let max_gas = context.node_config.api.max_gas_view_function;
+    let mut total_gas_used = 0;
+    let mut outputs = Vec::with_capacity(view_functions.len());
+    for view_function in view_functions {
+        let view_function = match view_function {
+            Ok(view_function) => view_function,
+            Err(err) => {
+                outputs.push((Err(err), 0));
+                continue;
+            },
+        };
+        if let Err(err) = check_view_filter(&context, &view_function) {
+            outputs.push((
+                Err(AptosError::new_with_error_code(
+                    err,
+                    AptosErrorCode::InvalidInput,
+                )),
+                0,
+            ));
+            continue;
+        }
+
+        let remaining_gas = max_gas.saturating_sub(total_gas_used);
+        if remaining_gas == 0 {
+            outputs.push((
+                Err(AptosError::new_with_error_code(
+                    "The gas limit of the view function batch has been exhausted",
+                    AptosErrorCode::InvalidInput,
+                )),
+                0,
+            ));
+            continue;
+        }
+
+        let output = AptosVM::execute_view_function(
+            &state_view,
+            view_function.module.clone(),
+            view_function.function.clone(),
+            view_function.ty_args.clone(),
+            view_function.args.clone(),
+            remaining_gas,
+        );
+        total_gas_used += output.gas_used;
+        context.view_function_stats().increment(
+            FunctionStats::function_to_key(&view_function.module, &view_function.function),
+            output.gas_used,
+        );
+
+        let result = output
+            .values
+            .map(|values| (view_function, values))
+            .map_err(|err| AptosError::new_with_error_code(err, AptosErrorCode::InvalidInput));
+        outputs.push((result, output.gas_used));
+    }
+
+    let result = match accept_type {
+        AcceptType::Bcs => {
+            let results: Vec<_> = outputs
+                .into_iter()
+                .map(|(result, gas_used)| match result {
+                    Ok((_, values)) => ViewFunctionBcsResult {
+                        values: Some(values),
+                        error: None,
+                        gas_used,
+                    },
+                    Err(err) => ViewFunctionBcsResult {
+                        values: None,
+                        error: Some(err),
+                        gas_used,
+                    },
+                })
+                .collect();
+            let bytes = bcs::to_bytes(&results).map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?;
+
+            BasicResponse::try_from_encoded((bytes, &ledger_info, BasicResponseStatus::Ok))
+        },
+        AcceptType::Json => {
+            let results: Vec<_> = outputs
+                .into_iter()
+                .map(|(result, gas_used)| {
+                    let result = result.and_then(|(view_function, values)| {
+                        convert_return_values(&context, &state_view, &view_function, values)
+                            .map_err(|err| {
+                                AptosError::new_with_error_code(err, AptosErrorCode::InternalError)
+                            })
+                    });
+                    match result {
+                        Ok(values) => ViewFunctionResult {
+                            values: Some(values),
+                            error: None,
+                            gas_used: gas_used.into(),
+                        },
+                        Err(err) => ViewFunctionResult {
+                            values: None,
+                            error: Some(err),
+                            gas_used: gas_used.into(),
+                        },
+                    }
+                })
+                .collect();
+
+            BasicResponse::try_from_json((results, &ledger_info, BasicResponseStatus::Ok))
+        },
+    };
+    result.map(|r| r.with_gas_used(Some(total_gas_used)))
+}
+
+/// Returns an error if the view function is not allowed by the view filter
+fn check_view_filter(context: &Context, view_function: &ViewFunction) -> anyhow::Result<()> {
+    if !context.node_config.api.view_filter.allows(
+        view_function.module.address(),
+        view_function.module.name().as_str(),
+        view_function.function.as_str(),
+    ) {
+        bail!(
+            "Function {}::{} is not allowed",
+            view_function.module,
+            view_function.function
+        );
+    }
+    Ok(())
+}
+
+/// Converts the BCS encoded return values of the view function into Move values
+fn convert_return_values(
+    context: &Context,
+    state_view: &DbStateView,
+    view_function: &ViewFunction,
+    values: Vec<Vec<u8>>,
+) -> anyhow::Result<Vec<MoveValue>> {
+    let converter = state_view.as_converter(context.db.clone(), context.indexer_reader.clone());
+    let return_types = converter
+        .function_return_types(view_function)
+        .and_then(|tys| {
+            tys.into_iter()
+                .map(TypeTag::try_from)
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+
+    values
+        .into_iter()
+        .zip(return_types.into_iter())
+        .map(|(v, ty)| converter.try_into_move_value(&ty, &v))
+        .collect()
+}
@@ -39,3 +39,12 @@ pub struct BcsBlock {
     /// The transactions in the block in sequential order
     pub transactions: Option<Vec<TransactionOnChainData>>,
 }
+
+/// The version of the last transaction committed before a timestamp
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct VersionByTimestamp {
+    /// Timestamp in microseconds
+    pub timestamp: U64,
+    /// Version of the last transaction committed before the timestamp
+    pub version: U64,
+}
@@ -96,6 +96,8 @@ pub enum AptosErrorCode {
     SequenceNumberTooOld = 402,
     /// The submitted transaction failed VM checks.
     VmError = 403,
+    /// The submitted transaction was rejected by the node's transaction filter.
+    RejectedByFilter = 404,
 
     /// Health check failed.
     HealthCheckFailed = 500,
@@ -110,6 +112,8 @@ pub enum AptosErrorCode {
     BcsNotSupported = 602,
     /// API Disabled
     ApiDisabled = 603,
+    /// Too many requests were made by the client, it has been rate limited
+    RateLimited = 604,
 }
 
 impl AptosErrorCode {
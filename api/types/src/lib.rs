@@ -15,9 +15,12 @@ mod hash;
 mod headers;
 mod index;
 mod ledger_info;
+mod mempool;
 pub mod mime_types;
 mod move_types;
+mod simulation;
 mod state;
+mod state_override;
 mod table;
 pub mod transaction;
 mod view;
@@ -25,7 +28,7 @@ mod wrappers;
 
 pub use account::AccountData;
 pub use address::Address;
-pub use block::{BcsBlock, Block};
+pub use block::{BcsBlock, Block, VersionByTimestamp};
 pub use bytecode::Bytecode;
 pub use convert::{new_vm_utf8_string, AsConverter, MoveConverter};
 pub use error::{AptosError, AptosErrorCode};
@@ -33,6 +36,10 @@ pub use hash::HashValue;
 pub use headers::*;
 pub use index::{IndexResponse, IndexResponseBcs};
 pub use ledger_info::LedgerInfo;
+pub use mempool::{
+    MempoolAccountTransactions, MempoolBroadcastState, MempoolTransactionInfo,
+    MempoolTransactionState, SequenceNumberGap,
+};
 pub use move_types::{
     verify_field_identifier, verify_function_identifier, verify_module_identifier, EntryFunctionId,
     HexEncodedBytes, MoveAbility, MoveFunction, MoveFunctionGenericTypeParam,
@@ -41,7 +48,15 @@ pub use move_types::{
     ResourceGroup, MAX_RECURSIVE_TYPES_ALLOWED, U128, U256, U64,
 };
 use serde::{Deserialize, Deserializer};
+pub use simulation::{
+    GasProfile, GasProfileDependency, GasProfileFrame, GasProfileInstruction, StateChangeType,
+    StateDiff,
+};
 pub use state::RawStateValueRequest;
+pub use state_override::{
+    BalanceOverride, ModuleOverride, ResourceOverride, SequenceNumberOverride,
+    SimulateTransactionWithOverridesRequest, StateOverrides,
+};
 use std::str::FromStr;
 pub use table::{RawTableItemRequest, TableItemRequest};
 pub use transaction::{
@@ -57,7 +72,7 @@ pub use transaction::{
     UserTransactionRequest, VersionedEvent, WriteModule, WriteResource, WriteSet, WriteSetChange,
     WriteSetPayload, WriteTableItem,
 };
-pub use view::{ViewFunction, ViewRequest};
+pub use view::{ViewFunction, ViewFunctionBcsResult, ViewFunctionResult, ViewRequest};
 pub use wrappers::{EventGuid, IdentifierWrapper, StateKeyWrapper};
 
 pub fn deserialize_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
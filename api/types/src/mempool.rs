@@ -0,0 +1,68 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{HashValue, U64};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+/// The transactions of an account that are pending in the mempool of the node
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct MempoolAccountTransactions {
+    /// Sequence number of the account as last seen by mempool
+    ///
+    /// This is only known if mempool has transactions for the account.
+    pub account_sequence_number: Option<U64>,
+    /// The first range of sequence numbers that mempool has no transactions for
+    ///
+    /// Parked transactions can't make progress until transactions filling this gap are
+    /// submitted.
+    pub sequence_number_gap: Option<SequenceNumberGap>,
+    /// Transactions of the account, ordered by sequence number
+    pub transactions: Vec<MempoolTransactionInfo>,
+}
+
+/// A range of sequence numbers, from `start` (inclusive) to `end` (exclusive)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct SequenceNumberGap {
+    pub start: U64,
+    pub end: U64,
+}
+
+/// A transaction in mempool, along with its state
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct MempoolTransactionInfo {
+    pub hash: HashValue,
+    pub sequence_number: U64,
+    pub gas_unit_price: U64,
+    /// Score used to order the transaction against transactions of other accounts
+    pub ranking_score: U64,
+    /// Time at which the transaction was inserted into mempool, in microseconds
+    pub insertion_timestamp_usecs: U64,
+    pub expiration_timestamp_secs: U64,
+    pub state: MempoolTransactionState,
+    pub broadcast_state: MempoolBroadcastState,
+}
+
+/// Whether a transaction in mempool can be included in a block
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum MempoolTransactionState {
+    /// The transaction can be included in the next block
+    Ready,
+    /// The transaction is waiting for transactions with lower sequence numbers
+    Parked,
+}
+
+/// Whether a transaction in mempool is broadcast to the peers of the node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum MempoolBroadcastState {
+    /// The transaction is being broadcast
+    Ready,
+    /// The transaction will be broadcast once it's ready
+    NotReady,
+    /// The transaction isn't broadcast by this node, e.g. because it came from a peer
+    NonQualified,
+}
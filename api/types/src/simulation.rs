@@ -0,0 +1,89 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{MoveModuleId, WriteSetChange, U64};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+/// Breakdown of the gas used by a simulated transaction
+///
+/// Execution and IO costs are in internal gas units, which can be converted to gas units
+/// by dividing them by the `gas_scaling_factor`. Storage fees are in octas.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct GasProfile {
+    /// Number of internal gas units per gas unit
+    pub gas_scaling_factor: U64,
+    /// Total execution and IO gas used by the transaction
+    pub total_execution_and_io_gas: U64,
+    /// Gas charged for the transaction regardless of its execution
+    pub intrinsic_gas: U64,
+    /// Gas charged for verifying keyless signatures
+    pub keyless_gas: U64,
+    /// Gas charged for loading the modules the transaction depends on
+    pub dependencies: Vec<GasProfileDependency>,
+    /// Gas used by the entry function or script, including the functions it calls
+    pub call_graph: GasProfileFrame,
+    /// IO gas charged for writing the transaction, its events and its write set
+    pub io_gas: U64,
+    /// Total storage fee charged for the transaction
+    pub total_storage_fee: U64,
+    /// Total storage fee refunded for deleted state items
+    pub total_storage_refund: U64,
+}
+
+/// Gas charged for loading a module
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct GasProfileDependency {
+    pub module: MoveModuleId,
+    /// Size of the module in bytes
+    pub size: U64,
+    pub gas: U64,
+}
+
+/// Gas used by a function call
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct GasProfileFrame {
+    /// Name of the function, or `<script>` for a script
+    pub name: String,
+    /// Gas used by the call, including its nested calls
+    pub total_gas: U64,
+    /// Gas used by the instructions executed directly by the call, aggregated by
+    /// instruction and sorted by gas used in descending order
+    pub instructions: Vec<GasProfileInstruction>,
+    /// Nested calls, in the order they were made
+    pub calls: Vec<GasProfileFrame>,
+}
+
+/// Gas used by all executions of an instruction within a call
+///
+/// Besides bytecode instructions, native function calls, resource loads and type
+/// creations are also reported as instructions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct GasProfileInstruction {
+    pub name: String,
+    /// Number of times the instruction was executed
+    pub count: U64,
+    pub gas: U64,
+}
+
+/// Type of change made to a state item
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+#[oai(rename_all = "snake_case")]
+pub enum StateChangeType {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// Change made to a state item by a simulated transaction, along with its previous value
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct StateDiff {
+    pub change_type: StateChangeType,
+    /// Value of the state item before the transaction, empty if the item was created
+    ///
+    /// A resource group is expanded into one change per resource in the group.
+    pub before: Vec<WriteSetChange>,
+    /// Value of the state item after the transaction
+    pub after: Vec<WriteSetChange>,
+}
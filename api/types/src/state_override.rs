@@ -0,0 +1,95 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    Address, HexEncodedBytes, MoveStructTag, SubmitTransactionRequest, VerifyInput,
+    VerifyInputWithRecursion, U64,
+};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+/// Overrides applied on top of the ledger state when simulating a transaction
+///
+/// Overrides are applied in the following order: resources, modules, balances,
+/// sequence numbers and then the block timestamp.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct StateOverrides {
+    /// Resources to set, replacing their current values
+    #[serde(default)]
+    #[oai(default)]
+    pub resources: Vec<ResourceOverride>,
+    /// Modules to publish, replacing the current modules with the same name
+    #[serde(default)]
+    #[oai(default)]
+    pub modules: Vec<ModuleOverride>,
+    /// APT balances to set on accounts
+    #[serde(default)]
+    #[oai(default)]
+    pub balances: Vec<BalanceOverride>,
+    /// Sequence numbers to set on accounts
+    #[serde(default)]
+    #[oai(default)]
+    pub sequence_numbers: Vec<SequenceNumberOverride>,
+    /// Block timestamp in microseconds to simulate the transaction at
+    pub timestamp_usecs: Option<U64>,
+}
+
+impl VerifyInput for StateOverrides {
+    fn verify(&self) -> anyhow::Result<()> {
+        for resource in self.resources.iter() {
+            resource.resource_type.verify(0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the value of a resource under an account
+///
+/// Resources that are members of a resource group can't be overridden individually.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct ResourceOverride {
+    pub address: Address,
+    pub resource_type: MoveStructTag,
+    /// BCS encoded value of the resource
+    pub data: HexEncodedBytes,
+}
+
+/// Publishes a module, replacing the current module with the same name (if any)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct ModuleOverride {
+    /// Compiled bytecode of the module
+    pub bytecode: HexEncodedBytes,
+}
+
+/// Sets the APT balance of an account
+///
+/// If the account has a primary fungible store for APT, the balance is set there and the
+/// coin store of the account (if any) is emptied. Otherwise, the account must already hold
+/// a coin store for APT.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct BalanceOverride {
+    pub address: Address,
+    /// Balance in octas
+    pub amount: U64,
+}
+
+/// Sets the sequence number of an account. The account must already exist.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct SequenceNumberOverride {
+    pub address: Address,
+    pub sequence_number: U64,
+}
+
+/// Request to simulate a transaction on top of state overrides
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct SimulateTransactionWithOverridesRequest {
+    pub transaction: SubmitTransactionRequest,
+    pub state_overrides: StateOverrides,
+}
+
+impl VerifyInput for SimulateTransactionWithOverridesRequest {
+    fn verify(&self) -> anyhow::Result<()> {
+        self.transaction.verify()?;
+        self.state_overrides.verify()
+    }
+}
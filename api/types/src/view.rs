@@ -1,7 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{EntryFunctionId, MoveType};
+use crate::{AptosError, EntryFunctionId, MoveType, MoveValue, U64};
 use aptos_types::serde_helper::vec_bytes;
 use move_core_types::{
     identifier::Identifier,
@@ -28,3 +28,25 @@ pub struct ViewFunction {
     #[serde(with = "vec_bytes")]
     pub args: Vec<Vec<u8>>,
 }
+
+/// Result of a single view function in a batch view request
+#[derive(Clone, Debug, Serialize, Deserialize, Object)]
+pub struct ViewFunctionResult {
+    /// Return values of the view function, set if the view function succeeded
+    pub values: Option<Vec<MoveValue>>,
+    /// Error returned by the view function, set if the view function failed
+    pub error: Option<AptosError>,
+    /// Gas used by the view function
+    pub gas_used: U64,
+}
+
+/// BCS representation of [`ViewFunctionResult`], where each return value is BCS encoded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ViewFunctionBcsResult {
+    /// BCS encoded return values of the view function, set if the view function succeeded
+    pub values: Option<Vec<Vec<u8>>>,
+    /// Error returned by the view function, set if the view function failed
+    pub error: Option<AptosError>,
+    /// Gas used by the view function
+    pub gas_used: u64,
+}
@@ -10,5 +10,8 @@ mod profiler;
 mod render;
 mod report;
 
-pub use log::{FrameName, TransactionGasLog};
+pub use log::{
+    CallFrame, Dependency, ExecutionAndIOCosts, ExecutionGasEvent, FrameName, StorageFees,
+    TransactionGasLog,
+};
 pub use profiler::GasProfiler;
@@ -235,6 +235,15 @@ impl AptosVM {
         env: Arc<Environment>,
         state_view: &impl StateView,
         inject_create_signer_for_gov_sim: bool,
+    ) -> Self {
+        Self::new_impl(env, state_view, inject_create_signer_for_gov_sim, true)
+    }
+
+    fn new_impl(
+        env: Arc<Environment>,
+        state_view: &impl StateView,
+        inject_create_signer_for_gov_sim: bool,
+        use_warm_vm_cache: bool,
     ) -> Self {
         let _timer = TIMER.timer_with(&["AptosVM::new"]);
 
@@ -242,12 +251,13 @@ impl AptosVM {
             get_gas_parameters(env.features(), state_view);
 
         let resolver = state_view.as_move_resolver();
-        let move_vm = MoveVmExt::new_with_extended_options(
+        let move_vm = MoveVmExt::new_impl(
             gas_feature_version,
             gas_params.as_ref(),
             env,
             None,
             inject_create_signer_for_gov_sim,
+            use_warm_vm_cache,
             &resolver,
         );
 
@@ -2686,29 +2696,111 @@ impl AptosSimulationVM {
         Self(vm)
     }
 
+    /// Same as [Self::new], but the Move VM is not taken from the warm VM cache. It must be
+    /// used when the state view overrides modules, so that the overridden modules are never
+    /// loaded into the Move VMs shared with block execution and other requests, and so that
+    /// modules cached by those VMs don't shadow the overrides.
+    pub fn new_with_uncached_move_vm(state_view: &impl StateView) -> Self {
+        let env = Arc::new(Environment::new(state_view));
+        let mut vm = AptosVM::new_impl(env, state_view, false, false);
+        vm.is_simulation = true;
+        Self(vm)
+    }
+
     /// Simulates a signed transaction (i.e., executes it without performing
     /// signature verification) on a newly created VM instance.
     /// *Precondition:* the transaction must **not** have a valid signature.
     pub fn create_vm_and_simulate_signed_transaction(
         transaction: &SignedTransaction,
         state_view: &impl StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        Self::new(state_view).simulate_signed_transaction(transaction, state_view)
+    }
+
+    /// Same as [Self::create_vm_and_simulate_signed_transaction], but allows the production
+    /// gas meter to be wrapped (e.g., by a gas profiler). The gas meter is returned along with
+    /// the output, unless the transaction was discarded before the gas meter was created.
+    /// *Precondition:* the transaction must **not** have a valid signature.
+    pub fn create_vm_and_simulate_signed_transaction_with_modified_gas_meter<G, F>(
+        transaction: &SignedTransaction,
+        state_view: &impl StateView,
+        modify_gas_meter: F,
+    ) -> (VMStatus, TransactionOutput, Option<G>)
+    where
+        F: FnOnce(ProdGasMeter) -> G,
+        G: AptosGasMeter,
+    {
+        Self::new(state_view).simulate_signed_transaction_with_modified_gas_meter(
+            transaction,
+            state_view,
+            modify_gas_meter,
+        )
+    }
+
+    /// Simulates a signed transaction (i.e., executes it without performing
+    /// signature verification) on this VM instance.
+    /// *Precondition:* the transaction must **not** have a valid signature.
+    pub fn simulate_signed_transaction(
+        &self,
+        transaction: &SignedTransaction,
+        state_view: &impl StateView,
     ) -> (VMStatus, TransactionOutput) {
         assert_err!(
             transaction.verify_signature(),
             "Simulated transaction should not have a valid signature"
         );
 
-        let vm = Self::new(state_view);
         let log_context = AdapterLogSchema::new(state_view.id(), 0);
 
         let resolver = state_view.as_move_resolver();
         let (vm_status, vm_output) =
-            vm.0.execute_user_transaction(&resolver, transaction, &log_context);
+            self.0
+                .execute_user_transaction(&resolver, transaction, &log_context);
         let txn_output = vm_output
             .try_materialize_into_transaction_output(&resolver)
             .expect("Materializing aggregator V1 deltas should never fail");
         (vm_status, txn_output)
     }
+
+    /// Same as [Self::simulate_signed_transaction], but allows the production gas meter to be
+    /// wrapped, see [Self::create_vm_and_simulate_signed_transaction_with_modified_gas_meter].
+    /// *Precondition:* the transaction must **not** have a valid signature.
+    pub fn simulate_signed_transaction_with_modified_gas_meter<G, F>(
+        &self,
+        transaction: &SignedTransaction,
+        state_view: &impl StateView,
+        modify_gas_meter: F,
+    ) -> (VMStatus, TransactionOutput, Option<G>)
+    where
+        F: FnOnce(ProdGasMeter) -> G,
+        G: AptosGasMeter,
+    {
+        assert_err!(
+            transaction.verify_signature(),
+            "Simulated transaction should not have a valid signature"
+        );
+
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+
+        let resolver = state_view.as_move_resolver();
+        let (vm_status, vm_output, gas_meter) =
+            match self.0.execute_user_transaction_with_modified_gas_meter(
+                &resolver,
+                transaction,
+                &log_context,
+                modify_gas_meter,
+            ) {
+                Ok((vm_status, vm_output, gas_meter)) => (vm_status, vm_output, Some(gas_meter)),
+                Err(vm_status) => {
+                    let vm_output = discarded_output(vm_status.status_code());
+                    (vm_status, vm_output, None)
+                },
+            };
+        let txn_output = vm_output
+            .try_materialize_into_transaction_output(&resolver)
+            .expect("Materializing aggregator V1 deltas should never fail");
+        (vm_status, txn_output, gas_meter)
+    }
 }
 
 fn create_account_if_does_not_exist(
@@ -10,6 +10,7 @@ use aptos_consensus::{
     },
     consensus_provider::start_consensus_observer,
     network_interface::ConsensusMsg,
+    transaction_filter::TransactionFilter,
 };
 use aptos_consensus_notifications::ConsensusNotifier;
 use aptos_dkg_runtime::{start_dkg_runtime, DKGMessage};
@@ -40,6 +41,7 @@ pub fn create_consensus_observer_runtime(
     consensus_observer_reconfig_subscription: Option<
         ReconfigNotificationListener<DbBackedOnChainConfig>,
     >,
+    transaction_filter: TransactionFilter,
 ) -> Option<Runtime> {
     if node_config
         .consensus_observer
@@ -59,6 +61,7 @@ pub fn create_consensus_observer_runtime(
             consensus_to_mempool_sender,
             db_rw,
             consensus_observer_reconfig_subscription,
+            transaction_filter,
         );
         Some(consensus_observer_runtime)
     } else {
@@ -98,6 +101,19 @@ pub fn create_consensus_publisher(
     }
 }
 
+/// Creates the consensus transaction filter and registers it with the admin
+/// service. The same (reloadable) filter is shared by consensus and the
+/// consensus observer, so updates through the admin service apply to both.
+pub fn create_consensus_transaction_filter(
+    node_config: &NodeConfig,
+    admin_service: &mut AdminService,
+) -> TransactionFilter {
+    let transaction_filter =
+        TransactionFilter::new(node_config.execution.transaction_filter.clone());
+    admin_service.set_consensus_transaction_filter(transaction_filter.clone());
+    transaction_filter
+}
+
 /// Creates and starts the consensus runtime (if enabled)
 pub fn create_consensus_runtime(
     node_config: &NodeConfig,
@@ -108,6 +124,7 @@ pub fn create_consensus_runtime(
     consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
     vtxn_pool: VTxnPoolState,
     consensus_publisher: Option<Arc<ConsensusPublisher>>,
+    transaction_filter: TransactionFilter,
     admin_service: &mut AdminService,
 ) -> Option<Runtime> {
     consensus_network_interfaces.map(|consensus_network_interfaces| {
@@ -120,6 +137,7 @@ pub fn create_consensus_runtime(
             consensus_to_mempool_sender.clone(),
             vtxn_pool,
             consensus_publisher.clone(),
+            transaction_filter,
         );
         admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
 
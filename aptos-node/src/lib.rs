@@ -689,7 +689,13 @@ pub fn setup_environment_and_start_node(
         indexer_runtime,
         indexer_grpc_runtime,
         internal_indexer_db_runtime,
-    ) = services::bootstrap_api_and_indexer(&node_config, db_rw.clone(), chain_id, indexer_db_opt)?;
+    ) = services::bootstrap_api_and_indexer(
+        &node_config,
+        db_rw.clone(),
+        chain_id,
+        indexer_db_opt,
+        &mut admin_service,
+    )?;
 
     // Create mempool and get the consensus to mempool sender
     let (mempool_runtime, consensus_to_mempool_sender) =
@@ -735,6 +741,10 @@ pub fn setup_environment_and_start_node(
     let (consensus_publisher_runtime, consensus_publisher) =
         consensus::create_consensus_publisher(&node_config, &consensus_observer_network_interfaces);
 
+    // Create the consensus transaction filter (shared by consensus and the observer)
+    let consensus_transaction_filter =
+        consensus::create_consensus_transaction_filter(&node_config, &mut admin_service);
+
     // Create the consensus runtime (if enabled)
     let consensus_runtime = consensus::create_consensus_runtime(
         &node_config,
@@ -745,6 +755,7 @@ pub fn setup_environment_and_start_node(
         consensus_to_mempool_sender.clone(),
         vtxn_pool,
         consensus_publisher.clone(),
+        consensus_transaction_filter.clone(),
         &mut admin_service,
     );
 
@@ -757,6 +768,7 @@ pub fn setup_environment_and_start_node(
         consensus_to_mempool_sender,
         db_rw,
         consensus_observer_reconfig_subscription,
+        consensus_transaction_filter,
     );
 
     Ok(AptosHandle {
@@ -3,6 +3,7 @@
 
 use crate::services::start_netbench_service;
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
+use aptos_compression::client::CompressionClient;
 use aptos_config::{
     config::{NetworkConfig, NodeConfig},
     network_id::NetworkId,
@@ -14,7 +15,7 @@ use aptos_consensus::{
 use aptos_dkg_runtime::DKGMessage;
 use aptos_event_notifications::EventSubscriptionService;
 use aptos_jwk_consensus::types::JWKConsensusMsg;
-use aptos_logger::debug;
+use aptos_logger::{debug, error};
 use aptos_mempool::network::MempoolSyncMsg;
 use aptos_network::{
     application::{
@@ -52,15 +53,38 @@ struct ApplicationNetworkHandle<T> {
     pub network_events: NetworkEvents<T>,
 }
 
-/// TODO: make this configurable (e.g., for compression)
+/// Returns the client and service protocols of an application that supports
+/// zstd. The service always supports the zstd protocols, but the client only
+/// prefers them (over the other protocols) if zstd compression is enabled.
+fn get_client_and_service_protocols(
+    use_zstd_compression: bool,
+    zstd_protocols: &[ProtocolId],
+    protocols: &[ProtocolId],
+) -> (Vec<ProtocolId>, Vec<ProtocolId>) {
+    let service_protocols = [zstd_protocols, protocols].concat();
+    let client_protocols = if use_zstd_compression {
+        service_protocols.clone()
+    } else {
+        protocols.to_vec()
+    };
+    (client_protocols, service_protocols)
+}
+
 /// Returns the network application config for the consensus client and service
 pub fn consensus_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
-    let direct_send_protocols: Vec<ProtocolId> =
-        aptos_consensus::network_interface::DIRECT_SEND.into();
-    let rpc_protocols: Vec<ProtocolId> = aptos_consensus::network_interface::RPC.into();
+    let (client_direct_send_protocols, direct_send_protocols) = get_client_and_service_protocols(
+        node_config.consensus.zstd_compression.enabled,
+        aptos_consensus::network_interface::ZSTD_DIRECT_SEND,
+        aptos_consensus::network_interface::DIRECT_SEND,
+    );
+    let (client_rpc_protocols, rpc_protocols) = get_client_and_service_protocols(
+        node_config.consensus.zstd_compression.enabled,
+        aptos_consensus::network_interface::ZSTD_RPC,
+        aptos_consensus::network_interface::RPC,
+    );
 
     let network_client_config =
-        NetworkClientConfig::new(direct_send_protocols.clone(), rpc_protocols.clone());
+        NetworkClientConfig::new(client_direct_send_protocols, client_rpc_protocols);
     let network_service_config = NetworkServiceConfig::new(
         direct_send_protocols,
         rpc_protocols,
@@ -107,11 +131,18 @@ pub fn jwk_consensus_network_configuration(node_config: &NodeConfig) -> NetworkA
 
 /// Returns the network application config for the mempool client and service
 pub fn mempool_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
-    let direct_send_protocols = vec![ProtocolId::MempoolDirectSend];
+    let (client_direct_send_protocols, direct_send_protocols) = get_client_and_service_protocols(
+        node_config.mempool.zstd_compression.enabled,
+        &[
+            ProtocolId::MempoolDirectSendZstdDictionary,
+            ProtocolId::MempoolDirectSendZstd,
+        ],
+        &[ProtocolId::MempoolDirectSend],
+    );
     let rpc_protocols = vec![]; // Mempool does not use RPC
 
     let network_client_config =
-        NetworkClientConfig::new(direct_send_protocols.clone(), rpc_protocols.clone());
+        NetworkClientConfig::new(client_direct_send_protocols, rpc_protocols.clone());
     let network_service_config = NetworkServiceConfig::new(
         direct_send_protocols,
         rpc_protocols,
@@ -146,14 +177,21 @@ pub fn peer_monitoring_network_configuration(node_config: &NodeConfig) -> Networ
 /// Returns the network application config for the storage service client and server
 pub fn storage_service_network_configuration(node_config: &NodeConfig) -> NetworkApplicationConfig {
     let direct_send_protocols = vec![]; // The storage service does not use direct send
-    let rpc_protocols = vec![ProtocolId::StorageServiceRpc];
+    let (client_rpc_protocols, rpc_protocols) = get_client_and_service_protocols(
+        node_config
+            .state_sync
+            .aptos_data_client
+            .use_zstd_compression,
+        &[ProtocolId::StorageServiceRpcZstd],
+        &[ProtocolId::StorageServiceRpc],
+    );
     let max_network_channel_size = node_config
         .state_sync
         .storage_service
         .max_network_channel_size as usize;
 
     let network_client_config =
-        NetworkClientConfig::new(direct_send_protocols.clone(), rpc_protocols.clone());
+        NetworkClientConfig::new(direct_send_protocols.clone(), client_rpc_protocols);
     let network_service_config = NetworkServiceConfig::new(
         direct_send_protocols,
         rpc_protocols,
@@ -236,6 +274,32 @@ fn extract_network_ids(node_config: &NodeConfig) -> Vec<NetworkId> {
         .collect()
 }
 
+/// Sets the zstd dictionaries configured for the network applications. The
+/// dictionaries are verified by the config sanitizer, but if one can no longer
+/// be loaded, the application falls back to zstd without a dictionary.
+fn set_zstd_dictionaries(node_config: &NodeConfig) {
+    let zstd_compression_configs = [
+        (
+            CompressionClient::Consensus,
+            &node_config.consensus.zstd_compression,
+        ),
+        (
+            CompressionClient::Mempool,
+            &node_config.mempool.zstd_compression,
+        ),
+    ];
+    for (client, zstd_compression_config) in zstd_compression_configs {
+        match zstd_compression_config.load_dictionary() {
+            Ok(Some(dictionary)) => aptos_compression::zstd::set_dictionary(client, dictionary),
+            Ok(None) => {},
+            Err(error) => error!(
+                "Failed to load the zstd dictionary of {:?}, using none! Error: {}",
+                client, error
+            ),
+        }
+    }
+}
+
 /// Creates the global peers and metadata struct
 pub fn create_peers_and_metadata(node_config: &NodeConfig) -> Arc<PeersAndMetadata> {
     let network_ids = extract_network_ids(node_config);
@@ -258,6 +322,9 @@ pub fn setup_networks_and_get_interfaces(
     ApplicationNetworkInterfaces<PeerMonitoringServiceMessage>,
     ApplicationNetworkInterfaces<StorageServiceMessage>,
 ) {
+    // Set the zstd dictionaries of the applications (if any)
+    set_zstd_dictionaries(node_config);
+
     // Gather all network configs
     let network_configs = extract_network_configs(node_config);
 
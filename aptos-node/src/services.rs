@@ -8,6 +8,7 @@ use aptos_config::config::NodeConfig;
 use aptos_consensus::{
     consensus_observer::publisher::ConsensusPublisher, network_interface::ConsensusMsg,
     persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
+    transaction_filter::TransactionFilter,
 };
 use aptos_consensus_notifications::ConsensusNotifier;
 use aptos_data_client::client::AptosDataClient;
@@ -45,6 +46,7 @@ pub fn bootstrap_api_and_indexer(
     db_rw: DbReaderWriter,
     chain_id: ChainId,
     internal_indexer_db: Option<InternalIndexerDB>,
+    admin_service: &mut AdminService,
 ) -> anyhow::Result<(
     Receiver<MempoolClientRequest>,
     Option<Runtime>,
@@ -56,6 +58,7 @@ pub fn bootstrap_api_and_indexer(
     // Create the mempool client and sender
     let (mempool_client_sender, mempool_client_receiver) =
         mpsc::channel(AC_SMP_CHANNEL_BUFFER_SIZE);
+    admin_service.set_mempool_client_sender(mempool_client_sender.clone());
 
     let (indexer_table_info_runtime, indexer_async_v2) = match bootstrap_indexer_table_info(
         node_config,
@@ -130,6 +133,7 @@ pub fn start_consensus_runtime(
     consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
     vtxn_pool: VTxnPoolState,
     consensus_publisher: Option<Arc<ConsensusPublisher>>,
+    transaction_filter: TransactionFilter,
 ) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
     let instant = Instant::now();
 
@@ -146,6 +150,7 @@ pub fn start_consensus_runtime(
         reconfig_subscription,
         vtxn_pool,
         consensus_publisher,
+        transaction_filter,
     );
     debug!("Consensus started in {} ms", instant.elapsed().as_millis());
 
@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use super::transaction_filter_type::{Filter, Matcher};
+use super::transaction_filter_type::Filter;
 use crate::{
     config::{
         config_sanitizer::ConfigSanitizer, gas_estimation_config::GasEstimationConfig,
@@ -65,6 +65,10 @@ pub struct ApiConfig {
     ///
     /// This limits the execution length of a view function to the given gas used.
     pub max_gas_view_function: u64,
+    /// Maximum number of view functions that can be executed with the batch view API.
+    ///
+    /// The total gas used by all view functions in a batch is limited by `max_gas_view_function`.
+    pub max_view_function_batch_size: usize,
     /// Optional: Maximum number of worker threads for the API.
     ///
     /// If not set, `runtime_worker_multiplier` will multiply times the number of CPU cores on the machine
@@ -79,8 +83,16 @@ pub struct ApiConfig {
     pub periodic_gas_estimation_ms: Option<u64>,
     /// Configuration to filter simulation requests.
     pub simulation_filter: Filter,
+    /// Configuration to filter transaction submission requests.
+    pub transaction_submission_filter: Filter,
     /// Configuration to filter view function requests.
     pub view_filter: ViewFilter,
+    /// Maximum number of resource, module, balance and sequence number overrides that a
+    /// transaction can be simulated with
+    pub max_state_overrides: usize,
+    /// Maximum total size in bytes of the resource and module overrides that a transaction
+    /// can be simulated with
+    pub max_state_override_bytes: usize,
     /// Periodically log stats for view function and simulate transaction usage
     pub periodic_function_stats_sec: Option<u64>,
     /// The time wait_by_hash will wait before returning 404.
@@ -89,6 +101,15 @@ pub struct ApiConfig {
     pub wait_by_hash_poll_interval_ms: u64,
     /// The number of active wait_by_hash requests that can be active at any given time.
     pub wait_by_hash_max_active_connections: usize,
+    /// Enables the committed transaction and event stream APIs
+    pub transaction_stream_enabled: bool,
+    /// The interval at which transaction and event streams poll the storage for new transactions.
+    /// Must be greater than 0.
+    pub transaction_stream_poll_interval_ms: u64,
+    /// The number of transaction and event streams that can be active at any given time.
+    pub transaction_stream_max_active_connections: usize,
+    /// Configuration for rate limiting requests per client
+    pub rate_limit: ApiRateLimitConfig,
 }
 
 const DEFAULT_ADDRESS: &str = "127.0.0.1";
@@ -98,6 +119,9 @@ pub const DEFAULT_MAX_SUBMIT_TRANSACTION_BATCH_SIZE: usize = 10;
 pub const DEFAULT_MAX_PAGE_SIZE: u16 = 100;
 const DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE: u16 = 9999;
 const DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE: u16 = 9999;
+const DEFAULT_MAX_VIEW_FUNCTION_BATCH_SIZE: usize = 100;
+const DEFAULT_MAX_STATE_OVERRIDES: usize = 100;
+const DEFAULT_MAX_STATE_OVERRIDE_BYTES: usize = 1024 * 1024; // 1 MB
 const DEFAULT_MAX_VIEW_GAS: u64 = 2_000_000; // We keep this value the same as the max number of gas allowed for one single transaction defined in aptos-gas.
 
 fn default_enabled() -> bool {
@@ -131,16 +155,24 @@ impl Default for ApiConfig {
             max_account_resources_page_size: DEFAULT_MAX_ACCOUNT_RESOURCES_PAGE_SIZE,
             max_account_modules_page_size: DEFAULT_MAX_ACCOUNT_MODULES_PAGE_SIZE,
             max_gas_view_function: DEFAULT_MAX_VIEW_GAS,
+            max_view_function_batch_size: DEFAULT_MAX_VIEW_FUNCTION_BATCH_SIZE,
             max_runtime_workers: None,
             runtime_worker_multiplier: 2,
             gas_estimation: GasEstimationConfig::default(),
             periodic_gas_estimation_ms: Some(30_000),
             simulation_filter: Filter::default(),
+            transaction_submission_filter: Filter::default(),
             view_filter: ViewFilter::default(),
+            max_state_overrides: DEFAULT_MAX_STATE_OVERRIDES,
+            max_state_override_bytes: DEFAULT_MAX_STATE_OVERRIDE_BYTES,
             periodic_function_stats_sec: Some(60),
             wait_by_hash_timeout_ms: 1_000,
             wait_by_hash_poll_interval_ms: 20,
             wait_by_hash_max_active_connections: 100,
+            transaction_stream_enabled: false,
+            transaction_stream_poll_interval_ms: 100,
+            transaction_stream_max_active_connections: 100,
+            rate_limit: ApiRateLimitConfig::default(),
         }
     }
 }
@@ -192,7 +224,7 @@ impl ConfigSanitizer for ApiConfig {
 
         // We don't support Block ID based simulation filters.
         for rule in api_config.simulation_filter.rules() {
-            if let Matcher::BlockId(_) = rule.matcher() {
+            if rule.matcher().depends_on_block_id() {
                 return Err(Error::ConfigSanitizerFailed(
                     sanitizer_name,
                     "Block ID based simulation filters are not supported!".into(),
@@ -200,6 +232,57 @@ impl ConfigSanitizer for ApiConfig {
             }
         }
 
+        // We don't support Block ID based transaction submission filters.
+        for rule in api_config.transaction_submission_filter.rules() {
+            if rule.matcher().depends_on_block_id() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "Block ID based transaction submission filters are not supported!".into(),
+                ));
+            }
+        }
+
+        // Validate the transaction stream poll interval, as streams would otherwise busy-loop
+        if api_config.transaction_stream_enabled
+            && api_config.transaction_stream_poll_interval_ms == 0
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "transaction_stream_poll_interval_ms must be greater than 0!".into(),
+            ));
+        }
+
+        // Validate the rate limits, as invalid buckets can't be created
+        let rate_limit_config = &api_config.rate_limit;
+        if rate_limit_config.enabled {
+            if rate_limit_config.garbage_collection_interval_secs == 0 {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "garbage_collection_interval_secs must be greater than 0!".into(),
+                ));
+            }
+            if rate_limit_config.initial_bucket_fill_percentage > 100 {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "initial_bucket_fill_percentage must be at most 100!".into(),
+                ));
+            }
+            for limits in [
+                &rate_limit_config.api_key_limits,
+                &rate_limit_config.ip_limits,
+            ] {
+                for bucket in [&limits.submit, &limits.simulate, &limits.view, &limits.read] {
+                    if bucket.fill_rate == 0 || bucket.bucket_size < bucket.fill_rate {
+                        return Err(Error::ConfigSanitizerFailed(
+                            sanitizer_name,
+                            "Rate limit fill_rate must be greater than 0 and at most bucket_size!"
+                                .into(),
+                        ));
+                    }
+                }
+            }
+        }
+
         // Sanitize the gas estimation config
         GasEstimationConfig::sanitize(node_config, node_type, chain_id)?;
 
@@ -249,6 +332,90 @@ impl ViewFilter {
     }
 }
 
+/// Configuration for rate limiting API requests per client. Clients that send a known API
+/// key are identified by it, while all other clients are identified by their IP address.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiRateLimitConfig {
+    /// Enables rate limiting of requests
+    pub enabled: bool,
+    /// Name of the header clients send their API key in
+    pub api_key_header: String,
+    /// Known API keys. Requests with an unknown API key are rate limited by IP address.
+    pub api_keys: Vec<String>,
+    /// Rate limits for each client identified by an API key
+    pub api_key_limits: EndpointGroupRateLimits,
+    /// Rate limits for each client identified by an IP address
+    pub ip_limits: EndpointGroupRateLimits,
+    /// Percentage of tokens initially in the bucket of a new client
+    pub initial_bucket_fill_percentage: u8,
+    /// Interval at which idle clients are removed from the rate limiters
+    pub garbage_collection_interval_secs: u64,
+}
+
+impl Default for ApiRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key_header: "x-api-key".to_string(),
+            api_keys: vec![],
+            api_key_limits: EndpointGroupRateLimits {
+                submit: ApiTokenBucketConfig::new(200, 100),
+                simulate: ApiTokenBucketConfig::new(100, 50),
+                view: ApiTokenBucketConfig::new(1_000, 500),
+                read: ApiTokenBucketConfig::new(2_000, 1_000),
+            },
+            ip_limits: EndpointGroupRateLimits::default(),
+            initial_bucket_fill_percentage: 100,
+            garbage_collection_interval_secs: 60,
+        }
+    }
+}
+
+/// Rate limits for each group of endpoints. Every client has a separate bucket per group.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointGroupRateLimits {
+    /// Transaction submission endpoints
+    pub submit: ApiTokenBucketConfig,
+    /// Transaction simulation endpoints
+    pub simulate: ApiTokenBucketConfig,
+    /// View function endpoints
+    pub view: ApiTokenBucketConfig,
+    /// All other endpoints
+    pub read: ApiTokenBucketConfig,
+}
+
+impl Default for EndpointGroupRateLimits {
+    fn default() -> Self {
+        Self {
+            submit: ApiTokenBucketConfig::new(20, 10),
+            simulate: ApiTokenBucketConfig::new(10, 5),
+            view: ApiTokenBucketConfig::new(100, 50),
+            read: ApiTokenBucketConfig::new(200, 100),
+        }
+    }
+}
+
+/// A token bucket of requests
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiTokenBucketConfig {
+    /// Maximum burst of requests
+    pub bucket_size: usize,
+    /// Maximum number of requests/s
+    pub fill_rate: usize,
+}
+
+impl ApiTokenBucketConfig {
+    pub fn new(bucket_size: usize, fill_rate: usize) -> Self {
+        Self {
+            bucket_size,
+            fill_rate,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,4 +482,48 @@ mod tests {
                 .unwrap_err();
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
+
+    #[test]
+    fn test_sanitize_invalid_transaction_stream_poll_interval() {
+        // Create a node config with transaction streams that don't wait between polls
+        let node_config = NodeConfig {
+            api: ApiConfig {
+                enabled: true,
+                transaction_stream_enabled: true,
+                transaction_stream_poll_interval_ms: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails because
+        // the poll interval is invalid.
+        let error = ApiConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_invalid_rate_limits() {
+        // Create a node config with a rate limit bucket that is smaller than its fill rate
+        let mut rate_limit = ApiRateLimitConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        rate_limit.ip_limits.submit = ApiTokenBucketConfig::new(1, 10);
+        let node_config = NodeConfig {
+            api: ApiConfig {
+                enabled: true,
+                rate_limit,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails because
+        // the rate limit bucket is invalid.
+        let error =
+            ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
 }
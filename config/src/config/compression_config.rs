@@ -0,0 +1,111 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::Error;
+use aptos_compression::zstd::ZstdDictionary;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// The zstd compression config of a network application (e.g., consensus).
+/// zstd compressed messages are always accepted from peers, so this only
+/// affects the messages sent to them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ZstdCompressionConfig {
+    /// Whether or not to prefer zstd over the default compression (LZ4)
+    /// when sending messages to peers that support it. zstd uses more CPU,
+    /// but significantly less bandwidth.
+    pub enabled: bool,
+    /// A zstd dictionary trained on the messages of the application (if any).
+    /// Note: the dictionary is only used with peers that are configured with
+    /// the same dictionary (the messages sent to other peers don't use one).
+    pub dictionary_path: Option<PathBuf>,
+}
+
+impl ZstdCompressionConfig {
+    /// Reads and prepares the zstd dictionary (if one is configured)
+    pub fn load_dictionary(&self) -> Result<Option<ZstdDictionary>, Error> {
+        let dictionary_path = match &self.dictionary_path {
+            Some(dictionary_path) => dictionary_path,
+            None => return Ok(None),
+        };
+        let dictionary = fs::read(dictionary_path)
+            .map_err(|error| Error::IO(dictionary_path.display().to_string(), error))?;
+        let dictionary = ZstdDictionary::new(&dictionary).map_err(|error| {
+            Error::Unexpected(format!(
+                "Invalid zstd dictionary at {:?}! Error: {}",
+                dictionary_path, error
+            ))
+        })?;
+        Ok(Some(dictionary))
+    }
+
+    /// Verifies that the zstd dictionary (if one is configured) can be loaded
+    pub fn sanitize_dictionary(&self, sanitizer_name: &str) -> Result<(), Error> {
+        self.load_dictionary().map(|_| ()).map_err(|error| {
+            Error::ConfigSanitizerFailed(sanitizer_name.to_string(), error.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_sanitize_missing_dictionary() {
+        // Create a config without a dictionary and verify that sanitization succeeds
+        let config = ZstdCompressionConfig::default();
+        config.sanitize_dictionary("test").unwrap();
+
+        // Create a config with a missing dictionary file and verify that sanitization fails
+        let config = ZstdCompressionConfig {
+            enabled: true,
+            dictionary_path: Some(TempPath::new().path().to_path_buf()),
+        };
+        let error = config.sanitize_dictionary("test").unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_invalid_dictionary() {
+        // Create a config with an invalid dictionary file
+        let dictionary_path = TempPath::new();
+        fs::write(dictionary_path.path(), b"not a zstd dictionary").unwrap();
+        let config = ZstdCompressionConfig {
+            enabled: true,
+            dictionary_path: Some(dictionary_path.path().to_path_buf()),
+        };
+
+        // Verify that sanitization fails
+        let error = config.sanitize_dictionary("test").unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_valid_dictionary() {
+        // Create a config with a dictionary trained on some samples
+        let samples: Vec<_> = (0..1000u64)
+            .map(|index| {
+                format!(
+                    "sender: {:064x}, sequence_number: {}, max_gas_amount: {}",
+                    index * 7919,
+                    index,
+                    index % 17
+                )
+            })
+            .collect();
+        let dictionary = aptos_compression::zstd::train_dictionary(&samples, 4 * 1024).unwrap();
+        let dictionary_path = TempPath::new();
+        fs::write(dictionary_path.path(), dictionary).unwrap();
+        let config = ZstdCompressionConfig {
+            enabled: true,
+            dictionary_path: Some(dictionary_path.path().to_path_buf()),
+        };
+
+        // Verify that sanitization succeeds, and that the dictionary can be loaded
+        config.sanitize_dictionary("test").unwrap();
+        assert!(config.load_dictionary().unwrap().is_some());
+    }
+}
@@ -5,11 +5,12 @@ use super::internal_indexer_db_config::InternalIndexerDBConfig;
 use crate::config::{
     node_config_loader::NodeType,
     utils::{are_failpoints_enabled, get_config_name},
-    AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig, DagConsensusConfig, Error,
-    ExecutionConfig, IndexerGrpcConfig, InspectionServiceConfig, LoggerConfig, MempoolConfig,
-    NetbenchConfig, NodeConfig, StateSyncConfig, StorageConfig,
+    AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig, DagConsensusConfig,
+    DiscoveryMethod, Error, ExecutionConfig, IndexerGrpcConfig, InspectionServiceConfig,
+    LoggerConfig, MempoolConfig, NetbenchConfig, NetworkConfig, NodeConfig, StateSyncConfig,
+    StorageConfig, PROTOCOL_NAMES,
 };
-use aptos_types::chain_id::ChainId;
+use aptos_types::{chain_id::ChainId, network_address::parse_ip_quic};
 use std::collections::HashSet;
 
 // Useful sanitizer constants
@@ -148,6 +149,11 @@ fn sanitize_fullnode_network_configs(
                 ),
             ));
         }
+
+        // Verify the listen address, the outbound protocol quotas and DNS discovery
+        sanitize_listen_address(&sanitizer_name, fullnode_network_config)?;
+        sanitize_outbound_protocol_quotas(&sanitizer_name, fullnode_network_config)?;
+        sanitize_dns_discovery(&sanitizer_name, fullnode_network_config)?;
     }
 
     Ok(())
@@ -195,6 +201,100 @@ fn sanitize_validator_network_config(
                 "Mutual authentication must be enabled for the validator network!".into(),
             ));
         }
+
+        // Verify the listen address, the outbound protocol quotas and DNS discovery
+        sanitize_listen_address(&sanitizer_name, validator_network_config)?;
+        sanitize_outbound_protocol_quotas(&sanitizer_name, validator_network_config)?;
+        sanitize_dns_discovery(&sanitizer_name, validator_network_config)?;
+    }
+
+    Ok(())
+}
+
+/// Sanitize the listen address of the given network config
+fn sanitize_listen_address(
+    sanitizer_name: &str,
+    network_config: &NetworkConfig,
+) -> Result<(), Error> {
+    // Verify that QUIC is enabled if the node listens on a QUIC address
+    if !network_config.enable_quic
+        && parse_ip_quic(network_config.listen_address.as_slice()).is_some()
+    {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name.to_string(),
+            format!(
+                "QUIC must be enabled to listen on a QUIC address in network {}! Found: {}",
+                network_config.network_id, network_config.listen_address
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sanitize the outbound protocol quotas of the given network config
+fn sanitize_outbound_protocol_quotas(
+    sanitizer_name: &str,
+    network_config: &NetworkConfig,
+) -> Result<(), Error> {
+    for (protocol_name, quota) in &network_config.outbound_protocol_quotas {
+        // Verify that the protocol exists
+        if !PROTOCOL_NAMES.contains(&protocol_name.as_str()) {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name.to_string(),
+                format!(
+                    "Unknown protocol in the outbound protocol quotas of network {}: {}",
+                    network_config.network_id, protocol_name
+                ),
+            ));
+        }
+
+        // Verify that the quota is valid
+        if !quota.is_valid() {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name.to_string(),
+                format!(
+                    "The outbound {} quota of network {} must have a non-zero rate and a size of at least the rate! Found: {:?}",
+                    protocol_name, network_config.network_id, quota
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Sanitize the DNS discovery methods of the given network config
+fn sanitize_dns_discovery(
+    sanitizer_name: &str,
+    network_config: &NetworkConfig,
+) -> Result<(), Error> {
+    let discovery_methods = std::iter::once(&network_config.discovery_method)
+        .chain(network_config.discovery_methods.iter());
+    for discovery_method in discovery_methods {
+        if let DiscoveryMethod::Dns(dns_discovery) = discovery_method {
+            // Verify that there is something to resolve
+            if dns_discovery.names.is_empty() && dns_discovery.srv_names.is_empty() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name.to_string(),
+                    format!(
+                        "DNS discovery in network {} must have names or SRV names to resolve!",
+                        network_config.network_id
+                    ),
+                ));
+            }
+
+            // Verify that the peers in the names can be dialed
+            if !dns_discovery.names.is_empty() && dns_discovery.port.is_none() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name.to_string(),
+                    format!(
+                        "DNS discovery in network {} must have a port to dial the names at!",
+                        network_config.network_id
+                    ),
+                ));
+            }
+        }
     }
 
     Ok(())
@@ -204,7 +304,9 @@ fn sanitize_validator_network_config(
 mod tests {
     use super::*;
     use crate::{
-        config::{node_startup_config::NodeStartupConfig, NetworkConfig},
+        config::{
+            node_startup_config::NodeStartupConfig, DnsDiscovery, PeerRole, ProtocolQuotaConfig,
+        },
         network_id::NetworkId,
     };
 
@@ -362,6 +464,179 @@ mod tests {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_sanitize_fullnode_quic_listen_address() {
+        // Create a fullnode network config that listens on QUIC without enabling it
+        let node_config = NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                listen_address: "/ip4/0.0.0.0/quic/6182".parse().unwrap(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = sanitize_fullnode_network_configs(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Enable QUIC and verify that the config is now valid
+        let node_config = NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                listen_address: "/ip4/0.0.0.0/quic/6182".parse().unwrap(),
+                enable_quic: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        sanitize_fullnode_network_configs(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_fullnode_dns_discovery() {
+        // Create a fullnode network config with DNS discovery of names without a port
+        let dns_discovery = DnsDiscovery {
+            names: vec!["fn0.internal".into()],
+            port: None,
+            srv_names: vec![],
+            role: PeerRole::Upstream,
+            interval_secs: 60,
+        };
+        let create_node_config = |dns_discovery: DnsDiscovery| NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                discovery_methods: vec![DiscoveryMethod::Dns(dns_discovery)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = sanitize_fullnode_network_configs(
+            &create_node_config(dns_discovery.clone()),
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Only use SRV names and verify that the config is now valid
+        let srv_dns_discovery = DnsDiscovery {
+            names: vec![],
+            srv_names: vec!["_aptosnet._tcp.internal".into()],
+            ..dns_discovery.clone()
+        };
+        sanitize_fullnode_network_configs(
+            &create_node_config(srv_dns_discovery.clone()),
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+
+        // Remove the SRV names and verify that the config fails
+        let empty_dns_discovery = DnsDiscovery {
+            srv_names: vec![],
+            ..srv_dns_discovery
+        };
+        let error = sanitize_fullnode_network_configs(
+            &create_node_config(empty_dns_discovery),
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Add a port and verify that the config is now valid
+        let dns_discovery = DnsDiscovery {
+            port: Some(6180),
+            ..dns_discovery
+        };
+        sanitize_fullnode_network_configs(
+            &create_node_config(dns_discovery),
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_fullnode_invalid_protocol_quotas() {
+        // Create a fullnode network config with a quota for an unknown protocol
+        let valid_quota = ProtocolQuotaConfig {
+            byte_bucket_rate: 1024,
+            byte_bucket_size: 1024,
+        };
+        let node_config = NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                outbound_protocol_quotas: [("MempoolDirectSnd".into(), valid_quota)].into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = sanitize_fullnode_network_configs(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Create a fullnode network config with a quota smaller than its rate
+        let invalid_quota = ProtocolQuotaConfig {
+            byte_bucket_rate: 1024,
+            byte_bucket_size: 512,
+        };
+        let node_config = NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                outbound_protocol_quotas: [("MempoolDirectSend".into(), invalid_quota)].into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = sanitize_fullnode_network_configs(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Create a fullnode network config with a valid quota
+        let node_config = NodeConfig {
+            full_node_networks: vec![NetworkConfig {
+                network_id: NetworkId::Public,
+                outbound_protocol_quotas: [("MempoolDirectSend".into(), valid_quota)].into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it succeeds
+        sanitize_fullnode_network_configs(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_sanitize_validator_disabled_authentication() {
         // Create a validator config with disabled mutual authentication
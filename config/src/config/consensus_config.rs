@@ -5,7 +5,8 @@
 use super::DEFEAULT_MAX_BATCH_TXNS;
 use crate::config::{
     config_sanitizer::ConfigSanitizer, node_config_loader::NodeType, Error, NodeConfig,
-    QuorumStoreConfig, ReliableBroadcastConfig, SafetyRulesConfig, BATCH_PADDING_BYTES,
+    QuorumStoreConfig, ReliableBroadcastConfig, SafetyRulesConfig, ZstdCompressionConfig,
+    BATCH_PADDING_BYTES,
 };
 use aptos_crypto::_once_cell::sync::Lazy;
 use aptos_types::chain_id::ChainId;
@@ -84,6 +85,8 @@ pub struct ConsensusConfig {
     pub proof_cache_capacity: u64,
     pub rand_rb_config: ReliableBroadcastConfig,
     pub num_bounded_executor_tasks: u64,
+    // The zstd compression config for consensus messages
+    pub zstd_compression: ZstdCompressionConfig,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -364,6 +367,7 @@ impl Default for ConsensusConfig {
                 rpc_timeout_ms: 10000,
             },
             num_bounded_executor_tasks: 16,
+            zstd_compression: ZstdCompressionConfig::default(),
         }
     }
 }
@@ -514,6 +518,12 @@ impl ConfigSanitizer for ConsensusConfig {
         // Quorum store batches must be <= consensus blocks
         Self::sanitize_batch_block_limits(&sanitizer_name, &node_config.consensus)?;
 
+        // Verify that the zstd dictionary (if any) is valid
+        node_config
+            .consensus
+            .zstd_compression
+            .sanitize_dictionary(&sanitizer_name)?;
+
         Ok(())
     }
 }
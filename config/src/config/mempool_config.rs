@@ -4,7 +4,8 @@
 
 use crate::config::{
     config_optimizer::ConfigOptimizer, config_sanitizer::ConfigSanitizer,
-    node_config_loader::NodeType, Error, NodeConfig, MAX_APPLICATION_MESSAGE_SIZE,
+    node_config_loader::NodeType, transaction_filter_type::Filter, Error, NodeConfig,
+    ZstdCompressionConfig, MAX_APPLICATION_MESSAGE_SIZE,
 };
 use aptos_global_constants::DEFAULT_BUCKETS;
 use aptos_types::chain_id::ChainId;
@@ -65,6 +66,50 @@ pub struct MempoolConfig {
     pub include_ready_time_in_broadcast: bool,
     pub usecase_stats_num_blocks_to_track: usize,
     pub usecase_stats_num_top_to_track: usize,
+    /// Filter applied to transactions before they are admitted into the Mempool
+    /// (both client submissions and peer broadcasts).
+    pub transaction_filter: Filter,
+    /// Persists the transactions in the Mempool to an on-disk journal in the storage directory,
+    /// so that they are re-validated and restored when the node restarts.
+    pub enable_journal: bool,
+    /// Max number of journal updates queued for the journal writer. Insertions beyond it are
+    /// dropped, rather than blocking mempool on the disk, while removals wait for the writer.
+    pub journal_max_queued_updates: usize,
+    /// Sends the CancelTransactionsRequest to upstream nodes when a pending transaction is
+    /// cancelled, so that they evict it too. Older nodes can't deserialize this message, so
+    /// it's disabled by default until all the upstream nodes support it. Without it, upstream
+    /// nodes only evict the transaction once the cancellation is broadcast to them.
+    pub broadcast_transaction_cancellations: bool,
+    /// How ready transactions are ordered when pulling transactions for consensus.
+    pub ordering_policy: MempoolOrderingPolicy,
+    /// Which transactions are evicted to make space for new ones when the Mempool is full.
+    pub eviction_policy: MempoolEvictionPolicy,
+    /// The zstd compression config for the broadcasts to peers.
+    pub zstd_compression: ZstdCompressionConfig,
+}
+
+/// The order in which ready transactions with the same ranking score are pulled for consensus.
+/// Transactions with a higher ranking score (i.e., gas unit price) always come first.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolOrderingPolicy {
+    /// Transactions that were inserted earlier come first
+    RankingScore,
+    /// Transactions that expire earlier come first, so that they are included before expiring
+    RankingScoreThenExpiration,
+}
+
+/// The transactions that can be evicted when the Mempool is full. Eviction only happens
+/// to make space for a transaction that would be ready for broadcast upon insertion.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolEvictionPolicy {
+    /// Only non-ready transactions in the parking lot are evicted
+    ParkingLot,
+    /// Non-ready transactions in the parking lot are evicted first. Then ready transactions
+    /// with a lower ranking score than the new transaction are evicted, lowest first, along
+    /// with the following transactions of the same account.
+    LowestRankingScore,
 }
 
 impl Default for MempoolConfig {
@@ -95,17 +140,51 @@ impl Default for MempoolConfig {
             include_ready_time_in_broadcast: false,
             usecase_stats_num_blocks_to_track: 40,
             usecase_stats_num_top_to_track: 5,
+            transaction_filter: Filter::empty(),
+            enable_journal: false,
+            journal_max_queued_updates: 100_000,
+            broadcast_transaction_cancellations: false,
+            ordering_policy: MempoolOrderingPolicy::RankingScore,
+            eviction_policy: MempoolEvictionPolicy::ParkingLot,
+            zstd_compression: ZstdCompressionConfig::default(),
         }
     }
 }
 
 impl ConfigSanitizer for MempoolConfig {
     fn sanitize(
-        _node_config: &NodeConfig,
+        node_config: &NodeConfig,
         _node_type: NodeType,
         _chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
-        Ok(()) // TODO: add reasonable verifications
+        let sanitizer_name = Self::get_sanitizer_name();
+        let mempool_config = &node_config.mempool;
+
+        // Transactions are not yet part of a block when they are admitted,
+        // so Block ID based filters are not supported.
+        for rule in mempool_config.transaction_filter.rules() {
+            if rule.matcher().depends_on_block_id() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "Block ID based mempool transaction filters are not supported!".into(),
+                ));
+            }
+        }
+
+        // Verify that the journal can queue updates, as they would all be dropped otherwise
+        if mempool_config.enable_journal && mempool_config.journal_max_queued_updates == 0 {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "The max number of queued mempool journal updates must be greater than 0!".into(),
+            ));
+        }
+
+        // Verify that the zstd dictionary (if any) is valid
+        mempool_config
+            .zstd_compression
+            .sanitize_dictionary(&sanitizer_name)?;
+
+        Ok(())
     }
 }
 
@@ -260,4 +339,54 @@ mod tests {
             local_max_broadcasts_per_peer
         );
     }
+
+    #[test]
+    fn test_sanitize_block_id_transaction_filter() {
+        // Create a node config with a block ID based mempool filter
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                transaction_filter: Filter::empty()
+                    .add_deny_block_id(aptos_crypto::HashValue::random()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error =
+            MempoolConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Sanitize a config with a sender based filter and verify that it succeeds
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                transaction_filter: Filter::empty()
+                    .add_deny_sender(aptos_types::account_address::AccountAddress::ONE),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        MempoolConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_invalid_journal_max_queued_updates() {
+        // Create a node config with a journal that can't queue updates
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                enable_journal: true,
+                journal_max_queued_updates: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error =
+            MempoolConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
 }
@@ -104,6 +104,10 @@ pub struct NetworkConfig {
     pub max_frame_size: usize,
     /// Enables proxy protocol on incoming connections to get original source addresses
     pub enable_proxy_protocol: bool,
+    /// Enables QUIC alongside TCP. If enabled, the node also listens for QUIC connections
+    /// on the UDP port matching its listen port, and dials peers over QUIC if they
+    /// advertise a QUIC address. Otherwise, only TCP is used.
+    pub enable_quic: bool,
     /// Interval to send healthcheck pings to peers
     pub ping_interval_ms: u64,
     /// Timeout until a healthcheck ping is rejected
@@ -118,6 +122,13 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     /// Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    /// Outbound bandwidth quotas per peer connection, keyed by protocol name
+    /// (e.g., "MempoolDirectSend"). Protocols without a quota are not limited.
+    pub outbound_protocol_quotas: HashMap<String, ProtocolQuotaConfig>,
+    /// Whether to disconnect from (and reject connections from) peers that are
+    /// temporarily banned for misbehaving. If not set, bans are enforced on all
+    /// networks except the validator network (see `enforce_peer_bans()`).
+    pub enforce_peer_bans: Option<bool>,
     /// The maximum size of an inbound or outbound message (it may be divided into multiple frame)
     pub max_message_size: usize,
     /// The maximum number of parallel message deserialization tasks that can run (per application)
@@ -147,6 +158,7 @@ impl NetworkConfig {
             seeds: PeerSet::default(),
             max_frame_size: MAX_FRAME_SIZE,
             enable_proxy_protocol: false,
+            enable_quic: false,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
             connectivity_check_interval_ms: CONNECTIVITY_CHECK_INTERVAL_MS,
             network_channel_size: NETWORK_CHANNEL_SIZE,
@@ -158,6 +170,8 @@ impl NetworkConfig {
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            outbound_protocol_quotas: HashMap::new(),
+            enforce_peer_bans: None,
             max_message_size: MAX_MESSAGE_SIZE,
             inbound_rx_buffer_size_bytes: None,
             inbound_tx_buffer_size_bytes: None,
@@ -214,6 +228,14 @@ impl NetworkConfig {
         }
     }
 
+    /// Returns true iff peer bans should be enforced on this network. By
+    /// default, bans are not enforced between validators, as the liveness of
+    /// consensus depends on the validators being connected.
+    pub fn enforce_peer_bans(&self) -> bool {
+        self.enforce_peer_bans
+            .unwrap_or_else(|| !self.network_id.is_validator_network())
+    }
+
     pub fn discovery_methods(&self) -> Vec<&DiscoveryMethod> {
         // TODO: This is a backwards compatibility feature.  Deprecate discovery_method
         if self.discovery_method != DiscoveryMethod::None && !self.discovery_methods.is_empty() {
@@ -347,6 +369,7 @@ pub enum DiscoveryMethod {
     Onchain,
     File(FileDiscovery),
     Rest(RestDiscovery),
+    Dns(DnsDiscovery),
     None,
 }
 
@@ -364,6 +387,29 @@ pub struct RestDiscovery {
     pub interval_secs: u64,
 }
 
+/// Discovers peers through DNS. Each peer is a DNS name that resolves to the
+/// peer's IP addresses (A/AAAA records) and carries the peer's x25519 public
+/// key in a TXT record of the form `x25519=<hex encoded key>`. The peer ID is
+/// derived from the key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct DnsDiscovery {
+    /// The names of individual peers, which are dialed at `port`
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// The port to dial the peers in `names` at (only required if there are
+    /// any `names`)
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// SRV names (e.g., `_aptosnet._tcp.fullnodes.internal`), each listing a
+    /// set of peers (i.e., the names and ports to dial them at)
+    #[serde(default)]
+    pub srv_names: Vec<String>,
+    /// The role of the discovered peers
+    pub role: PeerRole,
+    pub interval_secs: u64,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct RateLimitConfig {
@@ -388,6 +434,64 @@ impl Default for RateLimitConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolQuotaConfig {
+    /// Maximum number of bytes/s sent for the protocol
+    pub byte_bucket_rate: usize,
+    /// Maximum burst of bytes sent for the protocol
+    pub byte_bucket_size: usize,
+}
+
+impl ProtocolQuotaConfig {
+    /// Returns true iff the quota has a non-zero rate, and a size of at least the rate
+    pub fn is_valid(&self) -> bool {
+        self.byte_bucket_rate > 0 && self.byte_bucket_size >= self.byte_bucket_rate
+    }
+}
+
+/// The names of all network protocols (i.e., the valid keys of the outbound
+/// protocol quotas). Note: these must match `ProtocolId::as_str()` in the
+/// network framework, which can't be used here directly.
+pub const PROTOCOL_NAMES: &[&str] = &[
+    "ConsensusRpcBcs",
+    "ConsensusDirectSendBcs",
+    "MempoolDirectSend",
+    "StateSyncDirectSend",
+    "DiscoveryDirectSend",
+    "HealthCheckerRpc",
+    "ConsensusDirectSendJson",
+    "ConsensusRpcJson",
+    "StorageServiceRpc",
+    "MempoolRpc",
+    "PeerMonitoringServiceRpc",
+    "ConsensusRpcCompressed",
+    "ConsensusDirectSendCompressed",
+    "NetbenchDirectSend",
+    "NetbenchRpc",
+    "DKGDirectSendCompressed",
+    "DKGDirectSendBcs",
+    "DKGDirectSendJson",
+    "DKGRpcCompressed",
+    "DKGRpcBcs",
+    "DKGRpcJson",
+    "JWKConsensusDirectSendCompressed",
+    "JWKConsensusDirectSendBcs",
+    "JWKConsensusDirectSendJson",
+    "JWKConsensusRpcCompressed",
+    "JWKConsensusRpcBcs",
+    "JWKConsensusRpcJson",
+    "ConsensusObserver",
+    "ConsensusObserverRpc",
+    "ConsensusRpcZstd",
+    "ConsensusDirectSendZstd",
+    "MempoolDirectSendZstd",
+    "StorageServiceRpcZstd",
+    "ConsensusRpcZstdDictionary",
+    "ConsensusDirectSendZstdDictionary",
+    "MempoolDirectSendZstdDictionary",
+];
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
@@ -9,6 +9,7 @@ use crate::config::{
 use aptos_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::path::PathBuf;
 
 // The maximum message size per state sync message
 const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024; /* 4 MiB */
@@ -23,11 +24,12 @@ const MAX_TRANSACTION_OUTPUT_CHUNK_SIZE: u64 = 1000;
 const MAX_CONCURRENT_REQUESTS: u64 = 6;
 const MAX_CONCURRENT_STATE_REQUESTS: u64 = 6;
 
-#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StateSyncConfig {
     pub data_streaming_service: DataStreamingServiceConfig,
     pub aptos_data_client: AptosDataClientConfig,
+    pub backup_bootstrapping: BackupBootstrappingConfig,
     pub state_sync_driver: StateSyncDriverConfig,
     pub storage_service: StorageServiceConfig,
 }
@@ -144,6 +146,46 @@ impl Default for StateSyncDriverConfig {
     }
 }
 
+/// The config for bootstrapping from a backup storage. If a backup storage is
+/// specified, fast syncing nodes download the state snapshot from it (instead
+/// of from their peers). Epoch ending ledger infos are still fetched from peers,
+/// and everything read from the backups is verified against them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupBootstrappingConfig {
+    /// The backup storage to bootstrap from (if any)
+    pub backup_storage: Option<BackupStorageConfig>,
+    /// The file holding the hex encoded key to decrypt encrypted backups (if any)
+    pub backup_encryption_key_file: Option<PathBuf>,
+    /// The maximum number of backup files to download concurrently
+    pub max_concurrent_downloads: u64,
+    /// The directory to cache the backup metadata files in. Defaults to a temporary directory.
+    pub metadata_cache_dir: Option<PathBuf>,
+}
+
+impl Default for BackupBootstrappingConfig {
+    fn default() -> Self {
+        Self {
+            backup_storage: None,
+            backup_encryption_key_file: None,
+            max_concurrent_downloads: 8,
+            metadata_cache_dir: None,
+        }
+    }
+}
+
+/// The backup storage types supported for bootstrapping (see the backup CLI)
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStorageConfig {
+    /// The config file of a command adapter storage
+    CommandAdapter(PathBuf),
+    /// A local directory holding the backups
+    LocalFs(PathBuf),
+    /// The config file of an S3 compatible storage
+    S3(PathBuf),
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageServiceConfig {
@@ -426,6 +468,10 @@ pub struct AptosDataClientConfig {
     pub subscription_response_timeout_ms: u64,
     /// Whether or not to request compression for incoming data
     pub use_compression: bool,
+    /// Whether or not to prefer zstd compression on the wire for peers that
+    /// support it. This is on top of `use_compression`, which can be disabled
+    /// as zstd gains little on already compressed data.
+    pub use_zstd_compression: bool,
 }
 
 impl Default for AptosDataClientConfig {
@@ -448,6 +494,7 @@ impl Default for AptosDataClientConfig {
             response_timeout_ms: 10_000,              // 10 seconds
             subscription_response_timeout_ms: 15_000, // 15 seconds (longer than a regular timeout because of prefetching)
             use_compression: true,
+            use_zstd_compression: false,
         }
     }
 }
@@ -483,6 +530,16 @@ impl ConfigSanitizer for StateSyncDriverConfig {
             ));
         }
 
+        // Verify that nodes bootstrapping from a backup storage are fast syncing
+        let backup_storage = &node_config.state_sync.backup_bootstrapping.backup_storage;
+        if backup_storage.is_some() && !fast_sync_enabled {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "Bootstrapping from a backup storage requires the node to be fast syncing!"
+                    .to_string(),
+            ));
+        }
+
         Ok(())
     }
 }
@@ -788,6 +845,26 @@ mod tests {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_sanitize_backup_bootstrapping_no_fast_sync() {
+        // Create a node config that bootstraps from a
+        // backup storage, but executes transactions.
+        let mut node_config = create_execution_mode_config();
+        node_config.state_sync.backup_bootstrapping.backup_storage = Some(
+            BackupStorageConfig::LocalFs(PathBuf::from("/opt/aptos/backups")),
+        );
+
+        // Verify that sanitization fails
+        let error =
+            StateSyncConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Enable fast sync and verify that sanitization passes
+        node_config.state_sync.state_sync_driver.bootstrapping_mode =
+            BootstrappingMode::DownloadLatestStates;
+        StateSyncConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
+
     /// Creates and returns a node config with the syncing modes set to execution
     fn create_execution_mode_config() -> NodeConfig {
         NodeConfig {
@@ -18,6 +18,27 @@ pub enum Matcher {
     Sender(AccountAddress),
     ModuleAddress(AccountAddress),
     EntryFunction(AccountAddress, String, String),
+    /// Matches entry function transactions whose argument at the given index is
+    /// equal to the given BCS encoded value.
+    EntryFunctionArgument(usize, Vec<u8>),
+    /// Matches transactions with the given fee payer.
+    FeePayer(AccountAddress),
+    /// Matches multi-agent transactions with the given secondary signer.
+    SecondarySigner(AccountAddress),
+    /// Matches script transactions with the given SHA3-256 hash of the script bytecode.
+    ScriptHash(HashValue),
+    /// Matches multisig transactions executed as the given multisig account.
+    MultisigAddress(AccountAddress),
+    MaxGasAmountGreaterThan(u64),
+    MaxGasAmountLessThan(u64),
+    GasUnitPriceGreaterThan(u64),
+    GasUnitPriceLessThan(u64),
+    /// Matches if all of the inner matchers match.
+    And(Vec<Matcher>),
+    /// Matches if any of the inner matchers match.
+    Or(Vec<Matcher>),
+    /// Matches if the inner matcher does not match.
+    Not(Box<Matcher>),
 }
 
 impl Matcher {
@@ -43,6 +64,52 @@ impl Matcher {
                 },
                 _ => false,
             },
+            Matcher::EntryFunctionArgument(index, value) => match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    entry_function.args().get(*index) == Some(value)
+                },
+                _ => false,
+            },
+            Matcher::FeePayer(fee_payer) => {
+                txn.authenticator_ref().fee_payer_address() == Some(*fee_payer)
+            },
+            Matcher::SecondarySigner(signer) => txn
+                .authenticator_ref()
+                .secondary_signer_addresses()
+                .contains(signer),
+            Matcher::ScriptHash(hash) => match txn.payload() {
+                TransactionPayload::Script(script) => {
+                    HashValue::sha3_256_of(script.code()) == *hash
+                },
+                _ => false,
+            },
+            Matcher::MultisigAddress(address) => match txn.payload() {
+                TransactionPayload::Multisig(multisig) => multisig.multisig_address == *address,
+                _ => false,
+            },
+            Matcher::MaxGasAmountGreaterThan(amount) => txn.max_gas_amount() > *amount,
+            Matcher::MaxGasAmountLessThan(amount) => txn.max_gas_amount() < *amount,
+            Matcher::GasUnitPriceGreaterThan(price) => txn.gas_unit_price() > *price,
+            Matcher::GasUnitPriceLessThan(price) => txn.gas_unit_price() < *price,
+            Matcher::And(matchers) => matchers
+                .iter()
+                .all(|matcher| matcher.matches(block_id, timestamp, txn)),
+            Matcher::Or(matchers) => matchers
+                .iter()
+                .any(|matcher| matcher.matches(block_id, timestamp, txn)),
+            Matcher::Not(matcher) => !matcher.matches(block_id, timestamp, txn),
+        }
+    }
+
+    /// Returns true iff the matcher (or any of its inner matchers) depends on the block ID
+    pub fn depends_on_block_id(&self) -> bool {
+        match self {
+            Matcher::BlockId(_) => true,
+            Matcher::And(matchers) | Matcher::Or(matchers) => {
+                matchers.iter().any(|matcher| matcher.depends_on_block_id())
+            },
+            Matcher::Not(matcher) => matcher.depends_on_block_id(),
+            _ => false,
         }
     }
 }
@@ -113,6 +180,14 @@ impl Rule {
 /// This filter allows transactions from the sender with address f8871acf2c827d40e23b71f6ff2b9accef8dbb17709b88bd9eb95e6bb748c25a or
 /// from the module with address 0000000000000000000000000000000000000000000000000000000000000001 or entry functions
 /// test::check and test::new from the module 0000000000000000000000000000000000000000000000000000000000000001. All other transactions are denied.
+///
+/// Matchers can also be composed using `And`, `Or` and `Not`. For example, the following rule
+/// denies all transactions from the sender that do not have a gas unit price of at least 100:
+///             rules:
+///                 - Deny:
+///                     And:
+///                         - Sender: f8871acf2c827d40e23b71f6ff2b9accef8dbb17709b88bd9eb95e6bb748c25a
+///                         - GasUnitPriceLessThan: 100
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Filter {
     rules: Vec<Rule>,
@@ -197,6 +272,39 @@ impl Filter {
         self
     }
 
+    pub fn add_deny_fee_payer(mut self, fee_payer: AccountAddress) -> Self {
+        self.rules.push(Rule::Deny(Matcher::FeePayer(fee_payer)));
+        self
+    }
+
+    pub fn add_deny_secondary_signer(mut self, signer: AccountAddress) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::SecondarySigner(signer)));
+        self
+    }
+
+    pub fn add_deny_script_hash(mut self, script_hash: HashValue) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::ScriptHash(script_hash)));
+        self
+    }
+
+    pub fn add_deny_multisig_address(mut self, address: AccountAddress) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::MultisigAddress(address)));
+        self
+    }
+
+    pub fn add_allow(mut self, matcher: Matcher) -> Self {
+        self.rules.push(Rule::Allow(matcher));
+        self
+    }
+
+    pub fn add_deny(mut self, matcher: Matcher) -> Self {
+        self.rules.push(Rule::Deny(matcher));
+        self
+    }
+
     pub fn rules(&self) -> &[Rule] {
         &self.rules
     }
@@ -60,7 +60,7 @@ pub mod network_interface;
 mod payload_manager;
 mod qc_aggregator;
 mod transaction_deduper;
-mod transaction_filter;
+pub mod transaction_filter;
 mod transaction_shuffler;
 mod txn_hash_and_authenticator_deduper;
 
@@ -22,7 +22,7 @@ use aptos_consensus_types::{
     vote_msg::VoteMsg,
 };
 use aptos_network::{
-    application::{error::Error, interface::NetworkClientInterface},
+    application::{error::Error, interface::NetworkClientInterface, reputation::PeerPenalty},
     ProtocolId,
 };
 use aptos_types::{epoch_change::EpochChangeProof, PeerId};
@@ -138,6 +138,24 @@ pub const DIRECT_SEND: &[ProtocolId] = &[
     ProtocolId::ConsensusDirectSendJson,
 ];
 
+/// Supported zstd protocols. These are always supported, but only preferred
+/// over the protocols above if zstd compression is enabled in the config.
+/// The dictionary protocol is only negotiated with peers that have the same
+/// zstd dictionary.
+pub const ZSTD_RPC: &[ProtocolId] = &[
+    ProtocolId::ConsensusRpcZstdDictionary,
+    ProtocolId::ConsensusRpcZstd,
+];
+
+/// Supported zstd protocols. These are always supported, but only preferred
+/// over the protocols above if zstd compression is enabled in the config.
+/// The dictionary protocol is only negotiated with peers that have the same
+/// zstd dictionary.
+pub const ZSTD_DIRECT_SEND: &[ProtocolId] = &[
+    ProtocolId::ConsensusDirectSendZstdDictionary,
+    ProtocolId::ConsensusDirectSendZstd,
+];
+
 impl<NetworkClient: NetworkClientInterface<ConsensusMsg>> ConsensusNetworkClient<NetworkClient> {
     /// Returns a new consensus network client
     pub fn new(network_client: NetworkClient) -> Self {
@@ -211,4 +229,12 @@ impl<NetworkClient: NetworkClientInterface<ConsensusMsg>> ConsensusNetworkClient
         self.network_client
             .sort_peers_by_latency(NetworkId::Validator, peers);
     }
+
+    /// Penalizes the given peer for misbehaving (e.g., sending invalid messages)
+    pub fn report_peer_penalty(&self, peer: PeerId, penalty: PeerPenalty) {
+        let peer_network_id = self.get_peer_network_id_for_peer(peer);
+        self.network_client
+            .get_peers_and_metadata()
+            .report_peer_penalty(peer_network_id, penalty);
+    }
 }
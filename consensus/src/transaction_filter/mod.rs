@@ -3,15 +3,35 @@
 
 use aptos_config::config::transaction_filter_type::Filter;
 use aptos_crypto::HashValue;
+use aptos_infallible::RwLock;
+use aptos_logger::info;
 use aptos_types::transaction::SignedTransaction;
+use std::sync::Arc;
 
+/// A transaction filter whose rules can be replaced at runtime (e.g., via the admin service).
+/// Clones share the same underlying rules, so an update is visible to all of them.
+#[derive(Clone)]
 pub struct TransactionFilter {
-    filter: Filter,
+    filter: Arc<RwLock<Arc<Filter>>>,
 }
 
 impl TransactionFilter {
-    pub(crate) fn new(filter: Filter) -> Self {
-        Self { filter }
+    pub fn new(filter: Filter) -> Self {
+        Self {
+            filter: Arc::new(RwLock::new(Arc::new(filter))),
+        }
+    }
+
+    /// Returns the currently active filter rules
+    pub fn get_filter(&self) -> Arc<Filter> {
+        self.filter.read().clone()
+    }
+
+    /// Replaces the active filter rules. Blocks that are already being
+    /// filtered keep using the previous rules.
+    pub fn update_filter(&self, filter: Filter) {
+        info!("Updating the consensus transaction filter: {:?}", filter);
+        *self.filter.write() = Arc::new(filter);
     }
 
     pub fn filter(
@@ -20,12 +40,15 @@ impl TransactionFilter {
         timestamp: u64,
         txns: Vec<SignedTransaction>,
     ) -> Vec<SignedTransaction> {
+        // Take a snapshot of the filter so that the whole block is filtered using the same rules
+        let filter = self.get_filter();
+
         // Special case for no filter to avoid unnecessary iteration through all transactions in the default case
-        if self.filter.is_empty() {
+        if filter.is_empty() {
             return txns;
         }
         txns.into_iter()
-            .filter(|txn| self.filter.allows(block_id, timestamp, txn))
+            .filter(|txn| filter.allows(block_id, timestamp, txn))
             .collect()
     }
 }
@@ -33,12 +56,15 @@ impl TransactionFilter {
 #[cfg(test)]
 mod test {
     use crate::transaction_filter::TransactionFilter;
-    use aptos_config::config::transaction_filter_type::Filter;
+    use aptos_config::config::transaction_filter_type::{Filter, Matcher};
     use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
     use aptos_types::{
         chain_id::ChainId,
         move_utils::MemberId,
-        transaction::{EntryFunction, RawTransaction, SignedTransaction, TransactionPayload},
+        transaction::{
+            authenticator::AccountAuthenticator, EntryFunction, RawTransaction, Script,
+            SignedTransaction, TransactionPayload,
+        },
     };
     use move_core_types::account_address::AccountAddress;
 
@@ -68,6 +94,59 @@ mod test {
         )
     }
 
+    fn create_signed_transaction_with_payload(
+        payload: TransactionPayload,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+    ) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_transaction = RawTransaction::new(
+            AccountAddress::random(),
+            0,
+            payload,
+            max_gas_amount,
+            gas_unit_price,
+            0,
+            ChainId::new(10),
+        );
+
+        SignedTransaction::new(
+            raw_transaction.clone(),
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        )
+    }
+
+    fn create_fee_payer_transaction(
+        secondary_signer: AccountAddress,
+        fee_payer: AccountAddress,
+    ) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let payload = TransactionPayload::Script(Script::new(vec![], vec![], vec![]));
+        let raw_transaction = RawTransaction::new(
+            AccountAddress::random(),
+            0,
+            payload,
+            0,
+            0,
+            0,
+            ChainId::new(10),
+        );
+        let account_authenticator = AccountAuthenticator::ed25519(
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        );
+
+        SignedTransaction::new_fee_payer(
+            raw_transaction,
+            account_authenticator.clone(),
+            vec![secondary_signer],
+            vec![account_authenticator.clone()],
+            fee_payer,
+            account_authenticator,
+        )
+    }
+
     fn get_transactions() -> Vec<SignedTransaction> {
         vec![
             create_signed_transaction(str::parse("0x1::test::add").unwrap()),
@@ -119,6 +198,24 @@ mod test {
         assert_eq!(filtered_txns, vec![]);
     }
 
+    #[test]
+    fn test_update_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+        let filter = TransactionFilter::new(Filter::empty());
+        let shared_filter = filter.clone();
+
+        // Updating one clone of the filter should update all clones
+        shared_filter.update_filter(Filter::empty().add_deny_all());
+        assert_eq!(*filter.get_filter(), Filter::empty().add_deny_all());
+        let filtered_txns = filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, vec![]);
+
+        shared_filter.update_filter(Filter::empty());
+        let filtered_txns = filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+    }
+
     #[test]
     fn test_block_id_filter() {
         let txns = get_transactions();
@@ -287,4 +384,144 @@ mod test {
         let filtered_txns = allow_list_filter.filter(block_id, 0, txns.clone());
         assert_eq!(filtered_txns, txns[4..].to_vec());
     }
+
+    #[test]
+    fn test_fee_payer_and_secondary_signer_filter() {
+        let fee_payer = AccountAddress::random();
+        let secondary_signer = AccountAddress::random();
+        let txns = vec![
+            create_fee_payer_transaction(secondary_signer, fee_payer),
+            create_fee_payer_transaction(secondary_signer, AccountAddress::random()),
+            create_fee_payer_transaction(AccountAddress::random(), AccountAddress::random()),
+        ];
+        let block_id = HashValue::random();
+
+        let fee_payer_filter =
+            TransactionFilter::new(Filter::empty().add_deny_fee_payer(fee_payer));
+        let filtered_txns = fee_payer_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+
+        let secondary_signer_filter =
+            TransactionFilter::new(Filter::empty().add_deny_secondary_signer(secondary_signer));
+        let filtered_txns = secondary_signer_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[2..].to_vec());
+    }
+
+    #[test]
+    fn test_script_hash_filter() {
+        let script_code = vec![1, 2, 3];
+        let txns = vec![
+            create_signed_transaction_with_payload(
+                TransactionPayload::Script(Script::new(script_code.clone(), vec![], vec![])),
+                0,
+                0,
+            ),
+            create_signed_transaction_with_payload(
+                TransactionPayload::Script(Script::new(vec![4, 5, 6], vec![], vec![])),
+                0,
+                0,
+            ),
+        ];
+        let block_id = HashValue::random();
+
+        let script_hash_filter = TransactionFilter::new(
+            Filter::empty().add_deny_script_hash(HashValue::sha3_256_of(&script_code)),
+        );
+        let filtered_txns = script_hash_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+    }
+
+    #[test]
+    fn test_gas_filter() {
+        let txns: Vec<_> = [(100, 1), (1_000, 100), (10_000, 1_000)]
+            .into_iter()
+            .map(|(max_gas_amount, gas_unit_price)| {
+                create_signed_transaction_with_payload(
+                    TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+                    max_gas_amount,
+                    gas_unit_price,
+                )
+            })
+            .collect();
+        let block_id = HashValue::random();
+
+        let max_gas_filter = TransactionFilter::new(
+            Filter::empty().add_deny(Matcher::MaxGasAmountGreaterThan(5_000)),
+        );
+        let filtered_txns = max_gas_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[0..2].to_vec());
+
+        let gas_unit_price_filter =
+            TransactionFilter::new(Filter::empty().add_deny(Matcher::GasUnitPriceLessThan(100)));
+        let filtered_txns = gas_unit_price_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+    }
+
+    #[test]
+    fn test_entry_function_argument_filter() {
+        let denied_argument = bcs::to_bytes(&AccountAddress::ONE).unwrap();
+        let txns: Vec<_> = [
+            denied_argument.clone(),
+            bcs::to_bytes(&AccountAddress::TWO).unwrap(),
+        ]
+        .into_iter()
+        .map(|argument| {
+            let MemberId {
+                module_id,
+                member_id,
+            } = str::parse("0x1::test::add").unwrap();
+            create_signed_transaction_with_payload(
+                TransactionPayload::EntryFunction(EntryFunction::new(
+                    module_id,
+                    member_id,
+                    vec![],
+                    vec![argument],
+                )),
+                0,
+                0,
+            )
+        })
+        .collect();
+        let block_id = HashValue::random();
+
+        let argument_filter = TransactionFilter::new(
+            Filter::empty().add_deny(Matcher::EntryFunctionArgument(0, denied_argument)),
+        );
+        let filtered_txns = argument_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns[1..].to_vec());
+
+        // Out of range arguments never match
+        let out_of_range_filter = TransactionFilter::new(
+            Filter::empty().add_deny(Matcher::EntryFunctionArgument(1, vec![])),
+        );
+        let filtered_txns = out_of_range_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, txns);
+    }
+
+    #[test]
+    fn test_combinator_filter() {
+        let txns = get_transactions();
+        let block_id = HashValue::random();
+        let filter = serde_yaml::from_str::<Filter>(r#"
+            rules:
+                - Deny:
+                    And:
+                        - ModuleAddress: "0000000000000000000000000000000000000000000000000000000000000001"
+                        - Not:
+                            Or:
+                                - EntryFunction:
+                                    - "0000000000000000000000000000000000000000000000000000000000000001"
+                                    - test
+                                    - check
+                                - EntryFunction:
+                                    - "0000000000000000000000000000000000000000000000000000000000000001"
+                                    - test
+                                    - new
+              "#).unwrap();
+
+        // Denies all 0x1 transactions except test::check and test::new
+        let combinator_filter = TransactionFilter::new(filter);
+        let filtered_txns = combinator_filter.filter(block_id, 0, txns.clone());
+        assert_eq!(filtered_txns, [&txns[1..3], &txns[4..]].concat());
+    }
 }
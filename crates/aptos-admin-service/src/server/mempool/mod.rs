@@ -0,0 +1,143 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Error;
+use aptos_logger::info;
+use aptos_mempool::{AccountMempoolTransactions, MempoolClientRequest, MempoolClientSender};
+use aptos_system_utils::utils::{reply_with, reply_with_status};
+use aptos_types::account_address::AccountAddress;
+use futures::{channel::oneshot, SinkExt};
+use hyper::{Body, Request, Response, StatusCode};
+use std::collections::HashMap;
+
+/// Returns the transactions of the account (given by the `address` query parameter) that
+/// are in mempool, along with whether they are parked and the sequence number gap that
+/// blocks them (as YAML).
+pub async fn handle_get_account_transactions_request(
+    req: Request<Body>,
+    mempool_client_sender: MempoolClientSender,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+
+    let address: AccountAddress = match query_pairs.get("address") {
+        Some(val) => match val.parse() {
+            Ok(val) => val,
+            Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+        },
+        None => {
+            return Ok(reply_with_status(
+                StatusCode::BAD_REQUEST,
+                "Missing address query parameter.",
+            ))
+        },
+    };
+
+    info!("Getting mempool transactions of account {address}.");
+
+    match get_account_transactions(mempool_client_sender, address)
+        .await
+        .and_then(|txns| serde_yaml::to_string(&txns).map_err(Error::msg))
+    {
+        Ok(result) => Ok(reply_with(vec![], result)),
+        Err(e) => {
+            info!("Failed to get mempool transactions of account {address}: {e:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        },
+    }
+}
+
+async fn get_account_transactions(
+    mut mempool_client_sender: MempoolClientSender,
+    address: AccountAddress,
+) -> anyhow::Result<AccountMempoolTransactions> {
+    let (req_sender, callback) = oneshot::channel();
+    mempool_client_sender
+        .send(MempoolClientRequest::GetAccountTransactions(
+            address, req_sender,
+        ))
+        .await?;
+    Ok(callback.await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::HashValue;
+    use aptos_mempool::{
+        BroadcastState, PendingTransactionInfo, PendingTransactionState, SequenceNumberGap,
+    };
+    use futures::{channel::mpsc, StreamExt};
+    use std::time::SystemTime;
+
+    fn account_transactions() -> AccountMempoolTransactions {
+        AccountMempoolTransactions {
+            account_sequence_number: Some(3),
+            sequence_number_gap: Some(SequenceNumberGap { start: 4, end: 6 }),
+            transactions: vec![PendingTransactionInfo {
+                hash: HashValue::random(),
+                sequence_number: 6,
+                gas_unit_price: 100,
+                ranking_score: 100,
+                insertion_time: SystemTime::now(),
+                expiration_timestamp_secs: 1000,
+                state: PendingTransactionState::Parked,
+                broadcast_state: BroadcastState::NotReady,
+            }],
+        }
+    }
+
+    async fn get(uri: &str, txns: AccountMempoolTransactions) -> (StatusCode, String) {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mempool = tokio::spawn(async move {
+            if let Some(MempoolClientRequest::GetAccountTransactions(address, callback)) =
+                receiver.next().await
+            {
+                callback.send(txns).unwrap();
+                Some(address)
+            } else {
+                None
+            }
+        });
+
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+        let resp = handle_get_account_transactions_request(req, sender)
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        if status == StatusCode::OK {
+            assert_eq!(mempool.await.unwrap(), Some(AccountAddress::ONE));
+        }
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions() {
+        let txns = account_transactions();
+        let (status, body) = get(
+            "/debug/mempool/account_transactions?address=0x1",
+            txns.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_yaml::from_str::<AccountMempoolTransactions>(&body).unwrap(),
+            txns
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_bad_request() {
+        for uri in [
+            "/debug/mempool/account_transactions",
+            "/debug/mempool/account_transactions?address=0xzz",
+        ] {
+            let (status, _) = get(uri, account_transactions()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+}
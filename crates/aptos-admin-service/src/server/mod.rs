@@ -4,9 +4,11 @@
 use aptos_config::config::{AuthenticationConfig, NodeConfig};
 use aptos_consensus::{
     persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
+    transaction_filter::TransactionFilter,
 };
 use aptos_infallible::RwLock;
 use aptos_logger::info;
+use aptos_mempool::MempoolClientSender;
 use aptos_storage_interface::DbReaderWriter;
 use aptos_system_utils::utils::reply_with_status;
 #[cfg(target_os = "linux")]
@@ -26,6 +28,8 @@ use std::{
 use tokio::runtime::Runtime;
 
 mod consensus;
+mod mempool;
+mod transaction_filter;
 
 #[derive(Default)]
 pub struct Context {
@@ -34,6 +38,8 @@ pub struct Context {
     aptos_db: RwLock<Option<Arc<DbReaderWriter>>>,
     consensus_db: RwLock<Option<Arc<StorageWriteProxy>>>,
     quorum_store_db: RwLock<Option<Arc<QuorumStoreDB>>>,
+    consensus_transaction_filter: RwLock<Option<TransactionFilter>>,
+    mempool_client_sender: RwLock<Option<MempoolClientSender>>,
 }
 
 impl Context {
@@ -49,6 +55,14 @@ impl Context {
         *self.consensus_db.write() = Some(consensus_db);
         *self.quorum_store_db.write() = Some(quorum_store_db);
     }
+
+    fn set_consensus_transaction_filter(&self, transaction_filter: TransactionFilter) {
+        *self.consensus_transaction_filter.write() = Some(transaction_filter);
+    }
+
+    fn set_mempool_client_sender(&self, mempool_client_sender: MempoolClientSender) {
+        *self.mempool_client_sender.write() = Some(mempool_client_sender);
+    }
 }
 
 pub struct AdminService {
@@ -107,6 +121,16 @@ impl AdminService {
             .set_consensus_dbs(consensus_db, quorum_store_db)
     }
 
+    pub fn set_consensus_transaction_filter(&self, transaction_filter: TransactionFilter) {
+        self.context
+            .set_consensus_transaction_filter(transaction_filter)
+    }
+
+    pub fn set_mempool_client_sender(&self, mempool_client_sender: MempoolClientSender) {
+        self.context
+            .set_mempool_client_sender(mempool_client_sender)
+    }
+
     fn start(&self, address: SocketAddr, enabled: bool) {
         let context = self.context.clone();
         self.runtime.spawn(async move {
@@ -210,6 +234,63 @@ impl AdminService {
                     ))
                 }
             },
+            (hyper::Method::GET, "/debug/consensus/transaction_filter") => {
+                let transaction_filter = context.consensus_transaction_filter.read().clone();
+                if let Some(transaction_filter) = transaction_filter {
+                    transaction_filter::handle_get_transaction_filter_request(
+                        req,
+                        transaction_filter,
+                    )
+                    .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Consensus transaction filter is not available.",
+                    ))
+                }
+            },
+            (hyper::Method::POST, "/debug/consensus/transaction_filter") => {
+                let transaction_filter = context.consensus_transaction_filter.read().clone();
+                if let Some(transaction_filter) = transaction_filter {
+                    transaction_filter::handle_update_transaction_filter_request(
+                        req,
+                        transaction_filter,
+                    )
+                    .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Consensus transaction filter is not available.",
+                    ))
+                }
+            },
+            (hyper::Method::POST, "/debug/consensus/transaction_filter/dry_run") => {
+                let aptos_db = context.aptos_db.read().clone();
+                if let Some(aptos_db) = aptos_db {
+                    transaction_filter::handle_dry_run_transaction_filter_request(
+                        req,
+                        aptos_db.reader.clone(),
+                    )
+                    .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "AptosDB is not available.",
+                    ))
+                }
+            },
+            (hyper::Method::GET, "/debug/mempool/account_transactions") => {
+                let mempool_client_sender = context.mempool_client_sender.read().clone();
+                if let Some(mempool_client_sender) = mempool_client_sender {
+                    mempool::handle_get_account_transactions_request(req, mempool_client_sender)
+                        .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "Mempool is not available.",
+                    ))
+                }
+            },
             _ => Ok(reply_with_status(StatusCode::NOT_FOUND, "Not found.")),
         }
     }
@@ -0,0 +1,425 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{bail, Error};
+use aptos_config::config::transaction_filter_type::Filter;
+use aptos_consensus::transaction_filter::TransactionFilter;
+use aptos_logger::info;
+use aptos_storage_interface::{DbReader, MAX_REQUEST_LIMIT};
+use aptos_system_utils::utils::{reply_with, reply_with_status, spawn_blocking};
+use aptos_types::transaction::Transaction;
+use hyper::{Body, Request, Response, StatusCode};
+use std::{collections::HashMap, sync::Arc};
+
+const DEFAULT_DRY_RUN_NUM_BLOCKS: u64 = 10;
+const MAX_DRY_RUN_NUM_BLOCKS: u64 = 1000;
+
+/// Returns the active consensus transaction filter (as YAML)
+pub async fn handle_get_transaction_filter_request(
+    _req: Request<Body>,
+    transaction_filter: TransactionFilter,
+) -> hyper::Result<Response<Body>> {
+    match serde_yaml::to_string(transaction_filter.get_filter().as_ref()) {
+        Ok(filter) => Ok(reply_with(vec![], filter)),
+        Err(e) => Ok(reply_with_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        )),
+    }
+}
+
+/// Replaces the active consensus transaction filter with the filter (as YAML) in the
+/// request body. The new filter is applied starting from the next block that is prepared.
+pub async fn handle_update_transaction_filter_request(
+    req: Request<Body>,
+    transaction_filter: TransactionFilter,
+) -> hyper::Result<Response<Body>> {
+    let filter = match parse_filter(req).await? {
+        Ok(filter) => filter,
+        Err(e) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    info!("Replacing the consensus transaction filter.");
+    transaction_filter.update_filter(filter);
+
+    Ok(reply_with_status(
+        StatusCode::OK,
+        "Transaction filter updated.",
+    ))
+}
+
+/// Evaluates the filter (as YAML) in the request body against the user transactions of the
+/// last `num_blocks` committed blocks, and returns the transactions that would be denied.
+/// The active filter is not modified.
+pub async fn handle_dry_run_transaction_filter_request(
+    req: Request<Body>,
+    aptos_db: Arc<dyn DbReader>,
+) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+
+    let num_blocks: u64 = match query_pairs.get("num_blocks") {
+        Some(val) => match val.parse() {
+            Ok(val) => val,
+            Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+        },
+        None => DEFAULT_DRY_RUN_NUM_BLOCKS,
+    };
+    if num_blocks == 0 || num_blocks > MAX_DRY_RUN_NUM_BLOCKS {
+        return Ok(reply_with_status(
+            StatusCode::BAD_REQUEST,
+            format!("num_blocks must be between 1 and {MAX_DRY_RUN_NUM_BLOCKS}."),
+        ));
+    }
+
+    let filter = match parse_filter(req).await? {
+        Ok(filter) => filter,
+        Err(e) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+
+    info!("Dry running transaction filter against the last {num_blocks} blocks.");
+
+    match spawn_blocking(move || {
+        dry_run_filter(aptos_db.as_ref(), &filter, num_blocks, MAX_REQUEST_LIMIT)
+    })
+    .await
+    {
+        Ok(result) => {
+            info!("Finished dry running transaction filter.");
+            Ok(reply_with(vec![], result))
+        },
+        Err(e) => {
+            info!("Failed to dry run transaction filter: {e:?}");
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        },
+    }
+}
+
+async fn parse_filter(req: Request<Body>) -> hyper::Result<anyhow::Result<Filter>> {
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    Ok(serde_yaml::from_slice::<Filter>(&body).map_err(Error::msg))
+}
+
+/// Blocks are read in pages of at most `page_size` transactions, as the DB rejects larger
+/// requests.
+fn dry_run_filter(
+    aptos_db: &dyn DbReader,
+    filter: &Filter,
+    num_blocks: u64,
+    page_size: u64,
+) -> anyhow::Result<String> {
+    let mut body = String::new();
+
+    let ledger_version = aptos_db.get_latest_ledger_info_version()?;
+    let (_, _, latest_block) = aptos_db.get_block_info_by_version(ledger_version)?;
+    let latest_height = latest_block.height();
+    let first_height = latest_height.saturating_sub(num_blocks - 1);
+
+    let mut num_denied = 0;
+    for height in first_height..=latest_height {
+        let (start_version, end_version, block) = aptos_db.get_block_info_by_height(height)?;
+        if end_version < start_version {
+            bail!("Invalid version range for block at height {height}.");
+        }
+        let block_id = block.hash()?;
+
+        let mut page_start_version = start_version;
+        while page_start_version <= end_version {
+            let limit = std::cmp::min(page_size, end_version - page_start_version + 1);
+            let txns =
+                aptos_db.get_transactions(page_start_version, limit, ledger_version, false)?;
+            if txns.transactions.len() as u64 != limit {
+                bail!("Failed to read the transactions of the block at height {height}.");
+            }
+
+            for (idx, txn) in txns.transactions.iter().enumerate() {
+                if let Transaction::UserTransaction(signed_txn) = txn {
+                    if !filter.allows(block_id, block.proposed_time(), signed_txn) {
+                        num_denied += 1;
+                        body.push_str(&format!(
+                            "[height: {}, block_id: {:?}, version: {}, hash: {:?}, sender: {}, sequence_number: {}]\n",
+                            height,
+                            block_id,
+                            page_start_version + idx as u64,
+                            signed_txn.committed_hash(),
+                            signed_txn.sender(),
+                            signed_txn.sequence_number(),
+                        ));
+                    }
+                }
+            }
+            page_start_version += limit;
+        }
+    }
+
+    body.push_str(&format!(
+        "\nDone, {num_denied} transaction(s) in blocks [{first_height}, {latest_height}] would be denied.",
+    ));
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
+    use aptos_storage_interface::{AptosDbError, Result as DbResult};
+    use aptos_types::{
+        account_address::AccountAddress,
+        account_config::NewBlockEvent,
+        chain_id::ChainId,
+        transaction::{
+            RawTransaction, Script, SignedTransaction, TransactionListWithProof,
+            TransactionPayload, Version,
+        },
+    };
+
+    const NUM_BLOCKS: u64 = 3;
+    const TXNS_PER_BLOCK: u64 = 2;
+
+    fn create_signed_transaction(
+        sender: AccountAddress,
+        sequence_number: u64,
+    ) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_transaction = RawTransaction::new(
+            sender,
+            sequence_number,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+            0,
+            0,
+            ChainId::new(10),
+        );
+        SignedTransaction::new(
+            raw_transaction.clone(),
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        )
+    }
+
+    /// Blocks of user transactions, alternating between two senders
+    struct MockDb {
+        max_batch_size: u64,
+    }
+
+    impl Default for MockDb {
+        fn default() -> Self {
+            Self {
+                max_batch_size: MAX_REQUEST_LIMIT,
+            }
+        }
+    }
+
+    impl MockDb {
+        fn sender(version: Version) -> AccountAddress {
+            if version % 2 == 0 {
+                AccountAddress::ONE
+            } else {
+                AccountAddress::TWO
+            }
+        }
+
+        fn block_info(height: u64) -> (Version, Version, NewBlockEvent) {
+            let start_version = height * TXNS_PER_BLOCK;
+            let block = NewBlockEvent::new(
+                AccountAddress::new(*HashValue::sha3_256_of(&height.to_le_bytes())),
+                1,
+                height,
+                height,
+                vec![],
+                AccountAddress::ONE,
+                vec![],
+                height,
+            );
+            (start_version, start_version + TXNS_PER_BLOCK - 1, block)
+        }
+    }
+
+    impl DbReader for MockDb {
+        fn get_latest_ledger_info_version(&self) -> DbResult<Version> {
+            Ok(NUM_BLOCKS * TXNS_PER_BLOCK - 1)
+        }
+
+        fn get_block_info_by_version(
+            &self,
+            version: Version,
+        ) -> DbResult<(Version, Version, NewBlockEvent)> {
+            Ok(Self::block_info(version / TXNS_PER_BLOCK))
+        }
+
+        fn get_block_info_by_height(
+            &self,
+            height: u64,
+        ) -> DbResult<(Version, Version, NewBlockEvent)> {
+            Ok(Self::block_info(height))
+        }
+
+        fn get_transactions(
+            &self,
+            start_version: Version,
+            batch_size: u64,
+            _ledger_version: Version,
+            _fetch_events: bool,
+        ) -> DbResult<TransactionListWithProof> {
+            if batch_size > self.max_batch_size {
+                return Err(AptosDbError::TooManyRequested(
+                    batch_size,
+                    self.max_batch_size,
+                ));
+            }
+            let mut txns = TransactionListWithProof::new_empty();
+            txns.transactions = (start_version..start_version + batch_size)
+                .map(|version| {
+                    Transaction::UserTransaction(create_signed_transaction(
+                        Self::sender(version),
+                        version,
+                    ))
+                })
+                .collect();
+            Ok(txns)
+        }
+    }
+
+    fn request(uri: &str, body: impl Into<Body>) -> Request<Body> {
+        Request::post(uri).body(body.into()).unwrap()
+    }
+
+    async fn body_string(resp: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_and_update_transaction_filter() {
+        let transaction_filter = TransactionFilter::new(Filter::empty());
+        // The consensus observer holds a clone of the filter
+        let observer_filter = transaction_filter.clone();
+        let txns = vec![
+            create_signed_transaction(AccountAddress::ONE, 0),
+            create_signed_transaction(AccountAddress::TWO, 0),
+        ];
+        assert_eq!(
+            observer_filter.filter(HashValue::random(), 0, txns.clone()),
+            txns
+        );
+
+        // Replace the filter
+        let filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+        let resp = handle_update_transaction_filter_request(
+            request(
+                "/debug/consensus/transaction_filter",
+                serde_yaml::to_string(&filter).unwrap(),
+            ),
+            transaction_filter.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The new filter is returned, and applied by the consensus observer
+        let resp = handle_get_transaction_filter_request(
+            request("/debug/consensus/transaction_filter", Body::empty()),
+            transaction_filter,
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            serde_yaml::from_str::<Filter>(&body_string(resp).await).unwrap(),
+            filter
+        );
+        assert_eq!(
+            observer_filter.filter(HashValue::random(), 0, txns.clone()),
+            txns[1..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_transaction_filter_bad_request() {
+        let filter = Filter::empty().add_deny_sender(AccountAddress::ONE);
+        let transaction_filter = TransactionFilter::new(filter.clone());
+
+        let resp = handle_update_transaction_filter_request(
+            request("/debug/consensus/transaction_filter", "rules: [NotARule]"),
+            transaction_filter.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // The active filter is kept
+        assert_eq!(*transaction_filter.get_filter(), filter);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_transaction_filter() {
+        let aptos_db: Arc<dyn DbReader> = Arc::new(MockDb::default());
+        let filter =
+            serde_yaml::to_string(&Filter::empty().add_deny_sender(AccountAddress::ONE)).unwrap();
+
+        // Only the transactions of the last 2 blocks from the denied sender are returned
+        let resp = handle_dry_run_transaction_filter_request(
+            request(
+                "/debug/consensus/transaction_filter/dry_run?num_blocks=2",
+                filter.clone(),
+            ),
+            aptos_db.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = body_string(resp).await;
+        let denied_versions: Vec<_> = body
+            .lines()
+            .filter_map(|line| line.split("version: ").nth(1))
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(denied_versions, vec!["2", "4"]);
+        assert!(body.ends_with("2 transaction(s) in blocks [1, 2] would be denied."));
+
+        // Invalid block counts and filters are rejected
+        for (uri, filter) in [
+            (
+                "/debug/consensus/transaction_filter/dry_run?num_blocks=0",
+                filter.as_str(),
+            ),
+            (
+                "/debug/consensus/transaction_filter/dry_run?num_blocks=1001",
+                filter.as_str(),
+            ),
+            (
+                "/debug/consensus/transaction_filter/dry_run",
+                "rules: [NotARule]",
+            ),
+        ] {
+            let resp = handle_dry_run_transaction_filter_request(
+                request(uri, filter.to_string()),
+                aptos_db.clone(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_transaction_filter_pages_through_blocks() {
+        // Blocks are larger than what the DB returns per request
+        let aptos_db = MockDb { max_batch_size: 1 };
+        let filter = Filter::empty().add_deny_sender(AccountAddress::TWO);
+
+        assert!(dry_run_filter(&aptos_db, &filter, NUM_BLOCKS, TXNS_PER_BLOCK).is_err());
+        let body = dry_run_filter(&aptos_db, &filter, NUM_BLOCKS, 1).unwrap();
+        let denied_versions: Vec<_> = body
+            .lines()
+            .filter_map(|line| line.split("version: ").nth(1))
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(denied_versions, vec!["1", "3", "5"]);
+        assert!(body.ends_with("3 transaction(s) in blocks [0, 2] would be denied."));
+    }
+}
@@ -25,7 +25,10 @@ pub fn deserialize_uleb128(buffer: &[u8]) -> anyhow::Result<(u64, usize)> {
 
     // Go through values until the full number is found
     loop {
-        let byte = buffer[i];
+        let byte = match buffer.get(i) {
+            Some(byte) => *byte,
+            None => bail!("incomplete ULEB128 repr for usize"),
+        };
         let cur = (byte & 0x7F) as u64;
         if (cur << shift) >> shift != cur {
             bail!("invalid ULEB128 repr for usize");
@@ -84,6 +87,12 @@ mod tests {
         test_deserialize_case("FFFFFFFFFFFFFFFFFF01", u64::MAX, 10);
     }
 
+    #[test]
+    fn test_deserialize_incomplete() {
+        assert!(deserialize_uleb128(&[]).is_err());
+        assert!(deserialize_uleb128(&hex::decode("E58E").unwrap()).is_err());
+    }
+
     fn test_serialize_case(value: u64, expected: &str) {
         let mut buffer = vec![];
         serialize_uleb128(&mut buffer, value).expect("Should not fail");
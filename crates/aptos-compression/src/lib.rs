@@ -15,6 +15,7 @@ use thiserror::Error;
 /// sent across the network (e.g., by state sync and consensus).
 /// Internally, it uses LZ4 in fast mode to compress the data.
 /// See <https://github.com/10xGenomics/lz4-rs> for more information.
+/// A zstd alternative is also offered (see the `zstd` module).
 ///
 /// Note: the crate also exposes some basic compression metrics
 /// that can be used to track the cumulative compression ratio
@@ -23,6 +24,7 @@ pub mod client;
 mod metrics;
 #[cfg(test)]
 mod tests;
+pub mod zstd;
 
 /// The acceleration parameter to use for FAST compression mode.
 /// This was determined anecdotally.
@@ -74,6 +74,170 @@ fn test_compression_limits() {
     assert!(maybe_decompressed_bytes.is_err());
 }
 
+#[test]
+fn test_zstd_compression() {
+    // Test compress random bytes
+    let raw_bytes: Vec<_> = (0..MIB).map(|_| rand::thread_rng().gen::<u8>()).collect();
+    test_zstd_compress_and_decompress(raw_bytes);
+
+    // Test epoch ending ledger infos
+    let epoch_ending_ledger_infos = create_epoch_ending_ledger_infos(0, 999);
+    test_zstd_compress_and_decompress(epoch_ending_ledger_infos);
+
+    // Test transaction outputs with proof
+    let outputs_with_proof = create_output_list_with_proof(13434, 17000, 19000);
+    test_zstd_compress_and_decompress(outputs_with_proof);
+
+    // Verify that LZ4 compressed data is rejected
+    let bcs_encoded_bytes = bcs::to_bytes(&create_epoch_ending_ledger_infos(0, 10)).unwrap();
+    let compressed_bytes = crate::compress(
+        bcs_encoded_bytes,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let maybe_decompressed_bytes = crate::zstd::decompress(
+        &compressed_bytes,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    );
+    assert!(maybe_decompressed_bytes.is_err());
+}
+
+#[test]
+fn test_zstd_compression_limits() {
+    // Create test data
+    let too_small_bytes = 1;
+    let transactions_with_proof = create_transaction_list_with_proof(1000, 1999, 1999, true);
+
+    // Test compression limit
+    let bcs_encoded_bytes = bcs::to_bytes(&transactions_with_proof).unwrap();
+    let maybe_compressed_bytes = crate::zstd::compress(
+        bcs_encoded_bytes,
+        CompressionClient::StateSync,
+        too_small_bytes,
+    );
+    assert!(maybe_compressed_bytes.is_err());
+
+    // Test decompression limit
+    let bcs_encoded_bytes = bcs::to_bytes(&transactions_with_proof).unwrap();
+    let compressed_bytes = crate::zstd::compress(
+        bcs_encoded_bytes,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let maybe_decompressed_bytes = crate::zstd::decompress(
+        &compressed_bytes,
+        CompressionClient::StateSync,
+        too_small_bytes,
+    );
+    assert!(maybe_decompressed_bytes.is_err());
+}
+
+#[test]
+fn test_zstd_dictionary_compression() {
+    // Create a dictionary trained on BCS encoded transactions. Note: this
+    // is the only test that uses the mempool client, as dictionaries are global.
+    let client = CompressionClient::Mempool;
+    let samples: Vec<_> = (0..1000)
+        .map(|sequence_number| bcs::to_bytes(&create_test_transaction(sequence_number)).unwrap())
+        .collect();
+    let dictionary = crate::zstd::train_dictionary(&samples, 16 * 1024).unwrap();
+
+    // Compress a transaction without the dictionary
+    let transaction = create_test_transaction(1234);
+    let bcs_encoded_bytes = bcs::to_bytes(&transaction).unwrap();
+    let compressed_without_dictionary =
+        crate::zstd::compress(bcs_encoded_bytes.clone(), client, MAX_COMPRESSION_SIZE).unwrap();
+
+    // Verify that the transaction can't be compressed with a dictionary that isn't set
+    assert!(crate::zstd::compress_with_dictionary(
+        bcs_encoded_bytes.clone(),
+        client,
+        MAX_COMPRESSION_SIZE
+    )
+    .is_err());
+
+    // Set the dictionary and compress the transaction with it
+    let dictionary = crate::zstd::ZstdDictionary::new(&dictionary).unwrap();
+    let dictionary_id = dictionary.id();
+    crate::zstd::set_dictionary(client, dictionary);
+    assert_eq!(
+        crate::zstd::get_dictionary_ids().get(client.get_label()),
+        Some(&dictionary_id)
+    );
+    let compressed_with_dictionary = crate::zstd::compress_with_dictionary(
+        bcs_encoded_bytes.clone(),
+        client,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    assert!(compressed_with_dictionary.len() < compressed_without_dictionary.len());
+
+    // Verify that the dictionary is only used when requested
+    let compressed_bytes =
+        crate::zstd::compress(bcs_encoded_bytes.clone(), client, MAX_COMPRESSION_SIZE).unwrap();
+    assert_eq!(compressed_bytes, compressed_without_dictionary);
+
+    // Verify that both can be decompressed
+    for compressed_bytes in [compressed_with_dictionary, compressed_without_dictionary] {
+        let decompressed_bytes =
+            crate::zstd::decompress(&compressed_bytes, client, MAX_COMPRESSION_SIZE).unwrap();
+        assert_eq!(decompressed_bytes, bcs_encoded_bytes);
+    }
+
+    // Verify that data compressed with an unknown dictionary is rejected
+    let compressed_bytes = crate::zstd::compress_with_dictionary(
+        bcs_encoded_bytes.clone(),
+        client,
+        MAX_COMPRESSION_SIZE,
+    )
+    .unwrap();
+    let maybe_decompressed_bytes = crate::zstd::decompress(
+        &compressed_bytes,
+        CompressionClient::StateSync,
+        MAX_COMPRESSION_SIZE,
+    );
+    assert!(maybe_decompressed_bytes.is_err());
+}
+
+#[test]
+fn test_zstd_stream_compression() {
+    // Compress transaction outputs a piece at a time
+    let outputs_with_proof = create_output_list_with_proof(13434, 17000, 19000);
+    let bcs_encoded_bytes = bcs::to_bytes(&outputs_with_proof).unwrap();
+    let mut compressor = crate::zstd::StreamCompressor::new().unwrap();
+    let mut compressed_bytes = Vec::new();
+    for chunk in bcs_encoded_bytes.chunks(1000) {
+        compressed_bytes.extend(compressor.compress(chunk).unwrap());
+    }
+    compressed_bytes.extend(compressor.finish().unwrap());
+    assert!(compressed_bytes.len() < bcs_encoded_bytes.len());
+
+    // Decompress the data in differently sized pieces, and verify that the
+    // end of the frame is only reached at the end of the data.
+    let mut decompressor = crate::zstd::StreamDecompressor::new().unwrap();
+    let mut decompressed_bytes = Vec::new();
+    for chunk in compressed_bytes.chunks(777) {
+        assert!(!decompressor.is_frame_finished());
+        decompressed_bytes.extend(decompressor.decompress(chunk).unwrap());
+    }
+    assert!(decompressor.is_frame_finished());
+    assert_eq!(decompressed_bytes, bcs_encoded_bytes);
+
+    // Verify that truncated data is detected
+    let mut decompressor = crate::zstd::StreamDecompressor::new().unwrap();
+    decompressor
+        .decompress(&compressed_bytes[..compressed_bytes.len() - 1])
+        .unwrap();
+    assert!(!decompressor.is_frame_finished());
+
+    // Verify that corrupted data is rejected
+    let mut decompressor = crate::zstd::StreamDecompressor::new().unwrap();
+    assert!(decompressor.decompress(&[0u8; 100]).is_err());
+}
+
 /// Ensures that the given object can be compressed and decompressed successfully
 /// when BCS encoded.
 fn test_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serialize>(object: T) {
@@ -95,6 +259,22 @@ fn test_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serial
     assert_eq!(object, decoded_object);
 }
 
+/// Ensures that the given object can be compressed and decompressed successfully
+/// with zstd when BCS encoded.
+fn test_zstd_compress_and_decompress<T: Debug + DeserializeOwned + PartialEq + Serialize>(
+    object: T,
+) {
+    let client = CompressionClient::StateSync;
+    let bcs_encoded_bytes = bcs::to_bytes(&object).unwrap();
+    let compressed_bytes =
+        crate::zstd::compress(bcs_encoded_bytes, client, MAX_COMPRESSION_SIZE).unwrap();
+    let decompressed_bytes =
+        crate::zstd::decompress(&compressed_bytes, client, MAX_COMPRESSION_SIZE).unwrap();
+    let decoded_object = bcs::from_bytes::<T>(&decompressed_bytes).unwrap();
+
+    assert_eq!(object, decoded_object);
+}
+
 /// Creates a test epoch change proof
 fn create_epoch_ending_ledger_infos(
     start_epoch: u64,
@@ -0,0 +1,370 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A zstd alternative to the default LZ4 compression. It is slower than
+//! LZ4, but achieves a markedly better compression ratio, which is useful
+//! when bandwidth is more precious than CPU (e.g., between validators in
+//! different regions).
+//!
+//! Small messages (e.g., consensus votes and mempool broadcasts) compress
+//! poorly on their own, so each client can also be given a dictionary,
+//! trained on samples of its messages (see `train_dictionary`). Frames
+//! record the ID of the dictionary they were compressed with, so data should
+//! only be compressed with the dictionary (see `compress_with_dictionary`)
+//! if the peer is known to have the same dictionary for the client. Frames
+//! without a dictionary can always be decompressed.
+//!
+//! Data that is too large to be held in memory at once (e.g., backup files)
+//! can be compressed into a single frame a piece at a time, using the
+//! streaming API (see `StreamCompressor` and `StreamDecompressor`).
+
+use crate::{
+    client::CompressionClient, create_compression_error, create_decompression_error, metrics,
+    CompressedData, Error,
+};
+use aptos_infallible::RwLock;
+use once_cell::sync::Lazy;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+use zstd::{
+    bulk::{Compressor, Decompressor},
+    dict::{DecoderDictionary, EncoderDictionary},
+    stream::raw::{Decoder, Encoder, Operation, OutBuffer},
+    zstd_safe,
+};
+
+/// The zstd compression level. Level 3 is the zstd default, and offers a
+/// good trade-off between the compression ratio and speed.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The size of the output buffer used by the streaming API
+const STREAM_BUFFER_BYTES: usize = 128 * 1024;
+
+/// The dictionaries to use for each client (if any)
+static DICTIONARIES: Lazy<RwLock<HashMap<&'static str, Arc<ZstdDictionary>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// A zstd dictionary, prepared for both compression and decompression
+pub struct ZstdDictionary {
+    id: u32,
+    encoder_dictionary: EncoderDictionary<'static>,
+    decoder_dictionary: DecoderDictionary<'static>,
+}
+
+impl ZstdDictionary {
+    /// Prepares the given raw dictionary (e.g., as returned by `train_dictionary`)
+    pub fn new(dictionary: &[u8]) -> Result<Self, Error> {
+        let id = zstd_safe::get_dict_id_from_dict(dictionary)
+            .ok_or_else(|| {
+                Error::CompressionError("The zstd dictionary is missing a dictionary ID!".into())
+            })?
+            .get();
+        Ok(Self {
+            id,
+            encoder_dictionary: EncoderDictionary::copy(dictionary, COMPRESSION_LEVEL),
+            decoder_dictionary: DecoderDictionary::copy(dictionary),
+        })
+    }
+
+    /// Returns the ID of the dictionary
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+/// Trains a dictionary of at most `max_size` bytes on the given samples
+pub fn train_dictionary<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Vec<u8>, Error> {
+    zstd::dict::from_samples(samples, max_size).map_err(|error| {
+        Error::CompressionError(format!("Failed to train the zstd dictionary: {}", error))
+    })
+}
+
+/// Sets the dictionary to use for the given client
+pub fn set_dictionary(client: CompressionClient, dictionary: ZstdDictionary) {
+    DICTIONARIES
+        .write()
+        .insert(client.get_label(), Arc::new(dictionary));
+}
+
+/// Returns the dictionary of the given client (if one is set)
+fn get_dictionary(client: &CompressionClient) -> Option<Arc<ZstdDictionary>> {
+    DICTIONARIES.read().get(client.get_label()).cloned()
+}
+
+/// Returns the IDs of the dictionaries that are set, by client label
+pub fn get_dictionary_ids() -> BTreeMap<String, u32> {
+    DICTIONARIES
+        .read()
+        .iter()
+        .map(|(label, dictionary)| (label.to_string(), dictionary.id))
+        .collect()
+}
+
+/// Compresses the raw data stream using zstd (without a dictionary)
+pub fn compress(
+    raw_data: Vec<u8>,
+    client: CompressionClient,
+    max_bytes: usize,
+) -> Result<CompressedData, Error> {
+    compress_internal(raw_data, client, max_bytes, None)
+}
+
+/// Compresses the raw data stream using zstd and the client's dictionary.
+/// Note: the dictionary must be set for the client.
+pub fn compress_with_dictionary(
+    raw_data: Vec<u8>,
+    client: CompressionClient,
+    max_bytes: usize,
+) -> Result<CompressedData, Error> {
+    match get_dictionary(&client) {
+        Some(dictionary) => compress_internal(raw_data, client, max_bytes, Some(dictionary)),
+        None => create_compression_error(
+            &client,
+            "The zstd dictionary is not set for the client!".into(),
+        ),
+    }
+}
+
+/// Compresses the raw data stream using zstd (and the given dictionary, if any)
+fn compress_internal(
+    raw_data: Vec<u8>,
+    client: CompressionClient,
+    max_bytes: usize,
+    dictionary: Option<Arc<ZstdDictionary>>,
+) -> Result<CompressedData, Error> {
+    // Start the compression timer
+    let start_time = Instant::now();
+
+    // Ensure that the raw data size is not greater than the max bytes limit
+    if raw_data.len() > max_bytes {
+        let error_string = format!(
+            "Raw data size greater than max bytes limit: {}, max: {}",
+            raw_data.len(),
+            max_bytes
+        );
+        return create_compression_error(&client, error_string);
+    }
+
+    // Compress the data. The frame records the decompressed size, which is
+    // checked against the max size limit before decompressing.
+    let compressor = match &dictionary {
+        Some(dictionary) => Compressor::with_prepared_dictionary(&dictionary.encoder_dictionary),
+        None => Compressor::new(COMPRESSION_LEVEL),
+    };
+    let compressed_data = match compressor.and_then(|mut compressor| compressor.compress(&raw_data))
+    {
+        Ok(compressed_data) => compressed_data,
+        Err(error) => {
+            let error_string = format!("Failed to compress the data: {}", error);
+            return create_compression_error(&client, error_string);
+        },
+    };
+
+    // Ensure that the compressed data size is not greater than the max byte limit
+    if compressed_data.len() > max_bytes {
+        let error_string = format!(
+            "Compressed size greater than max bytes limit: {}, max: {}",
+            compressed_data.len(),
+            max_bytes
+        );
+        return create_compression_error(&client, error_string);
+    }
+
+    // Stop the timer and update the metrics
+    metrics::observe_compression_operation_time(&client, start_time);
+    metrics::update_compression_metrics(&client, &raw_data, &compressed_data);
+
+    Ok(compressed_data)
+}
+
+/// Decompresses the zstd compressed data stream
+pub fn decompress(
+    compressed_data: &CompressedData,
+    client: CompressionClient,
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    // Start the decompression timer
+    let start_time = Instant::now();
+
+    // Check the size of the data
+    let decompressed_size = match get_decompressed_size(compressed_data, max_size) {
+        Ok(size) => size,
+        Err(error) => {
+            let error_string = format!("Failed to get decompressed size: {}", error);
+            return create_decompression_error(&client, error_string);
+        },
+    };
+
+    // Ensure the data was compressed without a dictionary, or with the
+    // client's dictionary.
+    let dictionary = match zstd_safe::get_dict_id_from_frame(compressed_data) {
+        Some(frame_dictionary_id) => match get_dictionary(&client) {
+            Some(dictionary) if dictionary.id == frame_dictionary_id.get() => Some(dictionary),
+            dictionary => {
+                let error_string = format!(
+                    "Unexpected zstd dictionary ID: {}, expected: {:?}",
+                    frame_dictionary_id,
+                    dictionary.map(|dictionary| dictionary.id)
+                );
+                return create_decompression_error(&client, error_string);
+            },
+        },
+        None => None,
+    };
+
+    // Decompress the data
+    let decompressor = match &dictionary {
+        Some(dictionary) => Decompressor::with_prepared_dictionary(&dictionary.decoder_dictionary),
+        None => Decompressor::new(),
+    };
+    let raw_data = match decompressor
+        .and_then(|mut decompressor| decompressor.decompress(compressed_data, decompressed_size))
+    {
+        Ok(raw_data) => raw_data,
+        Err(error) => {
+            let error_string = format!("Failed to decompress the data: {}", error);
+            return create_decompression_error(&client, error_string);
+        },
+    };
+
+    // Stop the timer and update the metrics
+    metrics::observe_decompression_operation_time(&client, start_time);
+    metrics::update_decompression_metrics(&client, compressed_data, &raw_data);
+
+    Ok(raw_data)
+}
+
+/// Returns the decompressed size recorded in the zstd frame header
+fn get_decompressed_size(
+    compressed_data: &CompressedData,
+    max_size: usize,
+) -> Result<usize, Error> {
+    // Parse the size from the frame header
+    let size = match zstd_safe::get_frame_content_size(compressed_data) {
+        Ok(Some(size)) => size,
+        Ok(None) => {
+            return Err(Error::DecompressionError(
+                "The frame header is missing the decompressed size!".into(),
+            ))
+        },
+        Err(error) => {
+            return Err(Error::DecompressionError(format!(
+                "Failed to parse the frame header! Error: {:?}",
+                error
+            )))
+        },
+    };
+
+    // Ensure that the size is not greater than the max size limit
+    if size > max_size as u64 {
+        return Err(Error::DecompressionError(format!(
+            "Decompressed size in the frame header is too big: {} > {}",
+            size, max_size
+        )));
+    }
+
+    Ok(size as usize)
+}
+
+/// Compresses a stream of data into a single zstd frame, a piece at a time
+pub struct StreamCompressor {
+    encoder: Encoder<'static>,
+}
+
+impl StreamCompressor {
+    pub fn new() -> Result<Self, Error> {
+        let encoder = Encoder::new(COMPRESSION_LEVEL).map_err(|error| {
+            Error::CompressionError(format!("Failed to create the zstd encoder: {}", error))
+        })?;
+        Ok(Self { encoder })
+    }
+
+    /// Compresses the next piece of the stream, and returns the compressed
+    /// data that is ready so far (which may be empty).
+    pub fn compress(&mut self, raw_data: &[u8]) -> Result<CompressedData, Error> {
+        run_stream_operation(&mut self.encoder, raw_data)
+            .map(|(compressed_data, _)| compressed_data)
+            .map_err(|error| {
+                Error::CompressionError(format!("Failed to compress the data: {}", error))
+            })
+    }
+
+    /// Ends the frame, and returns the rest of the compressed data
+    pub fn finish(mut self) -> Result<CompressedData, Error> {
+        let mut compressed_data = Vec::new();
+        let mut buffer = vec![0; STREAM_BUFFER_BYTES];
+        loop {
+            let (written, remaining) = {
+                let mut output = OutBuffer::around(&mut buffer[..]);
+                let remaining = self.encoder.finish(&mut output, true).map_err(|error| {
+                    Error::CompressionError(format!("Failed to end the zstd frame: {}", error))
+                })?;
+                (output.pos(), remaining)
+            };
+            compressed_data.extend_from_slice(&buffer[..written]);
+            if remaining == 0 {
+                return Ok(compressed_data);
+            }
+        }
+    }
+}
+
+/// Decompresses a zstd frame produced by `StreamCompressor`, a piece at a time
+pub struct StreamDecompressor {
+    decoder: Decoder<'static>,
+    frame_finished: bool,
+}
+
+impl StreamDecompressor {
+    pub fn new() -> Result<Self, Error> {
+        let decoder = Decoder::new().map_err(|error| {
+            Error::DecompressionError(format!("Failed to create the zstd decoder: {}", error))
+        })?;
+        Ok(Self {
+            decoder,
+            frame_finished: false,
+        })
+    }
+
+    /// Decompresses the next piece of the stream, and returns the raw data
+    /// that is ready so far (which may be empty).
+    pub fn decompress(&mut self, compressed_data: &[u8]) -> Result<Vec<u8>, Error> {
+        if compressed_data.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (raw_data, hint) =
+            run_stream_operation(&mut self.decoder, compressed_data).map_err(|error| {
+                Error::DecompressionError(format!("Failed to decompress the data: {}", error))
+            })?;
+        self.frame_finished = hint == 0;
+        Ok(raw_data)
+    }
+
+    /// Returns true iff the end of the frame was reached, i.e., the stream
+    /// is not truncated.
+    pub fn is_frame_finished(&self) -> bool {
+        self.frame_finished
+    }
+}
+
+/// Runs the zstd operation on the whole input, and returns the output along
+/// with the hint of the operation, which is 0 once decompression reaches the
+/// end of the frame.
+fn run_stream_operation(
+    operation: &mut impl Operation,
+    mut input: &[u8],
+) -> std::io::Result<(Vec<u8>, usize)> {
+    let mut output = Vec::new();
+    let mut buffer = vec![0; STREAM_BUFFER_BYTES];
+    loop {
+        let status = operation.run_on_buffers(input, &mut buffer)?;
+        input = &input[status.bytes_read..];
+        output.extend_from_slice(&buffer[..status.bytes_written]);
+        if input.is_empty() && status.bytes_written < buffer.len() {
+            return Ok((output, status.remaining));
+        }
+    }
+}
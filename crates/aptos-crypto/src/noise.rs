@@ -595,6 +595,9 @@ pub struct NoiseSession {
     read_key: Vec<u8>,
     /// associated nonce (in practice the maximum u64 value cannot be reached)
     read_nonce: u64,
+    /// the ID of the stream the session is used for, which prefixes the nonces
+    /// (0 for the stream the handshake was performed on)
+    stream_id: u32,
 }
 
 impl NoiseSession {
@@ -606,6 +609,27 @@ impl NoiseSession {
             write_nonce: 0,
             read_key,
             read_nonce: 0,
+            stream_id: 0,
+        }
+    }
+
+    /// derives a session for another stream of the same connection (e.g., a QUIC stream).
+    /// The derived session uses the same keys, but its nonces are prefixed with the given
+    /// stream ID, so that they never collide with the nonces of the sessions of other streams.
+    /// Note: each stream of the connection must be given a different, non-zero ID.
+    pub fn derive_stream_session(&self, stream_id: u32) -> Self {
+        assert_ne!(
+            stream_id, 0,
+            "Stream ID 0 is reserved for the handshake stream"
+        );
+        Self {
+            valid: self.valid,
+            remote_public_key: self.remote_public_key,
+            write_key: self.write_key.clone(),
+            write_nonce: 0,
+            read_key: self.read_key.clone(),
+            read_nonce: 0,
+            stream_id,
         }
     }
 
@@ -638,7 +662,7 @@ impl NoiseSession {
         // encrypt in place
         let write_key = aes_key(&self.write_key[..]);
 
-        let mut nonce = [0u8; 4].to_vec();
+        let mut nonce = self.stream_id.to_be_bytes().to_vec();
         nonce.extend_from_slice(&self.write_nonce.to_be_bytes());
         assert_eq!(nonce.len(), 12);
         let nonce = aead::Nonce::assume_unique_for_key(
@@ -681,7 +705,7 @@ impl NoiseSession {
         // decrypt in place
         let read_key = aes_key(&self.read_key[..]);
 
-        let mut nonce = [0u8; 4].to_vec();
+        let mut nonce = self.stream_id.to_be_bytes().to_vec();
         nonce.extend_from_slice(&self.read_nonce.to_be_bytes());
         assert_eq!(nonce.len(), 12);
         let nonce = aead::Nonce::assume_unique_for_key(
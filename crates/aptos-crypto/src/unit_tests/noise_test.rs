@@ -106,6 +106,70 @@ fn simple_handshake() {
     }
 }
 
+#[test]
+fn derived_stream_sessions() {
+    // setup peers
+    let mut rng = ::rand::rngs::StdRng::from_seed(TEST_SEED);
+    let initiator_private = x25519::PrivateKey::generate(&mut rng);
+    let responder_private = x25519::PrivateKey::generate(&mut rng);
+    let responder_public = responder_private.public_key();
+    let initiator = NoiseConfig::new(initiator_private);
+    let responder = NoiseConfig::new(responder_private);
+
+    // perform the handshake
+    let prologue = b"prologue";
+    let mut first_message = vec![0u8; handshake_init_msg_len(0)];
+    let initiator_state = initiator
+        .initiate_connection(
+            &mut rng,
+            prologue,
+            responder_public,
+            None,
+            &mut first_message,
+        )
+        .unwrap();
+    let mut second_message = vec![0u8; handshake_resp_msg_len(0)];
+    let (_, mut responder_session) = responder
+        .respond_to_client_and_finalize(
+            &mut rng,
+            prologue,
+            &first_message,
+            None,
+            &mut second_message,
+        )
+        .unwrap();
+    let (_, initiator_session) = initiator
+        .finalize_connection(initiator_state, &second_message)
+        .unwrap();
+
+    // encrypt a message on a derived stream session
+    let mut initiator_stream_session = initiator_session.derive_stream_session(1);
+    let message_sent = b"payload".to_vec();
+    let mut message = message_sent.clone();
+    let auth_tag = initiator_stream_session
+        .write_message_in_place(&mut message)
+        .unwrap();
+    message.extend_from_slice(&auth_tag);
+
+    // the message can only be decrypted by the session of the same stream
+    // (note: a session is closed after failing to decrypt a message, so the
+    // stream sessions are derived first)
+    let mut responder_stream_session = responder_session.derive_stream_session(1);
+    for stream_id in [2, 3] {
+        let mut other_stream_session = responder_session.derive_stream_session(stream_id);
+        assert!(other_stream_session
+            .read_message_in_place(&mut message.clone())
+            .is_err());
+    }
+    assert!(responder_session
+        .read_message_in_place(&mut message.clone())
+        .is_err());
+    let received_message = responder_stream_session
+        .read_message_in_place(&mut message)
+        .unwrap();
+    assert_eq!(received_message, message_sent.as_slice());
+}
+
 #[test]
 fn test_vectors() {
     // structures needed to deserialize test vectors
@@ -75,6 +75,10 @@ fn get_peer_information(
     );
     peer_information_output.push("\n".into());
 
+    // Display the reputation of peers with recent penalties
+    display_peer_reputations(&mut peer_information_output, peers_and_metadata.deref());
+    peer_information_output.push("\n".into());
+
     // Display basic peer metadata for each peer
     display_peer_monitoring_metadata(
         &mut peer_information_output,
@@ -202,6 +206,31 @@ fn display_peer_monitoring_metadata(
     }
 }
 
+/// Displays the reputation of peers with recent penalties
+fn display_peer_reputations(
+    peer_information_output: &mut Vec<String>,
+    peers_and_metadata: &PeersAndMetadata,
+) {
+    peer_information_output.push("Reputation of peers with recent penalties:".into());
+
+    // Sort the peers before displaying them
+    let sorted_peer_reputations: BTreeMap<_, _> = peers_and_metadata
+        .get_peer_reputations()
+        .into_iter()
+        .collect();
+
+    // Display the reputation of each peer
+    for (peer, peer_reputation) in sorted_peer_reputations {
+        peer_information_output.push(format!(
+            "\t- Peer: {}, score: {:.2}, number of bans: {}, remaining ban duration: {:?}",
+            peer,
+            peer_reputation.get_score(),
+            peer_reputation.get_num_bans(),
+            peer_reputation.get_remaining_ban_duration()
+        ));
+    }
+}
+
 /// Displays state sync metadata for each peer
 fn display_state_sync_metadata(
     peer_information_output: &mut Vec<String>,
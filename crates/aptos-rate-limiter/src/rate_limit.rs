@@ -174,6 +174,22 @@ impl<Key: Eq + Hash + Clone + Debug> TokenBucketRateLimiter<Key> {
         }
         remove
     }
+
+    /// Garbage collects all buckets that aren't in use and are full, as a recreated bucket
+    /// starts with at most as many tokens.  Returns the number of buckets removed.
+    pub fn garbage_collect_full_buckets(&self) -> usize {
+        let mut buckets = self.buckets.write();
+        let num_buckets = buckets.len();
+        buckets.retain(|_, bucket| {
+            if Arc::strong_count(bucket) > 1 {
+                return true;
+            }
+            let mut bucket = bucket.lock();
+            bucket.refill();
+            !bucket.is_full()
+        });
+        num_buckets - buckets.len()
+    }
 }
 
 /// A token bucket object that keeps track of everything related to a key
@@ -367,6 +383,11 @@ impl Bucket {
         }
     }
 
+    /// Tells us if the bucket can't hold any more tokens
+    pub fn is_full(&self) -> bool {
+        self.tokens >= self.size
+    }
+
     /// Add new tokens
     /// Ensures bucket doesn't overfill
     fn add_tokens(&mut self, new_tokens: usize) {
@@ -549,4 +570,28 @@ mod tests {
         assert!(!rate_limiter.try_garbage_collect_key(&key_to_keep));
         assert_num_keys(&rate_limiter, 1);
     }
+
+    #[test]
+    fn test_garbage_collect_full_buckets() {
+        let key_in_use = "in use";
+        let key_not_full = "not full";
+        let key_full = "full";
+        let rate_limiter = TokenBucketRateLimiter::test(2, 1);
+
+        // Create a bucket to hold onto, and buckets to let go of
+        let _bucket_arc = rate_limiter.bucket(key_in_use);
+        rate_limiter
+            .bucket(key_not_full)
+            .lock()
+            .acquire_all_tokens(1)
+            .unwrap();
+        rate_limiter.bucket(key_full);
+        assert_num_keys(&rate_limiter, 3);
+
+        // Only the full bucket that isn't in use should be garbage collected
+        assert_eq!(1, rate_limiter.garbage_collect_full_buckets());
+        assert_num_keys(&rate_limiter, 2);
+        assert!(!rate_limiter.try_garbage_collect_key(&key_in_use));
+        assert!(rate_limiter.try_garbage_collect_key(&key_not_full));
+    }
 }
@@ -52,6 +52,7 @@ pub enum ApiError {
     SequenceNumberTooOld(Option<String>),
     VmError(Option<String>),
     MempoolIsFull(Option<String>),
+    RateLimited(Option<String>),
 }
 
 impl std::fmt::Display for ApiError {
@@ -101,6 +102,7 @@ impl ApiError {
             SequenceNumberTooOld(None),
             VmError(None),
             MempoolIsFull(None),
+            RateLimited(None),
         ]
     }
 
@@ -142,6 +144,7 @@ impl ApiError {
             MempoolIsFull(_) => 32,
             CoinTypeFailedToBeFetched(_) => 33,
             StateValueNotFound(_) => 34,
+            RateLimited(_) => 35,
         }
     }
 
@@ -154,6 +157,7 @@ impl ApiError {
             AccountNotFound(_)
                 | BlockNotFound(_)
                 | MempoolIsFull(_)
+                | RateLimited(_)
                 | GasEstimationFailed(_)
                 | CoinTypeFailedToBeFetched(_)
         )
@@ -200,6 +204,7 @@ impl ApiError {
             ApiError::SequenceNumberTooOld(_) => "Sequence number too old.  Please create a new transaction with an updated sequence number",
             ApiError::VmError(_) => "Transaction submission failed due to VM error",
             ApiError::MempoolIsFull(_) => "Mempool is full all accounts",
+            ApiError::RateLimited(_) => "Rate limited by the node API, please retry",
             ApiError::GasEstimationFailed(_) => "Gas estimation failed",
         }
     }
@@ -230,6 +235,7 @@ impl ApiError {
             ApiError::SequenceNumberTooOld(inner) => inner,
             ApiError::VmError(inner) => inner,
             ApiError::MempoolIsFull(inner) => inner,
+            ApiError::RateLimited(inner) => inner,
             ApiError::GasEstimationFailed(inner) => inner,
             ApiError::MaxGasFeeTooLow(inner) => inner,
             _ => None,
@@ -300,6 +306,7 @@ impl From<RestError> for ApiError {
                     ApiError::SequenceNumberTooOld(Some(err.error.message))
                 },
                 AptosErrorCode::VmError => ApiError::VmError(Some(err.error.message)),
+                AptosErrorCode::RejectedByFilter => ApiError::InvalidInput(Some(err.error.message)),
                 AptosErrorCode::HealthCheckFailed => {
                     ApiError::InternalError(Some(err.error.message))
                 },
@@ -310,6 +317,7 @@ impl From<RestError> for ApiError {
                 AptosErrorCode::BcsNotSupported => ApiError::InvalidInput(Some(err.error.message)),
                 AptosErrorCode::InternalError => ApiError::InternalError(Some(err.error.message)),
                 AptosErrorCode::ApiDisabled => ApiError::InternalError(Some(err.error.message)),
+                AptosErrorCode::RateLimited => ApiError::RateLimited(Some(err.error.message)),
             },
             RestError::Bcs(_) => ApiError::DeserializationFailed(None),
             RestError::Json(_) => ApiError::DeserializationFailed(None),
@@ -9,6 +9,7 @@ use crate::{
     logging::{LogEntry, LogSchema},
     shared_mempool::types::MultiBucketTimelineIndexIds,
 };
+use aptos_config::config::MempoolOrderingPolicy;
 use aptos_consensus_types::common::TransactionSummary;
 use aptos_crypto::HashValue;
 use aptos_logger::prelude::*;
@@ -26,20 +27,23 @@ pub type AccountTransactions = BTreeMap<u64, MempoolTransaction>;
 
 /// PriorityIndex represents the main Priority Queue in Mempool.
 /// It's used to form the transaction block for Consensus.
-/// Transactions are ordered by gas price. Second level ordering is done by expiration time.
+/// Transactions are ordered by gas price. Second level ordering is determined by the
+/// `MempoolOrderingPolicy`, and then by insertion time.
 ///
 /// We don't store the full content of transactions in the index.
 /// Instead we use `OrderedQueueKey` - logical reference to the transaction in the main store.
 pub struct PriorityIndex {
     data: BTreeSet<OrderedQueueKey>,
+    ordering_policy: MempoolOrderingPolicy,
 }
 
 pub type PriorityQueueIter<'a> = Rev<Iter<'a, OrderedQueueKey>>;
 
 impl PriorityIndex {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(ordering_policy: MempoolOrderingPolicy) -> Self {
         Self {
             data: BTreeSet::new(),
+            ordering_policy,
         }
     }
 
@@ -56,8 +60,15 @@ impl PriorityIndex {
     }
 
     fn make_key(&self, txn: &MempoolTransaction) -> OrderedQueueKey {
+        let tie_breaker = match self.ordering_policy {
+            MempoolOrderingPolicy::RankingScore => Duration::ZERO,
+            MempoolOrderingPolicy::RankingScoreThenExpiration => {
+                Duration::from_secs(txn.txn.expiration_timestamp_secs())
+            },
+        };
         OrderedQueueKey {
             gas_ranking_score: txn.ranking_score,
+            tie_breaker,
             expiration_time: txn.expiration_time,
             insertion_time: txn.insertion_info.insertion_time,
             address: txn.get_sender(),
@@ -70,6 +81,11 @@ impl PriorityIndex {
         self.data.iter().rev()
     }
 
+    /// Iterates from the lowest priority transaction to the highest.
+    pub(crate) fn iter_lowest(&self) -> Iter<OrderedQueueKey> {
+        self.data.iter()
+    }
+
     pub(crate) fn size(&self) -> usize {
         self.data.len()
     }
@@ -78,6 +94,8 @@ impl PriorityIndex {
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
 pub struct OrderedQueueKey {
     pub gas_ranking_score: u64,
+    // Orders transactions with the same ranking score, lowest first. Depends on the ordering policy.
+    pub tie_breaker: Duration,
     pub expiration_time: Duration,
     pub insertion_time: SystemTime,
     pub address: AccountAddress,
@@ -97,6 +115,10 @@ impl Ord for OrderedQueueKey {
             Ordering::Equal => {},
             ordering => return ordering,
         }
+        match self.tie_breaker.cmp(&other.tie_breaker).reverse() {
+            Ordering::Equal => {},
+            ordering => return ordering,
+        }
         match self.insertion_time.cmp(&other.insertion_time).reverse() {
             Ordering::Equal => {},
             ordering => return ordering,
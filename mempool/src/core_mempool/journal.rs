@@ -0,0 +1,292 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! An on-disk journal of the transactions in mempool, so that pending transactions
+//! survive node restarts.
+
+use crate::{
+    core_mempool::transaction::{MempoolTransaction, SubmittedBy, TimelineState},
+    counters,
+    logging::{LogEntry, LogSchema},
+    network::BroadcastPeerPriority,
+};
+use anyhow::{ensure, Result};
+use aptos_logger::prelude::*;
+use aptos_schemadb::{
+    schema::{KeyCodec, Schema, ValueCodec},
+    ColumnFamilyName, Options, SchemaBatch, DB,
+};
+use aptos_types::{account_address::AccountAddress, transaction::SignedTransaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    iter,
+    path::Path,
+    sync::{mpsc, Arc},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The name of the mempool journal db file
+pub const MEMPOOL_JOURNAL_DB_NAME: &str = "mempoolJournalDB";
+
+const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
+
+/// A transaction in the journal, along with the mempool metadata needed to restore it
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct JournaledTransaction {
+    pub txn: SignedTransaction,
+    // System expiration time of the transaction. It isn't extended by restarts.
+    pub expiration_time: Duration,
+    pub insertion_time: SystemTime,
+    pub timeline_state: TimelineState,
+    pub client_submitted: bool,
+    pub priority_of_sender: BroadcastPeerPriority,
+}
+
+impl From<&MempoolTransaction> for JournaledTransaction {
+    fn from(txn: &MempoolTransaction) -> Self {
+        Self {
+            txn: txn.txn.clone(),
+            expiration_time: txn.expiration_time,
+            insertion_time: txn.insertion_info.insertion_time,
+            timeline_state: txn.timeline_state,
+            client_submitted: txn.insertion_info.submitted_by == SubmittedBy::Client,
+            priority_of_sender: txn.priority_of_sender.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TransactionSchema;
+
+impl Schema for TransactionSchema {
+    type Key = (AccountAddress, u64);
+    type Value = JournaledTransaction;
+
+    const COLUMN_FAMILY_NAME: ColumnFamilyName = TRANSACTION_CF_NAME;
+}
+
+impl KeyCodec<TransactionSchema> for (AccountAddress, u64) {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let mut encoded = self.0.to_vec();
+        encoded.extend_from_slice(&self.1.to_be_bytes());
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure!(
+            data.len() == AccountAddress::LENGTH + 8,
+            "Unexpected mempool journal key length: {}",
+            data.len()
+        );
+        let (address, sequence_number) = data.split_at(AccountAddress::LENGTH);
+        Ok((
+            AccountAddress::from_bytes(address)?,
+            u64::from_be_bytes(sequence_number.try_into()?),
+        ))
+    }
+}
+
+impl ValueCodec<TransactionSchema> for JournaledTransaction {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(data)?)
+    }
+}
+
+/// An update sent to the journal writer
+enum JournalUpdate {
+    Insert(Box<JournaledTransaction>),
+    Remove(AccountAddress, u64),
+    /// Notifies the sender once all the previous updates are written
+    Flush(mpsc::Sender<()>),
+    Exit,
+}
+
+/// Journal of the transactions in mempool, keyed by sender and sequence number.
+///
+/// Updates are queued and written by a background thread, so that mempool doesn't wait for
+/// the disk while holding its lock. The queued updates are batched into a single write.
+/// Writes are best effort: a failure, or an insertion dropped because the queue is full, is
+/// logged and counted, but doesn't affect mempool. Removals are never dropped, as otherwise
+/// committed or expired transactions would linger in the journal.
+pub struct MempoolJournal {
+    db: Arc<DB>,
+    update_sender: mpsc::SyncSender<JournalUpdate>,
+    // The join handle of the writer thread, for graceful drop
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl MempoolJournal {
+    pub fn new<P: AsRef<Path>>(db_root_path: P, max_queued_updates: usize) -> Self {
+        let path = db_root_path.as_ref().join(MEMPOOL_JOURNAL_DB_NAME);
+        let instant = Instant::now();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open(
+            path.clone(),
+            MEMPOOL_JOURNAL_DB_NAME,
+            vec![TRANSACTION_CF_NAME],
+            &opts,
+        )
+        .expect("Mempool journal open failed; unable to continue");
+
+        info!(
+            "Opened mempool journal at {:?} in {} ms",
+            path,
+            instant.elapsed().as_millis()
+        );
+
+        let db = Arc::new(db);
+        let (update_sender, update_receiver) = mpsc::sync_channel(max_queued_updates);
+        let writer_db = db.clone();
+        let join_handle = std::thread::Builder::new()
+            .name("mempool-journal".to_string())
+            .spawn(move || Self::write_updates(writer_db, update_receiver))
+            .expect("Failed to spawn mempool journal writer thread.");
+
+        Self {
+            db,
+            update_sender,
+            join_handle: Some(join_handle),
+        }
+    }
+
+    /// Records a transaction that was inserted into mempool, replacing any previous
+    /// transaction with the same sender and sequence number. The update is dropped if the
+    /// queue is full.
+    pub(crate) fn insert(&self, txn: &MempoolTransaction) {
+        let journaled_txn = JournaledTransaction::from(txn);
+        self.send_update(JournalUpdate::Insert(Box::new(journaled_txn)));
+    }
+
+    /// Records the removal of a transaction from mempool. Waits for the writer if the queue is
+    /// full, rather than dropping the update.
+    pub(crate) fn remove(&self, sender: AccountAddress, sequence_number: u64) {
+        self.wait_to_send_update(JournalUpdate::Remove(sender, sequence_number));
+    }
+
+    /// Removes all the transactions for which `is_stale` returns true.
+    pub(crate) fn remove_stale(
+        &self,
+        is_stale: impl Fn(&AccountAddress, u64) -> bool,
+    ) -> Result<()> {
+        for (sender, sequence_number) in self.get_all_keys()? {
+            if is_stale(&sender, sequence_number) {
+                self.remove(sender, sequence_number);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all the transactions in the journal, including the queued updates.
+    pub(crate) fn get_all(&self) -> Result<Vec<JournaledTransaction>> {
+        self.flush();
+        let mut iter = self.db.iter::<TransactionSchema>()?;
+        iter.seek_to_first();
+        iter.map(|entry| Ok(entry?.1)).collect()
+    }
+
+    fn get_all_keys(&self) -> Result<Vec<(AccountAddress, u64)>> {
+        self.flush();
+        let mut iter = self.db.iter::<TransactionSchema>()?;
+        iter.seek_to_first();
+        iter.map(|entry| Ok(entry?.0)).collect()
+    }
+
+    /// Waits until all the queued updates are written.
+    fn flush(&self) {
+        let (flush_sender, flush_receiver) = mpsc::channel();
+        self.wait_to_send_update(JournalUpdate::Flush(flush_sender));
+        // The writer only stops on drop, so an error means it has panicked
+        let _ = flush_receiver.recv();
+    }
+
+    /// Queues the update, or drops it if the queue is full.
+    fn send_update(&self, update: JournalUpdate) {
+        match self.update_sender.try_send(update) {
+            Ok(()) => (),
+            Err(mpsc::TrySendError::Full(_)) => Self::log_error(anyhow::anyhow!(
+                "Mempool journal queue is full, update dropped"
+            )),
+            Err(mpsc::TrySendError::Disconnected(_)) => {
+                Self::log_error(anyhow::anyhow!("Mempool journal writer has stopped"))
+            },
+        }
+    }
+
+    /// Queues the update, waiting for the writer if the queue is full.
+    fn wait_to_send_update(&self, update: JournalUpdate) {
+        if let Err(e) = self.update_sender.send(update) {
+            Self::log_error(anyhow::anyhow!("Mempool journal writer has stopped: {}", e));
+        }
+    }
+
+    /// Writes the updates received from the journal, until it's dropped.
+    fn write_updates(db: Arc<DB>, update_receiver: mpsc::Receiver<JournalUpdate>) {
+        while let Ok(update) = update_receiver.recv() {
+            // Batch the updates that are already queued into a single write
+            let batch = SchemaBatch::new();
+            let mut flush_senders = vec![];
+            let mut exit = false;
+            for update in iter::once(update).chain(update_receiver.try_iter()) {
+                let result = match update {
+                    JournalUpdate::Insert(txn) => batch
+                        .put::<TransactionSchema>(
+                            &(txn.txn.sender(), txn.txn.sequence_number()),
+                            &txn,
+                        )
+                        .map_err(Into::into),
+                    JournalUpdate::Remove(sender, sequence_number) => batch
+                        .delete::<TransactionSchema>(&(sender, sequence_number))
+                        .map_err(Into::into),
+                    JournalUpdate::Flush(flush_sender) => {
+                        flush_senders.push(flush_sender);
+                        Ok(())
+                    },
+                    JournalUpdate::Exit => {
+                        exit = true;
+                        break;
+                    },
+                };
+                if let Err(e) = result {
+                    Self::log_error(e);
+                }
+            }
+
+            if let Err(e) = db.write_schemas(batch) {
+                Self::log_error(e.into());
+            }
+            for flush_sender in flush_senders {
+                let _ = flush_sender.send(());
+            }
+            if exit {
+                break;
+            }
+        }
+    }
+
+    fn log_error(e: anyhow::Error) {
+        counters::MEMPOOL_JOURNAL_ERRORS.inc();
+        sample!(
+            SampleRate::Duration(Duration::from_secs(60)),
+            error!(LogSchema::new(LogEntry::JournalError).error(&e))
+        );
+    }
+}
+
+impl Drop for MempoolJournal {
+    fn drop(&mut self) {
+        // The queued updates are written before the writer exits
+        self.wait_to_send_update(JournalUpdate::Exit);
+        if let Some(join_handle) = self.join_handle.take() {
+            join_handle
+                .join()
+                .expect("Mempool journal writer thread should join peacefully.");
+        }
+    }
+}
@@ -7,13 +7,14 @@
 use crate::{
     core_mempool::{
         index::TxnPointer,
+        journal::{JournaledTransaction, MempoolJournal},
         transaction::{InsertionInfo, MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
     },
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
     network::BroadcastPeerPriority,
-    shared_mempool::types::MultiBucketTimelineIndexIds,
+    shared_mempool::types::{AccountMempoolTransactions, MultiBucketTimelineIndexIds},
 };
 use aptos_config::config::NodeConfig;
 use aptos_consensus_types::common::{TransactionInProgress, TransactionSummary};
@@ -22,7 +23,7 @@ use aptos_logger::prelude::*;
 use aptos_types::{
     account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    transaction::{use_case::UseCaseKey, SignedTransaction},
+    transaction::{use_case::UseCaseKey, SignedTransaction, TransactionPayload},
     vm_status::DiscardedVMStatus,
 };
 use std::{
@@ -40,8 +41,14 @@ pub struct Mempool {
 
 impl Mempool {
     pub fn new(config: &NodeConfig) -> Self {
+        let journal = config.mempool.enable_journal.then(|| {
+            MempoolJournal::new(
+                config.storage.dir(),
+                config.mempool.journal_max_queued_updates,
+            )
+        });
         Mempool {
-            transactions: TransactionStore::new(&config.mempool),
+            transactions: TransactionStore::new(&config.mempool, journal),
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
@@ -258,6 +265,13 @@ impl Mempool {
         self.transactions.get_by_hash(hash)
     }
 
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> AccountMempoolTransactions {
+        self.transactions.get_account_transactions(address)
+    }
+
     /// Used to add a transaction to the Mempool.
     /// Performs basic validation: checks account's sequence number.
     pub(crate) fn add_txn(
@@ -327,6 +341,121 @@ impl Mempool {
         status
     }
 
+    /// Used to cancel a pending transaction, by replacing it with a no-op (see `is_no_op()`) from
+    /// the same sender with the same sequence number. Unlike `add_txn`, the replacement may have
+    /// a different payload or max gas amount than the pending transaction, but like `add_txn`,
+    /// it must have a higher gas unit price. Since the replacement uses up the sequence number
+    /// once it's committed, the pending transaction can't be committed anymore, even if other
+    /// nodes still have it. If the replacement is rejected, the pending transaction stays in
+    /// mempool.
+    pub(crate) fn cancel_txn(
+        &mut self,
+        txn: SignedTransaction,
+        ranking_score: u64,
+        db_sequence_number: u64,
+        timeline_state: TimelineState,
+        client_submitted: bool,
+        // The prority of this node for the peer that sent the cancellation
+        priority: BroadcastPeerPriority,
+    ) -> MempoolStatus {
+        let sender = txn.sender();
+        let sequence_number = txn.sequence_number();
+
+        if !is_no_op(&txn) {
+            return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
+                "Cancellation must be an empty script or a transfer of 0 to the sender".to_string(),
+            );
+        }
+
+        // If the cancellation is already in mempool, adding it again is idempotent
+        let cancellation_hash = txn.committed_hash();
+        let cancelled_txn = if sequence_number >= db_sequence_number
+            && self.get_by_hash(cancellation_hash).is_none()
+        {
+            if let Some(pending_txn) = self.transactions.get(&sender, sequence_number) {
+                if pending_txn.gas_unit_price() >= txn.gas_unit_price() {
+                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
+                        "Cancellation must have a higher gas unit price than the pending \
+                        transaction"
+                            .to_string(),
+                    );
+                }
+            }
+            self.transactions
+                .remove_cancelled_transaction(&sender, sequence_number)
+        } else {
+            None
+        };
+
+        let status = self.add_txn(
+            txn,
+            ranking_score,
+            db_sequence_number,
+            timeline_state,
+            client_submitted,
+            None,
+            priority,
+        );
+
+        if let Some(cancelled_txn) = cancelled_txn {
+            if status.code == MempoolStatusCode::Accepted {
+                counters::CORE_MEMPOOL_CANCELLED_TXNS.inc();
+                debug!(
+                    LogSchema::new(LogEntry::CancelTxn)
+                        .txns(TxnsLog::new_txn(sender, sequence_number)),
+                    cancelled_hash = cancelled_txn.get_committed_hash().to_hex(),
+                    cancellation_hash = cancellation_hash.to_hex(),
+                );
+            } else {
+                // The cancellation was rejected, so the pending transaction is put back
+                self.transactions
+                    .restore_cancelled_transaction(cancelled_txn);
+            }
+        }
+        status
+    }
+
+    /// Used to restore a transaction from the journal after a restart. Unlike `add_txn`, the
+    /// transaction keeps its original insertion time and system expiration time.
+    pub(crate) fn restore_txn(
+        &mut self,
+        journaled_txn: JournaledTransaction,
+        ranking_score: u64,
+        db_sequence_number: u64,
+    ) -> MempoolStatus {
+        if journaled_txn.txn.sequence_number() < db_sequence_number {
+            return MempoolStatus::new(MempoolStatusCode::InvalidSeqNumber);
+        }
+
+        // Readiness for broadcast is recomputed on insertion
+        let timeline_state = match journaled_txn.timeline_state {
+            TimelineState::NonQualified => TimelineState::NonQualified,
+            TimelineState::Ready(_) | TimelineState::NotReady => TimelineState::NotReady,
+        };
+        let txn_info = MempoolTransaction::new(
+            journaled_txn.txn,
+            journaled_txn.expiration_time,
+            ranking_score,
+            timeline_state,
+            db_sequence_number,
+            journaled_txn.insertion_time,
+            journaled_txn.client_submitted,
+            journaled_txn.priority_of_sender,
+        );
+        self.transactions.insert(txn_info)
+    }
+
+    /// Returns the transactions in the journal, or nothing if the journal isn't enabled.
+    pub(crate) fn get_journaled_transactions(&self) -> anyhow::Result<Vec<JournaledTransaction>> {
+        self.transactions.get_journaled_transactions()
+    }
+
+    /// Removes the transactions from the journal that are no longer in mempool, e.g. because
+    /// they failed validation when they were restored.
+    pub(crate) fn prune_journal(&self) -> anyhow::Result<()> {
+        self.transactions.prune_journal()
+    }
+
     /// Txn was already chosen, either in a local or remote previous pull (so now in consensus) or
     /// in the current pull.
     fn txn_was_chosen(
@@ -546,3 +675,26 @@ impl Mempool {
         &self.transactions
     }
 }
+
+/// Whether a transaction does nothing but use up its sequence number and pay for gas, i.e. it's
+/// an empty script or a transfer of 0 coins from the sender to itself.
+fn is_no_op(txn: &SignedTransaction) -> bool {
+    match txn.payload() {
+        TransactionPayload::Script(script) => {
+            script.code().is_empty() && script.ty_args().is_empty() && script.args().is_empty()
+        },
+        TransactionPayload::EntryFunction(entry_function) => {
+            let module = entry_function.module();
+            module.address() == &AccountAddress::ONE
+                && module.name().as_str() == "aptos_account"
+                && entry_function.function().as_str() == "transfer"
+                && entry_function.ty_args().is_empty()
+                && entry_function.args()
+                    == [
+                        bcs::to_bytes(&txn.sender()).expect("Address must serialize"),
+                        bcs::to_bytes(&0u64).expect("Amount must serialize"),
+                    ]
+        },
+        _ => false,
+    }
+}
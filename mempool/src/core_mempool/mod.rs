@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod index;
+mod journal;
 mod mempool;
 pub mod transaction;
 mod transaction_store;
@@ -10,6 +11,6 @@ mod transaction_store;
 #[cfg(test)]
 pub use self::transaction::{MempoolTransaction, SubmittedBy};
 pub use self::{
-    mempool::Mempool as CoreMempool, transaction::TimelineState,
+    journal::JournaledTransaction, mempool::Mempool as CoreMempool, transaction::TimelineState,
     transaction_store::TXN_INDEX_ESTIMATED_BYTES,
 };
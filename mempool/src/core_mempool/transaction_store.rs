@@ -8,15 +8,19 @@ use crate::{
             AccountTransactions, MultiBucketTimelineIndex, ParkingLotIndex, PriorityIndex,
             PriorityQueueIter, TTLIndex,
         },
+        journal::{JournaledTransaction, MempoolJournal},
         mempool::Mempool,
         transaction::{InsertionInfo, MempoolTransaction, TimelineState},
     },
     counters::{self, BROADCAST_BATCHED_LABEL, BROADCAST_READY_LABEL, CONSENSUS_READY_LABEL},
     logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
     network::BroadcastPeerPriority,
-    shared_mempool::types::MultiBucketTimelineIndexIds,
+    shared_mempool::types::{
+        AccountMempoolTransactions, MultiBucketTimelineIndexIds, PendingTransactionInfo,
+        PendingTransactionState, SequenceNumberGap,
+    },
 };
-use aptos_config::config::MempoolConfig;
+use aptos_config::config::{MempoolConfig, MempoolEvictionPolicy};
 use aptos_crypto::HashValue;
 use aptos_logger::{prelude::*, Level};
 use aptos_types::{
@@ -72,14 +76,18 @@ pub struct TransactionStore {
     capacity_bytes: usize,
     capacity_per_user: usize,
     max_batch_bytes: u64,
+    eviction_policy: MempoolEvictionPolicy,
 
     // eager expiration
     eager_expire_threshold: Option<Duration>,
     eager_expire_time: Duration,
+
+    // on-disk journal of the transactions, if enabled
+    journal: Option<MempoolJournal>,
 }
 
 impl TransactionStore {
-    pub(crate) fn new(config: &MempoolConfig) -> Self {
+    pub(crate) fn new(config: &MempoolConfig, journal: Option<MempoolJournal>) -> Self {
         Self {
             // main DS
             transactions: HashMap::new(),
@@ -90,7 +98,7 @@ impl TransactionStore {
             expiration_time_index: TTLIndex::new(Box::new(|t: &MempoolTransaction| {
                 Duration::from_secs(t.txn.expiration_timestamp_secs())
             })),
-            priority_index: PriorityIndex::new(),
+            priority_index: PriorityIndex::new(config.ordering_policy),
             timeline_index: MultiBucketTimelineIndex::new(config.broadcast_buckets.clone())
                 .unwrap(),
             parking_lot_index: ParkingLotIndex::new(),
@@ -103,10 +111,13 @@ impl TransactionStore {
             capacity_bytes: config.capacity_bytes,
             capacity_per_user: config.capacity_per_user,
             max_batch_bytes: config.shared_mempool_max_batch_bytes,
+            eviction_policy: config.eviction_policy,
 
             // eager expiration
             eager_expire_threshold: config.eager_expire_threshold_ms.map(Duration::from_millis),
             eager_expire_time: Duration::from_millis(config.eager_expire_time_ms),
+
+            journal,
         }
     }
 
@@ -266,6 +277,9 @@ impl TransactionStore {
                 .insert(txn.get_committed_hash(), (txn.get_sender(), txn_seq_num));
             self.sequence_numbers.insert(txn.get_sender(), acc_seq_num);
             self.size_bytes += txn.get_estimated_bytes();
+            if let Some(journal) = &self.journal {
+                journal.insert(&txn);
+            }
             txns.insert(txn_seq_num, txn);
             self.track_indices();
         }
@@ -303,7 +317,8 @@ impl TransactionStore {
     }
 
     /// Checks if Mempool is full.
-    /// If it's full, tries to free some space by evicting transactions from the ParkingLot.
+    /// If it's full, tries to free some space by evicting transactions from the ParkingLot, and
+    /// then lower ranked ready transactions if the eviction policy allows it.
     /// We only evict on attempt to insert a transaction that would be ready for broadcast upon insertion.
     fn check_is_full_after_eviction(
         &mut self,
@@ -324,13 +339,67 @@ impl TransactionStore {
                             txn.sequence_info.transaction_sequence_number
                         ))
                     );
+                    self.log_evicted_transaction(&txn, counters::EVICTION_PARKING_LOT_LABEL);
                     self.index_remove(&txn);
                 }
             }
+
+            if self.eviction_policy == MempoolEvictionPolicy::LowestRankingScore {
+                while self.is_full() && self.evict_lower_ranked_transactions(txn) {}
+            }
         }
         self.is_full()
     }
 
+    /// Evicts the lowest ranked ready transaction that has a lower ranking score than `txn`,
+    /// along with the following transactions of its account, which can't be ready without it.
+    /// The preceding transactions of the sender of `txn` are never evicted, so that `txn`
+    /// stays ready. Returns false if there is no transaction to evict.
+    fn evict_lower_ranked_transactions(&mut self, txn: &MempoolTransaction) -> bool {
+        let sender = txn.get_sender();
+        let lowest = self
+            .priority_index
+            .iter_lowest()
+            .find(|key| key.address != sender)
+            .filter(|key| key.gas_ranking_score < txn.ranking_score)
+            .map(|key| (key.address, key.sequence_number.transaction_sequence_number));
+        let (address, sequence_number) = match lowest {
+            Some(lowest) => lowest,
+            None => return false,
+        };
+
+        let evicted_txns = match self.transactions.get_mut(&address) {
+            Some(txns) => txns.split_off(&sequence_number),
+            None => return false,
+        };
+        let mut evicted_txns_log = TxnsLog::new_with_max(10);
+        for evicted_txn in evicted_txns.values() {
+            evicted_txns_log.add(
+                evicted_txn.get_sender(),
+                evicted_txn.sequence_info.transaction_sequence_number,
+            );
+            self.log_evicted_transaction(
+                evicted_txn,
+                counters::EVICTION_LOWEST_RANKING_SCORE_LABEL,
+            );
+            self.index_remove(evicted_txn);
+        }
+        debug!(LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(evicted_txns_log));
+        !evicted_txns.is_empty()
+    }
+
+    fn log_evicted_transaction(&self, txn: &MempoolTransaction, eviction_type: &'static str) {
+        counters::CORE_MEMPOOL_EVICTED_TXNS
+            .with_label_values(&[eviction_type])
+            .inc();
+        counters::core_mempool_txn_ranking_score(
+            counters::EVICTED_LABEL,
+            eviction_type,
+            self.get_bucket(txn.ranking_score),
+            txn.ranking_score,
+        );
+    }
+
     fn is_full(&self) -> bool {
         self.system_ttl_index.size() >= self.capacity || self.size_bytes >= self.capacity_bytes
     }
@@ -539,6 +608,52 @@ impl TransactionStore {
         }
     }
 
+    /// Removes the transaction of the account with the given sequence number, so that it can
+    /// be replaced by a cancellation. Returns the removed transaction, if any, so that it can be
+    /// put back if the cancellation is rejected.
+    pub(crate) fn remove_cancelled_transaction(
+        &mut self,
+        account: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<MempoolTransaction> {
+        let txn = self
+            .transactions
+            .get_mut(account)
+            .and_then(|txns| txns.remove(&sequence_number))?;
+        self.index_remove(&txn);
+        Some(txn)
+    }
+
+    /// Puts back a transaction removed by `remove_cancelled_transaction` after its cancellation
+    /// was rejected. The capacity checks are skipped, since the transaction already had its
+    /// place in mempool before it was removed.
+    pub(crate) fn restore_cancelled_transaction(&mut self, mut txn: MempoolTransaction) {
+        let address = txn.get_sender();
+        let txn_seq_num = txn.sequence_info.transaction_sequence_number;
+        let acc_seq_num = self
+            .get_sequence_number(&address)
+            .map_or(txn.sequence_info.account_sequence_number, |v| *v);
+
+        // Readiness for broadcast is recomputed on insertion
+        if let TimelineState::Ready(_) = txn.timeline_state {
+            txn.timeline_state = TimelineState::NotReady;
+        }
+        self.system_ttl_index.insert(&txn);
+        self.expiration_time_index.insert(&txn);
+        self.hash_index
+            .insert(txn.get_committed_hash(), (address, txn_seq_num));
+        self.size_bytes += txn.get_estimated_bytes();
+        if let Some(journal) = &self.journal {
+            journal.insert(&txn);
+        }
+        self.transactions
+            .entry(address)
+            .or_default()
+            .insert(txn_seq_num, txn);
+        self.track_indices();
+        self.process_ready_transactions(&address, acc_seq_num);
+    }
+
     /// Removes transaction from all indexes. Only call after removing from main transactions DS.
     fn index_remove(&mut self, txn: &MempoolTransaction) {
         counters::CORE_MEMPOOL_REMOVED_TXNS.inc();
@@ -549,6 +664,12 @@ impl TransactionStore {
         self.parking_lot_index.remove(txn);
         self.hash_index.remove(&txn.get_committed_hash());
         self.size_bytes -= txn.get_estimated_bytes();
+        if let Some(journal) = &self.journal {
+            journal.remove(
+                txn.get_sender(),
+                txn.sequence_info.transaction_sequence_number,
+            );
+        }
 
         // Remove account datastructures if there are no more transactions for the account.
         let address = &txn.get_sender();
@@ -766,6 +887,70 @@ impl TransactionStore {
         self.track_indices();
     }
 
+    /// Returns the transactions in the journal, or nothing if the journal isn't enabled.
+    pub(crate) fn get_journaled_transactions(&self) -> anyhow::Result<Vec<JournaledTransaction>> {
+        match &self.journal {
+            Some(journal) => journal.get_all(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Removes the transactions from the journal that aren't in the store.
+    pub(crate) fn prune_journal(&self) -> anyhow::Result<()> {
+        match &self.journal {
+            Some(journal) => journal.remove_stale(|address, sequence_number| {
+                self.get_mempool_txn(address, sequence_number).is_none()
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the transactions of an account, along with the state of each of them
+    pub(crate) fn get_account_transactions(
+        &self,
+        address: &AccountAddress,
+    ) -> AccountMempoolTransactions {
+        let account_sequence_number = self.get_sequence_number(address).copied();
+        let mut sequence_number_gap = None;
+        let mut transactions = vec![];
+
+        if let Some(txns) = self.transactions.get(address) {
+            let mut next_seq_num = account_sequence_number.unwrap_or(0);
+            for (seq_num, txn) in txns.iter() {
+                if sequence_number_gap.is_none() && *seq_num > next_seq_num {
+                    sequence_number_gap = Some(SequenceNumberGap {
+                        start: next_seq_num,
+                        end: *seq_num,
+                    });
+                }
+                next_seq_num = max(next_seq_num, *seq_num + 1);
+
+                let hash = txn.get_committed_hash();
+                let state = if self.parking_lot_index.contains(address, *seq_num, hash) {
+                    PendingTransactionState::Parked
+                } else {
+                    PendingTransactionState::Ready
+                };
+                transactions.push(PendingTransactionInfo {
+                    hash,
+                    sequence_number: *seq_num,
+                    gas_unit_price: txn.get_gas_price(),
+                    ranking_score: txn.ranking_score,
+                    insertion_time: txn.insertion_info.insertion_time,
+                    expiration_timestamp_secs: txn.txn.expiration_timestamp_secs(),
+                    state,
+                    broadcast_state: txn.timeline_state.into(),
+                });
+            }
+        }
+
+        AccountMempoolTransactions {
+            account_sequence_number,
+            sequence_number_gap,
+            transactions,
+        }
+    }
+
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
@@ -35,6 +35,7 @@ pub const BROADCAST_READY_LABEL: &str = "broadcast_ready";
 pub const BROADCAST_BATCHED_LABEL: &str = "broadcast_batched";
 pub const PARKED_TIME_LABEL: &str = "parked_time";
 pub const NON_PARKED_COMMIT_ACCEPTED_LABEL: &str = "non_park_commit_accepted";
+pub const EVICTED_LABEL: &str = "evicted";
 
 // Core mempool GC type labels
 pub const GC_SYSTEM_TTL_LABEL: &str = "system_ttl";
@@ -44,6 +45,10 @@ pub const GC_CLIENT_EXP_LABEL: &str = "client_expiration";
 pub const GC_ACTIVE_TXN_LABEL: &str = "active";
 pub const GC_PARKED_TXN_LABEL: &str = "parked";
 
+// Core mempool eviction type labels
+pub const EVICTION_PARKING_LOT_LABEL: &str = "parking_lot";
+pub const EVICTION_LOWEST_RANKING_SCORE_LABEL: &str = "lowest_ranking_score";
+
 // Mempool service request type labels
 pub const GET_BLOCK_LABEL: &str = "get_block";
 pub const GET_BLOCK_LOCK_LABEL: &str = "get_block_lock";
@@ -67,8 +72,11 @@ pub const SUCCESS_LABEL: &str = "success";
 // Bounded executor task labels
 pub const CLIENT_EVENT_LABEL: &str = "client_event";
 pub const CLIENT_EVENT_GET_TXN_LABEL: &str = "client_event_get_txn";
+pub const CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL: &str = "client_event_get_account_txns";
+pub const CLIENT_EVENT_CANCEL_TXN_LABEL: &str = "client_event_cancel_txn";
 pub const RECONFIG_EVENT_LABEL: &str = "reconfig";
 pub const PEER_BROADCAST_EVENT_LABEL: &str = "peer_broadcast";
+pub const PEER_CANCELLATION_EVENT_LABEL: &str = "peer_cancellation";
 
 // task spawn stage labels
 pub const SPAWN_LABEL: &str = "spawn";
@@ -77,6 +85,7 @@ pub const START_LABEL: &str = "start";
 // Mempool network msg failure type labels:
 pub const BROADCAST_TXNS: &str = "broadcast_txns";
 pub const ACK_TXNS: &str = "ack_txns";
+pub const CANCEL_TXNS: &str = "cancel_txns";
 
 // Broadcast/ACK type labels
 pub const EXPIRED_BROADCAST_LABEL: &str = "expired";
@@ -186,6 +195,34 @@ pub static CORE_MEMPOOL_GAS_UPGRADED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of pending txns that were cancelled by their sender
+pub static CORE_MEMPOOL_CANCELLED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_core_mempool_cancelled_txns_count",
+        "Number of pending txns that were cancelled by their sender"
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of txns evicted from a full core mempool, by eviction type
+pub static CORE_MEMPOOL_EVICTED_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_core_mempool_evicted_txns_count",
+        "Number of txns evicted to make space in a full core mempool",
+        &["type"]
+    )
+    .unwrap()
+});
+
+/// Counter tracking number of txns rejected by the mempool transaction filter
+pub static CORE_MEMPOOL_FILTERED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_core_mempool_filtered_txns_count",
+        "Number of txns rejected by the mempool transaction filter"
+    )
+    .unwrap()
+});
+
 pub fn core_mempool_txn_commit_latency(
     stage: &'static str,
     submitted_by: &'static str,
@@ -643,6 +680,24 @@ pub static DB_ERROR: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter of errors reading from or writing to the mempool journal
+pub static MEMPOOL_JOURNAL_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_mempool_journal_error_count",
+        "Number of times an error was encountered reading from or writing to the mempool journal"
+    )
+    .unwrap()
+});
+
+/// Counter of transactions restored from the mempool journal on startup
+pub static MEMPOOL_JOURNAL_RESTORED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_mempool_journal_restored_txns_count",
+        "Number of transactions restored from the mempool journal on startup"
+    )
+    .unwrap()
+});
+
 /// Counter for the current number of active upstream peers mempool can
 /// broadcast to, summed across each of its networks
 static ACTIVE_UPSTREAM_PEERS_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
@@ -62,8 +62,9 @@ pub use shared_mempool::{
     bootstrap, network,
     network::MempoolSyncMsg,
     types::{
-        MempoolClientRequest, MempoolClientSender, MempoolEventsReceiver, QuorumStoreRequest,
-        QuorumStoreResponse, SubmissionStatus,
+        AccountMempoolTransactions, BroadcastState, MempoolClientRequest, MempoolClientSender,
+        MempoolEventsReceiver, PendingTransactionInfo, PendingTransactionState, QuorumStoreRequest,
+        QuorumStoreResponse, SequenceNumberGap, SubmissionStatus,
     },
 };
 #[cfg(any(test, feature = "fuzzing"))]
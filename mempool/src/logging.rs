@@ -156,6 +156,7 @@ pub enum LogEntry {
     ReconfigUpdate,
     JsonRpc,
     GetTransaction,
+    GetAccountTransactions,
     GetBlock,
     QuorumStore,
     StateSyncCommit,
@@ -169,8 +170,11 @@ pub enum LogEntry {
     GCRemoveTxns,
     CleanCommittedTxn,
     CleanRejectedTxn,
+    CancelTxn,
     ProcessReadyTxns,
     DBError,
+    JournalError,
+    JournalRestore,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
 }
@@ -186,6 +186,27 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
                 ))
                 .await;
         },
+        MempoolClientRequest::CancelTransaction(txn, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_CANCEL_TXN_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_CANCEL_TXN_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_transaction_cancellation(
+                    smp.clone(),
+                    txn,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
         MempoolClientRequest::GetTransactionByHash(hash, callback) => {
             // This timer measures how long it took for the bounded executor to *schedule* the
             // task.
@@ -207,6 +228,27 @@ async fn handle_client_request<NetworkClient, TransactionValidator>(
                 ))
                 .await;
         },
+        MempoolClientRequest::GetAccountTransactions(address, callback) => {
+            // This timer measures how long it took for the bounded executor to *schedule* the
+            // task.
+            let _timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::SPAWN_LABEL,
+            );
+            // This timer measures how long it took for the task to go from scheduled to started.
+            let task_start_timer = counters::task_spawn_latency_timer(
+                counters::CLIENT_EVENT_GET_ACCOUNT_TXNS_LABEL,
+                counters::START_LABEL,
+            );
+            bounded_executor
+                .spawn(tasks::process_client_get_account_transactions(
+                    smp.clone(),
+                    address,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        },
     }
 }
 
@@ -320,6 +362,50 @@ async fn process_received_txns<NetworkClient, TransactionValidator>(
         .await;
 }
 
+/// Spawn a task to process cancellations of pending transactions received from a peer
+async fn process_received_cancellations<NetworkClient, TransactionValidator>(
+    bounded_executor: &BoundedExecutor,
+    smp: &mut SharedMempool<NetworkClient, TransactionValidator>,
+    network_id: NetworkId,
+    transactions: Vec<SignedTransaction>,
+    peer_id: PeerId,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg> + 'static,
+    TransactionValidator: TransactionValidation + 'static,
+{
+    let smp_clone = smp.clone();
+    let peer = PeerNetworkId::new(network_id, peer_id);
+    let ineligible_for_broadcast = (smp.network_interface.is_validator()
+        && !smp.broadcast_within_validator_network())
+        || smp.network_interface.is_upstream_peer(&peer, None);
+    let timeline_state = if ineligible_for_broadcast {
+        TimelineState::NonQualified
+    } else {
+        TimelineState::NotReady
+    };
+    // This timer measures how long it took for the bounded executor to
+    // *schedule* the task.
+    let _timer = counters::task_spawn_latency_timer(
+        counters::PEER_CANCELLATION_EVENT_LABEL,
+        counters::SPAWN_LABEL,
+    );
+    // This timer measures how long it took for the task to go from scheduled
+    // to started.
+    let task_start_timer = counters::task_spawn_latency_timer(
+        counters::PEER_CANCELLATION_EVENT_LABEL,
+        counters::START_LABEL,
+    );
+    bounded_executor
+        .spawn(tasks::process_transaction_cancellations(
+            smp_clone,
+            transactions,
+            timeline_state,
+            peer,
+            task_start_timer,
+        ))
+        .await;
+}
+
 /// Handles all network messages.
 /// - Network messages follow a simple Request/Response framework to accept new transactions
 /// TODO: Move to RPC off of DirectSend
@@ -367,6 +453,16 @@ async fn handle_network_event<NetworkClient, TransactionValidator>(
                     )
                     .await;
                 },
+                MempoolSyncMsg::CancelTransactionsRequest { transactions } => {
+                    process_received_cancellations(
+                        bounded_executor,
+                        smp,
+                        network_id,
+                        transactions,
+                        peer_id,
+                    )
+                    .await;
+                },
                 MempoolSyncMsg::BroadcastTransactionsResponse {
                     request_id,
                     retry,
@@ -24,7 +24,10 @@ use aptos_infallible::RwLock;
 use aptos_logger::prelude::*;
 use aptos_netcore::transport::ConnectionOrigin;
 use aptos_network::{
-    application::{error::Error, interface::NetworkClientInterface, metadata::PeerMetadata},
+    application::{
+        error::Error, interface::NetworkClientInterface, metadata::PeerMetadata,
+        reputation::PeerPenalty,
+    },
     transport::ConnectionMetadata,
 };
 use aptos_time_service::TimeService;
@@ -71,6 +74,11 @@ pub enum MempoolSyncMsg {
         /// Priority of the upstream node for the sender.
         priority: BroadcastPeerPriority,
     },
+    /// Cancellations of pending transactions issued by the sender. Each transaction replaces
+    /// the pending transaction with the same sender and sequence number. There is no ack.
+    CancelTransactionsRequest {
+        transactions: Vec<SignedTransaction>,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -511,6 +519,35 @@ impl<NetworkClient: NetworkClientInterface<MempoolSyncMsg>> MempoolNetworkInterf
         self.network_client.send_to_peer(message, peer)
     }
 
+    /// Penalizes the given peer for misbehaving (e.g., relaying invalid transactions)
+    pub fn report_peer_penalty(&self, peer: PeerNetworkId, penalty: PeerPenalty) {
+        self.network_client
+            .get_peers_and_metadata()
+            .report_peer_penalty(peer, penalty);
+    }
+
+    /// Sends cancellations of pending transactions to all upstream peers, if enabled
+    pub fn broadcast_cancellations(&self, transactions: Vec<SignedTransaction>) {
+        if !self.mempool_config.broadcast_transaction_cancellations {
+            return;
+        }
+
+        let peers: Vec<_> = self.sync_states.read().keys().cloned().collect();
+        for peer in peers {
+            let request = MempoolSyncMsg::CancelTransactionsRequest {
+                transactions: transactions.clone(),
+            };
+            if let Err(e) = self.send_message_to_peer(peer, request) {
+                counters::network_send_fail_inc(counters::CANCEL_TXNS);
+                warn!(
+                    LogSchema::event_log(LogEntry::CancelTxn, LogEvent::NetworkSendFail)
+                        .peer(&peer)
+                        .error(&e.into())
+                );
+            }
+        }
+    }
+
     /// Updates the local tracker for a broadcast.  This is used to handle `DirectSend` tracking of
     /// responses
     fn update_broadcast_state(
@@ -7,6 +7,7 @@ use crate::{
     network::MempoolSyncMsg,
     shared_mempool::{
         coordinator::{coordinator, gc_coordinator, snapshot_job},
+        tasks::restore_journaled_transactions,
         types::{MempoolEventsReceiver, SharedMempool, SharedMempoolNotification},
     },
     QuorumStoreRequest,
@@ -61,6 +62,9 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
             config.base.role,
         );
 
+    // Restore the transactions from before the restart, before accepting new ones
+    restore_journaled_transactions(&smp);
+
     executor.spawn(coordinator(
         smp,
         executor.clone(),
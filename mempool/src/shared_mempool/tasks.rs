@@ -4,14 +4,14 @@
 
 //! Tasks that are executed by coordinators (short-lived compared to coordinators)
 use crate::{
-    core_mempool::{CoreMempool, TimelineState},
+    core_mempool::{CoreMempool, JournaledTransaction, TimelineState},
     counters,
     logging::{LogEntry, LogEvent, LogSchema},
     network::{BroadcastError, BroadcastPeerPriority, MempoolSyncMsg},
     shared_mempool::{
         types::{
-            notify_subscribers, MultiBatchId, ScheduledBroadcast, SharedMempool,
-            SharedMempoolNotification, SubmissionStatusBundle,
+            notify_subscribers, AccountMempoolTransactions, MultiBatchId, ScheduledBroadcast,
+            SharedMempool, SharedMempoolNotification, SubmissionStatusBundle,
         },
         use_case_history::UseCaseHistory,
     },
@@ -19,16 +19,17 @@ use crate::{
     QuorumStoreRequest, QuorumStoreResponse, SubmissionStatus,
 };
 use anyhow::Result;
-use aptos_config::network_id::PeerNetworkId;
+use aptos_config::{config::transaction_filter_type::Filter, network_id::PeerNetworkId};
 use aptos_consensus_types::common::RejectedTransactionSummary;
 use aptos_crypto::HashValue;
 use aptos_infallible::{Mutex, RwLock};
 use aptos_logger::prelude::*;
 use aptos_mempool_notifications::CommittedTransaction;
 use aptos_metrics_core::HistogramTimer;
-use aptos_network::application::interface::NetworkClientInterface;
+use aptos_network::application::{interface::NetworkClientInterface, reputation::PeerPenalty};
 use aptos_storage_interface::state_view::LatestDbStateCheckpointView;
 use aptos_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::{OnChainConfigPayload, OnChainConfigProvider, OnChainConsensusConfig},
     transaction::SignedTransaction,
@@ -39,6 +40,7 @@ use futures::{channel::oneshot, stream::FuturesUnordered};
 use rayon::prelude::*;
 use std::{
     cmp,
+    collections::HashSet,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -137,6 +139,7 @@ pub(crate) async fn process_client_transaction_submission<NetworkClient, Transac
             timeline_state,
             true,
             BroadcastPeerPriority::Primary,
+            false,
         );
     log_txn_process_results(&statuses, None);
 
@@ -151,6 +154,39 @@ pub(crate) async fn process_client_transaction_submission<NetworkClient, Transac
     }
 }
 
+/// Processes a cancellation of a pending transaction directly submitted by client.
+pub(crate) async fn process_client_transaction_cancellation<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    transaction: SignedTransaction,
+    callback: oneshot::Sender<Result<SubmissionStatus>>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation + 'static,
+{
+    timer.stop_and_record();
+    let _timer = counters::process_txn_submit_latency_timer_client();
+    let ineligible_for_broadcast =
+        smp.network_interface.is_validator() && !smp.broadcast_within_validator_network();
+    let timeline_state = if ineligible_for_broadcast {
+        TimelineState::NonQualified
+    } else {
+        TimelineState::NotReady
+    };
+    let statuses = process_cancellations(&smp, vec![transaction], timeline_state, true);
+    log_txn_process_results(&statuses, None);
+
+    if let Some(status) = statuses.first() {
+        if callback.send(Ok(status.1.clone())).is_err() {
+            warn!(LogSchema::event_log(
+                LogEntry::CancelTxn,
+                LogEvent::CallbackFail
+            ));
+            counters::CLIENT_CALLBACK_FAIL.inc();
+        }
+    }
+}
+
 /// Processes get transaction by hash request by client.
 pub(crate) async fn process_client_get_transaction<NetworkClient, TransactionValidator>(
     smp: SharedMempool<NetworkClient, TransactionValidator>,
@@ -174,6 +210,28 @@ pub(crate) async fn process_client_get_transaction<NetworkClient, TransactionVal
     }
 }
 
+/// Processes get account transactions request by client.
+pub(crate) async fn process_client_get_account_transactions<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    address: AccountAddress,
+    callback: oneshot::Sender<AccountMempoolTransactions>,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    let txns = smp.mempool.lock().get_account_transactions(&address);
+
+    if callback.send(txns).is_err() {
+        warn!(LogSchema::event_log(
+            LogEntry::GetAccountTransactions,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionValidator>(
     smp: SharedMempool<NetworkClient, TransactionValidator>,
@@ -193,9 +251,19 @@ pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionVali
     timer.stop_and_record();
     let _timer = counters::process_txn_submit_latency_timer(peer.network_id());
     let results =
-        process_incoming_transactions(&smp, transactions, timeline_state, false, priority);
+        process_incoming_transactions(&smp, transactions, timeline_state, false, priority, false);
     log_txn_process_results(&results, Some(peer));
 
+    // Penalize the peer if it relayed transactions with invalid signatures
+    // (these should have been rejected by the peer before being broadcast).
+    if results
+        .iter()
+        .any(|(_, (_, vm_status))| *vm_status == Some(DiscardedVMStatus::INVALID_SIGNATURE))
+    {
+        smp.network_interface
+            .report_peer_penalty(peer, PeerPenalty::InvalidData);
+    }
+
     let ack_response = gen_ack_response(request_id, results, &peer);
 
     // Respond to the peer with an ack. Note: ack response messages should be
@@ -216,6 +284,71 @@ pub(crate) async fn process_transaction_broadcast<NetworkClient, TransactionVali
     notify_subscribers(SharedMempoolNotification::ACK, &smp.subscribers);
 }
 
+/// Processes cancellations of pending transactions from other nodes. Unlike broadcasts,
+/// cancellations are not acked.
+pub(crate) async fn process_transaction_cancellations<NetworkClient, TransactionValidator>(
+    smp: SharedMempool<NetworkClient, TransactionValidator>,
+    transactions: Vec<SignedTransaction>,
+    timeline_state: TimelineState,
+    peer: PeerNetworkId,
+    timer: HistogramTimer,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    timer.stop_and_record();
+    let _timer = counters::process_txn_submit_latency_timer(peer.network_id());
+    let results = process_cancellations(&smp, transactions, timeline_state, false);
+    log_txn_process_results(&results, Some(peer));
+}
+
+/// Replaces the pending transactions that have the same sender and sequence number as the
+/// given transactions, and sends the cancellations that are new to this node to upstream peers.
+fn process_cancellations<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+    transactions: Vec<SignedTransaction>,
+    timeline_state: TimelineState,
+    client_submitted: bool,
+) -> Vec<SubmissionStatusBundle>
+where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    // Cancellations that are already in mempool have been sent to upstream peers before,
+    // so they aren't sent again. This also keeps cancellations from cycling between peers.
+    let known_cancellations: HashSet<_> = {
+        let mempool = smp.mempool.lock();
+        transactions
+            .iter()
+            .map(SignedTransaction::committed_hash)
+            .filter(|hash| mempool.get_by_hash(*hash).is_some())
+            .collect()
+    };
+
+    let statuses = process_incoming_transactions(
+        smp,
+        transactions.into_iter().map(|t| (t, None)).collect(),
+        timeline_state,
+        client_submitted,
+        BroadcastPeerPriority::Primary,
+        true,
+    );
+
+    let new_cancellations: Vec<_> = statuses
+        .iter()
+        .filter(|(txn, (status, _))| {
+            status.code == MempoolStatusCode::Accepted
+                && !known_cancellations.contains(&txn.committed_hash())
+        })
+        .map(|(txn, _)| txn.clone())
+        .collect();
+    if !new_cancellations.is_empty() {
+        smp.network_interface
+            .broadcast_cancellations(new_cancellations);
+    }
+    statuses
+}
+
 /// If `MempoolIsFull` on any of the transactions, provide backpressure to the downstream peer.
 fn gen_ack_response(
     request_id: MultiBatchId,
@@ -274,6 +407,8 @@ pub(crate) fn process_incoming_transactions<NetworkClient, TransactionValidator>
     client_submitted: bool,
     // The priority of this node for the sender of the transactions
     priority: BroadcastPeerPriority,
+    // Whether the transactions cancel the pending transactions with the same sequence numbers
+    cancellation: bool,
 ) -> Vec<SubmissionStatusBundle>
 where
     NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
@@ -281,6 +416,10 @@ where
 {
     let mut statuses = vec![];
 
+    // Reject any transactions that are denied by the mempool transaction filter
+    let transactions =
+        filter_transactions(&smp.config.transaction_filter, transactions, &mut statuses);
+
     let start_storage_read = Instant::now();
     let state_view = smp
         .db
@@ -343,11 +482,70 @@ where
         &mut statuses,
         client_submitted,
         priority,
+        cancellation,
     );
     notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
     statuses
 }
 
+/// Removes the transactions that are denied by the given filter, and
+/// records a [MempoolStatusCode::RejectedByFilter] status for each of them.
+pub(crate) fn filter_transactions(
+    filter: &Filter,
+    transactions: Vec<(SignedTransaction, Option<u64>)>,
+    statuses: &mut Vec<SubmissionStatusBundle>,
+) -> Vec<(SignedTransaction, Option<u64>)> {
+    // Special case for no filter to avoid unnecessary iteration through all transactions in the default case
+    if filter.is_empty() {
+        return transactions;
+    }
+
+    // Transactions are not part of a block yet, so we use HashValue::zero() for the block ID
+    // and the current time for the timestamp. Block ID based filters are not allowed for
+    // mempool, see the ConfigSanitizer for MempoolConfig.
+    let timestamp_usecs = aptos_infallible::duration_since_epoch().as_micros() as u64;
+    transactions
+        .into_iter()
+        .filter_map(|(txn, ready_time_at_sender)| {
+            if filter.allows(HashValue::zero(), timestamp_usecs, &txn) {
+                return Some((txn, ready_time_at_sender));
+            }
+            counters::CORE_MEMPOOL_FILTERED_TXNS.inc();
+            statuses.push((
+                txn,
+                (
+                    MempoolStatus::new(MempoolStatusCode::RejectedByFilter).with_message(
+                        "Transaction was rejected by the mempool transaction filter".into(),
+                    ),
+                    None,
+                ),
+            ));
+            None
+        })
+        .collect()
+}
+
+/// Removes the journaled transactions that are denied by the given filter.
+/// See [filter_transactions] for the transactions that are submitted.
+pub(crate) fn filter_journaled_transactions(
+    filter: &Filter,
+    mut journaled_txns: Vec<JournaledTransaction>,
+) -> Vec<JournaledTransaction> {
+    if filter.is_empty() {
+        return journaled_txns;
+    }
+
+    let timestamp_usecs = aptos_infallible::duration_since_epoch().as_micros() as u64;
+    journaled_txns.retain(|journaled_txn| {
+        let allowed = filter.allows(HashValue::zero(), timestamp_usecs, &journaled_txn.txn);
+        if !allowed {
+            counters::CORE_MEMPOOL_FILTERED_TXNS.inc();
+        }
+        allowed
+    });
+    journaled_txns
+}
+
 /// Perfoms VM validation on the transactions and inserts those that passes
 /// validation into the mempool.
 #[cfg(not(feature = "consensus-only-perf-test"))]
@@ -358,6 +556,7 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
     statuses: &mut Vec<(SignedTransaction, (MempoolStatus, Option<StatusCode>))>,
     client_submitted: bool,
     priority: BroadcastPeerPriority,
+    cancellation: bool,
 ) where
     NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
     TransactionValidator: TransactionValidation,
@@ -380,15 +579,26 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
                 match validation_result.status() {
                     None => {
                         let ranking_score = validation_result.score();
-                        let mempool_status = mempool.add_txn(
-                            transaction.clone(),
-                            ranking_score,
-                            sequence_info,
-                            timeline_state,
-                            client_submitted,
-                            ready_time_at_sender,
-                            priority.clone(),
-                        );
+                        let mempool_status = if cancellation {
+                            mempool.cancel_txn(
+                                transaction.clone(),
+                                ranking_score,
+                                sequence_info,
+                                timeline_state,
+                                client_submitted,
+                                priority.clone(),
+                            )
+                        } else {
+                            mempool.add_txn(
+                                transaction.clone(),
+                                ranking_score,
+                                sequence_info,
+                                timeline_state,
+                                client_submitted,
+                                ready_time_at_sender,
+                                priority.clone(),
+                            )
+                        };
                         statuses.push((transaction, (mempool_status, None)));
                     },
                     Some(validation_status) => {
@@ -429,6 +639,7 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
     statuses: &mut Vec<(SignedTransaction, (MempoolStatus, Option<StatusCode>))>,
     client_submitted: bool,
     priority: BroadcastPeerPriority,
+    cancellation: bool,
 ) where
     NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
     TransactionValidator: TransactionValidation,
@@ -437,18 +648,120 @@ fn validate_and_add_transactions<NetworkClient, TransactionValidator>(
 
     let mut mempool = smp.mempool.lock();
     for (transaction, sequence_info, _ready_time_at_sender) in transactions.into_iter() {
-        let mempool_status = mempool.add_txn(
-            transaction.clone(),
-            0,
-            sequence_info,
-            timeline_state,
-            client_submitted,
-            priority,
-        );
+        let mempool_status = if cancellation {
+            mempool.cancel_txn(
+                transaction.clone(),
+                0,
+                sequence_info,
+                timeline_state,
+                client_submitted,
+                priority,
+            )
+        } else {
+            mempool.add_txn(
+                transaction.clone(),
+                0,
+                sequence_info,
+                timeline_state,
+                client_submitted,
+                priority,
+            )
+        };
         statuses.push((transaction, (mempool_status, None)));
     }
 }
 
+/// Restores the transactions in the mempool journal after a restart. Like new submissions,
+/// the transactions go through the mempool transaction filter and are validated against the
+/// latest state. Those that are rejected are dropped from the journal.
+pub(crate) fn restore_journaled_transactions<NetworkClient, TransactionValidator>(
+    smp: &SharedMempool<NetworkClient, TransactionValidator>,
+) where
+    NetworkClient: NetworkClientInterface<MempoolSyncMsg>,
+    TransactionValidator: TransactionValidation,
+{
+    let journaled_txns = match smp.mempool.lock().get_journaled_transactions() {
+        Ok(journaled_txns) => journaled_txns,
+        Err(e) => {
+            error!(LogSchema::new(LogEntry::JournalError).error(&e));
+            counters::MEMPOOL_JOURNAL_ERRORS.inc();
+            return;
+        },
+    };
+    if journaled_txns.is_empty() {
+        return;
+    }
+    let num_journaled_txns = journaled_txns.len();
+
+    // The filter may have changed since the transactions were journaled
+    let journaled_txns =
+        filter_journaled_transactions(&smp.config.transaction_filter, journaled_txns);
+
+    // The journal is left as is, so that the transactions are restored on the next restart
+    let state_view = match smp.db.latest_state_checkpoint_view() {
+        Ok(state_view) => state_view,
+        Err(e) => {
+            error!(LogSchema::new(LogEntry::JournalError).error(&anyhow::Error::from(e)));
+            counters::MEMPOOL_JOURNAL_ERRORS.inc();
+            return;
+        },
+    };
+    let validation_results = journaled_txns
+        .par_iter()
+        .map(|journaled_txn| {
+            let sequence_number =
+                get_account_sequence_number(&state_view, journaled_txn.txn.sender())?;
+            let validation_result = smp
+                .validator
+                .read()
+                .validate_transaction(journaled_txn.txn.clone())?;
+            Ok((sequence_number, validation_result))
+        })
+        .collect::<Vec<Result<_>>>();
+
+    let mut mempool = smp.mempool.lock();
+    let mut num_restored_txns = 0;
+    let mut num_failed_txns = 0;
+    for (journaled_txn, validation_result) in journaled_txns.into_iter().zip(validation_results) {
+        match validation_result {
+            Ok((sequence_number, validation_result)) => {
+                if validation_result.status().is_none() {
+                    let mempool_status = mempool.restore_txn(
+                        journaled_txn,
+                        validation_result.score(),
+                        sequence_number,
+                    );
+                    if mempool_status.code == MempoolStatusCode::Accepted {
+                        num_restored_txns += 1;
+                    }
+                }
+            },
+            Err(e) => {
+                error!(LogSchema::new(LogEntry::JournalError).error(&e));
+                counters::MEMPOOL_JOURNAL_ERRORS.inc();
+                num_failed_txns += 1;
+            },
+        }
+    }
+    // Like above, the transactions that couldn't be validated are kept in the journal, so
+    // that they are restored on the next restart
+    if num_failed_txns == 0 {
+        if let Err(e) = mempool.prune_journal() {
+            error!(LogSchema::new(LogEntry::JournalError).error(&e));
+            counters::MEMPOOL_JOURNAL_ERRORS.inc();
+        }
+    }
+    counters::MEMPOOL_JOURNAL_RESTORED_TXNS.inc_by(num_restored_txns);
+
+    info!(
+        LogSchema::new(LogEntry::JournalRestore),
+        num_journaled_txns = num_journaled_txns,
+        num_restored_txns = num_restored_txns,
+        num_failed_txns = num_failed_txns,
+        "Restored transactions from the mempool journal"
+    );
+}
+
 fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<PeerNetworkId>) {
     let network = match sender {
         Some(peer) => peer.network_id().to_string(),
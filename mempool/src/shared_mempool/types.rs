@@ -4,7 +4,7 @@
 
 //! Objects used by/related to shared mempool
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, TimelineState},
     network::{MempoolNetworkInterface, MempoolSyncMsg},
     shared_mempool::use_case_history::UseCaseHistory,
 };
@@ -21,7 +21,8 @@ use aptos_infallible::{Mutex, RwLock};
 use aptos_network::application::interface::NetworkClientInterface;
 use aptos_storage_interface::DbReader;
 use aptos_types::{
-    mempool_status::MempoolStatus, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    account_address::AccountAddress, mempool_status::MempoolStatus, transaction::SignedTransaction,
+    vm_status::DiscardedVMStatus,
 };
 use aptos_vm_validator::vm_validator::TransactionValidation;
 use futures::{
@@ -232,7 +233,73 @@ pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 
 pub enum MempoolClientRequest {
     SubmitTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
+    /// Cancels the pending transaction with the same sender and sequence number, by replacing
+    /// it with the given transaction
+    CancelTransaction(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>),
     GetTransactionByHash(HashValue, oneshot::Sender<Option<SignedTransaction>>),
+    GetAccountTransactions(AccountAddress, oneshot::Sender<AccountMempoolTransactions>),
+}
+
+/// The transactions of an account that are in mempool, and why they are (not) making progress
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountMempoolTransactions {
+    /// The sequence number of the account as last seen by mempool, if it has transactions
+    pub account_sequence_number: Option<u64>,
+    /// The first range of missing sequence numbers, which blocks all parked transactions
+    pub sequence_number_gap: Option<SequenceNumberGap>,
+    /// The transactions of the account, ordered by sequence number
+    pub transactions: Vec<PendingTransactionInfo>,
+}
+
+/// A range `[start, end)` of sequence numbers for which mempool has no transactions
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SequenceNumberGap {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// A transaction in mempool, along with its state
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PendingTransactionInfo {
+    pub hash: HashValue,
+    pub sequence_number: u64,
+    pub gas_unit_price: u64,
+    pub ranking_score: u64,
+    pub insertion_time: SystemTime,
+    pub expiration_timestamp_secs: u64,
+    pub state: PendingTransactionState,
+    pub broadcast_state: BroadcastState,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingTransactionState {
+    /// The transaction can be pulled into a block
+    Ready,
+    /// The transaction is in the parking lot, waiting for transactions with lower
+    /// sequence numbers
+    Parked,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastState {
+    /// The transaction is in the timeline, to be broadcast to peers
+    Ready,
+    /// The transaction will be broadcast once it's ready
+    NotReady,
+    /// The transaction isn't broadcast by this node, e.g. because it was received from a peer
+    NonQualified,
+}
+
+impl From<TimelineState> for BroadcastState {
+    fn from(timeline_state: TimelineState) -> Self {
+        match timeline_state {
+            TimelineState::Ready(_) => BroadcastState::Ready,
+            TimelineState::NotReady => BroadcastState::NotReady,
+            TimelineState::NonQualified => BroadcastState::NonQualified,
+        }
+    }
 }
 
 pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
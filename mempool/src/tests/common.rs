@@ -67,29 +67,42 @@ impl TestTransaction {
         &self,
         exp_timestamp_secs: u64,
     ) -> SignedTransaction {
-        self.make_signed_transaction_impl(100, exp_timestamp_secs)
+        self.make_signed_transaction_impl(
+            100,
+            exp_timestamp_secs,
+            Script::new(vec![], vec![], vec![]),
+        )
     }
 
     pub(crate) fn make_signed_transaction_with_max_gas_amount(
         &self,
         max_gas_amount: u64,
     ) -> SignedTransaction {
-        self.make_signed_transaction_impl(max_gas_amount, u64::MAX)
+        self.make_signed_transaction_impl(
+            max_gas_amount,
+            u64::MAX,
+            Script::new(vec![], vec![], vec![]),
+        )
+    }
+
+    pub(crate) fn make_signed_transaction_with_script(&self, script: Script) -> SignedTransaction {
+        self.make_signed_transaction_impl(100, u64::MAX, script)
     }
 
     pub(crate) fn make_signed_transaction(&self) -> SignedTransaction {
-        self.make_signed_transaction_impl(100, u64::MAX)
+        self.make_signed_transaction_impl(100, u64::MAX, Script::new(vec![], vec![], vec![]))
     }
 
     fn make_signed_transaction_impl(
         &self,
         max_gas_amount: u64,
         exp_timestamp_secs: u64,
+        script: Script,
     ) -> SignedTransaction {
         let raw_txn = RawTransaction::new_script(
             TestTransaction::get_address(self.address),
             self.sequence_number,
-            Script::new(vec![], vec![], vec![]),
+            script,
             max_gas_amount,
             self.gas_price,
             exp_timestamp_secs,
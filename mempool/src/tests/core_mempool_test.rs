@@ -5,16 +5,20 @@
 use crate::{
     core_mempool::{CoreMempool, MempoolTransaction, SubmittedBy, TimelineState},
     network::BroadcastPeerPriority,
+    shared_mempool::types::{BroadcastState, PendingTransactionState, SequenceNumberGap},
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, setup_mempool,
-        setup_mempool_with_broadcast_buckets, txn_bytes_len, TestTransaction,
+        setup_mempool_with_broadcast_buckets, txn_bytes_len, ConsensusMock, TestTransaction,
     },
 };
-use aptos_config::config::NodeConfig;
+use aptos_config::config::{MempoolEvictionPolicy, MempoolOrderingPolicy, NodeConfig};
 use aptos_consensus_types::common::{TransactionInProgress, TransactionSummary};
 use aptos_crypto::HashValue;
+use aptos_temppath::TempPath;
 use aptos_types::{
-    mempool_status::MempoolStatusCode, transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    mempool_status::MempoolStatusCode,
+    transaction::{Script, SignedTransaction},
+    vm_status::DiscardedVMStatus,
 };
 use itertools::Itertools;
 use maplit::btreemap;
@@ -818,6 +822,64 @@ fn test_parking_lot_evict_only_for_ready_txn_insertion() {
     }
 }
 
+#[test]
+fn test_lowest_ranking_score_eviction() {
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.capacity = 4;
+    config.mempool.eviction_policy = MempoolEvictionPolicy::LowestRankingScore;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 3)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 3)).unwrap();
+    let lowest = add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    let successor = add_txn(&mut pool, TestTransaction::new(1, 1, 5)).unwrap();
+
+    // Mempool is full. The lowest ranked txn is evicted along with the following txn of its account.
+    add_txn(&mut pool, TestTransaction::new(2, 0, 2)).unwrap();
+    assert!(pool.get_by_hash(lowest.committed_hash()).is_none());
+    assert!(pool.get_by_hash(successor.committed_hash()).is_none());
+
+    // Txns that don't have a higher ranking score than the lowest ranked txn are rejected.
+    assert!(add_txn(&mut pool, TestTransaction::new(3, 0, 2)).is_err());
+
+    // The preceding txns of the same account are never evicted.
+    add_txn(&mut pool, TestTransaction::new(2, 1, 10)).unwrap();
+    let mut txns: Vec<_> = pool
+        .get_batch(5, 5120, true, btreemap![])
+        .iter()
+        .map(|txn| (txn.sender(), txn.sequence_number()))
+        .collect();
+    txns.sort_unstable();
+    let mut expected = vec![
+        (TestTransaction::get_address(0), 0),
+        (TestTransaction::get_address(2), 0),
+        (TestTransaction::get_address(2), 1),
+    ];
+    expected.sort_unstable();
+    assert_eq!(txns, expected);
+}
+
+#[test]
+fn test_ordering_by_expiration() {
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.ordering_policy = MempoolOrderingPolicy::RankingScoreThenExpiration;
+    let mut pool = CoreMempool::new(&config);
+    let mut consensus = ConsensusMock::new();
+
+    let txns = vec![
+        TestTransaction::new(0, 0, 1).make_signed_transaction_with_expiration_time(u64::MAX - 1),
+        TestTransaction::new(1, 0, 1).make_signed_transaction_with_expiration_time(u64::MAX - 2),
+        TestTransaction::new(2, 0, 2).make_signed_transaction_with_expiration_time(u64::MAX),
+    ];
+    for txn in &txns {
+        add_signed_txn(&mut pool, txn.clone()).unwrap();
+    }
+
+    // Ranking score comes first, then the txns that expire earlier.
+    for txn in [&txns[2], &txns[1], &txns[0]] {
+        assert_eq!(consensus.get_block(&mut pool, 1, 1024), vec![txn.clone()]);
+    }
+}
+
 #[test]
 fn test_gc_ready_transaction() {
     let mut pool = setup_mempool().0;
@@ -1135,3 +1197,238 @@ fn test_include_gas_upgraded() {
     });
     assert_eq!(batch.len(), 0);
 }
+
+#[test]
+fn test_journal_restore() {
+    let tmp_dir = TempPath::new();
+    let mut config = NodeConfig::generate_random_config();
+    config.set_data_dir(tmp_dir.path().to_path_buf());
+    config.mempool.enable_journal = true;
+
+    let txns = {
+        let mut pool = CoreMempool::new(&config);
+        let txns = add_txns_to_mempool(&mut pool, vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(1, 0, 1),
+        ]);
+        // Committed transactions are removed from the journal
+        pool.commit_transaction(&TestTransaction::get_address(1), 0);
+        txns
+    };
+
+    // After a restart, the journal has the transactions that were still in mempool
+    let mut pool = CoreMempool::new(&config);
+    let journaled_txns = pool.get_journaled_transactions().unwrap();
+    assert_eq!(
+        journaled_txns
+            .iter()
+            .map(|journaled_txn| journaled_txn.txn.clone())
+            .collect::<Vec<_>>(),
+        txns[..2].to_vec()
+    );
+
+    // Restored transactions can be pulled by consensus again
+    for journaled_txn in journaled_txns {
+        let ranking_score = journaled_txn.txn.gas_unit_price();
+        let status = pool.restore_txn(journaled_txn, ranking_score, 0);
+        assert_eq!(status.code, MempoolStatusCode::Accepted);
+    }
+    assert_eq!(
+        pool.get_batch(10, 10240, true, btreemap![]),
+        txns[..2].to_vec()
+    );
+}
+
+#[test]
+fn test_get_account_transactions() {
+    let (mut pool, _) = setup_mempool();
+    let txns = add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 2),
+        TestTransaction::new(0, 4, 1),
+        TestTransaction::new(0, 5, 1),
+    ]);
+
+    // The transactions after the missing sequence numbers 2 and 3 are parked
+    let account_txns = pool.get_account_transactions(&TestTransaction::get_address(0));
+    assert_eq!(account_txns.account_sequence_number, Some(0));
+    assert_eq!(
+        account_txns.sequence_number_gap,
+        Some(SequenceNumberGap { start: 2, end: 4 })
+    );
+    assert_eq!(
+        account_txns
+            .transactions
+            .iter()
+            .map(|txn| (txn.hash, txn.sequence_number, txn.ranking_score))
+            .collect::<Vec<_>>(),
+        txns.iter()
+            .map(|txn| (
+                txn.committed_hash(),
+                txn.sequence_number(),
+                txn.gas_unit_price()
+            ))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        account_txns
+            .transactions
+            .iter()
+            .map(|txn| (txn.state, txn.broadcast_state))
+            .collect::<Vec<_>>(),
+        vec![
+            (PendingTransactionState::Ready, BroadcastState::Ready),
+            (PendingTransactionState::Ready, BroadcastState::Ready),
+            (PendingTransactionState::Parked, BroadcastState::NotReady),
+            (PendingTransactionState::Parked, BroadcastState::NotReady),
+        ]
+    );
+
+    // Once the gap is filled, all the transactions are ready
+    add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(0, 2, 1),
+        TestTransaction::new(0, 3, 1),
+    ]);
+    let account_txns = pool.get_account_transactions(&TestTransaction::get_address(0));
+    assert_eq!(account_txns.sequence_number_gap, None);
+    assert!(account_txns
+        .transactions
+        .iter()
+        .all(|txn| txn.state == PendingTransactionState::Ready));
+
+    // Accounts without transactions in mempool have nothing pending
+    let account_txns = pool.get_account_transactions(&TestTransaction::get_address(1));
+    assert_eq!(account_txns.account_sequence_number, None);
+    assert!(account_txns.transactions.is_empty());
+}
+
+#[test]
+fn test_cancel_transaction() {
+    let (mut pool, mut consensus) = setup_mempool();
+    let txns = add_txns_to_mempool(&mut pool, vec![
+        TestTransaction::new(0, 0, 1),
+        TestTransaction::new(0, 1, 1),
+    ]);
+
+    // A regular update can't change the max gas amount, but a cancellation can
+    let cancellation =
+        TestTransaction::new(0, 0, 2).make_signed_transaction_with_max_gas_amount(200);
+    assert!(add_signed_txn(&mut pool, cancellation.clone()).is_err());
+    let status = pool.cancel_txn(
+        cancellation.clone(),
+        cancellation.gas_unit_price(),
+        0,
+        TimelineState::NotReady,
+        true,
+        BroadcastPeerPriority::Primary,
+    );
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    assert!(pool.get_by_hash(txns[0].committed_hash()).is_none());
+    assert_eq!(
+        pool.get_by_hash(cancellation.committed_hash()),
+        Some(cancellation.clone())
+    );
+
+    // Cancelling again is idempotent
+    let status = pool.cancel_txn(
+        cancellation.clone(),
+        cancellation.gas_unit_price(),
+        0,
+        TimelineState::NotReady,
+        true,
+        BroadcastPeerPriority::Primary,
+    );
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+
+    // The cancellation takes the place of the pending transaction
+    assert_eq!(consensus.get_block(&mut pool, 10, 10240), vec![
+        cancellation,
+        txns[1].clone()
+    ]);
+}
+
+#[test]
+fn test_cancel_transaction_invalid() {
+    let (mut pool, _) = setup_mempool();
+    let txn = add_txn(&mut pool, TestTransaction::new(0, 0, 2)).unwrap();
+    let cancel = |pool: &mut CoreMempool, cancellation: &SignedTransaction| {
+        pool.cancel_txn(
+            cancellation.clone(),
+            cancellation.gas_unit_price(),
+            0,
+            TimelineState::NotReady,
+            true,
+            BroadcastPeerPriority::Primary,
+        )
+        .code
+    };
+
+    // Only a no-op can cancel a transaction
+    let script = Script::new(vec![1], vec![], vec![]);
+    let cancellation = TestTransaction::new(0, 0, 3).make_signed_transaction_with_script(script);
+    assert_eq!(
+        cancel(&mut pool, &cancellation),
+        MempoolStatusCode::InvalidUpdate
+    );
+
+    // The cancellation must have a higher gas unit price than the pending transaction
+    for gas_unit_price in [1, 2] {
+        let cancellation = TestTransaction::new(0, 0, gas_unit_price)
+            .make_signed_transaction_with_max_gas_amount(200);
+        assert_eq!(
+            cancel(&mut pool, &cancellation),
+            MempoolStatusCode::InvalidUpdate
+        );
+    }
+    assert_eq!(pool.get_by_hash(txn.committed_hash()), Some(txn));
+}
+
+#[test]
+fn test_cancel_transaction_rejected() {
+    // Fill mempool so that it stays full after the pending transaction is removed
+    let large_script = Script::new(vec![0; 1_024], vec![], vec![]);
+    let large_txn = TestTransaction::new(1, 0, 1).make_signed_transaction_with_script(large_script);
+    let mut config = NodeConfig::generate_random_config();
+    config.mempool.capacity_bytes = MempoolTransaction::new(
+        large_txn.clone(),
+        Duration::from_secs(1),
+        1,
+        TimelineState::NotReady,
+        0,
+        SystemTime::now(),
+        false,
+        BroadcastPeerPriority::Primary,
+    )
+    .get_estimated_bytes();
+    let mut pool = CoreMempool::new(&config);
+    let mut consensus = ConsensusMock::new();
+    let txn = add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_signed_txn(&mut pool, large_txn.clone()).unwrap();
+
+    // The rejected cancellation doesn't evict the pending transaction
+    let cancellation =
+        TestTransaction::new(0, 0, 2).make_signed_transaction_with_max_gas_amount(200);
+    let status = pool.cancel_txn(
+        cancellation.clone(),
+        cancellation.gas_unit_price(),
+        0,
+        TimelineState::NotReady,
+        true,
+        BroadcastPeerPriority::Primary,
+    );
+    assert_eq!(status.code, MempoolStatusCode::MempoolIsFull);
+    assert!(pool.get_by_hash(cancellation.committed_hash()).is_none());
+    assert_eq!(pool.get_by_hash(txn.committed_hash()), Some(txn.clone()));
+    assert_eq!(
+        consensus
+            .get_block(&mut pool, 10, 10240)
+            .into_iter()
+            .sorted_by_key(|txn| txn.sender())
+            .collect::<Vec<_>>(),
+        vec![txn, large_txn]
+            .into_iter()
+            .sorted_by_key(|txn| txn.sender())
+            .collect::<Vec<_>>()
+    );
+}
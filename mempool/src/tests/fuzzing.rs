@@ -63,6 +63,7 @@ pub fn test_mempool_process_incoming_transactions_impl(
         timeline_state,
         false,
         BroadcastPeerPriority::Primary,
+        false,
     );
 }
 
@@ -271,6 +271,64 @@ async fn test_update_gas_price() {
         .await;
 }
 
+/// Test that cancellations submitted to a node replace the pending transactions and are sent to
+/// upstream peers
+#[tokio::test]
+async fn test_cancel_txns_via_client() {
+    // A no-op with a higher gas unit price than the pending transaction
+    let cancellation =
+        TestTransaction::new(1, 0, 2).make_signed_transaction_with_max_gas_amount(200);
+    let cancellations = &[cancellation];
+
+    let mut node = MempoolTestFrameworkBuilder::single_validator();
+    let (other_peer_network_id, other_metadata) =
+        validator_mock_connection(ConnectionOrigin::Outbound, &ALL_PROTOCOLS);
+
+    // Get first txn and send it to the other node
+    node.add_txns_via_client(TXN_1).await;
+    node.connect_self(other_peer_network_id.network_id(), other_metadata.clone());
+    node.send_broadcast_and_receive_ack(other_peer_network_id, TXN_1)
+        .await;
+
+    // Cancel the txn, which should be sent to the other node too
+    node.cancel_txns_via_client(cancellations).await;
+    node.wait_on_cancellations_in_mempool(cancellations).await;
+    node.expect_cancellations_sent(other_peer_network_id, cancellations)
+        .await;
+}
+
+/// Tests if the node is a VFN, and it's getting cancellations from a PFN. It should apply them and
+/// forward them to the upstream VAL.
+#[tokio::test]
+async fn test_receive_cancellations() {
+    // A no-op with a higher gas unit price than the pending transaction
+    let cancellation =
+        TestTransaction::new(1, 0, 2).make_signed_transaction_with_max_gas_amount(200);
+    let cancellations = &[cancellation];
+
+    let mut node = MempoolTestFrameworkBuilder::single_vfn();
+    let (validator_peer_network_id, validator_metadata) =
+        vfn_validator_mock_connection(ConnectionOrigin::Outbound, &ALL_PROTOCOLS);
+    let (fn_peer_network_id, fn_metadata) =
+        pfn_vfn_mock_connection(ConnectionOrigin::Inbound, &ALL_PROTOCOLS);
+    node.connect_self(validator_peer_network_id.network_id(), validator_metadata);
+    node.connect_self(fn_peer_network_id.network_id(), fn_metadata);
+
+    // Incoming transactions should be accepted and forwarded upstream
+    node.receive_message(ProtocolId::MempoolDirectSend, fn_peer_network_id, TXN_1)
+        .await;
+    node.send_broadcast_and_receive_ack(validator_peer_network_id, TXN_1)
+        .await;
+
+    // Incoming cancellations should replace the pending transactions
+    node.receive_cancellations(fn_peer_network_id, cancellations);
+    node.wait_on_cancellations_in_mempool(cancellations).await;
+
+    // And they should be forwarded upstream
+    node.expect_cancellations_sent(validator_peer_network_id, cancellations)
+        .await;
+}
+
 /// In the event of a full mempool, retry and broadcast again
 #[tokio::test]
 async fn test_mempool_full_rebroadcast() {
@@ -3,15 +3,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    core_mempool::{JournaledTransaction, TimelineState},
     mocks::MockSharedMempool,
     network::BroadcastPeerPriority,
+    shared_mempool::tasks::{filter_journaled_transactions, filter_transactions},
     tests::common::{batch_add_signed_txn, TestTransaction},
     QuorumStoreRequest,
 };
+use aptos_config::config::transaction_filter_type::Filter;
 use aptos_consensus_types::common::RejectedTransactionSummary;
 use aptos_mempool_notifications::MempoolNotificationSender;
-use aptos_types::{transaction::Transaction, vm_status::DiscardedVMStatus};
+use aptos_types::{
+    mempool_status::MempoolStatusCode, transaction::Transaction, vm_status::DiscardedVMStatus,
+};
 use futures::{channel::oneshot, sink::SinkExt};
+use std::time::{Duration, SystemTime};
 use tokio::time::timeout;
 
 #[tokio::test]
@@ -116,3 +122,53 @@ async fn test_mempool_notify_committed_txns() {
         );
     }
 }
+
+#[test]
+fn test_filter_transactions() {
+    let denied_txn = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let allowed_txn = TestTransaction::new(1, 0, 1).make_signed_transaction();
+    let transactions = vec![(denied_txn.clone(), None), (allowed_txn.clone(), None)];
+
+    // An empty filter should keep all transactions
+    let mut statuses = vec![];
+    let filtered = filter_transactions(&Filter::empty(), transactions.clone(), &mut statuses);
+    assert_eq!(filtered.len(), 2);
+    assert!(statuses.is_empty());
+
+    // A sender filter should only reject the transactions of the denied sender
+    let filter = Filter::empty().add_deny_sender(denied_txn.sender());
+    let filtered = filter_transactions(&filter, transactions, &mut statuses);
+    assert_eq!(filtered, vec![(allowed_txn, None)]);
+    assert_eq!(statuses.len(), 1);
+    let (rejected_txn, (mempool_status, vm_status)) = &statuses[0];
+    assert_eq!(*rejected_txn, denied_txn);
+    assert_eq!(mempool_status.code, MempoolStatusCode::RejectedByFilter);
+    assert!(vm_status.is_none());
+}
+
+#[test]
+fn test_filter_journaled_transactions() {
+    let denied_txn = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let allowed_txn = TestTransaction::new(1, 0, 1).make_signed_transaction();
+    let journaled_txns: Vec<_> = [denied_txn.clone(), allowed_txn.clone()]
+        .into_iter()
+        .map(|txn| JournaledTransaction {
+            txn,
+            expiration_time: Duration::from_secs(1),
+            insertion_time: SystemTime::now(),
+            timeline_state: TimelineState::NotReady,
+            client_submitted: true,
+            priority_of_sender: BroadcastPeerPriority::Primary,
+        })
+        .collect();
+
+    // An empty filter should keep all transactions
+    let filtered = filter_journaled_transactions(&Filter::empty(), journaled_txns.clone());
+    assert_eq!(filtered, journaled_txns);
+
+    // Restored transactions are subject to the same filter as new submissions
+    let filter = Filter::empty().add_deny_sender(denied_txn.sender());
+    let filtered = filter_journaled_transactions(&filter, journaled_txns);
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].txn, allowed_txn);
+}
@@ -157,6 +157,23 @@ impl MempoolNode {
         }
     }
 
+    /// Cancels pending transactions on a node, uses client
+    pub async fn cancel_txns_via_client(&mut self, cancellations: &[SignedTransaction]) {
+        for cancellation in cancellations {
+            let (sender, receiver) = oneshot::channel();
+
+            self.mempool_client_sender
+                .send(MempoolClientRequest::CancelTransaction(
+                    cancellation.clone(),
+                    sender,
+                ))
+                .await
+                .unwrap();
+            let status = receiver.await.unwrap().unwrap();
+            assert_eq!(status.0.code, MempoolStatusCode::Accepted)
+        }
+    }
+
     pub async fn commit_txns(&mut self, txns: &[TestTransaction]) {
         for txn in sign_transactions(txns) {
             self.mempool
@@ -181,6 +198,25 @@ impl MempoolNode {
         }
     }
 
+    /// Asynchronously waits for up to 1 second for the cancellations to replace the pending
+    /// transactions in mempool
+    pub async fn wait_on_cancellations_in_mempool(&self, cancellations: &[SignedTransaction]) {
+        for _ in 0..10 {
+            let in_mempool = {
+                let mempool = self.mempool.lock();
+                cancellations
+                    .iter()
+                    .all(|txn| mempool.get_by_hash(txn.committed_hash()).is_some())
+            };
+            if in_mempool {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await
+        }
+        panic!("Expected cancellations in mempool: {:?}", cancellations);
+    }
+
     pub fn assert_only_txns_in_mempool(&self, txns: &[TestTransaction]) {
         if let Err((actual, expected)) =
             self.assert_condition_on_mempool_txns(txns, block_only_contains_transactions)
@@ -323,6 +359,59 @@ impl MempoolNode {
         }
     }
 
+    /// Receives cancellations of pending transactions from a peer. Cancellations aren't acked.
+    pub fn receive_cancellations(
+        &mut self,
+        remote_peer_network_id: PeerNetworkId,
+        cancellations: &[SignedTransaction],
+    ) {
+        let network_id = remote_peer_network_id.network_id();
+        let remote_peer_id = remote_peer_network_id.peer_id();
+        let inbound_handle = self.get_inbound_handle(network_id);
+        let msg = MempoolSyncMsg::CancelTransactionsRequest {
+            transactions: cancellations.to_vec(),
+        };
+        let notif = ReceivedMessage {
+            message: NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id: MempoolDirectSend,
+                priority: 0,
+                raw_msg: MempoolDirectSend.to_bytes(&msg).unwrap(),
+            }),
+            sender: remote_peer_network_id,
+            receive_timestamp_micros: 0,
+            rpc_replier: None,
+        };
+        inbound_handle
+            .inbound_message_sender
+            .push((remote_peer_id, MempoolDirectSend), notif)
+            .unwrap();
+    }
+
+    /// Waits for the cancellations to be sent to the given peer. Broadcasts sent to the peer
+    /// in the meantime are dropped.
+    pub async fn expect_cancellations_sent(
+        &mut self,
+        expected_peer_network_id: PeerNetworkId,
+        expected_cancellations: &[SignedTransaction],
+    ) {
+        let network_id = expected_peer_network_id.network_id();
+        loop {
+            let (peer_id, _, data) = self.drop_next_network_msg(network_id).await;
+            assert_eq!(peer_id, expected_peer_network_id.peer_id());
+            match common::decompress_and_deserialize(&data.to_vec()) {
+                MempoolSyncMsg::CancelTransactionsRequest { transactions } => {
+                    assert_eq!(transactions, expected_cancellations);
+                    return;
+                },
+                MempoolSyncMsg::BroadcastTransactionsRequest { .. }
+                | MempoolSyncMsg::BroadcastTransactionsRequestWithReadyTime { .. } => {},
+                MempoolSyncMsg::BroadcastTransactionsResponse { .. } => {
+                    panic!("We aren't supposed to be getting as response here");
+                },
+            }
+        }
+    }
+
     pub async fn send_broadcast_and_receive_ack(
         &mut self,
         expected_peer_network_id: PeerNetworkId,
@@ -433,6 +522,9 @@ impl MempoolNode {
             MempoolSyncMsg::BroadcastTransactionsResponse { .. } => {
                 panic!("We aren't supposed to be getting as response here");
             },
+            MempoolSyncMsg::CancelTransactionsRequest { .. } => {
+                panic!("We aren't supposed to be getting a cancellation here");
+            },
         };
         let response = MempoolSyncMsg::BroadcastTransactionsResponse {
             request_id,
@@ -479,9 +571,12 @@ impl TestFramework<MempoolNode> for MempoolTestFramework {
 
     fn build_node(
         node_id: NodeId,
-        config: NodeConfig,
+        mut config: NodeConfig,
         peer_network_ids: &[PeerNetworkId],
     ) -> MempoolNode {
+        // All the nodes of the test network support the cancellation messages
+        config.mempool.broadcast_transaction_cancellations = true;
+
         // Collect mappings of network_id to peer_network_id
         let mut network_ids = Vec::new();
         let mut network_id_mapping = HashMap::new();
@@ -12,9 +12,10 @@
 //! long as the latter is in its trusted peers set.
 use aptos_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RoleType, CONNECTION_BACKOFF_BASE,
-        CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
-        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, ProtocolQuotaConfig, RoleType,
+        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONNECTION_DELAY_MS,
+        MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS,
+        NETWORK_CHANNEL_SIZE,
     },
     network_id::NetworkContext,
 };
@@ -37,11 +38,17 @@ use aptos_network::{
             NewNetworkSender,
         },
     },
+    ProtocolId,
 };
 use aptos_network_discovery::DiscoveryChangeListener;
 use aptos_time_service::TimeService;
 use aptos_types::{chain_id::ChainId, network_address::NetworkAddress};
-use std::{clone::Clone, collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    clone::Clone,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::runtime::Handle;
 
 #[derive(Debug, PartialEq, PartialOrd)]
@@ -82,9 +89,12 @@ impl NetworkBuilder {
         max_frame_size: usize,
         max_message_size: usize,
         enable_proxy_protocol: bool,
+        enable_quic: bool,
         network_channel_size: usize,
         inbound_connection_limit: usize,
         tcp_buffer_cfg: TCPBufferCfg,
+        outbound_protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+        enforce_peer_bans: bool,
     ) -> Self {
         // A network cannot exist without a PeerManager
         // TODO:  construct this in create and pass it to new() as a parameter. The complication is manual construction of NetworkBuilder in various tests.
@@ -99,8 +109,11 @@ impl NetworkBuilder {
             max_frame_size,
             max_message_size,
             enable_proxy_protocol,
+            enable_quic,
             inbound_connection_limit,
             tcp_buffer_cfg,
+            outbound_protocol_quotas,
+            enforce_peer_bans,
         );
 
         NetworkBuilder {
@@ -137,9 +150,12 @@ impl NetworkBuilder {
             MAX_FRAME_SIZE,
             MAX_MESSAGE_SIZE,
             false, /* Disable proxy protocol */
+            false, /* Disable QUIC */
             NETWORK_CHANNEL_SIZE,
             MAX_INBOUND_CONNECTIONS,
             TCPBufferCfg::default(),
+            HashMap::new(),
+            true, /* enforce_peer_bans */
         );
 
         builder.add_connectivity_manager(
@@ -152,6 +168,7 @@ impl NetworkBuilder {
             NETWORK_CHANNEL_SIZE,
             mutual_authentication,
             true, /* enable_latency_aware_dialing */
+            true, /* enforce_peer_bans */
         );
 
         builder
@@ -187,6 +204,7 @@ impl NetworkBuilder {
             config.max_frame_size,
             config.max_message_size,
             config.enable_proxy_protocol,
+            config.enable_quic,
             config.network_channel_size,
             config.max_inbound_connections,
             TCPBufferCfg::new_configs(
@@ -195,6 +213,8 @@ impl NetworkBuilder {
                 config.outbound_rx_buffer_size_bytes,
                 config.outbound_tx_buffer_size_bytes,
             ),
+            outbound_protocol_quotas(config),
+            config.enforce_peer_bans(),
         );
 
         network_builder.add_connection_monitoring(
@@ -217,6 +237,7 @@ impl NetworkBuilder {
             config.network_channel_size,
             config.mutual_authentication,
             config.enable_latency_aware_dialing,
+            config.enforce_peer_bans(),
         );
 
         network_builder.discovery_listeners = Some(Vec::new());
@@ -318,6 +339,7 @@ impl NetworkBuilder {
         channel_size: usize,
         mutual_authentication: bool,
         enable_latency_aware_dialing: bool,
+        enforce_peer_bans: bool,
     ) -> &mut Self {
         let pm_conn_mgr_notifs_rx = self.peer_manager_builder.add_connection_event_listener();
         let outbound_connection_limit = if !self.network_context.network_id().is_validator_network()
@@ -341,6 +363,7 @@ impl NetworkBuilder {
             outbound_connection_limit,
             mutual_authentication,
             enable_latency_aware_dialing,
+            enforce_peer_bans,
         ));
         self
     }
@@ -384,6 +407,21 @@ impl NetworkBuilder {
                     Duration::from_secs(rest_discovery.interval_secs),
                     self.time_service.clone(),
                 ),
+                DiscoveryMethod::Dns(dns_discovery) => match DiscoveryChangeListener::dns(
+                    self.network_context,
+                    conn_mgr_reqs_tx.clone(),
+                    dns_discovery.clone(),
+                    self.time_service.clone(),
+                ) {
+                    Ok(listener) => listener,
+                    Err(error) => {
+                        error!(
+                            NetworkSchema::new(&self.network_context),
+                            "{} Unable to start DNS discovery: {:?}", self.network_context, error
+                        );
+                        continue;
+                    },
+                },
                 DiscoveryMethod::None => {
                     continue;
                 },
@@ -509,3 +547,27 @@ fn merge_seeds(config: &NetworkConfig) -> PeerSet {
     );
     seeds
 }
+
+/// Resolve the outbound protocol quotas, which are keyed by protocol name in the config.
+/// Note: invalid quotas are rejected by the config sanitizer, so they're only skipped here.
+fn outbound_protocol_quotas(config: &NetworkConfig) -> HashMap<ProtocolId, ProtocolQuotaConfig> {
+    config
+        .outbound_protocol_quotas
+        .iter()
+        .filter_map(|(name, quota)| {
+            let protocol_id = ProtocolId::all()
+                .iter()
+                .find(|protocol_id| protocol_id.as_str() == name);
+            match protocol_id {
+                Some(protocol_id) if quota.is_valid() => Some((*protocol_id, *quota)),
+                _ => {
+                    warn!(
+                        "Ignoring the invalid outbound protocol quota for {}: {:?}",
+                        name, quota
+                    );
+                    None
+                },
+            }
+        })
+        .collect()
+}
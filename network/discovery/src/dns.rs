@@ -0,0 +1,585 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::DiscoveryError;
+use aptos_config::{
+    config::{DnsDiscovery, Peer, PeerRole, PeerSet, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
+use aptos_crypto::{x25519, ValidCryptoMaterialStringExt};
+use aptos_logger::prelude::*;
+use aptos_network::logging::NetworkSchema;
+use aptos_time_service::{Interval, TimeService, TimeServiceTrait};
+use aptos_types::{
+    account_address::from_identity_public_key, network_address::NetworkAddress, PeerId,
+};
+use async_trait::async_trait;
+use futures::{
+    future::{try_join_all, BoxFuture},
+    FutureExt, Stream,
+};
+use hickory_resolver::{
+    error::{ResolveError, ResolveErrorKind},
+    TokioAsyncResolver,
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The prefix of the TXT records that carry the x25519 public key of a peer
+const KEY_TXT_RECORD_PREFIX: &str = "x25519=";
+
+/// Resolves the DNS records used by DNS discovery. Names without records of
+/// the requested type resolve to no records (rather than an error).
+#[async_trait]
+pub(crate) trait DnsResolver: Send + Sync {
+    /// Returns the IP addresses (i.e., A/AAAA records) of the name
+    async fn lookup_ips(&self, name: &str) -> Result<Vec<IpAddr>, DiscoveryError>;
+
+    /// Returns the TXT records of the name
+    async fn lookup_txts(&self, name: &str) -> Result<Vec<String>, DiscoveryError>;
+
+    /// Returns the targets (i.e., names and ports) of the SRV records of the name
+    async fn lookup_srvs(&self, name: &str) -> Result<Vec<(String, u16)>, DiscoveryError>;
+}
+
+/// A `DnsResolver` that uses the resolver configuration of the system
+/// (i.e., `/etc/resolv.conf`).
+struct SystemDnsResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemDnsResolver {
+    fn new() -> Result<Self, DiscoveryError> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().map_err(|error| {
+            DiscoveryError::Dns(format!(
+                "Unable to read the system resolver config: {}",
+                error
+            ))
+        })?;
+        Ok(Self { resolver })
+    }
+}
+
+/// Converts the given lookup result into the records, treating a lack of
+/// records as an empty set of records.
+fn records_or_empty<L, T>(
+    name: &str,
+    lookup: Result<L, ResolveError>,
+    records: impl FnOnce(L) -> Vec<T>,
+) -> Result<Vec<T>, DiscoveryError> {
+    match lookup {
+        Ok(lookup) => Ok(records(lookup)),
+        Err(error) => match error.kind() {
+            ResolveErrorKind::NoRecordsFound { .. } => Ok(vec![]),
+            _ => Err(DiscoveryError::Dns(format!(
+                "Failed to resolve {}: {}",
+                name, error
+            ))),
+        },
+    }
+}
+
+#[async_trait]
+impl DnsResolver for SystemDnsResolver {
+    async fn lookup_ips(&self, name: &str) -> Result<Vec<IpAddr>, DiscoveryError> {
+        records_or_empty(name, self.resolver.lookup_ip(name).await, |lookup| {
+            lookup.iter().collect()
+        })
+    }
+
+    async fn lookup_txts(&self, name: &str) -> Result<Vec<String>, DiscoveryError> {
+        records_or_empty(name, self.resolver.txt_lookup(name).await, |lookup| {
+            lookup
+                .iter()
+                .map(|txt| {
+                    // A TXT record may be split into multiple character strings
+                    let data: Vec<u8> = txt
+                        .txt_data()
+                        .iter()
+                        .flat_map(|data| data.iter())
+                        .copied()
+                        .collect();
+                    String::from_utf8_lossy(&data).into_owned()
+                })
+                .collect()
+        })
+    }
+
+    async fn lookup_srvs(&self, name: &str) -> Result<Vec<(String, u16)>, DiscoveryError> {
+        records_or_empty(name, self.resolver.srv_lookup(name).await, |lookup| {
+            lookup
+                .iter()
+                .map(|srv| (srv.target().to_utf8(), srv.port()))
+                .collect()
+        })
+    }
+}
+
+/// A discovery stream that periodically resolves the peers configured by
+/// `DnsDiscovery`. If any of the lookups fail, the update fails (so that a
+/// flaky resolver doesn't drop peers), but peers with invalid records are
+/// skipped.
+pub struct DnsStream {
+    network_context: NetworkContext,
+    dns_discovery: Arc<DnsDiscovery>,
+    resolver: Arc<dyn DnsResolver>,
+    interval: Pin<Box<Interval>>,
+    pending_update: Option<BoxFuture<'static, Result<PeerSet, DiscoveryError>>>,
+}
+
+impl DnsStream {
+    pub(crate) fn new(
+        network_context: NetworkContext,
+        dns_discovery: DnsDiscovery,
+        time_service: TimeService,
+    ) -> Result<Self, DiscoveryError> {
+        Ok(Self::new_with_resolver(
+            network_context,
+            dns_discovery,
+            Arc::new(SystemDnsResolver::new()?),
+            time_service,
+        ))
+    }
+
+    fn new_with_resolver(
+        network_context: NetworkContext,
+        dns_discovery: DnsDiscovery,
+        resolver: Arc<dyn DnsResolver>,
+        time_service: TimeService,
+    ) -> Self {
+        let interval_duration = Duration::from_secs(dns_discovery.interval_secs);
+        DnsStream {
+            network_context,
+            dns_discovery: Arc::new(dns_discovery),
+            resolver,
+            interval: Box::pin(time_service.interval(interval_duration)),
+            pending_update: None,
+        }
+    }
+}
+
+impl Stream for DnsStream {
+    type Item = Result<PeerSet, DiscoveryError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // Wait for delay, and then start resolving the peers
+        if this.pending_update.is_none() {
+            futures::ready!(this.interval.as_mut().poll_next(cx));
+            this.pending_update = Some(
+                resolve_peers(
+                    this.network_context,
+                    this.dns_discovery.clone(),
+                    this.resolver.clone(),
+                )
+                .boxed(),
+            );
+        }
+
+        // Wait for the peers to be resolved
+        let pending_update = this
+            .pending_update
+            .as_mut()
+            .expect("The update must be pending!");
+        let update = futures::ready!(pending_update.poll_unpin(cx));
+        this.pending_update = None;
+        Poll::Ready(Some(update))
+    }
+}
+
+/// Resolves all the peers configured by `DnsDiscovery`
+async fn resolve_peers(
+    network_context: NetworkContext,
+    dns_discovery: Arc<DnsDiscovery>,
+    resolver: Arc<dyn DnsResolver>,
+) -> Result<PeerSet, DiscoveryError> {
+    // Gather the names (and ports) of the peers
+    let mut targets: Vec<(String, u16)> = match dns_discovery.port {
+        Some(port) => dns_discovery
+            .names
+            .iter()
+            .map(|name| (name.clone(), port))
+            .collect(),
+        None if dns_discovery.names.is_empty() => vec![],
+        None => {
+            return Err(DiscoveryError::Parsing(
+                "A port is required to dial the peers in names".into(),
+            ))
+        },
+    };
+    let srv_targets = try_join_all(
+        dns_discovery
+            .srv_names
+            .iter()
+            .map(|srv_name| resolver.lookup_srvs(srv_name)),
+    )
+    .await?;
+    targets.extend(srv_targets.into_iter().flatten());
+
+    // Resolve the peers, merging any peers listed more than once
+    let peers = try_join_all(targets.iter().map(|(name, port)| {
+        resolve_peer(
+            network_context,
+            resolver.as_ref(),
+            name,
+            *port,
+            dns_discovery.role,
+        )
+    }))
+    .await?;
+    let mut peer_set = PeerSet::new();
+    for (peer_id, peer) in peers.into_iter().flatten() {
+        let existing_peer = peer_set
+            .entry(peer_id)
+            .or_insert_with(|| Peer::from_addrs(peer.role, vec![]));
+        for address in peer.addresses {
+            if !existing_peer.addresses.contains(&address) {
+                existing_peer.addresses.push(address);
+            }
+        }
+        existing_peer.keys.extend(peer.keys);
+    }
+    Ok(peer_set)
+}
+
+/// Resolves the peer at the given name. Returns None if the name has no
+/// addresses (e.g., the peer was removed), or doesn't have exactly one valid
+/// key.
+async fn resolve_peer(
+    network_context: NetworkContext,
+    resolver: &dyn DnsResolver,
+    name: &str,
+    port: u16,
+    role: PeerRole,
+) -> Result<Option<(PeerId, Peer)>, DiscoveryError> {
+    let (ips, txts) = futures::try_join!(resolver.lookup_ips(name), resolver.lookup_txts(name))?;
+    if ips.is_empty() {
+        return Ok(None);
+    }
+
+    let key = match parse_key(name, &txts) {
+        Ok(key) => key,
+        Err(error) => {
+            warn!(
+                NetworkSchema::new(&network_context),
+                "{} Skipping DNS discovered peer {}: {:?}", network_context, name, error
+            );
+            return Ok(None);
+        },
+    };
+
+    let addresses = ips
+        .into_iter()
+        .map(|ip| {
+            NetworkAddress::from(SocketAddr::new(ip, port))
+                .append_prod_protos(key, HANDSHAKE_VERSION)
+        })
+        .collect();
+    Ok(Some((
+        from_identity_public_key(key),
+        Peer::from_addrs(role, addresses),
+    )))
+}
+
+/// Parses the x25519 public key of the peer from its TXT records
+fn parse_key(name: &str, txts: &[String]) -> Result<x25519::PublicKey, DiscoveryError> {
+    let keys = txts
+        .iter()
+        .filter_map(|txt| txt.strip_prefix(KEY_TXT_RECORD_PREFIX))
+        .map(|key| {
+            x25519::PublicKey::from_encoded_string(key.trim()).map_err(|error| {
+                DiscoveryError::Parsing(format!("Invalid key {} of {}: {}", key, name, error))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match keys.as_slice() {
+        [key] => Ok(*key),
+        _ => Err(DiscoveryError::Parsing(format!(
+            "Expected exactly one key for {}, found {}",
+            name,
+            keys.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::Uniform;
+    use futures::StreamExt;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    /// A stub resolver, serving records from memory
+    #[derive(Default)]
+    struct StubResolver {
+        ips: HashMap<String, Vec<IpAddr>>,
+        txts: HashMap<String, Vec<String>>,
+        srvs: HashMap<String, Vec<(String, u16)>>,
+        failing_names: Vec<String>,
+    }
+
+    impl StubResolver {
+        fn add_peer(&mut self, name: &str, ips: &[&str], key: &x25519::PublicKey) {
+            let ips = ips.iter().map(|ip| ip.parse().unwrap()).collect();
+            self.ips.insert(name.into(), ips);
+            self.txts.insert(name.into(), vec![
+                "unrelated=record".into(),
+                format!(
+                    "{}{}",
+                    KEY_TXT_RECORD_PREFIX,
+                    key.to_encoded_string().unwrap()
+                ),
+            ]);
+        }
+
+        fn lookup<T: Clone>(
+            &self,
+            records: &HashMap<String, Vec<T>>,
+            name: &str,
+        ) -> Result<Vec<T>, DiscoveryError> {
+            if self
+                .failing_names
+                .iter()
+                .any(|failing_name| failing_name == name)
+            {
+                return Err(DiscoveryError::Dns(format!("Failed to resolve {}", name)));
+            }
+            Ok(records.get(name).cloned().unwrap_or_default())
+        }
+    }
+
+    #[async_trait]
+    impl DnsResolver for StubResolver {
+        async fn lookup_ips(&self, name: &str) -> Result<Vec<IpAddr>, DiscoveryError> {
+            self.lookup(&self.ips, name)
+        }
+
+        async fn lookup_txts(&self, name: &str) -> Result<Vec<String>, DiscoveryError> {
+            self.lookup(&self.txts, name)
+        }
+
+        async fn lookup_srvs(&self, name: &str) -> Result<Vec<(String, u16)>, DiscoveryError> {
+            self.lookup(&self.srvs, name)
+        }
+    }
+
+    fn create_keys(num_keys: usize) -> Vec<x25519::PublicKey> {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        (0..num_keys)
+            .map(|_| x25519::PrivateKey::generate(&mut rng).public_key())
+            .collect()
+    }
+
+    fn create_dns_discovery(names: &[&str], srv_names: &[&str]) -> DnsDiscovery {
+        DnsDiscovery {
+            names: names.iter().map(|name| name.to_string()).collect(),
+            port: Some(6180),
+            srv_names: srv_names.iter().map(|name| name.to_string()).collect(),
+            role: PeerRole::Upstream,
+            interval_secs: 1,
+        }
+    }
+
+    async fn resolve(
+        resolver: StubResolver,
+        dns_discovery: DnsDiscovery,
+    ) -> Result<PeerSet, DiscoveryError> {
+        resolve_peers(
+            NetworkContext::mock(),
+            Arc::new(dns_discovery),
+            Arc::new(resolver),
+        )
+        .await
+    }
+
+    fn expected_peer(key: &x25519::PublicKey, addresses: &[&str]) -> Peer {
+        let addresses = addresses
+            .iter()
+            .map(|address| {
+                address
+                    .parse::<NetworkAddress>()
+                    .unwrap()
+                    .append_prod_protos(*key, HANDSHAKE_VERSION)
+            })
+            .collect();
+        Peer::from_addrs(PeerRole::Upstream, addresses)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_names_and_srv_names() {
+        let keys = create_keys(3);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("fn0.internal", &["10.0.0.1", "fd00::1"], &keys[0]);
+        resolver.add_peer("fn1.internal.", &["10.0.0.2"], &keys[1]);
+        resolver.add_peer("fn2.internal.", &["10.0.0.3"], &keys[2]);
+        resolver.srvs.insert("_aptosnet._tcp.internal".into(), vec![
+            ("fn1.internal.".into(), 6181),
+            ("fn2.internal.".into(), 6182),
+        ]);
+
+        let peers = resolve(
+            resolver,
+            create_dns_discovery(&["fn0.internal"], &["_aptosnet._tcp.internal"]),
+        )
+        .await
+        .unwrap();
+
+        let mut expected_peers = PeerSet::new();
+        expected_peers.insert(
+            from_identity_public_key(keys[0]),
+            expected_peer(&keys[0], &[
+                "/ip4/10.0.0.1/tcp/6180",
+                "/ip6/fd00::1/tcp/6180",
+            ]),
+        );
+        expected_peers.insert(
+            from_identity_public_key(keys[1]),
+            expected_peer(&keys[1], &["/ip4/10.0.0.2/tcp/6181"]),
+        );
+        expected_peers.insert(
+            from_identity_public_key(keys[2]),
+            expected_peer(&keys[2], &["/ip4/10.0.0.3/tcp/6182"]),
+        );
+        assert_eq!(peers, expected_peers);
+    }
+
+    #[tokio::test]
+    async fn test_skip_invalid_peers() {
+        let keys = create_keys(3);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("valid", &["10.0.0.1"], &keys[0]);
+        resolver.add_peer("no_addresses", &[], &keys[1]);
+        resolver
+            .ips
+            .insert("no_key".into(), vec!["10.0.0.2".parse().unwrap()]);
+        resolver.add_peer("invalid_key", &["10.0.0.3"], &keys[2]);
+        resolver
+            .txts
+            .insert("invalid_key".into(), vec!["x25519=0xdeadbeef".into()]);
+        resolver.add_peer("multiple_keys", &["10.0.0.4"], &keys[1]);
+        resolver
+            .txts
+            .get_mut("multiple_keys")
+            .unwrap()
+            .push(format!("x25519={}", keys[2].to_encoded_string().unwrap()));
+
+        let peers = resolve(
+            resolver,
+            create_dns_discovery(
+                &[
+                    "valid",
+                    "no_addresses",
+                    "no_key",
+                    "invalid_key",
+                    "multiple_keys",
+                    "missing",
+                ],
+                &[],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let mut expected_peers = PeerSet::new();
+        expected_peers.insert(
+            from_identity_public_key(keys[0]),
+            expected_peer(&keys[0], &["/ip4/10.0.0.1/tcp/6180"]),
+        );
+        assert_eq!(peers, expected_peers);
+    }
+
+    #[tokio::test]
+    async fn test_merge_duplicate_peers() {
+        let keys = create_keys(1);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("fn0", &["10.0.0.1"], &keys[0]);
+        resolver.add_peer("fn0.alias", &["10.0.0.1", "10.0.0.2"], &keys[0]);
+
+        let peers = resolve(resolver, create_dns_discovery(&["fn0", "fn0.alias"], &[]))
+            .await
+            .unwrap();
+
+        let mut expected_peers = PeerSet::new();
+        expected_peers.insert(
+            from_identity_public_key(keys[0]),
+            expected_peer(&keys[0], &[
+                "/ip4/10.0.0.1/tcp/6180",
+                "/ip4/10.0.0.2/tcp/6180",
+            ]),
+        );
+        assert_eq!(peers, expected_peers);
+    }
+
+    #[tokio::test]
+    async fn test_optional_port() {
+        let keys = create_keys(1);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("fn0.internal.", &["10.0.0.1"], &keys[0]);
+        resolver
+            .srvs
+            .insert("_aptosnet._tcp.internal".into(), vec![(
+                "fn0.internal.".into(),
+                6181,
+            )]);
+
+        // The port is only required for the names
+        let mut dns_discovery = create_dns_discovery(&[], &["_aptosnet._tcp.internal"]);
+        dns_discovery.port = None;
+        let peers = resolve(resolver, dns_discovery.clone()).await.unwrap();
+        assert_eq!(
+            peers.get(&from_identity_public_key(keys[0])),
+            Some(&expected_peer(&keys[0], &["/ip4/10.0.0.1/tcp/6181"]))
+        );
+
+        dns_discovery.names.push("fn0.internal.".into());
+        let result = resolve(StubResolver::default(), dns_discovery).await;
+        assert!(matches!(result, Err(DiscoveryError::Parsing(_))));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_failure() {
+        let keys = create_keys(1);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("fn0", &["10.0.0.1"], &keys[0]);
+        resolver
+            .failing_names
+            .push("_aptosnet._tcp.internal".into());
+
+        let result = resolve(
+            resolver,
+            create_dns_discovery(&["fn0"], &["_aptosnet._tcp.internal"]),
+        )
+        .await;
+        assert!(matches!(result, Err(DiscoveryError::Dns(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dns_stream() {
+        let keys = create_keys(1);
+        let mut resolver = StubResolver::default();
+        resolver.add_peer("fn0", &["10.0.0.1"], &keys[0]);
+
+        let mut stream = DnsStream::new_with_resolver(
+            NetworkContext::mock(),
+            create_dns_discovery(&["fn0"], &[]),
+            Arc::new(resolver),
+            TimeService::real(),
+        );
+
+        // The peers are resolved at every interval
+        for _ in 0..2 {
+            let peers = stream.next().await.unwrap().unwrap();
+            assert_eq!(
+                peers.get(&from_identity_public_key(keys[0])),
+                Some(&expected_peer(&keys[0], &["/ip4/10.0.0.1/tcp/6180"]))
+            );
+        }
+    }
+}
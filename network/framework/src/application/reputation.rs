@@ -0,0 +1,235 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tracks the reputation of peers across all applications. Applications
+//! penalize peers that misbehave (e.g., peers that send invalid proofs), which
+//! lowers their score. Scores recover over time, but peers whose score drops
+//! to zero are banned for a while. Bans double in duration for repeat
+//! offenders, and after a ban, the score of the peer recovers from zero.
+//!
+//! Note: bans are enforced (if enabled for the network) by disconnecting from
+//! banned peers and rejecting their connections until the ban expires.
+
+use crate::counters;
+use aptos_config::network_id::PeerNetworkId;
+use aptos_infallible::Mutex;
+use aptos_logger::{info, sample, sample::SampleRate, warn};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The score of peers without recent penalties (i.e., the maximum score)
+const MAX_SCORE: f64 = 100.0;
+
+/// The number of points that scores recover per second
+const SCORE_RECOVERY_PER_SEC: f64 = 0.5;
+
+/// The duration of the first ban of a peer (each subsequent ban doubles it)
+const INITIAL_BAN_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// The maximum duration of a ban
+const MAX_BAN_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The minimum interval between logs of penalties that don't ban the peer
+const PENALTY_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The types of misbehavior that peers can be penalized for
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PeerPenalty {
+    /// The peer relayed invalid data (e.g., a mempool broadcast containing
+    /// transactions with invalid signatures).
+    InvalidData,
+    /// The peer sent a message that failed verification (e.g., a malformed
+    /// consensus message).
+    InvalidMessage,
+    /// The peer sent data with an invalid proof (e.g., a state sync response).
+    InvalidProof,
+}
+
+impl PeerPenalty {
+    /// Returns the number of points the penalty deducts from the score
+    fn get_points(&self) -> f64 {
+        match self {
+            PeerPenalty::InvalidData => 5.0,
+            PeerPenalty::InvalidMessage => 10.0,
+            PeerPenalty::InvalidProof => 25.0,
+        }
+    }
+
+    /// Returns a summary label for the penalty
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            PeerPenalty::InvalidData => "invalid_data",
+            PeerPenalty::InvalidMessage => "invalid_message",
+            PeerPenalty::InvalidProof => "invalid_proof",
+        }
+    }
+}
+
+/// The reputation of a single peer
+#[derive(Clone, Debug)]
+pub struct PeerReputation {
+    /// The score of the peer at `last_update_time`
+    score: f64,
+    /// The time at which the score was last updated (or at which the score
+    /// starts recovering, if the peer is banned).
+    last_update_time: Instant,
+    /// The number of times the peer has been banned
+    num_bans: u64,
+    /// The time at which the latest ban of the peer ends (if any)
+    ban_end_time: Option<Instant>,
+}
+
+impl PeerReputation {
+    fn new(time_now: Instant) -> Self {
+        Self {
+            score: MAX_SCORE,
+            last_update_time: time_now,
+            num_bans: 0,
+            ban_end_time: None,
+        }
+    }
+
+    /// Returns the score of the peer at the given time
+    pub(crate) fn get_score_at(&self, time_now: Instant) -> f64 {
+        let recovery_secs = time_now
+            .saturating_duration_since(self.last_update_time)
+            .as_secs_f64();
+        f64::min(
+            self.score + recovery_secs * SCORE_RECOVERY_PER_SEC,
+            MAX_SCORE,
+        )
+    }
+
+    /// Returns true iff the peer is banned at the given time
+    fn is_banned_at(&self, time_now: Instant) -> bool {
+        self.ban_end_time
+            .map_or(false, |ban_end_time| time_now < ban_end_time)
+    }
+
+    /// Returns true iff the reputation can be forgotten at the given time,
+    /// i.e., the score has fully recovered and any ban ended long ago.
+    fn is_forgettable_at(&self, time_now: Instant) -> bool {
+        let ban_expired = self.ban_end_time.map_or(true, |ban_end_time| {
+            time_now.saturating_duration_since(ban_end_time) > MAX_BAN_DURATION
+        });
+        ban_expired && self.get_score_at(time_now) >= MAX_SCORE
+    }
+
+    /// Penalizes the peer at the given time. If this bans the peer, the
+    /// duration of the ban is returned. Note: banned peers are not penalized.
+    fn penalize_at(&mut self, penalty: PeerPenalty, time_now: Instant) -> Option<Duration> {
+        if self.is_banned_at(time_now) {
+            return None;
+        }
+
+        // Update the score
+        self.score = self.get_score_at(time_now) - penalty.get_points();
+        self.last_update_time = time_now;
+        if self.score > 0.0 {
+            return None;
+        }
+
+        // Ban the peer, and only start recovering the score once the ban ends
+        let ban_multiplier = 2u32.saturating_pow(u32::try_from(self.num_bans).unwrap_or(u32::MAX));
+        let ban_duration = INITIAL_BAN_DURATION
+            .saturating_mul(ban_multiplier)
+            .min(MAX_BAN_DURATION);
+        let ban_end_time = time_now + ban_duration;
+        self.score = 0.0;
+        self.last_update_time = ban_end_time;
+        self.num_bans += 1;
+        self.ban_end_time = Some(ban_end_time);
+        Some(ban_duration)
+    }
+
+    /// Returns the current score of the peer
+    pub fn get_score(&self) -> f64 {
+        self.get_score_at(Instant::now())
+    }
+
+    /// Returns the number of times the peer has been banned
+    pub fn get_num_bans(&self) -> u64 {
+        self.num_bans
+    }
+
+    /// Returns the remaining duration of the current ban (if the peer is banned)
+    pub fn get_remaining_ban_duration(&self) -> Option<Duration> {
+        self.ban_end_time
+            .and_then(|ban_end_time| ban_end_time.checked_duration_since(Instant::now()))
+    }
+}
+
+/// A container for the reputations of all peers. Peers without a reputation
+/// have the maximum score.
+#[derive(Debug, Default)]
+pub struct PeerReputations {
+    reputations: Mutex<HashMap<PeerNetworkId, PeerReputation>>,
+}
+
+impl PeerReputations {
+    /// Penalizes the given peer for misbehaving
+    pub fn report_penalty(&self, peer_network_id: PeerNetworkId, penalty: PeerPenalty) {
+        self.report_penalty_at(peer_network_id, penalty, Instant::now());
+    }
+
+    /// Penalizes the given peer at the given time. If this bans the peer,
+    /// the duration of the ban is returned.
+    pub(crate) fn report_penalty_at(
+        &self,
+        peer_network_id: PeerNetworkId,
+        penalty: PeerPenalty,
+        time_now: Instant,
+    ) -> Option<Duration> {
+        counters::peer_penalties(&peer_network_id, penalty).inc();
+
+        // Update the reputation of the peer, and forget about any
+        // peers that have fully recovered.
+        let mut reputations = self.reputations.lock();
+        let reputation = reputations
+            .entry(peer_network_id)
+            .or_insert_with(|| PeerReputation::new(time_now));
+        let already_banned = reputation.is_banned_at(time_now);
+        let ban_duration = reputation.penalize_at(penalty, time_now);
+        reputations.retain(|_, reputation| !reputation.is_forgettable_at(time_now));
+
+        match ban_duration {
+            Some(ban_duration) => {
+                warn!(
+                    "Banning peer {} for {:?} after penalty: {:?}",
+                    peer_network_id, ban_duration, penalty
+                );
+                counters::peer_bans(&peer_network_id).inc();
+            },
+            None if !already_banned => {
+                // Misbehaving peers can be penalized for every message, so
+                // the logs are sampled (the counters track every penalty).
+                sample!(
+                    SampleRate::Duration(PENALTY_LOG_INTERVAL),
+                    info!("Penalized peer {}: {:?}", peer_network_id, penalty)
+                );
+            },
+            None => {}, // Banned peers are not penalized further
+        }
+        ban_duration
+    }
+
+    /// Returns true iff the given peer is currently banned
+    pub fn is_banned(&self, peer_network_id: &PeerNetworkId) -> bool {
+        self.is_banned_at(peer_network_id, Instant::now())
+    }
+
+    /// Returns true iff the given peer is banned at the given time
+    pub(crate) fn is_banned_at(&self, peer_network_id: &PeerNetworkId, time_now: Instant) -> bool {
+        self.reputations
+            .lock()
+            .get(peer_network_id)
+            .map_or(false, |reputation| reputation.is_banned_at(time_now))
+    }
+
+    /// Returns a copy of the reputations of all peers with recent penalties
+    pub fn get_reputations(&self) -> HashMap<PeerNetworkId, PeerReputation> {
+        self.reputations.lock().clone()
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     application::{
         error::Error,
         metadata::{ConnectionState, PeerMetadata},
+        reputation::{PeerPenalty, PeerReputation, PeerReputations},
     },
     counters,
     peer_manager::ConnectionNotification,
@@ -52,6 +53,10 @@ pub struct PeersAndMetadata {
     cached_peers_and_metadata: Arc<ArcSwap<HashMap<NetworkId, HashMap<PeerId, PeerMetadata>>>>,
 
     subscribers: Mutex<Vec<tokio::sync::mpsc::Sender<ConnectionNotification>>>,
+
+    // The reputations of all peers (including disconnected peers, so that
+    // bans outlive connections).
+    peer_reputations: PeerReputations,
 }
 
 impl PeersAndMetadata {
@@ -62,6 +67,7 @@ impl PeersAndMetadata {
             trusted_peers: HashMap::new(),
             cached_peers_and_metadata: Arc::new(ArcSwap::from(Arc::new(HashMap::new()))),
             subscribers: Mutex::new(vec![]),
+            peer_reputations: PeerReputations::default(),
         };
 
         // Initialize each network mapping and trusted peer set
@@ -317,6 +323,23 @@ impl PeersAndMetadata {
         Ok(())
     }
 
+    /// Penalizes the given peer for misbehaving. Peers that are penalized
+    /// too often are temporarily banned (see `is_peer_banned()`).
+    pub fn report_peer_penalty(&self, peer_network_id: PeerNetworkId, penalty: PeerPenalty) {
+        self.peer_reputations
+            .report_penalty(peer_network_id, penalty);
+    }
+
+    /// Returns true iff the given peer is currently banned
+    pub fn is_peer_banned(&self, peer_network_id: &PeerNetworkId) -> bool {
+        self.peer_reputations.is_banned(peer_network_id)
+    }
+
+    /// Returns the reputations of all peers with recent penalties
+    pub fn get_peer_reputations(&self) -> HashMap<PeerNetworkId, PeerReputation> {
+        self.peer_reputations.get_reputations()
+    }
+
     /// Updates the cached peers and metadata using the given map
     fn set_cached_peers_and_metadata(
         &self,
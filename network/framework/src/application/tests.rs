@@ -7,6 +7,7 @@ use crate::{
         error::Error,
         interface::{NetworkClient, NetworkClientInterface, NetworkServiceEvents},
         metadata::{ConnectionState, PeerMetadata},
+        reputation::{PeerPenalty, PeerReputations},
         storage::PeersAndMetadata,
     },
     peer_manager::{
@@ -41,7 +42,7 @@ use std::{
     hash::Hash,
     ops::Deref,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{sync::mpsc::error::TryRecvError, time::timeout};
 
@@ -938,6 +939,123 @@ async fn test_network_client_network_senders_rpc() {
     .await;
 }
 
+#[test]
+fn test_peer_reputation_penalties_and_bans() {
+    // Create the peer reputations and a peer
+    let peer_reputations = PeerReputations::default();
+    let peer_network_id = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    let time_now = Instant::now();
+
+    // Penalize the peer and verify the score is lowered, but the peer is not banned
+    for _ in 0..3 {
+        let ban_duration = peer_reputations.report_penalty_at(
+            peer_network_id,
+            PeerPenalty::InvalidProof,
+            time_now,
+        );
+        assert_eq!(ban_duration, None);
+    }
+    assert_eq!(
+        get_score(&peer_reputations, &peer_network_id, time_now),
+        25.0
+    );
+    assert!(!peer_reputations.is_banned_at(&peer_network_id, time_now));
+
+    // Verify the score recovers over time
+    let time_now = time_now + Duration::from_secs(10);
+    assert_eq!(
+        get_score(&peer_reputations, &peer_network_id, time_now),
+        30.0
+    );
+
+    // Penalize the peer until it's banned, and verify the ban duration
+    let ban_duration =
+        peer_reputations.report_penalty_at(peer_network_id, PeerPenalty::InvalidProof, time_now);
+    assert_eq!(ban_duration, None);
+    let ban_duration =
+        peer_reputations.report_penalty_at(peer_network_id, PeerPenalty::InvalidProof, time_now);
+    assert_eq!(ban_duration, Some(Duration::from_secs(5 * 60)));
+    assert!(peer_reputations.is_banned_at(&peer_network_id, time_now));
+
+    // Verify that banned peers are not penalized further
+    let ban_duration =
+        peer_reputations.report_penalty_at(peer_network_id, PeerPenalty::InvalidProof, time_now);
+    assert_eq!(ban_duration, None);
+
+    // Verify the ban expires, and that the score only recovers from then on
+    let ban_end_time = time_now + Duration::from_secs(5 * 60);
+    assert!(peer_reputations.is_banned_at(&peer_network_id, ban_end_time - Duration::from_secs(1)));
+    assert!(!peer_reputations.is_banned_at(&peer_network_id, ban_end_time));
+    assert_eq!(
+        get_score(&peer_reputations, &peer_network_id, ban_end_time),
+        0.0
+    );
+    let time_now = ban_end_time + Duration::from_secs(20);
+    assert_eq!(
+        get_score(&peer_reputations, &peer_network_id, time_now),
+        10.0
+    );
+
+    // Verify that the next ban is twice as long
+    let ban_duration =
+        peer_reputations.report_penalty_at(peer_network_id, PeerPenalty::InvalidMessage, time_now);
+    assert_eq!(ban_duration, Some(Duration::from_secs(10 * 60)));
+    assert!(peer_reputations.is_banned_at(&peer_network_id, time_now));
+}
+
+#[test]
+fn test_peer_reputation_forgotten() {
+    // Create the peer reputations and two peers
+    let peer_reputations = PeerReputations::default();
+    let peer_network_id_1 = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    let peer_network_id_2 = PeerNetworkId::new(NetworkId::Public, PeerId::random());
+    let time_now = Instant::now();
+
+    // Penalize the first peer and verify the reputation is tracked
+    peer_reputations.report_penalty_at(peer_network_id_1, PeerPenalty::InvalidData, time_now);
+    assert!(peer_reputations
+        .get_reputations()
+        .contains_key(&peer_network_id_1));
+
+    // Penalize the second peer once the first peer has fully
+    // recovered, and verify the first peer is forgotten.
+    let time_now = time_now + Duration::from_secs(11);
+    peer_reputations.report_penalty_at(peer_network_id_2, PeerPenalty::InvalidData, time_now);
+    let reputations = peer_reputations.get_reputations();
+    assert!(!reputations.contains_key(&peer_network_id_1));
+    assert!(reputations.contains_key(&peer_network_id_2));
+}
+
+#[test]
+fn test_peers_and_metadata_peer_bans() {
+    // Create the peers and metadata container
+    let network_ids = vec![NetworkId::Validator, NetworkId::Vfn];
+    let peers_and_metadata = PeersAndMetadata::new(&network_ids);
+
+    // Penalize a peer on the VFN network and verify it's not banned yet
+    let peer_id = PeerId::random();
+    let peer_network_id_vfn = PeerNetworkId::new(NetworkId::Vfn, peer_id);
+    peers_and_metadata.report_peer_penalty(peer_network_id_vfn, PeerPenalty::InvalidProof);
+    assert!(!peers_and_metadata.is_peer_banned(&peer_network_id_vfn));
+
+    // Penalize the peer until it's banned
+    for _ in 0..4 {
+        peers_and_metadata.report_peer_penalty(peer_network_id_vfn, PeerPenalty::InvalidProof);
+    }
+
+    // Verify the peer is only banned on the VFN network
+    assert!(peers_and_metadata.is_peer_banned(&peer_network_id_vfn));
+    let peer_network_id_validator = PeerNetworkId::new(NetworkId::Validator, peer_id);
+    assert!(!peers_and_metadata.is_peer_banned(&peer_network_id_validator));
+
+    // Verify the reputation of the peer
+    let peer_reputations = peers_and_metadata.get_peer_reputations();
+    assert_eq!(peer_reputations.len(), 1);
+    let peer_reputation = peer_reputations.get(&peer_network_id_vfn).unwrap();
+    assert_eq!(peer_reputation.get_num_bans(), 1);
+    assert!(peer_reputation.get_remaining_ban_duration().is_some());
+}
+
 /// Verifies that the available peers are correct
 fn check_available_peers(
     network_client: &NetworkClient<DummyMessage>,
@@ -989,6 +1107,19 @@ fn check_connected_supported_peers(
     compare_vectors_ignore_order(connected_and_supported_peers, expected_peers);
 }
 
+/// Returns the score of the given peer at the given time
+fn get_score(
+    peer_reputations: &PeerReputations,
+    peer_network_id: &PeerNetworkId,
+    time_now: Instant,
+) -> f64 {
+    peer_reputations
+        .get_reputations()
+        .get(peer_network_id)
+        .unwrap()
+        .get_score_at(time_now)
+}
+
 /// Compares two vectors and asserts equality, but
 /// ignores item ordering in the vectors.
 fn compare_vectors_ignore_order<T: Clone + Debug + Ord>(
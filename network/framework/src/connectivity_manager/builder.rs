@@ -36,6 +36,7 @@ impl ConnectivityManagerBuilder {
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
         enable_latency_aware_dialing: bool,
+        enforce_peer_bans: bool,
     ) -> Self {
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = aptos_channels::new(
             channel_size,
@@ -58,6 +59,7 @@ impl ConnectivityManagerBuilder {
                 outbound_connection_limit,
                 mutual_authentication,
                 enable_latency_aware_dialing,
+                enforce_peer_bans,
             )),
         }
     }
@@ -36,7 +36,7 @@ use crate::{
 };
 use aptos_config::{
     config::{Peer, PeerRole, PeerSet},
-    network_id::NetworkContext,
+    network_id::{NetworkContext, PeerNetworkId},
 };
 use aptos_crypto::x25519;
 use aptos_infallible::RwLock;
@@ -130,6 +130,8 @@ pub struct ConnectivityManager<TBackoff> {
     mutual_authentication: bool,
     /// Whether or not to enable latency aware peer dialing
     enable_latency_aware_dialing: bool,
+    /// Whether to disconnect from (and not dial) banned peers
+    enforce_peer_bans: bool,
 }
 
 /// Different sources for peer addresses, ordered by priority (Onchain=highest,
@@ -140,6 +142,7 @@ pub enum DiscoverySource {
     OnChainValidatorSet,
     File,
     Rest,
+    Dns,
     Config,
 }
 
@@ -156,6 +159,7 @@ impl fmt::Display for DiscoverySource {
             DiscoverySource::File => "File",
             DiscoverySource::Config => "Config",
             DiscoverySource::Rest => "Rest",
+            DiscoverySource::Dns => "Dns",
         })
     }
 }
@@ -359,6 +363,7 @@ where
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
         enable_latency_aware_dialing: bool,
+        enforce_peer_bans: bool,
     ) -> Self {
         // Verify that the trusted peers set exists and that it is empty
         let trusted_peers = peers_and_metadata
@@ -395,6 +400,7 @@ where
             outbound_connection_limit,
             mutual_authentication,
             enable_latency_aware_dialing,
+            enforce_peer_bans,
         };
 
         // Set the initial seed config addresses and public keys
@@ -528,6 +534,47 @@ where
         }
     }
 
+    /// Returns true iff bans are enforced on this network, and the peer is banned
+    fn is_peer_banned(&self, peer_id: &PeerId) -> bool {
+        let peer_network_id = PeerNetworkId::new(self.network_context.network_id(), *peer_id);
+        self.enforce_peer_bans && self.peers_and_metadata.is_peer_banned(&peer_network_id)
+    }
+
+    /// Disconnect from all peers that are banned (e.g., for misbehaving)
+    async fn close_banned_connections(&mut self) {
+        // Identify banned peer connections
+        let banned_peers: Vec<_> = self
+            .connected
+            .keys()
+            .filter(|peer_id| self.is_peer_banned(peer_id))
+            .cloned()
+            .collect();
+
+        // Close existing connections to banned peers
+        for banned_peer in banned_peers {
+            info!(
+                NetworkSchema::new(&self.network_context).remote_peer(&banned_peer),
+                "{} Closing connection to banned peer {}",
+                self.network_context,
+                banned_peer.short_str()
+            );
+
+            if let Err(disconnect_error) =
+                self.connection_reqs_tx.disconnect_peer(banned_peer).await
+            {
+                info!(
+                    NetworkSchema::new(&self.network_context)
+                        .remote_peer(&banned_peer),
+                    error = %disconnect_error,
+                    "{} Failed to close connection to banned peer {}, error: {}",
+                    self.network_context,
+                    banned_peer.short_str(),
+                    disconnect_error
+                );
+            }
+        }
+    }
+
     /// Cancel all pending dials to peers that are no longer eligible.
     ///
     /// For instance, a validator might leave the validator set after a
@@ -580,6 +627,7 @@ where
                     && !self.connected.contains_key(peer_id) // The node is not already connected
                     && !self.dial_queue.contains_key(peer_id) // There is no pending dial to this node
                     && roles_to_dial.contains(&peer.role) // We can dial this role
+                    && !self.is_peer_banned(peer_id) // The node is not banned
             })
             .collect();
 
@@ -825,6 +873,8 @@ where
         self.cancel_stale_dials().await;
         // Disconnect from connected peers that are no longer eligible.
         self.close_stale_connections().await;
+        // Disconnect from connected peers that are banned.
+        self.close_banned_connections().await;
         // Dial peers which are eligible but are neither connected nor queued for dialing in the
         // future.
         self.dial_eligible_peers(pending_dials).await;
@@ -4,6 +4,7 @@
 
 use super::*;
 use crate::{
+    application::reputation::PeerPenalty,
     peer_manager::{conn_notifs_channel, ConnectionNotification, ConnectionRequest},
     transport::ConnectionMetadata,
 };
@@ -106,6 +107,7 @@ impl TestHarness {
             Some(MAX_TEST_CONNECTIONS),
             true, /* mutual_authentication */
             true, /* enable_latency_aware_dialing */
+            true, /* enforce_peer_bans */
         );
         let mock = Self {
             network_context,
@@ -908,6 +910,54 @@ async fn test_stale_peers_vfn_inbound() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_banned_peers() {
+    // Create a connectivity manager (with peer bans enforced)
+    let (mut mock, mut connectivity_manager) = TestHarness::new(HashMap::new());
+    let network_context = mock.network_context;
+
+    // Create and connect peer 1 (an unknown inbound connection)
+    let peer_id_1 = PeerId::random();
+    let connection_metadata_1 = ConnectionMetadata::mock_with_role_and_origin(
+        peer_id_1,
+        PeerRole::Unknown,
+        ConnectionOrigin::Inbound,
+    );
+    let connection_notification = ConnectionNotification::NewPeer(
+        connection_metadata_1.clone(),
+        network_context.network_id(),
+    );
+    connectivity_manager.handle_control_notification(connection_notification);
+
+    // Create and connect peer 2 (an unknown inbound connection)
+    let peer_id_2 = PeerId::random();
+    let connection_metadata = ConnectionMetadata::mock_with_role_and_origin(
+        peer_id_2,
+        PeerRole::Unknown,
+        ConnectionOrigin::Inbound,
+    );
+    let connection_notification =
+        ConnectionNotification::NewPeer(connection_metadata, network_context.network_id());
+    connectivity_manager.handle_control_notification(connection_notification);
+
+    // Verify we have 2 peers
+    assert_eq!(connectivity_manager.get_connected_peers().len(), 2);
+
+    // Penalize peer 1 until it is banned
+    let peer_network_id_1 = PeerNetworkId::new(network_context.network_id(), peer_id_1);
+    for _ in 0..5 {
+        mock.peers_and_metadata
+            .report_peer_penalty(peer_network_id_1, PeerPenalty::InvalidProof);
+    }
+    assert!(mock.peers_and_metadata.is_peer_banned(&peer_network_id_1));
+
+    // Close the banned connections and verify that only peer 1 is disconnected
+    tokio::join!(
+        connectivity_manager.close_banned_connections(),
+        mock.expect_disconnect_fail(peer_id_1, connection_metadata_1.addr)
+    );
+}
+
 /// Verifies that the trusted peers match the expected set
 fn verify_trusted_peers(
     peers_and_metadata: &Arc<PeersAndMetadata>,
@@ -2,8 +2,8 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::protocols::wire::handshake::v1::ProtocolId;
-use aptos_config::network_id::NetworkContext;
+use crate::{application::reputation::PeerPenalty, protocols::wire::handshake::v1::ProtocolId};
+use aptos_config::network_id::{NetworkContext, PeerNetworkId};
 use aptos_metrics_core::{
     exponential_buckets, register_histogram_vec, register_int_counter_vec, register_int_gauge,
     register_int_gauge_vec, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec,
@@ -443,6 +443,44 @@ pub static PENDING_MULTIPLEX_STREAM: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter of outbound messages dropped because their protocol queue was full
+pub static DROPPED_OUTBOUND_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_dropped_outbound_messages",
+        "Number of outbound messages dropped because their protocol queue was full",
+        &["protocol_id"]
+    )
+    .unwrap()
+});
+
+/// Counter of penalties reported against peers
+pub static PEER_PENALTIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_peer_penalties",
+        "Number of penalties reported against peers",
+        &["network_id", "penalty"]
+    )
+    .unwrap()
+});
+
+pub fn peer_penalties(peer_network_id: &PeerNetworkId, penalty: PeerPenalty) -> IntCounter {
+    PEER_PENALTIES.with_label_values(&[peer_network_id.network_id().as_str(), penalty.get_label()])
+}
+
+/// Counter of peers banned due to penalties
+pub static PEER_BANS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_network_peer_bans",
+        "Number of peers banned due to penalties",
+        &["network_id"]
+    )
+    .unwrap()
+});
+
+pub fn peer_bans(peer_network_id: &PeerNetworkId) -> IntCounter {
+    PEER_BANS.with_label_values(&[peer_network_id.network_id().as_str()])
+}
+
 /// Counter of pending requests in Direct Send
 pub static PENDING_DIRECT_SEND_REQUESTS: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -13,6 +13,7 @@ use aptos_config::network_id::NetworkId;
 use aptos_types::chain_id::ChainId;
 use futures::executor::block_on;
 use proptest::{collection::btree_map, prelude::*};
+use std::collections::BTreeMap;
 
 //
 // Handshake Protocol Fuzzer
@@ -69,6 +70,7 @@ prop_compose! {
       supported_protocols,
       chain_id: ChainId::new(1), // doesn't matter for handshake protocol
       network_id: NetworkId::Validator, // doesn't matter for handshake protocol
+      zstd_dictionary_ids: BTreeMap::new(),
     }
   }
 }
@@ -61,6 +61,13 @@ impl<TSocket> NoiseStream<TSocket> {
     pub fn get_remote_static(&self) -> x25519::PublicKey {
         self.session.get_remote_static()
     }
+
+    /// Create a NoiseStream for another stream of the same connection (e.g., a QUIC stream),
+    /// with a session derived from this stream's session. Both peers must use the same non-zero
+    /// `stream_id` for the stream, and a different one for each stream of the connection.
+    pub fn new_stream<TStream>(&self, socket: TStream, stream_id: u32) -> NoiseStream<TStream> {
+        NoiseStream::new(socket, self.session.derive_stream_session(stream_id))
+    }
 }
 
 //
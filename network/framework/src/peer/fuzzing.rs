@@ -86,7 +86,11 @@ pub fn fuzz(data: &[u8]) {
         ProtocolIdSet::all_known(),
         PeerRole::Unknown,
     );
-    let connection = Connection { socket, metadata };
+    let connection = Connection {
+        socket,
+        metadata,
+        streams: vec![],
+    };
 
     let (connection_notifs_tx, connection_notifs_rx) = aptos_channels::new_test(8);
     let channel_size = 8;
@@ -108,6 +112,7 @@ pub fn fuzz(data: &[u8]) {
         constants::MAX_CONCURRENT_OUTBOUND_RPCS,
         constants::MAX_FRAME_SIZE,
         constants::MAX_MESSAGE_SIZE,
+        Arc::new(HashMap::new()),
     );
     executor.spawn(peer.start());
 
@@ -11,7 +11,9 @@
 //! shutting down when the [`PeerManager`] requests it or the connection is lost.
 //!
 //! [`Peer`] owns the actual underlying connection socket and is reponsible for
-//! the socket's shutdown, graceful or otherwise.
+//! the socket's shutdown, graceful or otherwise. If the connection has additional
+//! streams (e.g., over QUIC), each outbound message is sent on the stream of its
+//! class, and inbound messages are read from all the streams.
 //!
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
@@ -21,6 +23,7 @@ use crate::{
         DECLINED_LABEL, FAILED_LABEL, RECEIVED_LABEL, SENT_LABEL, UNKNOWN_LABEL,
     },
     logging::NetworkSchema,
+    noise::stream::NoiseStream,
     peer_manager::{PeerManagerError, TransportNotification},
     protocols::{
         direct_send::Message,
@@ -36,32 +39,57 @@ use crate::{
     ProtocolId,
 };
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
-use aptos_config::network_id::{NetworkContext, PeerNetworkId};
+use aptos_config::{
+    config::ProtocolQuotaConfig,
+    network_id::{NetworkContext, PeerNetworkId},
+};
 use aptos_logger::prelude::*;
+use aptos_netcore::transport::quic::QuicSocket;
 use aptos_short_hex_str::AsShortHexStr;
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::PeerId;
 use futures::{
     self,
     channel::oneshot,
+    future::{self, FutureExt},
     io::{AsyncRead, AsyncWrite},
-    stream::StreamExt,
+    stream::{self, BoxStream, StreamExt},
     SinkExt,
 };
 use futures_util::stream::select;
+use scheduler::OutboundScheduler;
 use serde::Serialize;
 use std::{collections::HashMap, fmt, panic, sync::Arc, time::Duration};
-use tokio::{runtime::Handle, time::timeout};
+use tokio::{
+    runtime::Handle,
+    time::{sleep_until, timeout, Instant},
+};
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
 };
 
+mod scheduler;
 #[cfg(test)]
 mod test;
 
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing;
 
+/// The maximum number of frames buffered between the multiplex and writer tasks.
+/// This is kept small, so that outbound messages wait in the `OutboundScheduler`
+/// (where they're ordered by priority) rather than in the writer's buffer.
+const MAX_PENDING_WRITER_FRAMES: usize = 64;
+
+/// An outbound message, along with the protocol it belongs to (if any)
+pub(crate) type WriteRequest = (Option<ProtocolId>, NetworkMessage);
+
+/// The class of the stream the connection was established on
+const PRIMARY_STREAM_CLASS: u8 = 0;
+
+/// The inbound messages of all the streams of a connection, along with the class of their stream.
+/// `None` is produced once any of the streams is closed.
+type InboundMessages = BoxStream<'static, Option<(u8, Result<MultiplexMessage, ReadError>)>>;
+
 /// Requests [`Peer`] receives from the [`PeerManager`](crate::peer_manager::PeerManager).
 #[derive(Debug)]
 pub enum PeerRequest {
@@ -111,6 +139,8 @@ pub struct Peer<TSocket> {
     connection_metadata: ConnectionMetadata,
     /// Underlying connection.
     connection: Option<TSocket>,
+    /// The additional streams of the connection, along with their classes.
+    streams: Vec<(u8, NoiseStream<QuicSocket>)>,
     /// Channel to notify PeerManager that we've disconnected.
     connection_notifs_tx: aptos_channels::Sender<TransportNotification<TSocket>>,
     /// Channel to receive requests from PeerManager to send messages and rpcs.
@@ -128,8 +158,12 @@ pub struct Peer<TSocket> {
     max_frame_size: usize,
     /// The maximum size of an inbound or outbound request message
     max_message_size: usize,
-    /// Inbound stream buffer
-    inbound_stream: InboundStreamBuffer,
+    /// Inbound stream buffers, by the class of the stream the fragments are read from
+    inbound_streams: HashMap<u8, InboundStreamBuffer>,
+    /// The maximum number of fragments of an inbound streamed message
+    max_fragments: usize,
+    /// The outbound bandwidth quotas of the protocols
+    outbound_protocol_quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>,
 }
 
 impl<TSocket> Peer<TSocket>
@@ -152,10 +186,12 @@ where
         max_concurrent_outbound_rpcs: u32,
         max_frame_size: usize,
         max_message_size: usize,
+        outbound_protocol_quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
             socket,
+            streams,
         } = connection;
         let remote_peer_id = connection_metadata.remote_peer_id;
         let max_fragments = max_message_size / max_frame_size;
@@ -165,6 +201,7 @@ where
             time_service: time_service.clone(),
             connection_metadata,
             connection: Some(socket),
+            streams,
             connection_notifs_tx,
             peer_reqs_rx,
             upstream_handlers,
@@ -184,7 +221,9 @@ where
             state: State::Connected,
             max_frame_size,
             max_message_size,
-            inbound_stream: InboundStreamBuffer::new(max_fragments),
+            inbound_streams: HashMap::new(),
+            max_fragments,
+            outbound_protocol_quotas,
         }
     }
 
@@ -206,8 +245,11 @@ where
         let (read_socket, write_socket) =
             tokio::io::split(self.connection.take().unwrap().compat());
 
-        let mut reader =
-            MultiplexMessageStream::new(read_socket.compat(), self.max_frame_size).fuse();
+        let mut readers = vec![Self::inbound_messages(
+            PRIMARY_STREAM_CLASS,
+            read_socket.compat(),
+            self.max_frame_size,
+        )];
         let writer = MultiplexMessageSink::new(write_socket.compat_write(), self.max_frame_size);
 
         // Start writer "process" as a separate task. We receive two handles to
@@ -222,7 +264,45 @@ where
             writer,
             self.max_frame_size,
             self.max_message_size,
+            self.outbound_protocol_quotas.clone(),
         );
+        let mut writer_close_txs = vec![writer_close_tx];
+
+        // Start a reader and a writer task for each additional stream, and route
+        // the outbound messages to the stream of their class.
+        let mut stream_write_reqs_txs = HashMap::new();
+        for (class, stream) in std::mem::take(&mut self.streams) {
+            let (read_socket, write_socket) = tokio::io::split(stream.compat());
+            readers.push(Self::inbound_messages(
+                class,
+                read_socket.compat(),
+                self.max_frame_size,
+            ));
+            let writer =
+                MultiplexMessageSink::new(write_socket.compat_write(), self.max_frame_size);
+            let (stream_write_reqs_tx, writer_close_tx) = Self::start_writer_task(
+                &self.executor,
+                self.time_service.clone(),
+                self.connection_metadata.clone(),
+                self.network_context,
+                writer,
+                self.max_frame_size,
+                self.max_message_size,
+                self.outbound_protocol_quotas.clone(),
+            );
+            stream_write_reqs_txs.insert(class, stream_write_reqs_tx);
+            writer_close_txs.push(writer_close_tx);
+        }
+        if !stream_write_reqs_txs.is_empty() {
+            write_reqs_tx = Self::start_router_task(
+                &self.executor,
+                self.network_context,
+                remote_peer_id,
+                write_reqs_tx,
+                stream_write_reqs_txs,
+            );
+        }
+        let mut reader = stream::select_all(readers).fuse();
 
         // Start main Peer event loop.
         let reason = loop {
@@ -244,8 +324,8 @@ where
                 // the wire from the remote peer.
                 maybe_message = reader.next() => {
                     match maybe_message {
-                        Some(message) =>  {
-                            if let Err(err) = self.handle_inbound_message(message, &mut write_reqs_tx) {
+                        Some(Some((class, message))) =>  {
+                            if let Err(err) = self.handle_inbound_message(class, message, &mut write_reqs_tx) {
                                 warn!(
                                     NetworkSchema::new(&self.network_context)
                                         .connection_metadata(&self.connection_metadata),
@@ -257,8 +337,8 @@ where
                                 );
                             }
                         },
-                        // The socket was gracefully closed by the remote peer.
-                        None => self.shutdown(DisconnectReason::ConnectionLost),
+                        // A stream was gracefully closed by the remote peer.
+                        Some(None) | None => self.shutdown(DisconnectReason::ConnectionLost),
                     }
                 },
                 // Drive the queue of pending inbound rpcs. When one is fulfilled
@@ -306,10 +386,61 @@ where
 
         // Finish shutting down the connection. Close the writer task and notify
         // PeerManager that this connection has shutdown.
-        self.do_shutdown(write_reqs_tx, writer_close_tx, reason)
+        self.do_shutdown(write_reqs_tx, writer_close_txs, reason)
             .await;
     }
 
+    // Reads the inbound messages of the stream of the given class, followed by `None` once the
+    // stream is closed.
+    fn inbound_messages(
+        class: u8,
+        socket: impl AsyncRead + Unpin + Send + 'static,
+        max_frame_size: usize,
+    ) -> InboundMessages {
+        MultiplexMessageStream::new(socket, max_frame_size)
+            .map(move |message| Some((class, message)))
+            .chain(stream::once(future::ready(None)))
+            .boxed()
+    }
+
+    // Start a new task on the given executor which routes the outbound messages to the writer
+    // task of the stream of their class (see `ProtocolPriority::stream_class`), or to the writer
+    // task of the primary stream for messages without a protocol. The function returns the
+    // channel to send outbound messages to the task, which ends when the channel is dropped.
+    fn start_router_task(
+        executor: &Handle,
+        network_context: NetworkContext,
+        remote_peer_id: PeerId,
+        primary_write_reqs_tx: aptos_channel::Sender<(), WriteRequest>,
+        stream_write_reqs_txs: HashMap<u8, aptos_channel::Sender<(), WriteRequest>>,
+    ) -> aptos_channel::Sender<(), WriteRequest> {
+        let (write_reqs_tx, mut write_reqs_rx): (aptos_channel::Sender<(), WriteRequest>, _) =
+            aptos_channel::new(
+                QueueStyle::KLAST,
+                1024,
+                Some(&counters::PENDING_WIRE_MESSAGES),
+            );
+        let router_task = async move {
+            while let Some((protocol_id, message)) = write_reqs_rx.next().await {
+                let write_reqs_tx = protocol_id
+                    .and_then(|protocol_id| {
+                        stream_write_reqs_txs.get(&protocol_id.priority().stream_class())
+                    })
+                    .unwrap_or(&primary_write_reqs_tx);
+                if let Err(err) = write_reqs_tx.push((), (protocol_id, message)) {
+                    warn!(
+                        error = %err,
+                        "{} Error in routing message to peer: {}",
+                        network_context,
+                        remote_peer_id.short_str(),
+                    );
+                }
+            }
+        };
+        executor.spawn(router_task);
+        write_reqs_tx
+    }
+
     // Start a new task on the given executor which is responsible for writing outbound messages on
     // the wire. The function returns two channels which can be used to send instructions to the
     // task:
@@ -317,6 +448,10 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
+    //
+    // Outbound messages are first queued in an `OutboundScheduler` by a multiplex task, which
+    // hands them to the writer in order of priority and within the protocol quotas.
+    #[allow(clippy::too_many_arguments)]
     fn start_writer_task(
         executor: &Handle,
         time_service: TimeService,
@@ -325,12 +460,10 @@ where
         mut writer: MultiplexMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
         max_frame_size: usize,
         max_message_size: usize,
-    ) -> (
-        aptos_channel::Sender<(), NetworkMessage>,
-        oneshot::Sender<()>,
-    ) {
+        outbound_protocol_quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>,
+    ) -> (aptos_channel::Sender<(), WriteRequest>, oneshot::Sender<()>) {
         let remote_peer_id = connection_metadata.remote_peer_id;
-        let (write_reqs_tx, mut write_reqs_rx): (aptos_channel::Sender<(), NetworkMessage>, _) =
+        let (write_reqs_tx, mut write_reqs_rx): (aptos_channel::Sender<(), WriteRequest>, _) =
             aptos_channel::new(
                 QueueStyle::KLAST,
                 1024,
@@ -338,9 +471,14 @@ where
             );
         let (close_tx, mut close_rx) = oneshot::channel();
 
-        let (mut msg_tx, msg_rx) = aptos_channels::new(1024, &counters::PENDING_MULTIPLEX_MESSAGE);
-        let (stream_msg_tx, stream_msg_rx) =
-            aptos_channels::new(1024, &counters::PENDING_MULTIPLEX_STREAM);
+        let (mut msg_tx, msg_rx) = aptos_channels::new(
+            MAX_PENDING_WRITER_FRAMES,
+            &counters::PENDING_MULTIPLEX_MESSAGE,
+        );
+        let (stream_msg_tx, stream_msg_rx) = aptos_channels::new(
+            MAX_PENDING_WRITER_FRAMES,
+            &counters::PENDING_MULTIPLEX_STREAM,
+        );
 
         // this task ends when the multiplex task ends (by dropping the senders) or receiving a close instruction
         let writer_task = async move {
@@ -412,7 +550,34 @@ where
         let multiplex_task = async move {
             let mut outbound_stream =
                 OutboundStream::new(max_frame_size, max_message_size, stream_msg_tx);
-            while let Some(message) = write_reqs_rx.next().await {
+            let mut scheduler = OutboundScheduler::new(outbound_protocol_quotas);
+            'multiplex: loop {
+                // Queue all pending write requests, so that the next message sent is the one
+                // with the highest priority.
+                while let Some(maybe_request) = write_reqs_rx.next().now_or_never() {
+                    match maybe_request {
+                        Some((protocol_id, message)) => scheduler.push(protocol_id, message),
+                        None => break 'multiplex,
+                    }
+                }
+
+                // If no message can be sent, wait for a new write request or for
+                // a message held back by its protocol quota.
+                let Some(message) = scheduler.pop() else {
+                    let refill = match scheduler.next_refill_time() {
+                        Some(refill_time) => sleep_until(Instant::from_std(refill_time)).boxed(),
+                        None => future::pending().boxed(),
+                    };
+                    futures::select! {
+                        maybe_request = write_reqs_rx.next() => match maybe_request {
+                            Some((protocol_id, message)) => scheduler.push(protocol_id, message),
+                            None => break 'multiplex,
+                        },
+                        _ = refill.fuse() => {},
+                    }
+                    continue;
+                };
+
                 // either channel full would block the other one
                 let result = if outbound_stream.should_stream(&message) {
                     outbound_stream.stream_message(message).await
@@ -535,14 +700,20 @@ where
 
     fn handle_inbound_stream_message(
         &mut self,
+        class: u8,
         message: StreamMessage,
     ) -> Result<(), PeerManagerError> {
+        let max_fragments = self.max_fragments;
+        let inbound_stream = self
+            .inbound_streams
+            .entry(class)
+            .or_insert_with(|| InboundStreamBuffer::new(max_fragments));
         match message {
             StreamMessage::Header(header) => {
-                self.inbound_stream.new_stream(header)?;
+                inbound_stream.new_stream(header)?;
             },
             StreamMessage::Fragment(fragment) => {
-                if let Some(message) = self.inbound_stream.append_fragment(fragment)? {
+                if let Some(message) = inbound_stream.append_fragment(fragment)? {
                     self.handle_inbound_network_message(message)?;
                 }
             },
@@ -552,8 +723,9 @@ where
 
     fn handle_inbound_message(
         &mut self,
+        class: u8,
         message: Result<MultiplexMessage, ReadError>,
-        write_reqs_tx: &mut aptos_channel::Sender<(), NetworkMessage>,
+        write_reqs_tx: &mut aptos_channel::Sender<(), WriteRequest>,
     ) -> Result<(), PeerManagerError> {
         trace!(
             NetworkSchema::new(&self.network_context)
@@ -575,7 +747,7 @@ where
                     let error_code = ErrorCode::parsing_error(*message_type, *protocol_id);
                     let message = NetworkMessage::Error(error_code);
 
-                    write_reqs_tx.push((), message)?;
+                    write_reqs_tx.push((), (None, message))?;
                     return Err(err.into());
                 },
                 ReadError::IoError(_) => {
@@ -588,14 +760,14 @@ where
 
         match message {
             MultiplexMessage::Message(message) => self.handle_inbound_network_message(message),
-            MultiplexMessage::Stream(message) => self.handle_inbound_stream_message(message),
+            MultiplexMessage::Stream(message) => self.handle_inbound_stream_message(class, message),
         }
     }
 
     fn handle_outbound_request(
         &mut self,
         request: PeerRequest,
-        write_reqs_tx: &mut aptos_channel::Sender<(), NetworkMessage>,
+        write_reqs_tx: &mut aptos_channel::Sender<(), WriteRequest>,
     ) {
         trace!(
             "Peer {} PeerRequest::{:?}",
@@ -615,7 +787,7 @@ where
                     raw_msg: Vec::from(message.mdata.as_ref()),
                 });
 
-                match write_reqs_tx.push((), message) {
+                match write_reqs_tx.push((), (Some(protocol_id), message)) {
                     Ok(_) => {
                         self.update_outbound_direct_send_metrics(protocol_id, message_len as u64);
                     },
@@ -671,25 +843,27 @@ where
 
     async fn do_shutdown(
         mut self,
-        write_req_tx: aptos_channel::Sender<(), NetworkMessage>,
-        writer_close_tx: oneshot::Sender<()>,
+        write_req_tx: aptos_channel::Sender<(), WriteRequest>,
+        writer_close_txs: Vec<oneshot::Sender<()>>,
         reason: DisconnectReason,
     ) {
-        // Drop the sender to shut down multiplex task.
+        // Drop the sender to shut down multiplex (and router) tasks.
         drop(write_req_tx);
 
-        // Send a close instruction to the writer task. On receipt of this
-        // instruction, the writer task drops all pending outbound messages and
-        // closes the connection.
-        if let Err(e) = writer_close_tx.send(()) {
-            info!(
-                NetworkSchema::new(&self.network_context)
-                    .connection_metadata(&self.connection_metadata),
-                error = ?e,
-                "{} Failed to send close instruction to writer task. It must already be terminating/terminated. Error: {:?}",
-                self.network_context,
-                e
-            );
+        // Send a close instruction to the writer tasks. On receipt of this
+        // instruction, a writer task drops all pending outbound messages and
+        // closes its stream of the connection.
+        for writer_close_tx in writer_close_txs {
+            if let Err(e) = writer_close_tx.send(()) {
+                info!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    error = ?e,
+                    "{} Failed to send close instruction to writer task. It must already be terminating/terminated. Error: {:?}",
+                    self.network_context,
+                    e
+                );
+            }
         }
 
         let remote_peer_id = self.remote_peer_id();
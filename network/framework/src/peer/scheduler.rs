@@ -0,0 +1,170 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! [`OutboundScheduler`] decides the order in which the outbound messages of a
+//! [`Peer`](crate::peer::Peer) are written to the wire.
+//!
+//! Messages are queued per protocol. Whenever the writer is ready for another
+//! message, the oldest queued message with the highest [`ProtocolPriority`] is
+//! sent. This only matters when the connection is saturated, as the queues are
+//! otherwise drained as soon as messages arrive.
+//!
+//! Protocols can also be given a bandwidth quota (a byte token bucket), in
+//! which case their messages are held back (but not dropped) while the quota
+//! is exhausted, leaving the bandwidth to other protocols.
+
+use crate::{
+    counters,
+    protocols::wire::{handshake::v1::ProtocolPriority, messaging::v1::NetworkMessage},
+    ProtocolId,
+};
+use aptos_config::config::ProtocolQuotaConfig;
+use aptos_rate_limiter::rate_limit::Bucket;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Instant,
+};
+
+/// The maximum number of messages queued for a protocol. Once reached, the
+/// oldest message of the protocol is dropped for the new one.
+const MAX_QUEUED_MESSAGES_PER_PROTOCOL: usize = 1024;
+
+/// The label of messages that don't belong to any protocol (i.e., errors)
+const NO_PROTOCOL_LABEL: &str = "none";
+
+/// The queued messages of a single protocol
+struct ProtocolQueue {
+    priority: ProtocolPriority,
+    /// The bandwidth quota of the protocol (if any)
+    quota: Option<Bucket>,
+    /// The queued messages, along with their sequence numbers across all queues
+    messages: VecDeque<(u64, NetworkMessage)>,
+}
+
+impl ProtocolQueue {
+    fn new(protocol_id: Option<ProtocolId>, quota: Option<&ProtocolQuotaConfig>) -> Self {
+        // Messages without a protocol are errors, which are tiny and sent first
+        let priority = protocol_id.map_or(ProtocolPriority::High, ProtocolId::priority);
+        let quota = quota.map(|quota| {
+            Bucket::new(
+                "outbound_protocol_quota".into(),
+                String::new(),
+                protocol_label(protocol_id).into(),
+                quota.byte_bucket_size,
+                quota.byte_bucket_size,
+                quota.byte_bucket_rate,
+                None,
+            )
+        });
+
+        Self {
+            priority,
+            quota,
+            messages: VecDeque::new(),
+        }
+    }
+
+    /// Acquires the quota to send the message at the front of the queue.
+    /// Otherwise, returns the time at which enough quota will be available.
+    fn acquire_quota(&mut self) -> Result<(), Instant> {
+        let (quota, (_, message)) = match (self.quota.as_mut(), self.messages.front()) {
+            (Some(quota), Some(message)) => (quota, message),
+            _ => return Ok(()),
+        };
+
+        let num_bytes = message.data_len();
+        match quota.acquire_all_tokens(num_bytes) {
+            Ok(()) => Ok(()),
+            Err(Some(refill_time)) => Err(refill_time),
+            // The message is larger than the bucket, so send it whenever the
+            // bucket isn't empty (otherwise, it could never be sent).
+            Err(None) => quota.acquire_tokens(num_bytes).map(|_| ()),
+        }
+    }
+}
+
+/// Queues the outbound messages of a peer, and hands them back in the order
+/// in which they should be sent.
+pub struct OutboundScheduler {
+    /// The bandwidth quotas of the protocols
+    quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>,
+    /// The message queue of each protocol (or of no protocol)
+    queues: HashMap<Option<ProtocolId>, ProtocolQueue>,
+    /// The sequence number of the next queued message
+    next_sequence_number: u64,
+    /// The earliest time at which a message held back by its quota can be sent
+    next_refill_time: Option<Instant>,
+}
+
+impl OutboundScheduler {
+    pub fn new(quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>) -> Self {
+        Self {
+            quotas,
+            queues: HashMap::new(),
+            next_sequence_number: 0,
+            next_refill_time: None,
+        }
+    }
+
+    /// Queues the given message of the given protocol (if any)
+    pub fn push(&mut self, protocol_id: Option<ProtocolId>, message: NetworkMessage) {
+        let quotas = &self.quotas;
+        let queue = self.queues.entry(protocol_id).or_insert_with(|| {
+            let quota = protocol_id.and_then(|protocol_id| quotas.get(&protocol_id));
+            ProtocolQueue::new(protocol_id, quota)
+        });
+
+        if queue.messages.len() >= MAX_QUEUED_MESSAGES_PER_PROTOCOL {
+            queue.messages.pop_front();
+            counters::DROPPED_OUTBOUND_MESSAGES
+                .with_label_values(&[protocol_label(protocol_id)])
+                .inc();
+        }
+        queue
+            .messages
+            .push_back((self.next_sequence_number, message));
+        self.next_sequence_number += 1;
+    }
+
+    /// Returns the next message to send, i.e., the oldest queued message with
+    /// the highest priority whose protocol has enough quota left to send it.
+    /// If no message can be sent, see `next_refill_time`.
+    pub fn pop(&mut self) -> Option<NetworkMessage> {
+        let mut candidates: Vec<_> = self
+            .queues
+            .values_mut()
+            .filter_map(|queue| {
+                let sequence_number = queue.messages.front()?.0;
+                Some((queue.priority, sequence_number, queue))
+            })
+            .collect();
+        candidates
+            .sort_unstable_by_key(|(priority, sequence_number, _)| (*priority, *sequence_number));
+
+        self.next_refill_time = None;
+        for (_, _, queue) in candidates {
+            match queue.acquire_quota() {
+                Ok(()) => return queue.messages.pop_front().map(|(_, message)| message),
+                Err(refill_time) => {
+                    self.next_refill_time = Some(
+                        self.next_refill_time
+                            .map_or(refill_time, |time| time.min(refill_time)),
+                    );
+                },
+            }
+        }
+        None
+    }
+
+    /// Returns the earliest time at which a message held back by its quota
+    /// can be sent, as of the last call to `pop`. Returns None if no message
+    /// was held back.
+    pub fn next_refill_time(&self) -> Option<Instant> {
+        self.next_refill_time
+    }
+}
+
+fn protocol_label(protocol_id: Option<ProtocolId>) -> &'static str {
+    protocol_id.map_or(NO_PROTOCOL_LABEL, ProtocolId::as_str)
+}
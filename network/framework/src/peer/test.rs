@@ -7,7 +7,7 @@ use crate::{
         INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
         MAX_FRAME_SIZE, MAX_MESSAGE_SIZE, NETWORK_CHANNEL_SIZE,
     },
-    peer::{DisconnectReason, Peer, PeerRequest},
+    peer::{scheduler::OutboundScheduler, DisconnectReason, Peer, PeerRequest},
     peer_manager::TransportNotification,
     protocols::{
         direct_send::Message,
@@ -25,7 +25,10 @@ use crate::{
     ProtocolId,
 };
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
-use aptos_config::{config::PeerRole, network_id::NetworkContext};
+use aptos_config::{
+    config::{PeerRole, ProtocolQuotaConfig},
+    network_id::NetworkContext,
+};
 use aptos_logger::info;
 use aptos_memsocket::MemorySocket;
 use aptos_netcore::transport::ConnectionOrigin;
@@ -78,6 +81,7 @@ fn build_test_peer(
             PeerRole::Unknown,
         ),
         socket: a,
+        streams: vec![],
     };
 
     let (connection_notifs_tx, connection_notifs_rx) = aptos_channels::new_test(1);
@@ -97,6 +101,7 @@ fn build_test_peer(
         MAX_CONCURRENT_OUTBOUND_RPCS,
         MAX_FRAME_SIZE,
         MAX_MESSAGE_SIZE,
+        Arc::new(HashMap::new()),
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -1033,3 +1038,60 @@ fn peers_send_multiplex() {
 
     rt.block_on(future::join3(peer_a.start(), peer_b.start(), test));
 }
+
+fn direct_send_message(protocol_id: ProtocolId, num_bytes: usize) -> NetworkMessage {
+    NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id,
+        priority: 0,
+        raw_msg: vec![0; num_bytes],
+    })
+}
+
+#[test]
+fn scheduler_sends_by_priority() {
+    let mut scheduler = OutboundScheduler::new(Arc::new(HashMap::new()));
+
+    let mempool_message = direct_send_message(ProtocolId::MempoolDirectSend, 10);
+    let storage_message = direct_send_message(ProtocolId::StorageServiceRpc, 10);
+    let observer_message = direct_send_message(ProtocolId::ConsensusObserver, 10);
+    let consensus_message = direct_send_message(ProtocolId::ConsensusDirectSendBcs, 10);
+    for (protocol_id, message) in [
+        (ProtocolId::MempoolDirectSend, &mempool_message),
+        (ProtocolId::StorageServiceRpc, &storage_message),
+        (ProtocolId::ConsensusObserver, &observer_message),
+        (ProtocolId::ConsensusDirectSendBcs, &consensus_message),
+    ] {
+        scheduler.push(Some(protocol_id), message.clone());
+    }
+
+    // Consensus goes first, and messages of the same priority are sent in order
+    assert_eq!(scheduler.pop(), Some(consensus_message));
+    assert_eq!(scheduler.pop(), Some(observer_message));
+    assert_eq!(scheduler.pop(), Some(mempool_message));
+    assert_eq!(scheduler.pop(), Some(storage_message));
+    assert_eq!(scheduler.pop(), None);
+    assert_eq!(scheduler.next_refill_time(), None);
+}
+
+#[test]
+fn scheduler_holds_back_protocols_over_quota() {
+    let quotas = HashMap::from([(ProtocolId::MempoolDirectSend, ProtocolQuotaConfig {
+        byte_bucket_rate: 100,
+        byte_bucket_size: 100,
+    })]);
+    let mut scheduler = OutboundScheduler::new(Arc::new(quotas));
+
+    let mempool_message_1 = direct_send_message(ProtocolId::MempoolDirectSend, 80);
+    let mempool_message_2 = direct_send_message(ProtocolId::MempoolDirectSend, 80);
+    let storage_message = direct_send_message(ProtocolId::StorageServiceRpc, 80);
+    let mempool_protocol = Some(ProtocolId::MempoolDirectSend);
+    scheduler.push(mempool_protocol, mempool_message_1.clone());
+    scheduler.push(mempool_protocol, mempool_message_2);
+    scheduler.push(Some(ProtocolId::StorageServiceRpc), storage_message.clone());
+
+    // The second mempool message exceeds the quota, so it's held back
+    assert_eq!(scheduler.pop(), Some(mempool_message_1));
+    assert_eq!(scheduler.pop(), Some(storage_message));
+    assert_eq!(scheduler.pop(), None);
+    assert!(scheduler.next_refill_time().is_some());
+}
@@ -18,13 +18,18 @@ use crate::{
     ProtocolId,
 };
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
-use aptos_config::{config::HANDSHAKE_VERSION, network_id::NetworkContext};
+use aptos_config::{
+    config::{ProtocolQuotaConfig, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
 use aptos_crypto::x25519;
 use aptos_logger::prelude::*;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use aptos_netcore::transport::memory::MemoryTransport;
 use aptos_netcore::transport::{
+    quic::QuicTransport,
     tcp::{TCPBufferCfg, TcpSocket, TcpTransport},
+    tcp_and_quic::{TcpAndQuicTransport, TcpOrQuicSocket},
     Transport,
 };
 use aptos_time_service::TimeService;
@@ -52,6 +57,7 @@ struct TransportContext {
     authentication_mode: AuthenticationMode,
     peers_and_metadata: Arc<PeersAndMetadata>,
     enable_proxy_protocol: bool,
+    enable_quic: bool,
 }
 
 impl TransportContext {
@@ -78,6 +84,8 @@ struct PeerManagerContext {
     max_message_size: usize,
     inbound_connection_limit: usize,
     tcp_buffer_cfg: TCPBufferCfg,
+    outbound_protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+    enforce_peer_bans: bool,
 }
 
 impl PeerManagerContext {
@@ -100,6 +108,8 @@ impl PeerManagerContext {
         max_message_size: usize,
         inbound_connection_limit: usize,
         tcp_buffer_cfg: TCPBufferCfg,
+        outbound_protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+        enforce_peer_bans: bool,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -116,6 +126,8 @@ impl PeerManagerContext {
             max_message_size,
             inbound_connection_limit,
             tcp_buffer_cfg,
+            outbound_protocol_quotas,
+            enforce_peer_bans,
         }
     }
 
@@ -139,11 +151,14 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<AptosNetTransport<MemoryTransport>, NoiseStream<aptos_memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<AptosNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type TcpAndQuicPeerManager =
+    PeerManager<AptosNetTransport<TcpAndQuicTransport>, NoiseStream<TcpOrQuicSocket>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    TcpAndQuic(TcpAndQuicPeerManager),
 }
 
 pub struct PeerManagerBuilder {
@@ -171,8 +186,11 @@ impl PeerManagerBuilder {
         max_frame_size: usize,
         max_message_size: usize,
         enable_proxy_protocol: bool,
+        enable_quic: bool,
         inbound_connection_limit: usize,
         tcp_buffer_cfg: TCPBufferCfg,
+        outbound_protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+        enforce_peer_bans: bool,
     ) -> Self {
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = aptos_channel::new(
@@ -193,6 +211,7 @@ impl PeerManagerBuilder {
                 authentication_mode,
                 peers_and_metadata: peers_and_metadata.clone(),
                 enable_proxy_protocol,
+                enable_quic,
             }),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -207,6 +226,8 @@ impl PeerManagerBuilder {
                 max_message_size,
                 inbound_connection_limit,
                 tcp_buffer_cfg,
+                outbound_protocol_quotas,
+                enforce_peer_bans,
             )),
             peer_manager: None,
             listen_address,
@@ -250,6 +271,7 @@ impl PeerManagerBuilder {
         let protos = transport_context.supported_protocols;
         let chain_id = transport_context.chain_id;
         let enable_proxy_protocol = transport_context.enable_proxy_protocol;
+        let enable_quic = transport_context.enable_quic;
 
         let (key, auth_mode) = match transport_context.authentication_mode {
             AuthenticationMode::MaybeMutual(key) => (
@@ -267,6 +289,26 @@ impl PeerManagerBuilder {
         aptos_tcp_transport.set_tcp_buffers(&tcp_cfg);
 
         self.peer_manager = match self.listen_address.as_slice() {
+            // If QUIC is enabled, listen on both TCP and QUIC (on the same port),
+            // and dial peers over the transport of their address.
+            [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] | [Ip4(_), Quic(_)] | [Ip6(_), Quic(_)]
+                if enable_quic =>
+            {
+                Some(TransportPeerManager::TcpAndQuic(self.build_with_transport(
+                    AptosNetTransport::new(
+                        TcpAndQuicTransport::new(aptos_tcp_transport, QuicTransport),
+                        self.network_context,
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                )))
+            },
             [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
                 Some(TransportPeerManager::Tcp(self.build_with_transport(
                     AptosNetTransport::new(
@@ -300,7 +342,8 @@ impl PeerManagerBuilder {
             ))),
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>' (or \
+                 '/ip4/<addr>/quic/<port>' and '/ip6/<addr>/quic/<port>' if QUIC is enabled).",
                 self.network_context, self.listen_address
             ),
         };
@@ -340,6 +383,8 @@ impl PeerManagerBuilder {
             pm_context.max_frame_size,
             pm_context.max_message_size,
             pm_context.inbound_connection_limit,
+            pm_context.outbound_protocol_quotas,
+            pm_context.enforce_peer_bans,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -370,6 +415,7 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::TcpAndQuic(pm) => self.start_peer_manager(pm, executor),
         }
     }
 
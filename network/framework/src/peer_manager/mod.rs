@@ -23,7 +23,10 @@ use crate::{
     ProtocolId,
 };
 use aptos_channels::{self, aptos_channel, message_queues::QueueStyle};
-use aptos_config::network_id::{NetworkContext, PeerNetworkId};
+use aptos_config::{
+    config::ProtocolQuotaConfig,
+    network_id::{NetworkContext, PeerNetworkId},
+};
 use aptos_logger::prelude::*;
 use aptos_netcore::transport::{ConnectionOrigin, Transport};
 use aptos_short_hex_str::AsShortHexStr;
@@ -117,6 +120,10 @@ where
     max_message_size: usize,
     /// Inbound connection limit separate of outbound connections
     inbound_connection_limit: usize,
+    /// Outbound bandwidth quotas of the protocols, for each peer connection
+    outbound_protocol_quotas: Arc<HashMap<ProtocolId, ProtocolQuotaConfig>>,
+    /// Whether to reject connections to and from banned peers
+    enforce_peer_bans: bool,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -144,6 +151,8 @@ where
         max_frame_size: usize,
         max_message_size: usize,
         inbound_connection_limit: usize,
+        outbound_protocol_quotas: HashMap<ProtocolId, ProtocolQuotaConfig>,
+        enforce_peer_bans: bool,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = aptos_channels::new(
             channel_size,
@@ -185,6 +194,8 @@ where
             max_frame_size,
             max_message_size,
             inbound_connection_limit,
+            outbound_protocol_quotas: Arc::new(outbound_protocol_quotas),
+            enforce_peer_bans,
         }
     }
 
@@ -349,6 +360,24 @@ where
             },
         };
 
+        // Reject connections to and from banned peers
+        let peer_network_id = PeerNetworkId::new(
+            self.network_context.network_id(),
+            conn.metadata.remote_peer_id,
+        );
+        if self.enforce_peer_bans && self.peers_and_metadata.is_peer_banned(&peer_network_id) {
+            info!(
+                NetworkSchema::new(&self.network_context)
+                    .connection_metadata_with_address(&conn.metadata),
+                "{} Connection rejected due to a ban on the peer: {}",
+                self.network_context,
+                conn.metadata
+            );
+            counters::connections_rejected(&self.network_context, conn.metadata.origin).inc();
+            self.disconnect(conn);
+            return;
+        }
+
         // Verify that we have not reached the max connection limit for unknown inbound peers
         if conn.metadata.origin == ConnectionOrigin::Inbound {
             // Everything below here is meant for unknown peers only. The role comes from
@@ -661,6 +690,7 @@ where
             constants::MAX_CONCURRENT_OUTBOUND_RPCS,
             self.max_frame_size,
             self.max_message_size,
+            self.outbound_protocol_quotas.clone(),
         );
         self.executor.spawn(peer.start());
 
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    application::storage::PeersAndMetadata,
+    application::{reputation::PeerPenalty, storage::PeersAndMetadata},
     constants,
     peer::DisconnectReason,
     peer_manager::{
@@ -25,7 +25,7 @@ use anyhow::anyhow;
 use aptos_channels::{aptos_channel, message_queues::QueueStyle};
 use aptos_config::{
     config::{PeerRole, MAX_INBOUND_CONNECTIONS},
-    network_id::{NetworkContext, NetworkId},
+    network_id::{NetworkContext, NetworkId, PeerNetworkId},
 };
 use aptos_memsocket::MemorySocket;
 use aptos_netcore::transport::{
@@ -35,7 +35,7 @@ use aptos_time_service::TimeService;
 use aptos_types::{network_address::NetworkAddress, PeerId};
 use bytes::Bytes;
 use futures::{channel::oneshot, io::AsyncWriteExt, stream::StreamExt};
-use std::error::Error;
+use std::{collections::HashMap, error::Error};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -61,6 +61,7 @@ pub fn build_test_transport(
                     ProtocolIdSet::mock(),
                     PeerRole::Unknown,
                 ),
+                streams: vec![],
             })
         })
         .boxed()
@@ -116,6 +117,8 @@ fn build_test_peer_manager(
         constants::MAX_FRAME_SIZE,
         constants::MAX_MESSAGE_SIZE,
         MAX_INBOUND_CONNECTIONS,
+        HashMap::new(),
+        true,
     );
 
     (
@@ -242,6 +245,7 @@ fn create_connection<TSocket: transport::TSocket>(
             ProtocolIdSet::mock(),
             PeerRole::Unknown,
         ),
+        streams: vec![],
     }
 }
 
@@ -651,6 +655,64 @@ fn test_dial_disconnect() {
     runtime.block_on(test);
 }
 
+#[test]
+fn test_reject_banned_peers() {
+    ::aptos_logger::Logger::init_for_testing();
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    // Create a list of ordered PeerIds so we can ensure how PeerIds will be compared.
+    let ids = ordered_peer_ids(3);
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, mut conn_status_rx) =
+        build_test_peer_manager(runtime.handle().clone(), ids[2]);
+
+    let test = async move {
+        // Penalize the first peer until it's banned
+        let network_id = peer_manager.network_context.network_id();
+        let banned_peer = PeerNetworkId::new(network_id, ids[0]);
+        for _ in 0..5 {
+            peer_manager
+                .peers_and_metadata
+                .report_peer_penalty(banned_peer, PeerPenalty::InvalidProof);
+        }
+        assert!(peer_manager.peers_and_metadata.is_peer_banned(&banned_peer));
+
+        // Verify that connections to and from the banned peer are rejected
+        for (connection_id, origin) in [
+            (0, ConnectionOrigin::Inbound),
+            (1, ConnectionOrigin::Outbound),
+        ] {
+            let (connection, _remote_connection) = build_test_connection();
+            peer_manager.handle_new_connection_event(create_connection(
+                connection,
+                ids[0],
+                NetworkAddress::mock(),
+                origin,
+                ConnectionId::from(connection_id),
+            ));
+            assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+        }
+
+        // Verify that connections from other peers are accepted
+        let (connection, _remote_connection) = build_test_connection();
+        peer_manager.handle_new_connection_event(create_connection(
+            connection,
+            ids[1],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Inbound,
+            ConnectionId::from(2),
+        ));
+        assert!(peer_manager.active_peers.contains_key(&ids[1]));
+        match conn_status_rx.next().await.unwrap() {
+            ConnectionNotification::NewPeer(metadata, _) => {
+                assert_eq!(metadata.remote_peer_id, ids[1])
+            },
+            notification => panic!("Unexpected notification: {:?}", notification),
+        }
+    };
+
+    runtime.block_on(test);
+}
+
 fn add_peer_to_manager<TSocket: transport::TSocket>(
     peer_manager: &mut PeerManager<
         BoxedTransport<Connection<TSocket>, impl Error + Sync + Send + 'static>,
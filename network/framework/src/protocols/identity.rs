@@ -10,7 +10,9 @@ use bytes::BytesMut;
 use futures::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use std::io;
 
-/// The Handshake exchange protocol.
+/// The Handshake exchange protocol. If both peers support the zstd dictionary
+/// protocols, the IDs of their zstd dictionaries are exchanged right after the
+/// handshake messages (older peers don't expect them).
 pub async fn exchange_handshake<T>(
     own_handshake: &HandshakeMsg,
     socket: &mut T,
@@ -31,12 +33,34 @@ where
     // Read handshake message from the Remote
     let mut response = BytesMut::new();
     read_u16frame(socket, &mut response).await?;
-    let identity = bcs::from_bytes(&response).map_err(|e| {
+    let mut identity: HandshakeMsg = bcs::from_bytes(&response).map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData,
             format!("Failed to parse identity msg: {}", e),
         )
     })?;
+
+    // Exchange the zstd dictionary IDs (if both peers support them)
+    if own_handshake.supports_zstd_dictionaries() && identity.supports_zstd_dictionaries() {
+        let msg = bcs::to_bytes(&own_handshake.zstd_dictionary_ids).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize zstd dictionary ids: {}", e),
+            )
+        })?;
+        write_u16frame(socket, &msg).await?;
+        socket.flush().await?;
+
+        let mut response = BytesMut::new();
+        read_u16frame(socket, &mut response).await?;
+        identity.zstd_dictionary_ids = bcs::from_bytes(&response).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse zstd dictionary ids: {}", e),
+            )
+        })?;
+    }
+
     Ok(identity)
 }
 
@@ -51,8 +75,10 @@ mod tests {
     };
     use aptos_config::network_id::NetworkId;
     use aptos_memsocket::MemorySocket;
+    use aptos_netcore::framing::{read_u16frame, write_u16frame};
     use aptos_types::chain_id::ChainId;
-    use futures::{executor::block_on, future::join};
+    use bytes::BytesMut;
+    use futures::{executor::block_on, future::join, io::AsyncWriteExt};
     use std::{collections::BTreeMap, iter::FromIterator};
 
     fn build_test_connection() -> (MemorySocket, MemorySocket) {
@@ -78,6 +104,7 @@ mod tests {
             chain_id,
             network_id,
             supported_protocols,
+            zstd_dictionary_ids: BTreeMap::new(),
         };
         let mut supported_protocols = BTreeMap::new();
         supported_protocols.insert(
@@ -91,6 +118,7 @@ mod tests {
             supported_protocols,
             chain_id,
             network_id,
+            zstd_dictionary_ids: BTreeMap::new(),
         };
 
         let server_handshake_clone = server_handshake.clone();
@@ -186,4 +214,98 @@ mod tests {
 
         block_on(join(server, client));
     }
+
+    #[test]
+    fn handshake_zstd_dictionaries() {
+        let (mut outbound, mut inbound) = MemorySocket::new_pair();
+
+        // Create handshake messages that support the zstd dictionary protocols,
+        // with the same consensus dictionary, but different mempool dictionaries.
+        let protocols = ProtocolIdSet::from_iter([
+            ProtocolId::ConsensusRpcZstdDictionary,
+            ProtocolId::ConsensusRpcZstd,
+            ProtocolId::MempoolDirectSendZstdDictionary,
+            ProtocolId::MempoolDirectSendZstd,
+        ]);
+        let mut server_handshake = HandshakeMsg::from_supported(protocols.clone());
+        server_handshake.zstd_dictionary_ids =
+            BTreeMap::from([("consensus".into(), 1), ("mempool".into(), 2)]);
+        let mut client_handshake = HandshakeMsg::from_supported(protocols);
+        client_handshake.zstd_dictionary_ids =
+            BTreeMap::from([("consensus".into(), 1), ("mempool".into(), 3)]);
+
+        // The dictionary protocols are only negotiated for the consensus dictionary
+        let expected_protocols = ProtocolIdSet::from_iter([
+            ProtocolId::ConsensusRpcZstdDictionary,
+            ProtocolId::ConsensusRpcZstd,
+            ProtocolId::MempoolDirectSendZstd,
+        ]);
+        let server_expected_protocols = expected_protocols.clone();
+        let server = async move {
+            let remote_handshake = exchange_handshake(&server_handshake, &mut inbound)
+                .await
+                .unwrap();
+            assert_eq!(
+                remote_handshake.zstd_dictionary_ids,
+                BTreeMap::from([("consensus".into(), 1), ("mempool".into(), 3)])
+            );
+            assert_eq!(
+                server_handshake
+                    .perform_handshake(&remote_handshake)
+                    .unwrap(),
+                (MessagingProtocolVersion::V1, server_expected_protocols)
+            );
+        };
+
+        let client = async move {
+            let remote_handshake = exchange_handshake(&client_handshake, &mut outbound)
+                .await
+                .unwrap();
+            assert_eq!(
+                client_handshake
+                    .perform_handshake(&remote_handshake)
+                    .unwrap(),
+                (MessagingProtocolVersion::V1, expected_protocols)
+            );
+        };
+
+        block_on(join(server, client));
+    }
+
+    #[test]
+    fn handshake_zstd_dictionaries_old_peer() {
+        let (mut outbound, mut inbound) = MemorySocket::new_pair();
+
+        // Create a handshake message that supports the zstd dictionary protocols,
+        // and one for an older peer that doesn't know about them.
+        let mut server_handshake = HandshakeMsg::from_supported(ProtocolIdSet::from_iter([
+            ProtocolId::ConsensusRpcZstdDictionary,
+            ProtocolId::ConsensusRpcZstd,
+        ]));
+        server_handshake.zstd_dictionary_ids = BTreeMap::from([("consensus".into(), 1)]);
+        let client_handshake =
+            HandshakeMsg::from_supported(ProtocolIdSet::from_iter([ProtocolId::ConsensusRpcZstd]));
+
+        // The server must not send (or wait for) the zstd dictionary IDs, so
+        // the next frame it reads is the first message of the older peer.
+        let server = async move {
+            let remote_handshake = exchange_handshake(&server_handshake, &mut inbound)
+                .await
+                .unwrap();
+            assert!(remote_handshake.zstd_dictionary_ids.is_empty());
+            let mut message = BytesMut::new();
+            read_u16frame(&mut inbound, &mut message).await.unwrap();
+            assert_eq!(&message[..], b"message");
+        };
+
+        let client = async move {
+            exchange_handshake(&client_handshake, &mut outbound)
+                .await
+                .unwrap();
+            write_u16frame(&mut outbound, b"message").await.unwrap();
+            outbound.flush().await.unwrap();
+        };
+
+        block_on(join(server, client));
+    }
 }
@@ -11,6 +11,11 @@
 //! supported over that messaging protocol. On receipt, both ends will determine the highest
 //! intersecting messaging protocol version and use that for the remainder of the session.
 //!
+//! If both end-points support the zstd dictionary protocols, they also exchange the IDs of
+//! their zstd dictionaries (see [`exchange_handshake`]). The dictionary protocols are only
+//! negotiated for clients that have the same dictionary on both end-points.
+//!
+//! [`exchange_handshake`]: crate::protocols::identity::exchange_handshake
 //! [AptosNet Handshake v1 Specification]: https://github.com/aptos-labs/aptos-core/blob/main/specifications/network/handshake-v1.md
 
 use crate::counters::{start_serialization_timer, DESERIALIZATION_LABEL, SERIALIZATION_LABEL};
@@ -73,15 +78,52 @@ pub enum ProtocolId {
     JWKConsensusRpcJson = 26,
     ConsensusObserver = 27,
     ConsensusObserverRpc = 28,
+    ConsensusRpcZstd = 29,
+    ConsensusDirectSendZstd = 30,
+    MempoolDirectSendZstd = 31,
+    StorageServiceRpcZstd = 32,
+    ConsensusRpcZstdDictionary = 33,
+    ConsensusDirectSendZstdDictionary = 34,
+    MempoolDirectSendZstdDictionary = 35,
 }
 
 /// The encoding types for Protocols
 enum Encoding {
     Bcs(usize),
     CompressedBcs(usize),
+    ZstdCompressedBcs(usize),
+    ZstdDictionaryCompressedBcs(usize),
     Json,
 }
 
+/// The priority with which the messages of a protocol are sent to a peer,
+/// when the connection is saturated. Higher priorities are ordered first.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ProtocolPriority {
+    High,
+    Medium,
+    Low,
+}
+
+impl ProtocolPriority {
+    /// All the priorities, from the highest to the lowest
+    pub const ALL: [ProtocolPriority; 3] = [
+        ProtocolPriority::High,
+        ProtocolPriority::Medium,
+        ProtocolPriority::Low,
+    ];
+
+    /// The class of the stream the messages are sent on, if the connection supports multiple
+    /// streams (e.g., QUIC). Class 0 is the stream the connection was established on.
+    pub fn stream_class(self) -> u8 {
+        match self {
+            ProtocolPriority::High => 0,
+            ProtocolPriority::Medium => 1,
+            ProtocolPriority::Low => 2,
+        }
+    }
+}
+
 impl ProtocolId {
     pub fn as_str(self) -> &'static str {
         use ProtocolId::*;
@@ -115,6 +157,13 @@ impl ProtocolId {
             JWKConsensusRpcJson => "JWKConsensusRpcJson",
             ConsensusObserver => "ConsensusObserver",
             ConsensusObserverRpc => "ConsensusObserverRpc",
+            ConsensusRpcZstd => "ConsensusRpcZstd",
+            ConsensusDirectSendZstd => "ConsensusDirectSendZstd",
+            MempoolDirectSendZstd => "MempoolDirectSendZstd",
+            StorageServiceRpcZstd => "StorageServiceRpcZstd",
+            ConsensusRpcZstdDictionary => "ConsensusRpcZstdDictionary",
+            ConsensusDirectSendZstdDictionary => "ConsensusDirectSendZstdDictionary",
+            MempoolDirectSendZstdDictionary => "MempoolDirectSendZstdDictionary",
         }
     }
 
@@ -150,9 +199,62 @@ impl ProtocolId {
             ProtocolId::JWKConsensusRpcJson,
             ProtocolId::ConsensusObserver,
             ProtocolId::ConsensusObserverRpc,
+            ProtocolId::ConsensusRpcZstd,
+            ProtocolId::ConsensusDirectSendZstd,
+            ProtocolId::MempoolDirectSendZstd,
+            ProtocolId::StorageServiceRpcZstd,
+            ProtocolId::ConsensusRpcZstdDictionary,
+            ProtocolId::ConsensusDirectSendZstdDictionary,
+            ProtocolId::MempoolDirectSendZstdDictionary,
         ]
     }
 
+    /// Returns the priority of the protocol's outbound messages. Consensus
+    /// (including quorum store) and the health checker are sent first, so that
+    /// bulk transfers (e.g., mempool broadcasts and storage service responses)
+    /// can't delay them on a saturated connection.
+    pub fn priority(self) -> ProtocolPriority {
+        use ProtocolId::*;
+        match self {
+            ConsensusRpcBcs
+            | ConsensusDirectSendBcs
+            | ConsensusDirectSendJson
+            | ConsensusRpcJson
+            | ConsensusRpcCompressed
+            | ConsensusDirectSendCompressed
+            | ConsensusRpcZstd
+            | ConsensusDirectSendZstd
+            | ConsensusRpcZstdDictionary
+            | ConsensusDirectSendZstdDictionary
+            | DKGDirectSendCompressed
+            | DKGDirectSendBcs
+            | DKGDirectSendJson
+            | DKGRpcCompressed
+            | DKGRpcBcs
+            | DKGRpcJson
+            | JWKConsensusDirectSendCompressed
+            | JWKConsensusDirectSendBcs
+            | JWKConsensusDirectSendJson
+            | JWKConsensusRpcCompressed
+            | JWKConsensusRpcBcs
+            | JWKConsensusRpcJson
+            | HealthCheckerRpc => ProtocolPriority::High,
+            ConsensusObserver
+            | ConsensusObserverRpc
+            | StateSyncDirectSend
+            | DiscoveryDirectSend
+            | PeerMonitoringServiceRpc => ProtocolPriority::Medium,
+            MempoolDirectSend
+            | MempoolDirectSendZstd
+            | MempoolDirectSendZstdDictionary
+            | MempoolRpc
+            | StorageServiceRpc
+            | StorageServiceRpcZstd
+            | NetbenchDirectSend
+            | NetbenchRpc => ProtocolPriority::Low,
+        }
+    }
+
     /// Specifies how to encode messages for a given `ProtocolId`
     fn encoding(self) -> Encoding {
         match self {
@@ -168,6 +270,20 @@ impl ProtocolId {
             | ProtocolId::JWKConsensusRpcCompressed => Encoding::CompressedBcs(RECURSION_LIMIT),
             ProtocolId::MempoolDirectSend => Encoding::CompressedBcs(USER_INPUT_RECURSION_LIMIT),
             ProtocolId::MempoolRpc => Encoding::Bcs(USER_INPUT_RECURSION_LIMIT),
+            ProtocolId::ConsensusDirectSendZstd | ProtocolId::ConsensusRpcZstd => {
+                Encoding::ZstdCompressedBcs(RECURSION_LIMIT)
+            },
+            ProtocolId::MempoolDirectSendZstd => {
+                Encoding::ZstdCompressedBcs(USER_INPUT_RECURSION_LIMIT)
+            },
+            ProtocolId::StorageServiceRpcZstd => Encoding::ZstdCompressedBcs(RECURSION_LIMIT),
+            ProtocolId::ConsensusDirectSendZstdDictionary
+            | ProtocolId::ConsensusRpcZstdDictionary => {
+                Encoding::ZstdDictionaryCompressedBcs(RECURSION_LIMIT)
+            },
+            ProtocolId::MempoolDirectSendZstdDictionary => {
+                Encoding::ZstdDictionaryCompressedBcs(USER_INPUT_RECURSION_LIMIT)
+            },
             _ => Encoding::Bcs(RECURSION_LIMIT),
         }
     }
@@ -175,11 +291,17 @@ impl ProtocolId {
     /// Returns the compression client label based on the current protocol id
     fn get_compression_client(self) -> CompressionClient {
         match self {
-            ProtocolId::ConsensusDirectSendCompressed | ProtocolId::ConsensusRpcCompressed => {
-                CompressionClient::Consensus
-            },
+            ProtocolId::ConsensusDirectSendCompressed
+            | ProtocolId::ConsensusRpcCompressed
+            | ProtocolId::ConsensusDirectSendZstd
+            | ProtocolId::ConsensusRpcZstd
+            | ProtocolId::ConsensusDirectSendZstdDictionary
+            | ProtocolId::ConsensusRpcZstdDictionary => CompressionClient::Consensus,
             ProtocolId::ConsensusObserver => CompressionClient::ConsensusObserver,
-            ProtocolId::MempoolDirectSend => CompressionClient::Mempool,
+            ProtocolId::MempoolDirectSend
+            | ProtocolId::MempoolDirectSendZstd
+            | ProtocolId::MempoolDirectSendZstdDictionary => CompressionClient::Mempool,
+            ProtocolId::StorageServiceRpcZstd => CompressionClient::StateSync,
             ProtocolId::DKGDirectSendCompressed | ProtocolId::DKGRpcCompressed => {
                 CompressionClient::DKG
             },
@@ -192,6 +314,12 @@ impl ProtocolId {
         }
     }
 
+    /// Returns true iff the protocol compresses messages with the zstd
+    /// dictionary of its compression client.
+    pub fn uses_zstd_dictionary(self) -> bool {
+        matches!(self.encoding(), Encoding::ZstdDictionaryCompressedBcs(_))
+    }
+
     /// Serializes the given message into bytes (based on the protocol ID
     /// and encoding to use).
     pub fn to_bytes<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
@@ -211,6 +339,26 @@ impl ProtocolId {
                 )
                 .map_err(|e| anyhow!("{:?}", e))
             },
+            Encoding::ZstdCompressedBcs(limit) => {
+                let compression_client = self.get_compression_client();
+                let bcs_bytes = self.bcs_encode(value, limit)?;
+                aptos_compression::zstd::compress(
+                    bcs_bytes,
+                    compression_client,
+                    MAX_APPLICATION_MESSAGE_SIZE,
+                )
+                .map_err(|e| anyhow!("{:?}", e))
+            },
+            Encoding::ZstdDictionaryCompressedBcs(limit) => {
+                let compression_client = self.get_compression_client();
+                let bcs_bytes = self.bcs_encode(value, limit)?;
+                aptos_compression::zstd::compress_with_dictionary(
+                    bcs_bytes,
+                    compression_client,
+                    MAX_APPLICATION_MESSAGE_SIZE,
+                )
+                .map_err(|e| anyhow!("{:?}", e))
+            },
             Encoding::Json => serde_json::to_vec(value).map_err(|e| anyhow!("{:?}", e)),
         };
 
@@ -241,6 +389,16 @@ impl ProtocolId {
                 .map_err(|e| anyhow! {"{:?}", e})?;
                 self.bcs_decode(&raw_bytes, limit)
             },
+            Encoding::ZstdCompressedBcs(limit) | Encoding::ZstdDictionaryCompressedBcs(limit) => {
+                let compression_client = self.get_compression_client();
+                let raw_bytes = aptos_compression::zstd::decompress(
+                    &bytes.to_vec(),
+                    compression_client,
+                    MAX_APPLICATION_MESSAGE_SIZE,
+                )
+                .map_err(|e| anyhow!("{:?}", e))?;
+                self.bcs_decode(&raw_bytes, limit)
+            },
             Encoding::Json => serde_json::from_slice(bytes).map_err(|e| anyhow!("{:?}", e)),
         };
 
@@ -406,6 +564,11 @@ pub struct HandshakeMsg {
     pub supported_protocols: BTreeMap<MessagingProtocolVersion, ProtocolIdSet>,
     pub chain_id: ChainId,
     pub network_id: NetworkId,
+    /// The IDs of the node's zstd dictionaries, by compression client label.
+    /// These are not part of the serialized message (older nodes would fail
+    /// to parse it), but are exchanged separately after the message.
+    #[serde(skip)]
+    pub zstd_dictionary_ids: BTreeMap<String, u32>,
 }
 
 impl HandshakeMsg {
@@ -423,12 +586,33 @@ impl HandshakeMsg {
             chain_id: ChainId::test(),
             network_id: NetworkId::Validator,
             supported_protocols,
+            zstd_dictionary_ids: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true iff the node supports any of the zstd dictionary protocols,
+    /// in which case it also exchanges the IDs of its zstd dictionaries.
+    pub fn supports_zstd_dictionaries(&self) -> bool {
+        self.supported_protocols
+            .values()
+            .any(|protocols| protocols.iter().any(ProtocolId::uses_zstd_dictionary))
+    }
+
+    /// Returns true iff both nodes have the same zstd dictionary for the
+    /// compression client of the given protocol.
+    fn has_same_zstd_dictionary(&self, other: &HandshakeMsg, protocol: ProtocolId) -> bool {
+        let label = protocol.get_compression_client().get_label();
+        match self.zstd_dictionary_ids.get(label) {
+            Some(dictionary_id) => other.zstd_dictionary_ids.get(label) == Some(dictionary_id),
+            None => false,
         }
     }
 
     /// This function:
     /// 1. verifies that both HandshakeMsg are compatible and
-    /// 2. finds out the intersection of protocols that is supported
+    /// 2. finds out the intersection of protocols that is supported. The zstd
+    ///    dictionary protocols are only supported if both nodes have the same
+    ///    dictionary, so the peers fall back to the protocols without one.
     pub fn perform_handshake(
         &self,
         other: &HandshakeMsg,
@@ -453,7 +637,14 @@ impl HandshakeMsg {
         // at least one common ProtocolId.
         for (our_handshake_version, our_protocols) in self.supported_protocols.iter().rev() {
             if let Some(their_protocols) = other.supported_protocols.get(our_handshake_version) {
-                let common_protocols = our_protocols.intersect(their_protocols);
+                let common_protocols: ProtocolIdSet = our_protocols
+                    .intersect(their_protocols)
+                    .iter()
+                    .filter(|protocol| {
+                        !protocol.uses_zstd_dictionary()
+                            || self.has_same_zstd_dictionary(other, *protocol)
+                    })
+                    .collect();
 
                 if !common_protocols.is_empty() {
                     return Ok((*our_handshake_version, common_protocols));
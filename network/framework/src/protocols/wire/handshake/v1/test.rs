@@ -71,6 +71,7 @@ fn common_protocols() {
         chain_id,
         network_id,
         supported_protocols,
+        zstd_dictionary_ids: BTreeMap::new(),
     };
 
     // Case 1: One intersecting protocol is found for common messaging protocol version.
@@ -83,6 +84,7 @@ fn common_protocols() {
         chain_id,
         network_id,
         supported_protocols,
+        zstd_dictionary_ids: BTreeMap::new(),
     };
 
     assert_eq!(
@@ -98,6 +100,7 @@ fn common_protocols() {
         chain_id,
         network_id,
         supported_protocols: BTreeMap::new(),
+        zstd_dictionary_ids: BTreeMap::new(),
     };
     assert_eq!(
         h1.perform_handshake(&h2).unwrap_err(),
@@ -111,6 +114,7 @@ fn common_protocols() {
         supported_protocols,
         chain_id,
         network_id,
+        zstd_dictionary_ids: BTreeMap::new(),
     };
     assert_eq!(
         h1.perform_handshake(&h2).unwrap_err(),
@@ -183,3 +187,100 @@ fn ignore_unknown_protocols() {
         ProtocolIdSet::empty(),
     );
 }
+
+#[test]
+fn zstd_protocols() {
+    let zstd_protocols = [
+        ProtocolId::ConsensusRpcZstd,
+        ProtocolId::ConsensusDirectSendZstd,
+        ProtocolId::MempoolDirectSendZstd,
+        ProtocolId::StorageServiceRpcZstd,
+    ];
+
+    // Messages round trip, and are not understood by the LZ4 compressed protocols
+    let message = vec![7u64; 1000];
+    for protocol in zstd_protocols {
+        let bytes = protocol.to_bytes(&message).unwrap();
+        assert_eq!(protocol.from_bytes::<Vec<u64>>(&bytes).unwrap(), message);
+        assert!(ProtocolId::ConsensusRpcCompressed
+            .from_bytes::<Vec<u64>>(&bytes)
+            .is_err());
+    }
+
+    // The zstd protocols are only used if both peers support them
+    let old_protocols = ProtocolIdSet::from_iter([
+        ProtocolId::ConsensusRpcCompressed,
+        ProtocolId::ConsensusDirectSendCompressed,
+    ]);
+    let new_protocols = ProtocolIdSet::from_iter(zstd_protocols).union(&old_protocols);
+    let old_hs = HandshakeMsg::from_supported(old_protocols.clone());
+    let new_hs = HandshakeMsg::from_supported(new_protocols.clone());
+    assert_eq!(
+        (MessagingProtocolVersion::V1, old_protocols),
+        new_hs.perform_handshake(&old_hs).unwrap()
+    );
+    assert_eq!(
+        (MessagingProtocolVersion::V1, new_protocols),
+        new_hs.perform_handshake(&new_hs).unwrap()
+    );
+}
+
+#[test]
+fn zstd_dictionary_protocols() {
+    let protocols = ProtocolIdSet::from_iter([
+        ProtocolId::ConsensusRpcZstdDictionary,
+        ProtocolId::ConsensusDirectSendZstdDictionary,
+        ProtocolId::ConsensusRpcZstd,
+        ProtocolId::ConsensusDirectSendZstd,
+    ]);
+    let zstd_protocols = ProtocolIdSet::from_iter([
+        ProtocolId::ConsensusRpcZstd,
+        ProtocolId::ConsensusDirectSendZstd,
+    ]);
+
+    // The dictionary protocols are not negotiated without dictionaries
+    let mut h1 = HandshakeMsg::from_supported(protocols.clone());
+    let mut h2 = HandshakeMsg::from_supported(protocols.clone());
+    assert!(h1.supports_zstd_dictionaries());
+    assert_eq!(
+        (MessagingProtocolVersion::V1, zstd_protocols.clone()),
+        h1.perform_handshake(&h2).unwrap()
+    );
+
+    // Or if only one of the peers has a dictionary
+    h1.zstd_dictionary_ids = BTreeMap::from([("consensus".into(), 1)]);
+    assert_eq!(
+        (MessagingProtocolVersion::V1, zstd_protocols.clone()),
+        h1.perform_handshake(&h2).unwrap()
+    );
+
+    // Or if the dictionaries are different
+    h2.zstd_dictionary_ids = BTreeMap::from([("consensus".into(), 2)]);
+    assert_eq!(
+        (MessagingProtocolVersion::V1, zstd_protocols.clone()),
+        h1.perform_handshake(&h2).unwrap()
+    );
+
+    // But they are if the dictionaries are the same
+    h2.zstd_dictionary_ids = BTreeMap::from([("consensus".into(), 1)]);
+    assert_eq!(
+        (MessagingProtocolVersion::V1, protocols),
+        h1.perform_handshake(&h2).unwrap()
+    );
+
+    // Older peers don't support the dictionary protocols
+    assert!(!HandshakeMsg::from_supported(zstd_protocols).supports_zstd_dictionaries());
+}
+
+#[test]
+fn protocol_names_match_config() {
+    // The config sanitizer relies on the protocol names to verify the outbound quotas
+    let protocol_names: Vec<_> = ProtocolId::all()
+        .iter()
+        .map(|protocol_id| protocol_id.as_str())
+        .collect();
+    assert_eq!(
+        protocol_names,
+        aptos_config::config::PROTOCOL_NAMES.to_vec()
+    );
+}
@@ -7,7 +7,9 @@ use crate::{
     noise::{stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, NoiseUpgrader},
     protocols::{
         identity::exchange_handshake,
-        wire::handshake::v1::{HandshakeMsg, MessagingProtocolVersion, ProtocolIdSet},
+        wire::handshake::v1::{
+            HandshakeMsg, MessagingProtocolVersion, ProtocolIdSet, ProtocolPriority,
+        },
     },
 };
 use aptos_config::{
@@ -19,12 +21,18 @@ use aptos_id_generator::{IdGenerator, U32IdGenerator};
 use aptos_logger::prelude::*;
 // Re-exposed for aptos-network-checker
 pub use aptos_netcore::transport::tcp::{resolve_and_connect, TCPBufferCfg, TcpSocket};
-use aptos_netcore::transport::{proxy_protocol, tcp, ConnectionOrigin, Transport};
+use aptos_netcore::transport::{
+    proxy_protocol,
+    quic::{QuicSocket, QuicStreams},
+    tcp, ConnectionOrigin, MultiStreamSocket, Transport,
+};
 use aptos_short_hex_str::AsShortHexStr;
 use aptos_time_service::{timeout, TimeService, TimeServiceTrait};
 use aptos_types::{
     chain_id::ChainId,
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress},
+    network_address::{
+        parse_dns_quic, parse_dns_tcp, parse_ip_quic, parse_ip_tcp, parse_memory, NetworkAddress,
+    },
     PeerId,
 };
 use futures::{
@@ -189,6 +197,63 @@ impl fmt::Display for ConnectionMetadata {
 pub struct Connection<TSocket> {
     pub socket: TSocket,
     pub metadata: ConnectionMetadata,
+    /// The additional streams of the connection and their classes, ordered by class. This is
+    /// empty if the base transport only supports a single stream (e.g., TCP).
+    pub streams: Vec<(u8, NoiseStream<QuicSocket>)>,
+}
+
+/// Returns the classes of the streams opened in addition to the stream the connection was
+/// established on (see [`ProtocolPriority::stream_class`]).
+fn additional_stream_classes() -> Vec<u8> {
+    ProtocolPriority::ALL
+        .iter()
+        .map(|priority| priority.stream_class())
+        .filter(|class| *class != 0)
+        .collect()
+}
+
+/// Opens the additional streams of an upgraded outbound connection, if the base
+/// transport supports multiple streams.
+async fn open_streams<T>(
+    socket: &NoiseStream<T>,
+    streams: Option<QuicStreams>,
+) -> io::Result<Vec<(u8, NoiseStream<QuicSocket>)>> {
+    let Some(streams) = streams else {
+        return Ok(vec![]);
+    };
+
+    let mut noise_streams = vec![];
+    for class in additional_stream_classes() {
+        let stream = streams.open(class).await?;
+        noise_streams.push((class, socket.new_stream(stream, class.into())));
+    }
+    Ok(noise_streams)
+}
+
+/// Accepts the additional streams of an upgraded inbound connection, if the base
+/// transport supports multiple streams.
+async fn accept_streams<T>(
+    socket: &NoiseStream<T>,
+    streams: Option<QuicStreams>,
+) -> io::Result<Vec<(u8, NoiseStream<QuicSocket>)>> {
+    let Some(streams) = streams else {
+        return Ok(vec![]);
+    };
+
+    let classes = additional_stream_classes();
+    let mut noise_streams: Vec<(u8, NoiseStream<QuicSocket>)> = vec![];
+    while noise_streams.len() < classes.len() {
+        let (class, stream) = streams.accept().await?;
+        if !classes.contains(&class) || noise_streams.iter().any(|(other, _)| *other == class) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected stream class: {}", class),
+            ));
+        }
+        noise_streams.push((class, socket.new_stream(stream, class.into())));
+    }
+    noise_streams.sort_by_key(|(class, _)| *class);
+    Ok(noise_streams)
 }
 
 /// Convenience function for adding a timeout to a Future that returns an `io::Result`.
@@ -247,7 +312,7 @@ fn add_pp_addr(proxy_protocol_enabled: bool, error: io::Error, addr: &NetworkAdd
 /// `ctxt.noise.auth_mode` is `HandshakeAuthMode::Mutual( anti_replay_timestamps , trusted_peers )`,
 /// then we will only allow connections from peers with a pubkey in the `trusted_peers`
 /// set. Otherwise, we will allow inbound connections from any pubkey.
-async fn upgrade_inbound<T: TSocket>(
+async fn upgrade_inbound<T: TSocket + MultiStreamSocket>(
     ctxt: Arc<UpgradeContext>,
     fut_socket: impl Future<Output = io::Result<T>>,
     addr: NetworkAddress,
@@ -255,6 +320,11 @@ async fn upgrade_inbound<T: TSocket>(
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Inbound;
     let mut socket = fut_socket.await?;
+    let streams = socket.streams();
+
+    // The proxy protocol only applies to TCP (i.e., single stream) connections, as
+    // QUIC connections aren't proxied
+    let proxy_protocol_enabled = proxy_protocol_enabled && streams.is_none();
 
     // If we have proxy protocol enabled, process the event, otherwise skip it
     // TODO: This would make more sense to build this in at instantiation so we don't need to put the if statement here
@@ -300,6 +370,7 @@ async fn upgrade_inbound<T: TSocket>(
         supported_protocols: ctxt.supported_protocols.clone(),
         chain_id: ctxt.chain_id,
         network_id: ctxt.network_id,
+        zstd_dictionary_ids: aptos_compression::zstd::get_dictionary_ids(),
     };
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket)
         .await
@@ -321,9 +392,15 @@ async fn upgrade_inbound<T: TSocket>(
             )
         })?;
 
+    // accept the additional streams opened by the dialer
+    let streams = accept_streams(&socket, streams)
+        .await
+        .map_err(|err| add_pp_addr(proxy_protocol_enabled, err, &addr))?;
+
     // return successful connection
     Ok(Connection {
         socket,
+        streams,
         metadata: ConnectionMetadata::new(
             remote_peer_id,
             CONNECTION_ID_GENERATOR.next(),
@@ -338,7 +415,7 @@ async fn upgrade_inbound<T: TSocket>(
 
 /// Upgrade an outbound connection. This means we run a Noise IK handshake for
 /// authentication and then negotiate common supported protocols.
-pub async fn upgrade_outbound<T: TSocket>(
+pub async fn upgrade_outbound<T: TSocket + MultiStreamSocket>(
     ctxt: Arc<UpgradeContext>,
     fut_socket: impl Future<Output = io::Result<T>>,
     addr: NetworkAddress,
@@ -347,6 +424,7 @@ pub async fn upgrade_outbound<T: TSocket>(
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Outbound;
     let socket = fut_socket.await?;
+    let streams = socket.streams();
 
     // noise handshake
     let (mut socket, peer_role) = ctxt
@@ -382,6 +460,7 @@ pub async fn upgrade_outbound<T: TSocket>(
         supported_protocols: ctxt.supported_protocols.clone(),
         chain_id: ctxt.chain_id,
         network_id: ctxt.network_id,
+        zstd_dictionary_ids: aptos_compression::zstd::get_dictionary_ids(),
     };
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket).await?;
 
@@ -396,9 +475,13 @@ pub async fn upgrade_outbound<T: TSocket>(
             io::Error::new(io::ErrorKind::Other, e)
         })?;
 
+    // open the additional streams
+    let streams = open_streams(&socket, streams).await?;
+
     // return successful connection
     Ok(Connection {
         socket,
+        streams,
         metadata: ConnectionMetadata::new(
             remote_peer_id,
             CONNECTION_ID_GENERATOR.next(),
@@ -415,7 +498,9 @@ pub async fn upgrade_outbound<T: TSocket>(
 ///
 /// The base transport layer is pluggable, so long as it provides a reliable,
 /// ordered, connection-oriented, byte-stream abstraction (e.g., TCP). We currently
-/// use either `MemoryTransport` or `TcpTransport` as this base layer.
+/// use either `MemoryTransport`, `TcpTransport`, `QuicTransport` or `TcpAndQuicTransport`
+/// as this base layer. If the base transport supports multiple streams (i.e., QUIC),
+/// an additional stream is opened for each stream class (see [`Connection::streams`]).
 ///
 /// Inbound and outbound connections are first established with the `base_transport`
 /// and then negotiate a secure, authenticated transport layer (currently Noise
@@ -434,7 +519,7 @@ pub struct AptosNetTransport<TTransport> {
 impl<TTransport> AptosNetTransport<TTransport>
 where
     TTransport: Transport<Error = io::Error>,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -487,13 +572,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_quic(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_dns_quic(protos).map(|x| (&protos[..2], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+quic, or dns+quic",
                         addr
                     ),
                 )
@@ -539,6 +626,14 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then `/<base_transport>` is
+    /// any of the above `TcpTransport` formats, with `/quic/<port>` in place
+    /// of `/tcp/<port>`.
+    ///
+    /// If the base transport is `TcpAndQuicTransport`, then `/<base_transport>` is
+    /// any of the above `TcpTransport` or `QuicTransport` formats, and the peer is
+    /// dialed over the transport of the address.
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -585,6 +680,15 @@ where
     ///
     /// `/ip4/<ipaddr>/tcp/<port>` or
     /// `/ip6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then we expect:
+    ///
+    /// `/ip4/<ipaddr>/quic/<port>` or
+    /// `/ip6/<ipaddr>/quic/<port>`
+    ///
+    /// If the base transport is `TcpAndQuicTransport`, then we expect any of the
+    /// above `TcpTransport` or `QuicTransport` formats, and we also listen on the
+    /// same ip and port with the other transport.
     pub fn listen_on(
         &self,
         addr: NetworkAddress,
@@ -641,7 +745,7 @@ where
 impl<TTransport: Transport> Transport for AptosNetTransport<TTransport>
 where
     TTransport: Transport<Error = io::Error> + Send + 'static,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -12,7 +12,10 @@ use aptos_config::config::{Peer, PeerRole, PeerSet, HANDSHAKE_VERSION};
 use aptos_crypto::{test_utils::TEST_SEED, traits::Uniform, x25519, x25519::PrivateKey};
 use aptos_netcore::{
     framing::{read_u16frame, write_u16frame},
-    transport::{memory, ConnectionOrigin, Transport},
+    transport::{
+        memory, quic::QuicTransport, tcp_and_quic::TcpAndQuicTransport, ConnectionOrigin,
+        MultiStreamSocket, Transport,
+    },
 };
 use aptos_time_service::MockTimeService;
 use aptos_types::{
@@ -22,7 +25,11 @@ use aptos_types::{
     PeerId,
 };
 use bytes::{Bytes, BytesMut};
-use futures::{future, io::AsyncWriteExt, stream::StreamExt};
+use futures::{
+    future,
+    io::{AsyncReadExt, AsyncWriteExt},
+    stream::StreamExt,
+};
 use rand::{rngs::StdRng, SeedableRng};
 use std::{io, iter::FromIterator, sync::Arc};
 use tokio::runtime::Runtime;
@@ -58,7 +65,7 @@ fn setup<TTransport>(
 )
 where
     TTransport: Transport<Error = io::Error> + Clone,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -203,6 +210,13 @@ async fn write_read_msg(socket: &mut impl TSocket, msg: &[u8]) -> Bytes {
     buf.freeze()
 }
 
+/// Waits for the remote peer to close the socket (or for the connection to be lost), so
+/// that the connection isn't dropped before the remote peer has read everything (QUIC
+/// connections are closed abruptly once dropped)
+async fn wait_for_remote_close(socket: &mut impl TSocket) {
+    let _ = socket.read(&mut [0; 1]).await;
+}
+
 /// Check that the network address matches the format
 /// `"/memory/<port>/noise-ik/<pubkey>/handshake/<version>"`
 fn expect_memory_noise_addr(addr: &NetworkAddress) {
@@ -223,6 +237,30 @@ fn expect_ip4_tcp_noise_addr(addr: &NetworkAddress) {
     );
 }
 
+/// Check that the network address matches the format
+/// `"/ip4/<ipaddr>/quic/<port>/noise-ik/<pubkey>/handshake/<version>"`
+fn expect_ip4_quic_noise_addr(addr: &NetworkAddress) {
+    assert!(
+        matches!(addr.as_slice(), [Ip4(_), Quic(_), NoiseIK(_), Handshake(_)]),
+        "addr: '{}'",
+        addr
+    );
+}
+
+/// Returns the classes of the additional streams expected for a connection to the
+/// given address (only QUIC connections have additional streams)
+fn expected_stream_classes(addr: &NetworkAddress) -> Vec<u8> {
+    if addr.as_slice().iter().any(|proto| matches!(proto, Quic(_))) {
+        additional_stream_classes()
+    } else {
+        vec![]
+    }
+}
+
+fn stream_classes<TSocket>(conn: &Connection<TSocket>) -> Vec<u8> {
+    conn.streams.iter().map(|(class, _)| *class).collect()
+}
+
 fn test_transport_success<TTransport>(
     base_transport: TTransport,
     auth: Auth,
@@ -230,7 +268,7 @@ fn test_transport_success<TTransport>(
     expect_formatted_addr: fn(&NetworkAddress),
 ) where
     TTransport: Transport<Error = io::Error> + Clone,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -250,6 +288,8 @@ fn test_transport_success<TTransport>(
         .unwrap();
     expect_formatted_addr(&listener_addr);
     let supported_protocols_clone = supported_protocols.clone();
+    let expected_classes = expected_stream_classes(&listener_addr);
+    let expected_classes_clone = expected_classes.clone();
 
     // we accept the dialer's inbound connection, check the connection metadata,
     // and verify that the upgraded socket actually works (sends and receives
@@ -272,10 +312,16 @@ fn test_transport_success<TTransport>(
             supported_protocols_clone,
         );
 
-        // test the socket works
+        // test the socket and the additional streams work
         let msg = write_read_msg(&mut conn.socket, b"foobar").await;
         assert_eq!(&msg, b"barbaz".as_ref());
+        assert_eq!(stream_classes(&conn), expected_classes_clone);
+        for (_, stream) in conn.streams.iter_mut() {
+            let msg = write_read_msg(stream, b"foobar").await;
+            assert_eq!(&msg, b"barbaz".as_ref());
+        }
         conn.socket.close().await.unwrap();
+        wait_for_remote_close(&mut conn.socket).await;
     };
 
     // dial the listener, check the connection metadata, and verify that the
@@ -298,10 +344,16 @@ fn test_transport_success<TTransport>(
         );
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
-        // test the socket works
+        // test the socket and the additional streams work
         let msg = write_read_msg(&mut conn.socket, b"barbaz").await;
         assert_eq!(&msg, b"foobar".as_ref());
+        assert_eq!(stream_classes(&conn), expected_classes);
+        for (_, stream) in conn.streams.iter_mut() {
+            let msg = write_read_msg(stream, b"barbaz").await;
+            assert_eq!(&msg, b"foobar".as_ref());
+        }
         conn.socket.close().await.unwrap();
+        wait_for_remote_close(&mut conn.socket).await;
     };
 
     rt.block_on(future::join(listener_task, dialer_task));
@@ -313,7 +365,7 @@ fn test_transport_rejects_unauthed_dialer<TTransport>(
     expect_formatted_addr: fn(&NetworkAddress),
 ) where
     TTransport: Transport<Error = io::Error> + Clone,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -372,7 +424,7 @@ fn test_transport_maybe_mutual<TTransport>(
     expect_formatted_addr: fn(&NetworkAddress),
 ) where
     TTransport: Transport<Error = io::Error> + Clone,
-    TTransport::Output: TSocket,
+    TTransport::Output: TSocket + MultiStreamSocket,
     TTransport::Outbound: Send + 'static,
     TTransport::Inbound: Send + 'static,
     TTransport::Listener: Send + 'static,
@@ -587,6 +639,63 @@ fn test_tcp_transport_rejects_unauthed_dialer() {
     );
 }
 
+//////////////////////////////////////
+// AptosNetTransport<QuicTransport> //
+//////////////////////////////////////
+
+#[test]
+fn test_quic_transport_mutual_auth() {
+    test_transport_success(
+        QuicTransport,
+        Auth::Mutual,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_server_only_auth() {
+    test_transport_success(
+        QuicTransport,
+        Auth::ServerOnly,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_rejects_unauthed_dialer() {
+    test_transport_rejects_unauthed_dialer(
+        QuicTransport,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
+////////////////////////////////////////////
+// AptosNetTransport<TcpAndQuicTransport> //
+////////////////////////////////////////////
+
+#[test]
+fn test_tcp_and_quic_transport_over_tcp() {
+    test_transport_success(
+        TcpAndQuicTransport::new(APTOS_TCP_TRANSPORT.clone(), QuicTransport),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/tcp/0",
+        expect_ip4_tcp_noise_addr,
+    );
+}
+
+#[test]
+fn test_tcp_and_quic_transport_over_quic() {
+    test_transport_success(
+        TcpAndQuicTransport::new(APTOS_TCP_TRANSPORT.clone(), QuicTransport),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/quic/0",
+        expect_ip4_quic_noise_addr,
+    );
+}
+
 /// Inserts the given peers into the trusted peer set for the specified network
 fn insert_trusted_peers(
     peers_and_metadata: &Arc<PeersAndMetadata>,
@@ -2,7 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::transport::Transport;
+use crate::transport::{MultiStreamSocket, Transport};
 use aptos_memsocket::{MemoryListener, MemorySocket};
 use aptos_types::{
     network_address::{parse_memory, NetworkAddress, Protocol},
@@ -68,6 +68,8 @@ impl Transport for MemoryTransport {
     }
 }
 
+impl MultiStreamSocket for MemorySocket {}
+
 #[must_use = "streams do nothing unless polled"]
 #[derive(Debug)]
 pub struct Listener {
@@ -13,7 +13,10 @@
 //! [`TransportExt`]: crate::transport::TransportExt
 
 use aptos_types::{network_address::NetworkAddress, PeerId};
-use futures::{future::Future, stream::Stream};
+use futures::{
+    future::{Either, Future},
+    stream::Stream,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -22,7 +25,9 @@ pub mod boxed;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+pub mod quic;
 pub mod tcp;
+pub mod tcp_and_quic;
 
 /// Origin of how a Connection was established.
 #[derive(Clone, Copy, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -115,6 +120,29 @@ pub trait Transport {
         Self: Sized;
 }
 
+/// A socket over which more streams can be opened with the remote peer, e.g., to avoid
+/// head-of-line blocking between different classes of messages.
+pub trait MultiStreamSocket {
+    /// Returns the handle to open and accept more streams, or `None` if the socket only
+    /// supports a single stream.
+    fn streams(&self) -> Option<quic::QuicStreams> {
+        None
+    }
+}
+
+impl<A, B> MultiStreamSocket for Either<A, B>
+where
+    A: MultiStreamSocket,
+    B: MultiStreamSocket,
+{
+    fn streams(&self) -> Option<quic::QuicStreams> {
+        match self {
+            Either::Left(socket) => socket.streams(),
+            Either::Right(socket) => socket.streams(),
+        }
+    }
+}
+
 impl<T: ?Sized> TransportExt for T where T: Transport {}
 
 /// An extension trait for [`Transport`]s that provides a variety of convenient
@@ -0,0 +1,469 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Connections are made over QUIC (using quinn), which avoids the head-of-line blocking and slow
+//! start recovery of TCP on lossy links. Each connection starts with a single bidirectional
+//! stream, over which the rest of the AptosNet stack (i.e., Noise IK and the handshake) runs
+//! exactly as it does over TCP. As such, peers are authenticated by Noise IK with their x25519
+//! network keys, and QUIC's TLS layer only provides transport encryption: its certificates are
+//! self-signed and are not verified.
+//!
+//! Once the connection is upgraded, more streams can be opened with [`QuicStreams`] (e.g., one per
+//! message class), so that the messages of one stream aren't blocked behind the lost packets of
+//! another. Each additional stream starts with a single byte, identifying its class.
+//!
+//! Note: QUIC only announces a stream to the listener once the dialer has written to it. This is
+//! always the case for AptosNet, where the dialer starts the Noise IK handshake.
+use crate::transport::{tcp::resolve_with_filter, MultiStreamSocket, Transport};
+use aptos_types::{
+    network_address::{parse_dns_quic, parse_ip_quic, NetworkAddress, Protocol},
+    PeerId,
+};
+use futures::{
+    future::{BoxFuture, FutureExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    stream::{self, BoxStream, StreamExt},
+};
+use quinn::{
+    crypto::rustls::{QuicClientConfig, QuicServerConfig},
+    ClientConfig, Connection, Endpoint, RecvStream, SendStream, ServerConfig, TransportConfig,
+};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, CryptoProvider},
+    pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime},
+    DigitallySignedStruct, SignatureScheme,
+};
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+/// The server name used for the (unverified) TLS certificates
+const SERVER_NAME: &str = "aptos-node";
+/// The interval at which keep-alive packets are sent, so that idle connections aren't timed out
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Transport to build QUIC connections
+#[derive(Debug, Clone, Default)]
+pub struct QuicTransport;
+
+impl Transport for QuicTransport {
+    type Error = io::Error;
+    type Inbound = BoxFuture<'static, io::Result<QuicSocket>>;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, NetworkAddress)>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicSocket>>;
+    type Output = QuicSocket;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let endpoint = Endpoint::server(server_config()?, SocketAddr::new(ipaddr, port))?;
+        let listen_addr = quic_addr(endpoint.local_addr()?);
+
+        let listener = stream::unfold(endpoint, |endpoint| async move {
+            let incoming = endpoint.accept().await?;
+            let dialer_addr = quic_addr(incoming.remote_address());
+            let inbound_endpoint = endpoint.clone();
+            let inbound = async move {
+                let connection = incoming.await?;
+                let (send, recv) = connection.accept_bi().await?;
+                Ok::<_, io::Error>(QuicSocket::new(send, recv, connection, inbound_endpoint))
+            }
+            .boxed();
+            Some((Ok((inbound, dialer_addr)), endpoint))
+        })
+        .boxed();
+
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let protos = addr.as_slice();
+
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        parse_ip_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(resolve_and_connect(addr).boxed())
+    }
+}
+
+/// Note: we need to take ownership of this `NetworkAddress` (instead of just
+/// borrowing the `&[Protocol]` slice) so this future can be `Send + 'static`.
+async fn resolve_and_connect(addr: NetworkAddress) -> io::Result<QuicSocket> {
+    let protos = addr.as_slice();
+
+    if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_quic(protos) {
+        // this is an /ip4 or /ip6 address, so we can just connect without any
+        // extra resolving or filtering.
+        connect(SocketAddr::new(ipaddr, port)).await
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_quic(protos) {
+        // resolve dns name and filter
+        let socketaddr_iter = resolve_with_filter(ip_filter, dns_name.as_ref(), port).await?;
+        let mut last_err = None;
+
+        // try to connect until the first succeeds
+        for socketaddr in socketaddr_iter {
+            match connect(socketaddr).await {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "could not resolve dns name to any address: name: {}, ip filter: {:?}",
+                    dns_name.as_ref(),
+                    ip_filter,
+                ),
+            )
+        }))
+    } else {
+        Err(invalid_addr_error(&addr))
+    }
+}
+
+/// Connects to the given address from a new client endpoint, and opens the stream
+async fn connect(addr: SocketAddr) -> io::Result<QuicSocket> {
+    let bind_addr = if addr.is_ipv4() {
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+    } else {
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+    };
+    let mut endpoint = Endpoint::client(bind_addr)?;
+    endpoint.set_default_client_config(client_config()?);
+
+    let connection = endpoint
+        .connect(addr, SERVER_NAME)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?
+        .await?;
+    let (send, recv) = connection.open_bi().await?;
+    Ok(QuicSocket::new(send, recv, connection, endpoint))
+}
+
+fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn transport_config() -> Arc<TransportConfig> {
+    let mut transport_config = TransportConfig::default();
+    transport_config.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    Arc::new(transport_config)
+}
+
+/// Creates the server config, with a new self-signed certificate
+fn server_config() -> io::Result<ServerConfig> {
+    let certified_key = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    let cert = certified_key.cert.der().clone();
+    let key = PrivatePkcs8KeyDer::from(certified_key.key_pair.serialize_der());
+
+    let tls_config = rustls::ServerConfig::builder_with_provider(crypto_provider())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .and_then(|builder| {
+            builder
+                .with_no_client_auth()
+                .with_single_cert(vec![cert], key.into())
+        })
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    let quic_config = QuicServerConfig::try_from(tls_config)
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+    let mut server_config = ServerConfig::with_crypto(Arc::new(quic_config));
+    server_config.transport_config(transport_config());
+    Ok(server_config)
+}
+
+/// Creates the client config, which accepts any server certificate (see the module docs)
+fn client_config() -> io::Result<ClientConfig> {
+    let provider = crypto_provider();
+    let tls_config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+        .with_no_client_auth();
+    let quic_config = QuicClientConfig::try_from(tls_config)
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+    let mut client_config = ClientConfig::new(Arc::new(quic_config));
+    client_config.transport_config(transport_config());
+    Ok(client_config)
+}
+
+/// Skips the verification of the server certificate, as peers are authenticated by Noise IK.
+/// The handshake signatures are still verified, so that the TLS handshake itself is sound.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Returns the `"/ip4/<addr>/quic/<port>"` or `"/ip6/<addr>/quic/<port>"` address
+pub(crate) fn quic_addr(addr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::from_protocols(vec![addr.ip().into(), Protocol::Quic(addr.port())])
+        .expect("An ip and quic address should be valid")
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+/// A handle to open (as the dialer) or accept (as the listener) more streams on a QUIC connection
+#[derive(Clone, Debug)]
+pub struct QuicStreams {
+    connection: Connection,
+    endpoint: Endpoint,
+}
+
+impl QuicStreams {
+    /// Opens a new stream of the given class
+    pub async fn open(&self, class: u8) -> io::Result<QuicSocket> {
+        let (send, recv) = self.connection.open_bi().await?;
+        let mut socket =
+            QuicSocket::new(send, recv, self.connection.clone(), self.endpoint.clone());
+        // Writing the class also announces the stream to the listener
+        socket.write_all(&[class]).await?;
+        socket.flush().await?;
+        Ok(socket)
+    }
+
+    /// Accepts the next stream opened by the remote peer, along with its class
+    pub async fn accept(&self) -> io::Result<(u8, QuicSocket)> {
+        let (send, recv) = self.connection.accept_bi().await?;
+        let mut socket =
+            QuicSocket::new(send, recv, self.connection.clone(), self.endpoint.clone());
+        let mut class = [0u8; 1];
+        socket.read_exact(&mut class).await?;
+        Ok((class[0], socket))
+    }
+}
+
+/// A wrapper around a bidirectional stream of a QUIC connection
+///
+/// The connection and endpoint handles are held so that they live as long as the stream. Closing
+/// the socket finishes the send side of the stream.
+#[derive(Debug)]
+pub struct QuicSocket {
+    send: Compat<SendStream>,
+    recv: Compat<RecvStream>,
+    streams: QuicStreams,
+}
+
+impl QuicSocket {
+    fn new(send: SendStream, recv: RecvStream, connection: Connection, endpoint: Endpoint) -> Self {
+        Self {
+            send: send.compat_write(),
+            recv: recv.compat(),
+            streams: QuicStreams {
+                connection,
+                endpoint,
+            },
+        }
+    }
+}
+
+impl MultiStreamSocket for QuicSocket {
+    fn streams(&self) -> Option<QuicStreams> {
+        Some(self.streams.clone())
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_close(context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, Transport, TransportExt};
+    use aptos_types::PeerId;
+    use futures::future::join;
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport.and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                    // Wait for the dialer to close the connection, so that it isn't
+                    // dropped before the dialer has read everything
+                    let _ = out.read(&mut [0; 1]).await;
+                },
+                ConnectionOrigin::Outbound => {
+                    // The dialer writes first, so that the stream is announced
+                    out.write_all(b"Earth").await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                    out.close().await?;
+                },
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())?;
+        let peer_id = PeerId::random();
+        let dial = t.dial(peer_id, addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_and_accept_streams() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport.and_then(|mut out, _addr, origin| async move {
+            let streams = out.streams().unwrap();
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    for expected_class in [1, 2] {
+                        let (class, mut stream) = streams.accept().await?;
+                        assert_eq!(class, expected_class);
+                        stream.read_exact(&mut buf).await?;
+                        assert_eq!(&buf, b"Water");
+                        stream.write_all(&[class]).await?;
+                    }
+                    // Wait for the dialer to close the connection, so that it isn't
+                    // dropped before the dialer has read everything
+                    let _ = out.read(&mut [0; 1]).await;
+                },
+                ConnectionOrigin::Outbound => {
+                    out.write_all(b"Earth").await?;
+                    for class in [1, 2] {
+                        let mut stream = streams.open(class).await?;
+                        stream.write_all(b"Water").await?;
+                        let mut buf = [0; 1];
+                        stream.read_exact(&mut buf).await?;
+                        assert_eq!(buf[0], class);
+                    }
+                    out.close().await?;
+                },
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())?;
+        let dial = t.dial(PeerId::random(), addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport;
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let peer_id = PeerId::random();
+        let result = t.dial(peer_id, "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+        let result = t.dial(peer_id, "/dns/example.com/tcp/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
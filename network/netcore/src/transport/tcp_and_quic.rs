@@ -0,0 +1,173 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! TCP and QUIC Transport
+//!
+//! Combines the [`TcpTransport`] and the [`QuicTransport`], so that a node can both dial and accept
+//! connections over either of them. The transport to dial with is chosen by the dial address
+//! (i.e., `/tcp/<port>` or `/quic/<port>`), and listening on an address also listens on the same
+//! ip and port with the other transport (TCP and UDP ports don't conflict).
+use crate::transport::{
+    quic::{quic_addr, QuicSocket, QuicTransport},
+    tcp::{TcpSocket, TcpTransport},
+    Transport,
+};
+use aptos_types::{
+    network_address::{parse_dns_quic, parse_ip_quic, parse_ip_tcp, NetworkAddress},
+    PeerId,
+};
+use futures::{
+    future::{BoxFuture, Either, FutureExt, TryFutureExt},
+    stream::{self, BoxStream, StreamExt, TryStreamExt},
+};
+use std::{io, net::SocketAddr};
+
+/// The socket of a connection made over either TCP or QUIC
+pub type TcpOrQuicSocket = Either<TcpSocket, QuicSocket>;
+
+/// Transport to build connections over both TCP and QUIC
+#[derive(Debug, Clone, Default)]
+pub struct TcpAndQuicTransport {
+    pub tcp: TcpTransport,
+    pub quic: QuicTransport,
+}
+
+impl TcpAndQuicTransport {
+    pub fn new(tcp: TcpTransport, quic: QuicTransport) -> Self {
+        Self { tcp, quic }
+    }
+
+    fn listen_on_tcp(&self, addr: NetworkAddress) -> io::Result<(Listener, NetworkAddress)> {
+        let (listener, listen_addr) = self.tcp.listen_on(addr)?;
+        let listener = listener
+            .map_ok(|(inbound, dialer_addr)| (inbound.map_ok(Either::Left).boxed(), dialer_addr))
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn listen_on_quic(&self, addr: NetworkAddress) -> io::Result<(Listener, NetworkAddress)> {
+        let (listener, listen_addr) = self.quic.listen_on(addr)?;
+        let listener = listener
+            .map_ok(|(inbound, dialer_addr)| (inbound.map_ok(Either::Right).boxed(), dialer_addr))
+            .boxed();
+        Ok((listener, listen_addr))
+    }
+}
+
+type Inbound = BoxFuture<'static, io::Result<TcpOrQuicSocket>>;
+type Listener = BoxStream<'static, io::Result<(Inbound, NetworkAddress)>>;
+
+impl Transport for TcpAndQuicTransport {
+    type Error = io::Error;
+    type Inbound = Inbound;
+    type Listener = Listener;
+    type Outbound = BoxFuture<'static, io::Result<TcpOrQuicSocket>>;
+    type Output = TcpOrQuicSocket;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        // Listen on the requested transport first (as its port may be assigned by the OS),
+        // and then on the other transport, with the same ip and port.
+        let protos = addr.as_slice();
+        let (tcp_listener, quic_listener, listen_addr) =
+            if let Some(((ipaddr, _), _)) = parse_ip_tcp(protos) {
+                let (tcp_listener, listen_addr) = self.listen_on_tcp(addr.clone())?;
+                let port = listen_port(&listen_addr)?;
+                let (quic_listener, _) =
+                    self.listen_on_quic(quic_addr(SocketAddr::new(ipaddr, port)))?;
+                (tcp_listener, quic_listener, listen_addr)
+            } else if let Some(((ipaddr, _), _)) = parse_ip_quic(protos) {
+                let (quic_listener, listen_addr) = self.listen_on_quic(addr.clone())?;
+                let port = listen_port(&listen_addr)?;
+                let (tcp_listener, _) =
+                    self.listen_on_tcp(NetworkAddress::from(SocketAddr::new(ipaddr, port)))?;
+                (tcp_listener, quic_listener, listen_addr)
+            } else {
+                return Err(invalid_addr_error(&addr));
+            };
+
+        let listener = stream::select(tcp_listener, quic_listener).boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let protos = addr.as_slice();
+        if parse_ip_quic(protos).is_some() || parse_dns_quic(protos).is_some() {
+            let outbound = self.quic.dial(peer_id, addr)?;
+            Ok(outbound.map_ok(Either::Right).boxed())
+        } else {
+            let outbound = self.tcp.dial(peer_id, addr)?;
+            Ok(outbound.map_ok(Either::Left).boxed())
+        }
+    }
+}
+
+/// Returns the port of an ip+tcp or ip+quic listening address
+fn listen_port(addr: &NetworkAddress) -> io::Result<u16> {
+    let protos = addr.as_slice();
+    parse_ip_tcp(protos)
+        .or_else(|| parse_ip_quic(protos))
+        .map(|((_, port), _)| port)
+        .ok_or_else(|| invalid_addr_error(addr))
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, TransportExt};
+    use futures::{
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    #[tokio::test]
+    async fn dial_tcp_and_quic() -> Result<(), ::std::io::Error> {
+        let t = TcpAndQuicTransport::default().and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                    // Wait for the dialer to close the connection, so that it isn't
+                    // dropped before the dialer has read everything
+                    let _ = out.read(&mut [0; 1]).await;
+                },
+                ConnectionOrigin::Outbound => {
+                    out.write_all(b"Earth").await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                    out.close().await?;
+                },
+            }
+            Ok(matches!(out, Either::Right(_)))
+        });
+
+        let (mut listener, tcp_addr) = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())?;
+        let port = listen_port(&tcp_addr)?;
+        let quic_listen_addr: NetworkAddress =
+            format!("/ip4/127.0.0.1/quic/{}", port).parse().unwrap();
+
+        for (addr, is_quic) in [(tcp_addr, false), (quic_listen_addr, true)] {
+            let dial = t.dial(PeerId::random(), addr)?;
+            let accept = async {
+                let (inbound, _addr) = listener.next().await.unwrap().unwrap();
+                inbound.await.unwrap()
+            };
+            let (outbound, inbound) = join(dial, accept).await;
+            assert_eq!(outbound?, is_quic);
+            assert_eq!(inbound, is_quic);
+        }
+        Ok(())
+    }
+}
@@ -27,7 +27,7 @@ use aptos_id_generator::{IdGenerator, U64IdGenerator};
 use aptos_infallible::Mutex;
 use aptos_logger::{info, sample, sample::SampleRate, trace, warn};
 use aptos_network::{
-    application::{interface::NetworkClient, storage::PeersAndMetadata},
+    application::{interface::NetworkClient, reputation::PeerPenalty, storage::PeersAndMetadata},
     protocols::network::RpcError,
 };
 use aptos_storage_interface::DbReader;
@@ -862,6 +862,13 @@ impl AptosDataClient {
         _request: &StorageServiceRequest,
         error_type: ErrorType,
     ) {
+        // Malicious responses (i.e., invalid proofs) also count against
+        // the reputation of the peer in the network layer.
+        if matches!(error_type, ErrorType::Malicious) {
+            self.get_peers_and_metadata()
+                .report_peer_penalty(peer, PeerPenalty::InvalidProof);
+        }
+
         self.peer_states.update_score_error(peer, error_type);
     }
 
@@ -0,0 +1,378 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    error::Error,
+    logging::{LogEntry, LogSchema},
+};
+use aptos_backup_cli::{
+    backup_types::{
+        state_snapshot::{
+            manifest::{StateSnapshotBackup, StateSnapshotChunk},
+            restore::StateSnapshotRestoreController,
+        },
+        transaction::{manifest::TransactionBackup, restore::LoadedChunk},
+    },
+    metadata::{
+        cache::{sync_and_load, MetadataCacheOpt},
+        view::MetadataView,
+    },
+    storage::{BackupStorage, DBToolStorageOpt},
+    utils::storage_ext::BackupStorageExt,
+};
+use aptos_config::config::BackupBootstrappingConfig;
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_data_streaming_service::{
+    data_notification::{DataNotification, DataPayload, NotificationId},
+    data_stream::{DataStreamId, DataStreamListener},
+};
+use aptos_logger::prelude::*;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::TransactionInfoWithProof,
+    state_store::state_value::StateValueChunkWithProof,
+    transaction::{
+        TransactionAuxiliaryData, TransactionInfoListWithProof, TransactionOutput,
+        TransactionOutputListWithProof, Version,
+    },
+};
+use futures::{channel::mpsc, stream, SinkExt, StreamExt};
+use std::sync::Arc;
+
+// The number of notifications buffered by the data streams
+const DATA_STREAM_CHANNEL_SIZE: usize = 2;
+
+/// The backup storage and its metadata
+struct Backups {
+    storage: Arc<dyn BackupStorage>,
+    metadata_view: MetadataView,
+}
+
+/// A data source that serves the state snapshot (and the transaction
+/// output at the snapshot version) from a backup storage, instead of
+/// the network. The data is served as data streams, so that it can be
+/// verified and processed exactly like the data streamed from peers.
+pub struct BackupDataSource {
+    // The config of the backup storage
+    config: BackupBootstrappingConfig,
+
+    // The backup storage and metadata (loaded lazily on first use)
+    backups: Option<Arc<Backups>>,
+
+    // The ID of the next data stream to create
+    next_data_stream_id: DataStreamId,
+}
+
+impl BackupDataSource {
+    /// Returns a new backup data source, or None if no backup storage is configured
+    pub fn new(config: BackupBootstrappingConfig) -> Option<Self> {
+        if config.backup_storage.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            config,
+            backups: None,
+            next_data_stream_id: 0,
+        })
+    }
+
+    /// Returns the version of the latest state snapshot in the backups
+    /// that is not higher than the given version (if any).
+    pub async fn latest_state_snapshot_version(
+        &mut self,
+        max_version: Version,
+    ) -> Result<Option<Version>, Error> {
+        let backups = self.get_backups().await?;
+        let state_snapshot = backups
+            .metadata_view
+            .select_state_snapshot(max_version)
+            .map_err(backup_storage_error)?;
+        Ok(state_snapshot.map(|state_snapshot| state_snapshot.version))
+    }
+
+    /// Returns true iff the backups contain a state snapshot at the given version
+    pub async fn has_state_snapshot(&mut self, version: Version) -> Result<bool, Error> {
+        let backups = self.get_backups().await?;
+        Ok(backups.metadata_view.expect_state_snapshot(version).is_ok())
+    }
+
+    /// Returns a data stream holding the transaction output at the given
+    /// version, with a proof against the ledger info of the state snapshot.
+    pub async fn get_transaction_output(
+        &mut self,
+        version: Version,
+    ) -> Result<DataStreamListener, Error> {
+        let backups = self.get_backups().await?;
+        let (transaction_info_with_proof, _) = load_state_snapshot_proof(&backups, version).await?;
+        let transaction_output =
+            load_transaction_output(&backups, version, transaction_info_with_proof).await?;
+
+        let (mut notification_sender, data_stream) = self.create_data_stream();
+        notification_sender
+            .send(DataNotification::new(
+                0,
+                DataPayload::TransactionOutputsWithProof(transaction_output),
+            ))
+            .await?;
+        notification_sender
+            .send(DataNotification::new(1, DataPayload::EndOfStream))
+            .await?;
+        Ok(data_stream)
+    }
+
+    /// Returns a data stream of the state values in the snapshot at the
+    /// given version, starting at `start_index`. Chunks are downloaded
+    /// concurrently in the background. If a download fails, the stream is
+    /// closed without an end of stream notification, so that the
+    /// bootstrapper resets it and falls back to the network right away.
+    pub async fn get_state_values(
+        &mut self,
+        version: Version,
+        start_index: u64,
+    ) -> Result<DataStreamListener, Error> {
+        let backups = self.get_backups().await?;
+        let manifest = load_state_snapshot_manifest(&backups, version).await?;
+        let root_hash = manifest.root_hash;
+        let chunks: Vec<_> = manifest
+            .chunks
+            .into_iter()
+            .filter(|chunk| chunk.last_idx as u64 >= start_index)
+            .collect();
+
+        let (mut notification_sender, data_stream) = self.create_data_stream();
+        let max_concurrent_downloads = self.config.max_concurrent_downloads as usize;
+        tokio::spawn(async move {
+            let mut state_value_chunks = stream::iter(chunks)
+                .map(|chunk| {
+                    let backups = backups.clone();
+                    async move { load_state_value_chunk(&backups, chunk, root_hash).await }
+                })
+                .buffered(max_concurrent_downloads);
+
+            let mut notification_id: NotificationId = 0;
+            while let Some(state_value_chunk) = state_value_chunks.next().await {
+                let state_value_chunk = match state_value_chunk {
+                    Ok(state_value_chunk) => {
+                        slice_state_value_chunk(state_value_chunk, start_index)
+                    },
+                    Err(error) => {
+                        error!(LogSchema::new(LogEntry::BackupDataSource)
+                            .error(&error)
+                            .message("Failed to load the state values from the backups!"));
+                        notification_sender.close_channel();
+                        return;
+                    },
+                };
+                let data_notification = DataNotification::new(
+                    notification_id,
+                    DataPayload::StateValuesWithProof(state_value_chunk),
+                );
+                if notification_sender.send(data_notification).await.is_err() {
+                    return; // The stream has been dropped
+                }
+                notification_id += 1;
+            }
+
+            let _ = notification_sender
+                .send(DataNotification::new(
+                    notification_id,
+                    DataPayload::EndOfStream,
+                ))
+                .await;
+        });
+
+        Ok(data_stream)
+    }
+
+    /// Creates a new data stream and returns it with its notification sender
+    fn create_data_stream(&mut self) -> (mpsc::Sender<DataNotification>, DataStreamListener) {
+        let data_stream_id = self.next_data_stream_id;
+        self.next_data_stream_id += 1;
+
+        let (notification_sender, notification_receiver) = mpsc::channel(DATA_STREAM_CHANNEL_SIZE);
+        let data_stream = DataStreamListener::new(data_stream_id, notification_receiver);
+        (notification_sender, data_stream)
+    }
+
+    /// Returns the backup storage and metadata, loading them if required
+    async fn get_backups(&mut self) -> Result<Arc<Backups>, Error> {
+        if let Some(backups) = &self.backups {
+            return Ok(backups.clone());
+        }
+
+        let backup_storage = self
+            .config
+            .backup_storage
+            .as_ref()
+            .ok_or_else(|| Error::UnexpectedError("No backup storage is configured!".into()))?;
+        info!(LogSchema::new(LogEntry::BackupDataSource).message(&format!(
+            "Loading the backup metadata from the backup storage: {:?}",
+            backup_storage
+        )));
+        let storage = DBToolStorageOpt::from_config(
+            backup_storage,
+            self.config.backup_encryption_key_file.clone(),
+        )
+        .init_storage()
+        .await
+        .map_err(backup_storage_error)?;
+        let metadata_view = sync_and_load(
+            &MetadataCacheOpt::new(self.config.metadata_cache_dir.as_ref()),
+            storage.clone(),
+            self.config.max_concurrent_downloads as usize,
+        )
+        .await
+        .map_err(backup_storage_error)?;
+
+        let backups = Arc::new(Backups {
+            storage,
+            metadata_view,
+        });
+        self.backups = Some(backups.clone());
+        Ok(backups)
+    }
+}
+
+/// Loads the manifest of the state snapshot at the given version
+async fn load_state_snapshot_manifest(
+    backups: &Backups,
+    version: Version,
+) -> Result<StateSnapshotBackup, Error> {
+    let state_snapshot = backups
+        .metadata_view
+        .expect_state_snapshot(version)
+        .map_err(backup_storage_error)?;
+    backups
+        .storage
+        .load_json_file(&state_snapshot.manifest)
+        .await
+        .map_err(backup_storage_error)
+}
+
+/// Loads the proof of the state snapshot at the given version
+async fn load_state_snapshot_proof(
+    backups: &Backups,
+    version: Version,
+) -> Result<(TransactionInfoWithProof, LedgerInfoWithSignatures), Error> {
+    let manifest = load_state_snapshot_manifest(backups, version).await?;
+    backups
+        .storage
+        .load_bcs_file(&manifest.proof)
+        .await
+        .map_err(backup_storage_error)
+}
+
+/// Loads the transaction output at the given version from the transaction
+/// backups, and proves it with the given transaction info and proof.
+async fn load_transaction_output(
+    backups: &Backups,
+    version: Version,
+    transaction_info_with_proof: TransactionInfoWithProof,
+) -> Result<TransactionOutputListWithProof, Error> {
+    let transaction_backup = backups
+        .metadata_view
+        .select_transaction_backups(version, version)
+        .map_err(backup_storage_error)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            Error::BackupStorageError(format!(
+                "No transaction backup holds the transaction at version {}!",
+                version
+            ))
+        })?;
+    let manifest: TransactionBackup = backups
+        .storage
+        .load_json_file(&transaction_backup.manifest)
+        .await
+        .map_err(backup_storage_error)?;
+    let chunk = manifest
+        .chunks
+        .into_iter()
+        .find(|chunk| chunk.first_version <= version && version <= chunk.last_version)
+        .ok_or_else(|| {
+            Error::BackupStorageError(format!(
+                "No transaction chunk holds the transaction at version {}!",
+                version
+            ))
+        })?;
+    let first_version = chunk.first_version;
+    let mut loaded_chunk = LoadedChunk::load(chunk, &backups.storage, None)
+        .await
+        .map_err(backup_storage_error)?;
+
+    // Identify the transaction in the chunk
+    let index = (version - first_version) as usize;
+    let transaction = loaded_chunk.txns.swap_remove(index);
+    let events = loaded_chunk.event_vecs.swap_remove(index);
+    let write_set = loaded_chunk.write_sets.swap_remove(index);
+
+    // Create the transaction output with a proof against the snapshot ledger info
+    let transaction_info = transaction_info_with_proof.transaction_info().clone();
+    let transaction_output = TransactionOutput::new(
+        write_set,
+        events,
+        transaction_info.gas_used(),
+        transaction_info.status().clone().into(),
+        TransactionAuxiliaryData::default(),
+    );
+    let proof = TransactionInfoListWithProof::new(
+        transaction_info_with_proof
+            .ledger_info_to_transaction_info_proof()
+            .to_range_proof(version),
+        vec![transaction_info],
+    );
+    Ok(TransactionOutputListWithProof::new(
+        vec![(transaction, transaction_output)],
+        Some(version),
+        proof,
+    ))
+}
+
+/// Loads the state values and proof of the given state snapshot chunk
+async fn load_state_value_chunk(
+    backups: &Backups,
+    chunk: StateSnapshotChunk,
+    root_hash: HashValue,
+) -> Result<StateValueChunkWithProof, Error> {
+    let raw_values =
+        StateSnapshotRestoreController::read_state_value(&backups.storage, chunk.blobs)
+            .await
+            .map_err(backup_storage_error)?;
+    let proof = backups
+        .storage
+        .load_bcs_file(&chunk.proof)
+        .await
+        .map_err(backup_storage_error)?;
+    Ok(StateValueChunkWithProof {
+        first_index: chunk.first_idx as u64,
+        last_index: chunk.last_idx as u64,
+        first_key: chunk.first_key,
+        last_key: chunk.last_key,
+        raw_values,
+        proof,
+        root_hash,
+    })
+}
+
+/// Drops the state values before `start_index` from the given chunk. The
+/// proof of the chunk remains valid, as it only proves the last key.
+fn slice_state_value_chunk(
+    mut state_value_chunk: StateValueChunkWithProof,
+    start_index: u64,
+) -> StateValueChunkWithProof {
+    if state_value_chunk.first_index < start_index {
+        let num_values_to_skip = (start_index - state_value_chunk.first_index) as usize;
+        state_value_chunk.raw_values.drain(..num_values_to_skip);
+        if let Some((state_key, _)) = state_value_chunk.raw_values.first() {
+            state_value_chunk.first_key = state_key.hash();
+        }
+        state_value_chunk.first_index = start_index;
+    }
+    state_value_chunk
+}
+
+fn backup_storage_error(error: anyhow::Error) -> Error {
+    Error::BackupStorageError(format!("{:#}", error))
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    backup_data_source::BackupDataSource,
     driver::DriverConfiguration,
     error::Error,
     logging::{LogEntry, LogSchema},
@@ -291,6 +292,12 @@ pub struct Bootstrapper<MetadataStorage, StorageSyncer, StreamingClient> {
     // The currently active data stream (provided by the data streaming service)
     active_data_stream: Option<DataStreamListener>,
 
+    // If the active data stream is provided by the backup data source
+    active_stream_from_backup: bool,
+
+    // The backup storage to fetch the state snapshot from (if one is configured)
+    backup_data_source: Option<BackupDataSource>,
+
     // The channel used to notify a listener of successful bootstrapping
     bootstrap_notifier_channel: Option<oneshot::Sender<Result<(), Error>>>,
 
@@ -344,9 +351,15 @@ impl<
             .expect("Unable to fetch latest epoch state!");
         let verified_epoch_states = VerifiedEpochStates::new(latest_epoch_state);
 
+        // Create the backup data source (if a backup storage is configured)
+        let backup_data_source =
+            BackupDataSource::new(driver_configuration.backup_bootstrapping_config.clone());
+
         Self {
             state_value_syncer: StateValueSyncer::new(),
             active_data_stream: None,
+            active_stream_from_backup: false,
+            backup_data_source,
             bootstrap_notifier_channel: None,
             bootstrapped: false,
             driver_configuration,
@@ -543,9 +556,12 @@ impl<
                     self.fetch_missing_state_values(target, true).await
                 }
             } else {
-                // No snapshot sync has started. Start a new sync for the highest known ledger info.
-                self.fetch_missing_state_values(highest_known_ledger_info, false)
-                    .await
+                // No snapshot sync has started. Start a new sync for the highest known
+                // ledger info (or the latest state snapshot in the backup storage).
+                let target = self
+                    .get_state_snapshot_sync_target(highest_known_ledger_info)
+                    .await?;
+                self.fetch_missing_state_values(target, false).await
             }
         } else {
             // This node has already synced some state. Ensure the node is not too far behind.
@@ -578,6 +594,79 @@ impl<
         }
     }
 
+    /// Returns the target ledger info for a new state snapshot sync. If a backup
+    /// storage is configured, this is the ledger info of the latest state snapshot
+    /// in the backups (as long as it's epoch ending, and thus already verified).
+    /// Otherwise, this is the given highest known ledger info.
+    async fn get_state_snapshot_sync_target(
+        &mut self,
+        highest_known_ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<LedgerInfoWithSignatures, Error> {
+        let backup_data_source = match self.backup_data_source.as_mut() {
+            Some(backup_data_source) => backup_data_source,
+            None => return Ok(highest_known_ledger_info),
+        };
+
+        // Identify the latest state snapshot in the backups
+        let highest_known_ledger_version = highest_known_ledger_info.ledger_info().version();
+        let snapshot_version = match backup_data_source
+            .latest_state_snapshot_version(highest_known_ledger_version)
+            .await
+        {
+            Ok(Some(snapshot_version)) => snapshot_version,
+            Ok(None) => {
+                warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "No state snapshot was found in the backups at or below version {}! \
+                    Falling back to the network.",
+                    highest_known_ledger_version
+                )));
+                return Ok(highest_known_ledger_info);
+            },
+            Err(error) => {
+                self.disable_backup_data_source(error);
+                return Ok(highest_known_ledger_info);
+            },
+        };
+
+        // Verify the state snapshot is recent enough for the node to catch up afterwards
+        let num_versions_behind = highest_known_ledger_version - snapshot_version;
+        if num_versions_behind
+            >= self
+                .driver_configuration
+                .config
+                .num_versions_to_skip_snapshot_sync
+        {
+            warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                "The latest state snapshot in the backups is {} versions behind! \
+                Falling back to the network.",
+                num_versions_behind
+            )));
+            return Ok(highest_known_ledger_info);
+        }
+
+        // Fetch the verified ledger info for the state snapshot
+        match self
+            .verified_epoch_states
+            .get_epoch_ending_ledger_info(snapshot_version)
+        {
+            Some(epoch_ending_ledger_info) => {
+                info!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "Found a state snapshot in the backups at version {}!",
+                    snapshot_version
+                )));
+                Ok(epoch_ending_ledger_info)
+            },
+            None => {
+                warn!(LogSchema::new(LogEntry::Bootstrapper).message(&format!(
+                    "The state snapshot in the backups at version {} is not epoch ending! \
+                    Falling back to the network.",
+                    snapshot_version
+                )));
+                Ok(highest_known_ledger_info)
+            },
+        }
+    }
+
     /// Attempts to fetch a data notification from the active stream
     async fn fetch_next_data_notification(&mut self) -> Result<DataNotification, Error> {
         let max_stream_wait_time_ms = self.driver_configuration.config.max_stream_wait_time_ms;
@@ -588,10 +677,18 @@ impl<
             self.active_data_stream.as_mut(),
         )
         .await;
-        if matches!(result, Err(Error::CriticalDataStreamTimeout(_))) {
-            // If the stream has timed out too many times, we need to reset it
-            warn!("Resetting the currently active data stream due to too many timeouts!");
-            self.reset_active_stream(None).await?;
+        match result {
+            Err(Error::CriticalDataStreamTimeout(_)) => {
+                // If the stream has timed out too many times, we need to reset it
+                warn!("Resetting the currently active data stream due to too many timeouts!");
+                self.reset_active_stream(None).await?;
+            },
+            Err(Error::DataStreamClosed(_)) => {
+                // If the stream was closed, it will never make progress
+                warn!("Resetting the currently active data stream as it was closed!");
+                self.reset_active_stream(None).await?;
+            },
+            _ => {},
         }
         result
     }
@@ -681,10 +778,36 @@ impl<
                 .set_ledger_info_to_sync(target_ledger_info.clone());
         }
 
-        // Fetch the data that we're missing
+        // Check if the data can be fetched from the backup storage
         let target_ledger_info_version = target_ledger_info.ledger_info().version();
+        let fetch_from_backup = match self.backup_data_source.as_mut() {
+            Some(backup_data_source) => match backup_data_source
+                .has_state_snapshot(target_ledger_info_version)
+                .await
+            {
+                Ok(has_state_snapshot) => has_state_snapshot,
+                Err(error) => {
+                    self.disable_backup_data_source(error);
+                    false
+                },
+            },
+            None => false,
+        };
+
+        // Fetch the data that we're missing
         let data_stream = if self.state_value_syncer.transaction_output_to_sync.is_none() {
             // Fetch the transaction info first, before the states
+            if let Some(backup_data_source) = self.backup_data_source.as_mut() {
+                if fetch_from_backup {
+                    match backup_data_source
+                        .get_transaction_output(target_ledger_info_version)
+                        .await
+                    {
+                        Ok(data_stream) => return self.set_active_stream_from_backup(data_stream),
+                        Err(error) => self.disable_backup_data_source(error),
+                    }
+                }
+            }
             self.streaming_client
                 .get_all_transaction_outputs(
                     target_ledger_info_version,
@@ -715,6 +838,17 @@ impl<
             // Fetch the missing state values
             self.state_value_syncer
                 .update_next_state_index_to_process(next_state_index_to_process);
+            if let Some(backup_data_source) = self.backup_data_source.as_mut() {
+                if fetch_from_backup {
+                    match backup_data_source
+                        .get_state_values(target_ledger_info_version, next_state_index_to_process)
+                        .await
+                    {
+                        Ok(data_stream) => return self.set_active_stream_from_backup(data_stream),
+                        Err(error) => self.disable_backup_data_source(error),
+                    }
+                }
+            }
             self.streaming_client
                 .get_all_state_values(
                     target_ledger_info_version,
@@ -727,6 +861,26 @@ impl<
         Ok(())
     }
 
+    /// Drops the backup data source after it failed to serve data, so that
+    /// the rest of the sync falls back to the network (instead of retrying
+    /// the backup storage on every cycle).
+    fn disable_backup_data_source(&mut self, error: Error) {
+        warn!(LogSchema::new(LogEntry::Bootstrapper)
+            .error(&error)
+            .message("Failed to read from the backup storage! Falling back to the network."));
+        self.backup_data_source = None;
+    }
+
+    /// Sets the active data stream to the given stream from the backup data source
+    fn set_active_stream_from_backup(
+        &mut self,
+        data_stream: DataStreamListener,
+    ) -> Result<(), Error> {
+        self.active_data_stream = Some(data_stream);
+        self.active_stream_from_backup = true;
+        Ok(())
+    }
+
     /// Fetches all missing transaction data in order to bootstrap the node
     async fn fetch_missing_transaction_data(
         &mut self,
@@ -1531,7 +1685,23 @@ impl<
         &mut self,
         notification_and_feedback: Option<NotificationAndFeedback>,
     ) -> Result<(), Error> {
-        if let Some(active_data_stream) = &self.active_data_stream {
+        if self.active_stream_from_backup {
+            // Streams from the backup data source are simply dropped. If the backups
+            // served invalid data, or the stream was closed (e.g., because a download
+            // failed) or timed out, fall back to the network for the rest of the sync.
+            let backup_stream_failed = notification_and_feedback
+                .map(|notification_and_feedback| {
+                    notification_and_feedback.notification_feedback
+                        != NotificationFeedback::EndOfStream
+                })
+                .unwrap_or_else(|| !self.is_bootstrapped());
+            if backup_stream_failed && self.backup_data_source.is_some() {
+                warn!(LogSchema::new(LogEntry::Bootstrapper).message(
+                    "The backup storage failed to serve valid data! Falling back to the network."
+                ));
+                self.backup_data_source = None;
+            }
+        } else if let Some(active_data_stream) = &self.active_data_stream {
             let data_stream_id = active_data_stream.data_stream_id;
             utils::terminate_stream_with_feedback(
                 &mut self.streaming_client,
@@ -1542,6 +1712,7 @@ impl<
         }
 
         self.active_data_stream = None;
+        self.active_stream_from_backup = false;
         self.speculative_stream_state = None;
         Ok(())
     }
@@ -1558,6 +1729,12 @@ impl<
         &mut self.state_value_syncer
     }
 
+    /// Returns true iff the bootstrapper still reads from the backup storage
+    #[cfg(test)]
+    pub(crate) fn has_backup_data_source(&self) -> bool {
+        self.backup_data_source.is_some()
+    }
+
     /// Manually sets the waypoint for testing purposes
     #[cfg(test)]
     pub(crate) fn set_waypoint(&mut self, waypoint: Waypoint) {
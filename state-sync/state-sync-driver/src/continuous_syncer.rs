@@ -190,10 +190,18 @@ impl<
             self.active_data_stream.as_mut(),
         )
         .await;
-        if matches!(result, Err(Error::CriticalDataStreamTimeout(_))) {
-            // If the stream has timed out too many times, we need to reset it
-            warn!("Resetting the currently active data stream due to too many timeouts!");
-            self.reset_active_stream(None).await?;
+        match result {
+            Err(Error::CriticalDataStreamTimeout(_)) => {
+                // If the stream has timed out too many times, we need to reset it
+                warn!("Resetting the currently active data stream due to too many timeouts!");
+                self.reset_active_stream(None).await?;
+            },
+            Err(Error::DataStreamClosed(_)) => {
+                // If the stream was closed, it will never make progress
+                warn!("Resetting the currently active data stream as it was closed!");
+                self.reset_active_stream(None).await?;
+            },
+            _ => {},
         }
         result
     }
@@ -20,7 +20,9 @@ use crate::{
     utils,
     utils::{OutputFallbackHandler, PENDING_DATA_LOG_FREQ_SECS},
 };
-use aptos_config::config::{ConsensusObserverConfig, RoleType, StateSyncDriverConfig};
+use aptos_config::config::{
+    BackupBootstrappingConfig, ConsensusObserverConfig, RoleType, StateSyncDriverConfig,
+};
 use aptos_consensus_notifications::{
     ConsensusCommitNotification, ConsensusNotification, ConsensusSyncNotification,
 };
@@ -53,6 +55,9 @@ pub struct DriverConfiguration {
     // The config file of the driver
     pub config: StateSyncDriverConfig,
 
+    // The config for bootstrapping from a backup storage
+    pub backup_bootstrapping_config: BackupBootstrappingConfig,
+
     // The config for consensus observer
     pub consensus_observer_config: ConsensusObserverConfig,
 
@@ -66,12 +71,14 @@ pub struct DriverConfiguration {
 impl DriverConfiguration {
     pub fn new(
         config: StateSyncDriverConfig,
+        backup_bootstrapping_config: BackupBootstrappingConfig,
         consensus_observer_config: ConsensusObserverConfig,
         role: RoleType,
         waypoint: Waypoint,
     ) -> Self {
         Self {
             config,
+            backup_bootstrapping_config,
             consensus_observer_config,
             role,
             waypoint,
@@ -0,0 +1,84 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{backup_data_source::BackupDataSource, error::Error};
+use aptos_config::config::{BackupBootstrappingConfig, BackupStorageConfig};
+use aptos_temppath::TempPath;
+use claims::{assert_matches, assert_none};
+use std::path::PathBuf;
+
+#[test]
+fn test_no_backup_storage() {
+    // Verify no data source is created without a backup storage
+    assert!(BackupDataSource::new(BackupBootstrappingConfig::default()).is_none());
+}
+
+#[tokio::test]
+async fn test_empty_backup_storage() {
+    // Create a data source for an empty local backup storage
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let metadata_cache_dir = TempPath::new();
+    let mut backup_data_source = create_backup_data_source(
+        BackupStorageConfig::LocalFs(backup_dir.path().to_path_buf()),
+        metadata_cache_dir.path().to_path_buf(),
+    );
+
+    // Verify the backups hold no state snapshot
+    assert_none!(backup_data_source
+        .latest_state_snapshot_version(100)
+        .await
+        .unwrap());
+    assert!(!backup_data_source.has_state_snapshot(100).await.unwrap());
+
+    // Verify the data can't be streamed
+    assert_matches!(
+        backup_data_source.get_transaction_output(100).await,
+        Err(Error::BackupStorageError(_))
+    );
+    assert_matches!(
+        backup_data_source.get_state_values(100, 0).await,
+        Err(Error::BackupStorageError(_))
+    );
+}
+
+#[tokio::test]
+async fn test_unreadable_backup_storage() {
+    // Create a data source for a command adapter storage with a missing config file
+    let metadata_cache_dir = TempPath::new();
+    let mut backup_data_source = create_backup_data_source(
+        BackupStorageConfig::CommandAdapter(PathBuf::from("/missing/command_adapter_config.yaml")),
+        metadata_cache_dir.path().to_path_buf(),
+    );
+
+    // Verify all requests fail with a backup storage error
+    assert_matches!(
+        backup_data_source.latest_state_snapshot_version(100).await,
+        Err(Error::BackupStorageError(_))
+    );
+    assert_matches!(
+        backup_data_source.has_state_snapshot(100).await,
+        Err(Error::BackupStorageError(_))
+    );
+    assert_matches!(
+        backup_data_source.get_transaction_output(100).await,
+        Err(Error::BackupStorageError(_))
+    );
+    assert_matches!(
+        backup_data_source.get_state_values(100, 0).await,
+        Err(Error::BackupStorageError(_))
+    );
+}
+
+/// Creates a backup data source for the given storage and metadata cache
+fn create_backup_data_source(
+    backup_storage: BackupStorageConfig,
+    metadata_cache_dir: PathBuf,
+) -> BackupDataSource {
+    let config = BackupBootstrappingConfig {
+        backup_storage: Some(backup_storage),
+        metadata_cache_dir: Some(metadata_cache_dir),
+        ..Default::default()
+    };
+    BackupDataSource::new(config).unwrap()
+}
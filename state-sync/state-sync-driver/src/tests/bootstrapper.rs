@@ -20,7 +20,7 @@ use crate::{
     },
     utils::OutputFallbackHandler,
 };
-use aptos_config::config::BootstrappingMode;
+use aptos_config::config::{BackupStorageConfig, BootstrappingMode};
 use aptos_data_client::global_summary::GlobalDataSummary;
 use aptos_data_streaming_service::{
     data_notification::{DataNotification, DataPayload, NotificationId},
@@ -34,7 +34,7 @@ use aptos_types::{
 use claims::{assert_matches, assert_none, assert_ok};
 use futures::{channel::oneshot, FutureExt, SinkExt};
 use mockall::{predicate::eq, Sequence};
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 #[tokio::test]
 async fn test_bootstrap_genesis_waypoint() {
@@ -342,6 +342,62 @@ async fn test_critical_timeout() {
     assert_matches!(error, Error::DataStreamNotificationTimeout(_));
 }
 
+#[tokio::test]
+async fn test_data_stream_closed() {
+    // Create a driver configuration with a genesis waypoint and a stream timeout of 1 second
+    let mut driver_configuration = create_full_node_driver_configuration();
+    driver_configuration.config.max_stream_wait_time_ms = 1000;
+
+    // Create the mock streaming client
+    let mut mock_streaming_client = create_mock_streaming_client();
+    let mut expectation_sequence = Sequence::new();
+    let (notification_sender_1, data_stream_listener_1) = create_data_stream_listener();
+    let (_notification_sender_2, data_stream_listener_2) = create_data_stream_listener();
+    let data_stream_id_1 = data_stream_listener_1.data_stream_id;
+    for data_stream_listener in [data_stream_listener_1, data_stream_listener_2] {
+        mock_streaming_client
+            .expect_get_all_epoch_ending_ledger_infos()
+            .times(1)
+            .with(eq(1))
+            .return_once(move |_| Ok(data_stream_listener))
+            .in_sequence(&mut expectation_sequence);
+    }
+    mock_streaming_client
+        .expect_terminate_stream_with_feedback()
+        .with(eq(data_stream_id_1), eq(None))
+        .return_const(Ok(()));
+
+    // Create the bootstrapper
+    let (mut bootstrapper, _) =
+        create_bootstrapper(driver_configuration, mock_streaming_client, None, true);
+
+    // Create a global data summary where epoch 0 and 1 have ended
+    let global_data_summary = create_global_summary(1);
+
+    // Drive progress to initialize the epoch ending data stream
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+
+    // Close the data stream and verify the stream is reset right away
+    drop(notification_sender_1);
+    let error = drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap_err();
+    assert_matches!(error, Error::DataStreamClosed(_));
+
+    // Drive progress to initialize the epoch ending data stream again
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+
+    // Drive progress again and verify we get a non-critical timeout
+    let error = drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap_err();
+    assert_matches!(error, Error::DataStreamNotificationTimeout(_));
+}
+
 #[tokio::test]
 async fn test_data_stream_state_values() {
     // Create test data
@@ -1300,6 +1356,66 @@ async fn test_snapshot_sync_fresh_state() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_snapshot_sync_backup_storage_error() {
+    // Create test data
+    let synced_version = GENESIS_TRANSACTION_VERSION; // Genesis is the highest synced
+    let highest_version = 1000;
+    let highest_ledger_info = create_random_epoch_ending_ledger_info(highest_version, 1);
+
+    // Create a driver configuration with state syncing and a backup storage that can't be read
+    let mut driver_configuration = create_full_node_driver_configuration();
+    driver_configuration.config.bootstrapping_mode = BootstrappingMode::DownloadLatestStates;
+    driver_configuration
+        .backup_bootstrapping_config
+        .backup_storage = Some(BackupStorageConfig::CommandAdapter(PathBuf::from(
+        "/missing/command_adapter_config.yaml",
+    )));
+
+    // Create the mock streaming client (the state values are fetched from the network)
+    let mut mock_streaming_client = create_mock_streaming_client();
+    let (_notification_sender_1, data_stream_listener_1) = create_data_stream_listener();
+    mock_streaming_client
+        .expect_get_all_state_values()
+        .times(1)
+        .with(eq(highest_version), eq(Some(0)))
+        .return_once(move |_, _| Ok(data_stream_listener_1));
+
+    // Create the mock metadata storage
+    let mut metadata_storage = MockMetadataStorage::new();
+    metadata_storage
+        .expect_previous_snapshot_sync_target()
+        .returning(move || Ok(None));
+
+    // Create the bootstrapper
+    let mut bootstrapper = create_bootstrapper_with_storage(
+        driver_configuration,
+        mock_streaming_client,
+        metadata_storage,
+        None,
+        synced_version,
+        true,
+    );
+
+    // Insert an epoch ending ledger info into the verified states of the bootstrapper
+    manipulate_verified_epoch_states(&mut bootstrapper, true, true, Some(highest_version));
+
+    // Manually insert a transaction output to sync
+    bootstrapper
+        .get_state_value_syncer()
+        .set_transaction_output_to_sync(create_output_list_with_proof());
+
+    // Create a global data summary
+    let mut global_data_summary = create_global_summary(1);
+    global_data_summary.advertised_data.synced_ledger_infos = vec![highest_ledger_info.clone()];
+
+    // Drive progress and verify the bootstrapper falls back to the network
+    drive_progress(&mut bootstrapper, &global_data_summary, false)
+        .await
+        .unwrap();
+    assert!(!bootstrapper.has_backup_data_source());
+}
+
 #[tokio::test]
 #[should_panic(
     expected = "The snapshot sync for the target was marked as complete but the highest synced version is genesis!"
@@ -195,9 +195,9 @@ impl OutputFallbackHandler {
 }
 
 /// Fetches a data notification from the given data stream listener. Returns an
-/// error if the data stream times out after `max_stream_wait_time_ms`. Also,
-/// tracks the number of consecutive timeouts to identify when the stream has
-/// timed out too many times.
+/// error if the data stream times out after `max_stream_wait_time_ms`, or if
+/// the data stream was closed. Also, tracks the number of consecutive timeouts
+/// to identify when the stream has timed out too many times.
 pub async fn get_data_notification(
     max_stream_wait_time_ms: u64,
     max_num_stream_timeouts: u64,
@@ -207,34 +207,44 @@ pub async fn get_data_notification(
         .ok_or_else(|| Error::UnexpectedError("The active data stream does not exist!".into()))?;
 
     let timeout_ms = Duration::from_millis(max_stream_wait_time_ms);
-    if let Ok(data_notification) = timeout(timeout_ms, active_data_stream.select_next_some()).await
-    {
-        // Update the metrics for the data notification receive latency
-        metrics::observe_duration(
-            &metrics::DATA_NOTIFICATION_LATENCIES,
-            metrics::NOTIFICATION_CREATE_TO_RECEIVE,
-            data_notification.creation_time,
-        );
-
-        // Reset the number of consecutive timeouts for the data stream
-        active_data_stream.num_consecutive_timeouts = 0;
-        Ok(data_notification)
-    } else {
-        // Increase the number of consecutive timeouts for the data stream
-        active_data_stream.num_consecutive_timeouts += 1;
-
-        // Check if we've timed out too many times
-        if active_data_stream.num_consecutive_timeouts >= max_num_stream_timeouts {
-            Err(Error::CriticalDataStreamTimeout(format!(
-                "{:?}",
-                max_num_stream_timeouts
-            )))
-        } else {
-            Err(Error::DataStreamNotificationTimeout(format!(
+    match timeout(timeout_ms, active_data_stream.next()).await {
+        Ok(Some(data_notification)) => {
+            // Update the metrics for the data notification receive latency
+            metrics::observe_duration(
+                &metrics::DATA_NOTIFICATION_LATENCIES,
+                metrics::NOTIFICATION_CREATE_TO_RECEIVE,
+                data_notification.creation_time,
+            );
+
+            // Reset the number of consecutive timeouts for the data stream
+            active_data_stream.num_consecutive_timeouts = 0;
+            Ok(data_notification)
+        },
+        Ok(None) => {
+            // The sender was dropped (e.g., because the data source failed),
+            // so no more notifications will arrive.
+            Err(Error::DataStreamClosed(format!(
                 "{:?}",
-                timeout_ms
+                active_data_stream.data_stream_id
             )))
-        }
+        },
+        Err(_) => {
+            // Increase the number of consecutive timeouts for the data stream
+            active_data_stream.num_consecutive_timeouts += 1;
+
+            // Check if we've timed out too many times
+            if active_data_stream.num_consecutive_timeouts >= max_num_stream_timeouts {
+                Err(Error::CriticalDataStreamTimeout(format!(
+                    "{:?}",
+                    max_num_stream_timeouts
+                )))
+            } else {
+                Err(Error::DataStreamNotificationTimeout(format!(
+                    "{:?}",
+                    timeout_ms
+                )))
+            }
+        },
     }
 }
 
@@ -144,6 +144,23 @@ impl BackupHandler {
         Ok(Box::new(iterator))
     }
 
+    /// Iterate through the state changes between two state snapshots, `None` meaning the key is
+    /// deleted.
+    pub fn get_state_snapshot_diff_iter(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<impl Iterator<Item = Result<(StateKey, Option<StateValue>)>> + Send> {
+        let iterator = self
+            .state_store
+            .get_state_diff_iter(base_version, version)?
+            .map(move |res| {
+                BACKUP_STATE_SNAPSHOT_VERSION.set(version as i64);
+                res
+            });
+        Ok(Box::new(iterator))
+    }
+
     /// Gets the proof that proves a range of accounts.
     pub fn get_account_state_range_proof(
         &self,
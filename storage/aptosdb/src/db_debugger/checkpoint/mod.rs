@@ -1,10 +1,22 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{db_debugger::common::DbDir, AptosDB};
+use crate::{
+    db_debugger::common::DbDir,
+    utils::truncation_helper::{
+        get_current_version_in_state_merkle_db, get_state_kv_commit_progress,
+    },
+    AptosDB,
+};
+use aptos_config::config::{RocksdbConfigs, StorageDirPaths};
 use aptos_storage_interface::{db_ensure as ensure, AptosDbError, Result};
+use aptos_types::transaction::Version;
 use clap::Parser;
-use std::{fs, path::PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 #[derive(Parser)]
 #[clap(about = "Make a DB checkpoint by hardlinks.")]
@@ -28,3 +40,62 @@ impl Cmd {
         )
     }
 }
+
+/// The commit progress of each of the sub-DBs of an AptosDB.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CommitProgress {
+    pub overall_version: Version,
+    pub ledger_db_version: Version,
+    pub state_kv_db_version: Version,
+    pub state_merkle_db_version: Option<Version>,
+}
+
+impl CommitProgress {
+    /// Reads the commit progress of the DB at `db_dir` and verifies that the sub-DBs are
+    /// consistent with each other, i.e., the DB can be opened at the overall commit progress.
+    pub fn load(db_dir: &Path, enable_storage_sharding: bool) -> Result<Self> {
+        let (ledger_db, state_merkle_db, state_kv_db) = AptosDB::open_dbs(
+            &StorageDirPaths::from_path(db_dir),
+            RocksdbConfigs {
+                enable_storage_sharding,
+                ..Default::default()
+            },
+            /*readonly=*/ true,
+            /*max_num_nodes_per_lru_cache_shard=*/ 0,
+        )?;
+
+        let overall_version = ledger_db.metadata_db().get_synced_version()?;
+        let ledger_db_version = ledger_db.metadata_db().get_ledger_commit_progress()?;
+        let state_kv_db_version = get_state_kv_commit_progress(&state_kv_db)?
+            .ok_or_else(|| AptosDbError::NotFound("No StateKvCommitProgress in db.".to_string()))?;
+        let state_merkle_db_version = get_current_version_in_state_merkle_db(&state_merkle_db)?;
+
+        ensure!(
+            ledger_db_version >= overall_version,
+            "Ledger db version {} is behind the overall commit progress {}.",
+            ledger_db_version,
+            overall_version,
+        );
+        ensure!(
+            state_kv_db_version >= overall_version,
+            "State kv db version {} is behind the overall commit progress {}.",
+            state_kv_db_version,
+            overall_version,
+        );
+        if let Some(state_merkle_db_version) = state_merkle_db_version {
+            ensure!(
+                state_merkle_db_version <= overall_version,
+                "State merkle db version {} is ahead of the overall commit progress {}.",
+                state_merkle_db_version,
+                overall_version,
+            );
+        }
+
+        Ok(Self {
+            overall_version,
+            ledger_db_version,
+            state_kv_db_version,
+            state_merkle_db_version,
+        })
+    }
+}
@@ -21,7 +21,7 @@ use aptos_db_indexer_schemas::schema::{
     event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
 };
 use aptos_schemadb::{iterator::SchemaIterator, schema::ValueCodec, ReadOptions, SchemaBatch, DB};
-use aptos_storage_interface::{db_ensure as ensure, db_other_bail, AptosDbError, Result};
+use aptos_storage_interface::{db_not_found_bail, db_other_bail, AptosDbError, Result};
 use aptos_types::{
     account_address::AccountAddress,
     account_config::{new_block_event_key, NewBlockEvent},
@@ -310,11 +310,9 @@ impl EventStore {
             timestamp,
         )))?;
 
-        ensure!(
-            seq_at_or_after_ts > 0,
-            "First block started at or after timestamp {}.",
-            timestamp,
-        );
+        if seq_at_or_after_ts == 0 {
+            db_not_found_bail!("First block started at or after timestamp {}.", timestamp);
+        }
 
         let (version, _idx) =
             self.lookup_event_by_key(&event_key, seq_at_or_after_ts, ledger_version)?;
@@ -43,7 +43,10 @@ use aptos_db_indexer_schemas::{
 use aptos_executor::components::in_memory_state_calculator_v2::InMemoryStateCalculatorV2;
 use aptos_experimental_runtimes::thread_manager::THREAD_MANAGER;
 use aptos_infallible::Mutex;
-use aptos_jellyfish_merkle::iterator::JellyfishMerkleIterator;
+use aptos_jellyfish_merkle::{
+    diff_iterator::{JellyfishMerkleDiffIterator, LeafChange},
+    iterator::JellyfishMerkleIterator,
+};
 use aptos_logger::info;
 use aptos_schemadb::SchemaBatch;
 use aptos_scratchpad::{SmtAncestors, SparseMerkleTree};
@@ -1015,6 +1018,34 @@ impl StateStore {
         }))
     }
 
+    /// Returns the state changes from the snapshot at `base_version` to the one at `version`, in
+    /// the order of the key hashes, with `None` for the deleted keys.
+    ///
+    /// The changes are found by walking both trees at once, skipping the subtrees they share, so
+    /// both snapshots must still be in the DB, i.e. not pruned.
+    pub fn get_state_diff_iter(
+        self: &Arc<Self>,
+        base_version: Version,
+        version: Version,
+    ) -> Result<impl Iterator<Item = Result<(StateKey, Option<StateValue>)>> + Send + Sync> {
+        let store = Arc::clone(self);
+        Ok(JellyfishMerkleDiffIterator::new(
+            Arc::clone(&self.state_merkle_db),
+            base_version,
+            version,
+        )?
+        .map(move |res| match res? {
+            LeafChange::Updated(leaf) => {
+                let (key, key_version) = leaf.value_index();
+                Ok((
+                    key.clone(),
+                    Some(store.expect_value_by_version(key, *key_version)?),
+                ))
+            },
+            LeafChange::Deleted(leaf) => Ok((leaf.value_index().0.clone(), None)),
+        }))
+    }
+
     pub fn get_value_chunk_with_proof(
         self: &Arc<Self>,
         version: Version,
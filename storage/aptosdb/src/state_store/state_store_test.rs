@@ -26,7 +26,7 @@ use aptos_types::{
 };
 use arr_macro::arr;
 use proptest::{collection::hash_map, prelude::*};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 fn put_value_set(
     state_store: &StateStore,
@@ -569,6 +569,56 @@ proptest! {
         }
 
     }
+
+    #[test]
+    fn test_get_state_diff_iter(
+        input in arb_state_kv_sets(10, 5, 5)
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = AptosDB::new_for_test(&tmp_dir);
+        let store = &db.state_store;
+
+        let mut version = 0;
+        let mut snapshots = Vec::new();
+        for batch in input {
+            let next_version = version + batch.len() as Version;
+            update_store(store, batch.into_iter(), version);
+            let snapshot: BTreeMap<_, _> = db
+                .get_backup_handler()
+                .get_state_item_iter(next_version - 1, 0, usize::MAX)
+                .unwrap()
+                .map(|res| res.map(|(k, v)| (k.hash(), (k, v))))
+                .collect::<Result<_>>()
+                .unwrap();
+            snapshots.push((next_version - 1, snapshot));
+            version = next_version;
+        }
+
+        for (i, (base_version, base)) in snapshots.iter().enumerate() {
+            for (version, expected) in &snapshots[i + 1..] {
+                let diff = store
+                    .get_state_diff_iter(*base_version, *version)
+                    .unwrap()
+                    .collect::<Result<Vec<_>>>()
+                    .unwrap();
+                let key_hashes: Vec<_> = diff.iter().map(|(k, _v)| k.hash()).collect();
+                prop_assert!(key_hashes.windows(2).all(|w| w[0] < w[1]));
+
+                let mut actual = base.clone();
+                for (key, value) in diff {
+                    match value {
+                        Some(value) => {
+                            actual.insert(key.hash(), (key, value));
+                        },
+                        None => {
+                            prop_assert!(actual.remove(&key.hash()).is_some());
+                        },
+                    }
+                }
+                prop_assert_eq!(&actual, expected);
+            }
+        }
+    }
 }
 
 // Initializes the state store by inserting one key at each version.
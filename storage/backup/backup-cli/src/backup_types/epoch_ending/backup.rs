@@ -199,6 +199,7 @@ impl EpochEndingBackupController {
             manifest.waypoints.first().expect("No waypoints.").version(),
             manifest.waypoints.last().expect("No waypoints.").version(),
             manifest_handle.clone(),
+            self.storage.codec(),
         );
 
         self.storage
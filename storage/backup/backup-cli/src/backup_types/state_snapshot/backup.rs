@@ -234,7 +234,7 @@ impl StateSnapshotBackupController {
     }
 
     async fn run_impl(mut self) -> Result<FileHandle> {
-        self.version = Some(self.get_version_for_epoch_ending(self.epoch).await?);
+        self.version = Some(get_version_for_epoch_ending(&self.client, self.epoch).await?);
         let backup_handle = self
             .storage
             .create_backup_with_random_suffix(&self.backup_name())
@@ -314,6 +314,22 @@ impl StateSnapshotBackupController {
     }
 }
 
+pub(super) async fn get_version_for_epoch_ending(
+    client: &BackupServiceClient,
+    epoch: u64,
+) -> Result<Version> {
+    let ledger_info: LedgerInfoWithSignatures = bcs::from_bytes(
+        client
+            .get_epoch_ending_ledger_infos(epoch, epoch + 1)
+            .await?
+            .read_record_bytes()
+            .await?
+            .ok_or_else(|| anyhow!("Failed to get epoch ending ledger info for epoch {}", epoch))?
+            .as_ref(),
+    )?;
+    Ok(ledger_info.ledger_info().version())
+}
+
 async fn send_records(
     client: Arc<BackupServiceClient>,
     version: Version,
@@ -386,21 +402,6 @@ impl StateSnapshotBackupController {
             .unwrap()
     }
 
-    async fn get_version_for_epoch_ending(&self, epoch: u64) -> Result<u64> {
-        let ledger_info: LedgerInfoWithSignatures = bcs::from_bytes(
-            self.client
-                .get_epoch_ending_ledger_infos(epoch, epoch + 1)
-                .await?
-                .read_record_bytes()
-                .await?
-                .ok_or_else(|| {
-                    anyhow!("Failed to get epoch ending ledger info for epoch {}", epoch)
-                })?
-                .as_ref(),
-        )?;
-        Ok(ledger_info.ledger_info().version())
-    }
-
     async fn write_chunk(
         &self,
         backup_handle: &BackupHandleRef,
@@ -483,6 +484,7 @@ impl StateSnapshotBackupController {
             self.epoch,
             self.version(),
             manifest_handle.clone(),
+            self.storage.codec(),
         );
         self.storage
             .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
@@ -0,0 +1,371 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::state_snapshot::{
+        backup::get_version_for_epoch_ending,
+        manifest::{StateSnapshotDiffBackup, StateSnapshotDiffChunk, StateSnapshotManifest},
+    },
+    metadata::Metadata,
+    metrics::backup::BACKUP_TIMER,
+    storage::{BackupHandleRef, BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
+        should_cut_chunk, storage_ext::BackupStorageExt, stream::StreamX, GlobalBackupOpt,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_logger::prelude::*;
+use aptos_metrics_core::TimerHelper;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{SparseMerkleRangeProof, TransactionInfoWithProof},
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use clap::Parser;
+use futures::{stream, TryStreamExt};
+use once_cell::sync::Lazy;
+use std::{collections::HashSet, convert::TryInto, str::FromStr, sync::Arc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Parser)]
+pub struct StateSnapshotDiffBackupOpt {
+    #[clap(
+        long = "state-snapshot-epoch",
+        help = "Epoch at the end of which a state snapshot is to be taken."
+    )]
+    pub epoch: u64,
+    #[clap(
+        long = "base-state-manifest",
+        help = "Manifest of the state snapshot that only the changes since are backed up, \
+        either a full snapshot or another diff."
+    )]
+    pub base_manifest: FileHandle,
+}
+
+/// Backs up a state snapshot as the changes since an older one, found by comparing both versions
+/// of the state tree. The older snapshot must not be pruned yet on the backup service.
+pub struct StateSnapshotDiffBackupController {
+    epoch: u64,
+    base_manifest: FileHandle,
+    max_chunk_size: usize,
+    client: Arc<BackupServiceClient>,
+    storage: Arc<dyn BackupStorage>,
+    concurrent_data_requests: usize,
+}
+
+impl StateSnapshotDiffBackupController {
+    pub fn new(
+        opt: StateSnapshotDiffBackupOpt,
+        global_opt: GlobalBackupOpt,
+        client: Arc<BackupServiceClient>,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            epoch: opt.epoch,
+            base_manifest: opt.base_manifest,
+            max_chunk_size: global_opt.max_chunk_size,
+            client,
+            storage,
+            concurrent_data_requests: global_opt.concurrent_data_requests,
+        }
+    }
+
+    pub async fn run(self) -> Result<FileHandle> {
+        info!(
+            "State snapshot diff backup started, for epoch {}. Base: {}",
+            self.epoch, self.base_manifest,
+        );
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("State snapshot diff backup failed: {}", e))?;
+        info!("State snapshot diff backup succeeded. Manifest: {}", ret);
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<FileHandle> {
+        let version = get_version_for_epoch_ending(&self.client, self.epoch).await?;
+        let (base_version, base_range_keys) = self.load_base().await?;
+        ensure!(
+            base_version < version,
+            "Base state snapshot at version {} is not older than version {}.",
+            base_version,
+            version,
+        );
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(&Self::backup_name(self.epoch, version))
+            .await?;
+
+        let mut input = self
+            .client
+            .get_state_snapshot_diff(base_version, version)
+            .await?;
+        let mut chunks = Vec::new();
+        let mut deleted_keys = HashSet::new();
+        let mut buf = BytesMut::new();
+        let mut first_key = HashValue::zero();
+        let mut last_key = HashValue::zero();
+        while let Some(record_bytes) = input.read_record_bytes().await? {
+            let (key, value): (StateKey, Option<StateValue>) = bcs::from_bytes(&record_bytes)?;
+            let key_hash = key.hash();
+            if value.is_none() {
+                deleted_keys.insert(key_hash);
+            }
+
+            if should_cut_chunk(&buf, &record_bytes, self.max_chunk_size) {
+                let chunk = self
+                    .write_chunk(
+                        &backup_handle,
+                        chunks.len(),
+                        buf.split().freeze(),
+                        first_key,
+                        last_key,
+                    )
+                    .await?;
+                chunks.push(chunk);
+            }
+            if buf.is_empty() {
+                first_key = key_hash;
+            }
+            buf.put_slice(&(record_bytes.len() as u32).to_be_bytes());
+            buf.extend(record_bytes);
+            last_key = key_hash;
+        }
+        if !buf.is_empty() {
+            let chunk = self
+                .write_chunk(
+                    &backup_handle,
+                    chunks.len(),
+                    buf.freeze(),
+                    first_key,
+                    last_key,
+                )
+                .await?;
+            chunks.push(chunk);
+        }
+        info!(
+            base_version = base_version,
+            version = version,
+            chunks = chunks.len(),
+            deleted_keys = deleted_keys.len(),
+            "State snapshot diff written."
+        );
+
+        // Keep cutting the state where the base is cut, so chunks stay roughly the same size.
+        let mut range_keys: Vec<_> = base_range_keys
+            .into_iter()
+            .filter(|key| !deleted_keys.contains(key))
+            .collect();
+        let last_key = self.get_last_key(version).await?;
+        match range_keys.last() {
+            Some(key) if *key == last_key => {},
+            Some(key) => {
+                ensure!(
+                    *key < last_key,
+                    "Key {:x} of the base snapshot is after the last key {:x}.",
+                    key,
+                    last_key,
+                );
+                range_keys.push(last_key);
+            },
+            None => range_keys.push(last_key),
+        }
+        let range_proofs_handle = self
+            .write_range_proofs(&backup_handle, version, range_keys)
+            .await?;
+
+        self.write_manifest(
+            &backup_handle,
+            version,
+            base_version,
+            chunks,
+            range_proofs_handle,
+        )
+        .await
+    }
+
+    /// Returns the version of the base snapshot and the keys at which it's cut into chunks.
+    async fn load_base(&self) -> Result<(Version, Vec<HashValue>)> {
+        let manifest: StateSnapshotManifest =
+            self.storage.load_json_file(&self.base_manifest).await?;
+        Ok(match manifest {
+            StateSnapshotManifest::Full(manifest) => (
+                manifest.version,
+                manifest.chunks.iter().map(|c| c.last_key).collect(),
+            ),
+            StateSnapshotManifest::Diff(manifest) => {
+                let range_proofs: Vec<(HashValue, SparseMerkleRangeProof)> =
+                    self.storage.load_bcs_file(&manifest.range_proofs).await?;
+                (
+                    manifest.version,
+                    range_proofs.into_iter().map(|(key, _)| key).collect(),
+                )
+            },
+        })
+    }
+
+    async fn get_last_key(&self, version: Version) -> Result<HashValue> {
+        let count = self.client.get_state_item_count(version).await?;
+        ensure!(count > 0, "State is empty.");
+        let record_bytes = self
+            .client
+            .get_state_snapshot_chunk(version, count - 1, 1)
+            .await?
+            .read_record_bytes()
+            .await?
+            .ok_or_else(|| anyhow!("Failed to get the last state item at version {}", version))?;
+        let (key, _): (StateKey, StateValue) = bcs::from_bytes(&record_bytes)?;
+        Ok(key.hash())
+    }
+
+    async fn write_chunk(
+        &self,
+        backup_handle: &BackupHandleRef,
+        chunk_idx: usize,
+        bytes: Bytes,
+        first_key: HashValue,
+        last_key: HashValue,
+    ) -> Result<StateSnapshotDiffChunk> {
+        let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_diff_write_chunk"]);
+
+        let (chunk_handle, mut chunk_file) = self
+            .storage
+            .create_for_write(backup_handle, &Self::chunk_name(chunk_idx))
+            .await?;
+        chunk_file.write_all(&bytes).await?;
+        chunk_file.shutdown().await?;
+
+        Ok(StateSnapshotDiffChunk {
+            first_key,
+            last_key,
+            blobs: chunk_handle,
+        })
+    }
+
+    async fn write_range_proofs(
+        &self,
+        backup_handle: &BackupHandleRef,
+        version: Version,
+        range_keys: Vec<HashValue>,
+    ) -> Result<FileHandle> {
+        let _timer = BACKUP_TIMER.timer_with(&["state_snapshot_diff_write_range_proofs"]);
+
+        let client = self.client.clone();
+        let futs_iter = range_keys.into_iter().map(|key| {
+            let client = client.clone();
+            async move {
+                let mut proof_bytes = Vec::new();
+                client
+                    .get_account_range_proof(key, version)
+                    .await?
+                    .read_to_end(&mut proof_bytes)
+                    .await?;
+                let proof: SparseMerkleRangeProof = bcs::from_bytes(&proof_bytes)?;
+                Result::<_>::Ok((key, proof))
+            }
+        });
+        let con = self.concurrent_data_requests;
+        let range_proofs: Vec<_> = stream::iter(futs_iter)
+            .buffered_x(con * 2, con)
+            .try_collect()
+            .await?;
+
+        let (range_proofs_handle, mut range_proofs_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::range_proofs_name())
+            .await?;
+        range_proofs_file
+            .write_all(&bcs::to_bytes(&range_proofs)?)
+            .await?;
+        range_proofs_file.shutdown().await?;
+
+        Ok(range_proofs_handle)
+    }
+
+    async fn write_manifest(
+        &self,
+        backup_handle: &BackupHandleRef,
+        version: Version,
+        base_version: Version,
+        chunks: Vec<StateSnapshotDiffChunk>,
+        range_proofs: FileHandle,
+    ) -> Result<FileHandle> {
+        let proof_bytes = self.client.get_state_root_proof(version).await?;
+        let (txn_info, _): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            bcs::from_bytes(&proof_bytes)?;
+
+        let (proof_handle, mut proof_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::proof_name())
+            .await?;
+        proof_file.write_all(&proof_bytes).await?;
+        proof_file.shutdown().await?;
+
+        let manifest = StateSnapshotDiffBackup {
+            version,
+            epoch: self.epoch,
+            root_hash: txn_info.transaction_info().ensure_state_checkpoint_hash()?,
+            base_version,
+            base_manifest: self.base_manifest.clone(),
+            chunks,
+            range_proofs,
+            proof: proof_handle,
+        };
+
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(backup_handle, Self::manifest_name())
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(&manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+
+        let metadata = Metadata::new_state_snapshot_diff_backup(
+            self.epoch,
+            version,
+            base_version,
+            self.base_manifest.clone(),
+            manifest_handle.clone(),
+            self.storage.codec(),
+        );
+        self.storage
+            .save_metadata_line(&metadata.name(), &metadata.to_text_line()?)
+            .await?;
+
+        Ok(manifest_handle)
+    }
+}
+
+impl StateSnapshotDiffBackupController {
+    fn backup_name(epoch: u64, version: Version) -> String {
+        format!("state_diff_epoch_{}_ver_{}", epoch, version)
+    }
+
+    fn manifest_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("state_diff.manifest").unwrap());
+        &NAME
+    }
+
+    fn proof_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("state.proof").unwrap());
+        &NAME
+    }
+
+    fn range_proofs_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("range.proofs").unwrap());
+        &NAME
+    }
+
+    fn chunk_name(chunk_idx: usize) -> ShellSafeName {
+        format!("{}.chunk", chunk_idx).try_into().unwrap()
+    }
+}
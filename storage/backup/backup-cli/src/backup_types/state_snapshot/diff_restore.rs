@@ -0,0 +1,378 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    backup_types::{
+        epoch_ending::restore::EpochHistory,
+        state_snapshot::{
+            manifest::{StateSnapshotBackup, StateSnapshotDiffBackup, StateSnapshotManifest},
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+    },
+    metrics::{
+        restore::STATE_SNAPSHOT_VERSION, verify::VERIFY_STATE_SNAPSHOT_VERSION,
+        OTHER_TIMERS_SECONDS,
+    },
+    storage::{BackupStorage, FileHandle},
+    utils::{
+        read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX,
+        GlobalRestoreOptions, RestoreRunMode,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_db::state_restore::StateSnapshotRestoreMode;
+use aptos_infallible::Mutex;
+use aptos_logger::prelude::*;
+use aptos_storage_interface::StateSnapshotReceiver;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{SparseMerkleRangeProof, TransactionInfoWithProof},
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+};
+use futures::{stream, stream::BoxStream, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tokio::time::Instant;
+
+type Record = (StateKey, Option<StateValue>);
+
+/// Restores a state snapshot backed up as a chain of diffs on top of a full snapshot, by merging
+/// all of them into the full state view and feeding it to the state restore in the ranges proven
+/// by the latest diff. The root hash is verified against that of the latest diff at the end.
+pub struct StateSnapshotDiffRestoreController {
+    storage: Arc<dyn BackupStorage>,
+    run_mode: Arc<RestoreRunMode>,
+    /// State snapshot restores to this version.
+    version: Version,
+    /// Manifest of the latest diff in the chain.
+    manifest_handle: FileHandle,
+    /// Global "target_version" for the entire restore process, if `version` is newer than this,
+    /// nothing will be done, otherwise, this has no effect.
+    target_version: Version,
+    epoch_history: Option<Arc<EpochHistory>>,
+    concurrent_downloads: usize,
+    validate_modules: bool,
+    restore_mode: StateSnapshotRestoreMode,
+}
+
+impl StateSnapshotDiffRestoreController {
+    pub fn new(
+        opt: StateSnapshotRestoreOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+        epoch_history: Option<Arc<EpochHistory>>,
+    ) -> Self {
+        Self {
+            storage,
+            run_mode: global_opt.run_mode,
+            version: opt.version,
+            manifest_handle: opt.manifest_handle,
+            target_version: global_opt.target_version,
+            epoch_history,
+            concurrent_downloads: global_opt.concurrent_downloads,
+            validate_modules: opt.validate_modules,
+            restore_mode: opt.restore_mode,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let name = self.name();
+        let start = Instant::now();
+        info!("{} started. Manifest: {}", name, self.manifest_handle);
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("{} failed: {}", name, e))?;
+        info!(time = start.elapsed().as_secs(), "{} succeeded.", name);
+        Ok(())
+    }
+}
+
+impl StateSnapshotDiffRestoreController {
+    fn name(&self) -> String {
+        format!("state snapshot diff {}", self.run_mode.name())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        if self.version > self.target_version {
+            warn!(
+                "Trying to restore state snapshot to version {}, which is newer than the target version {}, skipping.",
+                self.version,
+                self.target_version,
+            );
+            return Ok(());
+        }
+
+        let (base, diffs) = self.load_chain().await?;
+        let manifest = diffs.last().expect("Chain has at least one diff.");
+        ensure!(
+            manifest.version == self.version,
+            "State snapshot diff is at version {}, expected: {}",
+            manifest.version,
+            self.version,
+        );
+        let (txn_info_with_proof, li): (TransactionInfoWithProof, LedgerInfoWithSignatures) =
+            self.storage.load_bcs_file(&manifest.proof).await?;
+        txn_info_with_proof.verify(li.ledger_info(), manifest.version)?;
+        let state_root_hash = txn_info_with_proof
+            .transaction_info()
+            .ensure_state_checkpoint_hash()?;
+        ensure!(
+            state_root_hash == manifest.root_hash,
+            "Root hash mismatch with that in proof. root hash: {}, expected: {}",
+            manifest.root_hash,
+            state_root_hash,
+        );
+        if let Some(epoch_history) = self.epoch_history.as_ref() {
+            epoch_history.verify_ledger_info(&li)?;
+        }
+
+        let receiver = Arc::new(Mutex::new(Some(self.run_mode.get_state_restore_receiver(
+            self.version,
+            manifest.root_hash,
+            self.restore_mode,
+        )?)));
+        if self.run_mode.is_verify() {
+            VERIFY_STATE_SNAPSHOT_VERSION.set(self.version as i64);
+        } else {
+            STATE_SNAPSHOT_VERSION.set(self.version as i64);
+        }
+
+        let range_proofs: Vec<(HashValue, SparseMerkleRangeProof)> =
+            self.storage.load_bcs_file(&manifest.range_proofs).await?;
+        let total_ranges = range_proofs.len();
+        let resume_point_opt = receiver.lock().as_mut().unwrap().previous_key_hash()?;
+        let is_after_resume_point =
+            |key: &HashValue| resume_point_opt.map_or(true, |resume_point| *key > resume_point);
+        let range_proofs: Vec<_> = range_proofs
+            .into_iter()
+            .filter(|(key, _)| is_after_resume_point(key))
+            .collect();
+        if range_proofs.len() < total_ranges {
+            info!(
+                ranges_to_add = range_proofs.len(),
+                total_ranges = total_ranges,
+                "Resumed state snapshot diff restore."
+            )
+        };
+
+        let mut layers = vec![self.base_records(base, is_after_resume_point)];
+        layers.extend(
+            diffs
+                .iter()
+                .map(|diff| self.diff_records(diff, is_after_resume_point)),
+        );
+
+        let ranges_to_add = range_proofs.len();
+        let mut blobs = Vec::new();
+        for (range_idx, (range_key, proof)) in range_proofs.into_iter().enumerate() {
+            loop {
+                let (key, value) = Self::next_record(&mut layers)
+                    .await?
+                    .ok_or_else(|| anyhow!("Range key {:x} not found in the state.", range_key))?;
+                let key_hash = key.hash();
+                if !is_after_resume_point(&key_hash) {
+                    continue;
+                }
+                ensure!(
+                    key_hash <= range_key,
+                    "Range key {:x} not found in the state.",
+                    range_key,
+                );
+                blobs.push((key, value));
+                if key_hash == range_key {
+                    break;
+                }
+            }
+
+            let _timer = OTHER_TIMERS_SECONDS
+                .with_label_values(&["add_state_chunk"])
+                .start_timer();
+            let mut chunk = std::mem::take(&mut blobs);
+            let receiver = receiver.clone();
+            if self.validate_modules {
+                chunk = tokio::task::spawn_blocking(move || {
+                    StateSnapshotRestoreController::validate_modules(&chunk);
+                    chunk
+                })
+                .await?;
+            }
+            tokio::task::spawn_blocking(move || {
+                receiver.lock().as_mut().unwrap().add_chunk(chunk, proof)
+            })
+            .await??;
+            info!(
+                range = range_idx,
+                ranges_to_add = ranges_to_add,
+                "State chunk added.",
+            );
+        }
+        while let Some((key, _)) = Self::next_record(&mut layers).await? {
+            let key_hash = key.hash();
+            ensure!(
+                !is_after_resume_point(&key_hash),
+                "Key {:x} is after the last range key of the state.",
+                key_hash,
+            );
+        }
+
+        tokio::task::spawn_blocking(move || receiver.lock().take().unwrap().finish()).await??;
+        self.run_mode.finish();
+        Ok(())
+    }
+
+    /// Follows the base manifests from the latest diff down to the full snapshot, returning the
+    /// full snapshot and the diffs, oldest first.
+    async fn load_chain(&self) -> Result<(StateSnapshotBackup, Vec<StateSnapshotDiffBackup>)> {
+        let mut diffs: Vec<StateSnapshotDiffBackup> =
+            vec![self.storage.load_json_file(&self.manifest_handle).await?];
+        loop {
+            let diff = diffs.last().unwrap();
+            let base: StateSnapshotManifest =
+                self.storage.load_json_file(&diff.base_manifest).await?;
+            let base_version = match &base {
+                StateSnapshotManifest::Full(full) => full.version,
+                StateSnapshotManifest::Diff(diff) => diff.version,
+            };
+            ensure!(
+                base_version == diff.base_version && base_version < diff.version,
+                "Base state snapshot {} is at version {}, expected: {}",
+                diff.base_manifest,
+                base_version,
+                diff.base_version,
+            );
+            match base {
+                StateSnapshotManifest::Full(full) => {
+                    diffs.reverse();
+                    return Ok((full, diffs));
+                },
+                StateSnapshotManifest::Diff(diff) => diffs.push(diff),
+            }
+        }
+    }
+
+    fn base_records(
+        &self,
+        manifest: StateSnapshotBackup,
+        is_after_resume_point: impl Fn(&HashValue) -> bool,
+    ) -> LayerRecords {
+        let blobs = manifest
+            .chunks
+            .into_iter()
+            .filter(|chunk| is_after_resume_point(&chunk.last_key))
+            .map(|chunk| chunk.blobs)
+            .collect();
+        LayerRecords::new(
+            self.read_chunks::<(StateKey, StateValue)>(blobs)
+                .map_ok(|records| {
+                    records
+                        .into_iter()
+                        .map(|(key, value)| (key, Some(value)))
+                        .collect::<Vec<_>>()
+                })
+                .boxed(),
+        )
+    }
+
+    fn diff_records(
+        &self,
+        manifest: &StateSnapshotDiffBackup,
+        is_after_resume_point: impl Fn(&HashValue) -> bool,
+    ) -> LayerRecords {
+        let blobs = manifest
+            .chunks
+            .iter()
+            .filter(|chunk| is_after_resume_point(&chunk.last_key))
+            .map(|chunk| chunk.blobs.clone())
+            .collect();
+        LayerRecords::new(self.read_chunks::<Record>(blobs).boxed())
+    }
+
+    fn read_chunks<T: DeserializeOwned + Send + 'static>(
+        &self,
+        blobs: Vec<FileHandle>,
+    ) -> impl futures::Stream<Item = Result<Vec<T>>> {
+        let storage = self.storage.clone();
+        let futs_iter = blobs.into_iter().map(move |file_handle| {
+            let storage = storage.clone();
+            async move {
+                tokio::spawn(async move {
+                    let mut file = storage.open_for_read(&file_handle).await?;
+                    let mut chunk = vec![];
+                    while let Some(record_bytes) = file.read_record_bytes().await? {
+                        chunk.push(bcs::from_bytes(&record_bytes)?);
+                    }
+                    Result::<_>::Ok(chunk)
+                })
+                .await?
+            }
+        });
+        // Every layer downloads concurrently, so they share the concurrency.
+        let con = self.concurrent_downloads.max(2) / 2;
+        stream::iter(futs_iter).buffered_x(con * 2, con)
+    }
+
+    /// Returns the next record of the merged state, in the order of the key hashes. For a key in
+    /// more than one layer, the latest layer wins, and keys deleted by it are skipped.
+    async fn next_record(layers: &mut [LayerRecords]) -> Result<Option<(StateKey, StateValue)>> {
+        loop {
+            let mut min_key_hash = None;
+            for layer in layers.iter_mut() {
+                if let Some(key_hash) = layer.peek_key_hash().await? {
+                    if min_key_hash.map_or(true, |min| key_hash < min) {
+                        min_key_hash = Some(key_hash);
+                    }
+                }
+            }
+            let min_key_hash = match min_key_hash {
+                Some(key_hash) => key_hash,
+                None => return Ok(None),
+            };
+
+            let mut latest = None;
+            for layer in layers.iter_mut() {
+                if layer.peek_key_hash().await? == Some(min_key_hash) {
+                    latest = layer.take();
+                }
+            }
+            if let Some((key, Some(value))) = latest {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+}
+
+/// Records of one of the snapshots in a chain, in the order of the key hashes.
+struct LayerRecords {
+    chunks: BoxStream<'static, Result<Vec<Record>>>,
+    records: std::vec::IntoIter<Record>,
+    next: Option<(HashValue, Record)>,
+}
+
+impl LayerRecords {
+    fn new(chunks: BoxStream<'static, Result<Vec<Record>>>) -> Self {
+        Self {
+            chunks,
+            records: Vec::new().into_iter(),
+            next: None,
+        }
+    }
+
+    async fn peek_key_hash(&mut self) -> Result<Option<HashValue>> {
+        while self.next.is_none() {
+            if let Some(record) = self.records.next() {
+                self.next = Some((record.0.hash(), record));
+            } else if let Some(records) = self.chunks.try_next().await? {
+                self.records = records.into_iter();
+            } else {
+                break;
+            }
+        }
+        Ok(self.next.as_ref().map(|(key_hash, _)| *key_hash))
+    }
+
+    fn take(&mut self) -> Option<Record> {
+        self.next.take().map(|(_, record)| record)
+    }
+}
@@ -50,3 +50,52 @@ pub struct StateSnapshotBackup {
     /// limits the requirement on such `EpochStateBackup` to no older than the same epoch.
     pub proof: FileHandle,
 }
+
+/// A chunk of a state snapshot diff manifest, representing the changed keys in the key range
+/// [`first_key`, `last_key`] (right side inclusive).
+#[derive(Deserialize, Serialize)]
+pub struct StateSnapshotDiffChunk {
+    /// key of the first changed account in this chunk.
+    pub first_key: HashValue,
+    /// key of the last changed account in this chunk.
+    pub last_key: HashValue,
+    /// Repeated `len(record) + record` where `record` is BCS serialized tuple
+    /// `(key, Option<state_value>)`, `None` meaning the key is deleted.
+    pub blobs: FileHandle,
+}
+
+/// State snapshot diff backup manifest, representing the changes to the state view of the base
+/// snapshot, which results in the state view at specified version.
+#[derive(Deserialize, Serialize)]
+pub struct StateSnapshotDiffBackup {
+    /// Version at which this state snapshot is taken.
+    pub version: Version,
+    /// Epoch in which this state snapshot is taken.
+    pub epoch: u64,
+    /// Hash of the state tree root.
+    pub root_hash: HashValue,
+    /// Version of the base state snapshot.
+    pub base_version: Version,
+    /// Manifest of the base state snapshot, either a `StateSnapshotBackup` or a
+    /// `StateSnapshotDiffBackup`.
+    pub base_manifest: FileHandle,
+    /// Changed account blobs in chunks.
+    pub chunks: Vec<StateSnapshotDiffChunk>,
+    /// BCS serialized `Vec<(HashValue, SparseMerkleRangeProof)>`, the keys at which the full
+    /// state view is cut into chunks when restored, each with the proof that the accounts up to
+    /// it add up to `root_hash`. The keys are those of the base snapshot that still exist, plus
+    /// the last key at this version.
+    pub range_proofs: FileHandle,
+    /// BCS serialized `Tuple(TransactionInfoWithProof, LedgerInfoWithSignatures)`, same as
+    /// `StateSnapshotBackup::proof`.
+    pub proof: FileHandle,
+}
+
+/// Either kind of state snapshot manifest, for loading the base of a diff.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum StateSnapshotManifest {
+    // The kinds are told apart by their fields, e.g. only diffs have `base_version`.
+    Diff(StateSnapshotDiffBackup),
+    Full(StateSnapshotBackup),
+}
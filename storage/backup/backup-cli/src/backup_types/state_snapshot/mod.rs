@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod diff_backup;
+pub mod diff_restore;
 pub mod manifest;
 pub mod restore;
 
@@ -232,7 +232,7 @@ impl StateSnapshotRestoreController {
         Ok(())
     }
 
-    fn validate_modules(blob: &[(StateKey, StateValue)]) {
+    pub(super) fn validate_modules(blob: &[(StateKey, StateValue)]) {
         // TODO: Instead of using default features, fetch them from the the state.
         let features = Features::default();
 
@@ -252,7 +252,7 @@ impl StateSnapshotRestoreController {
         }
     }
 
-    async fn read_state_value(
+    pub async fn read_state_value(
         storage: &Arc<dyn BackupStorage>,
         file_handle: FileHandle,
     ) -> Result<Vec<(StateKey, StateValue)>> {
@@ -5,6 +5,8 @@
 use crate::{
     backup_types::state_snapshot::{
         backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        diff_backup::{StateSnapshotDiffBackupController, StateSnapshotDiffBackupOpt},
+        diff_restore::StateSnapshotDiffRestoreController,
         restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
     },
     storage::{local_fs::LocalFs, BackupStorage},
@@ -111,3 +113,133 @@ fn end_to_end() {
 
     rt.shutdown_timeout(Duration::from_secs(1));
 }
+
+#[test]
+fn diff_end_to_end() {
+    // The chain of diffs needs two epochs ending after the first one, which always ends right
+    // away.
+    let (_src_db_dir, src_db) = loop {
+        let (src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+        let next_epoch = src_db
+            .get_latest_ledger_info()
+            .unwrap()
+            .ledger_info()
+            .next_block_epoch();
+        if next_epoch > 2 {
+            break (src_db_dir, src_db);
+        }
+    };
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+    let latest_epoch = src_db
+        .get_latest_ledger_info()
+        .unwrap()
+        .ledger_info()
+        .next_block_epoch()
+        - 1;
+    let snapshots: Vec<_> = [1, latest_epoch]
+        .into_iter()
+        .map(|epoch| {
+            let version = src_db
+                .get_epoch_ending_ledger_infos(epoch, epoch + 1)
+                .unwrap()
+                .ledger_info_with_sigs
+                .pop()
+                .unwrap()
+                .ledger_info()
+                .version();
+            let state_root_hash = src_db
+                .get_transactions(version, 1, version, false)
+                .unwrap()
+                .proof
+                .transaction_infos
+                .pop()
+                .unwrap()
+                .state_checkpoint_hash()
+                .unwrap();
+            (epoch, version, state_root_hash)
+        })
+        .collect();
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+    let global_backup_opt = GlobalBackupOpt {
+        max_chunk_size: 500,
+        concurrent_data_requests: 2,
+    };
+    let mut base_manifest = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt { epoch: 0 },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+
+    // Each diff is on top of the previous one, so the latest one is restored through the chain.
+    for (epoch, version, state_root_hash) in snapshots {
+        let manifest_handle = rt
+            .block_on(
+                StateSnapshotDiffBackupController::new(
+                    StateSnapshotDiffBackupOpt {
+                        epoch,
+                        base_manifest,
+                    },
+                    global_backup_opt.clone(),
+                    Arc::clone(&client),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap();
+
+        let tgt_db_dir = TempPath::new();
+        tgt_db_dir.create_as_dir().unwrap();
+        rt.block_on(
+            StateSnapshotDiffRestoreController::new(
+                StateSnapshotRestoreOpt {
+                    manifest_handle: manifest_handle.clone(),
+                    version,
+                    validate_modules: false,
+                    restore_mode: StateSnapshotRestoreMode::Default,
+                },
+                GlobalRestoreOpt {
+                    dry_run: false,
+                    db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                    target_version: None, // max
+                    trusted_waypoints: TrustedWaypointOpt::default(),
+                    rocksdb_opt: RocksdbOpt::default(),
+                    concurrent_downloads: ConcurrentDownloadsOpt::default(),
+                    replay_concurrency_level: ReplayConcurrencyLevelOpt::default(),
+                    enable_state_indices: false,
+                }
+                .try_into()
+                .unwrap(),
+                Arc::clone(&store),
+                None, /* epoch_history */
+            )
+            .run(),
+        )
+        .unwrap();
+
+        let tgt_db = AptosDB::new_readonly_for_test(&tgt_db_dir);
+        assert_eq!(
+            tgt_db
+                .get_state_snapshot_before(version + 1)
+                .unwrap()
+                .unwrap(),
+            (version, state_root_hash)
+        );
+        base_manifest = manifest_handle;
+    }
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
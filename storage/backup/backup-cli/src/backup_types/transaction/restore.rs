@@ -83,8 +83,7 @@ pub struct TransactionRestoreController {
     inner: TransactionRestoreBatchController,
 }
 
-#[allow(dead_code)]
-struct LoadedChunk {
+pub struct LoadedChunk {
     pub manifest: TransactionChunk,
     pub txns: Vec<Transaction>,
     pub txn_infos: Vec<TransactionInfo>,
@@ -95,7 +94,7 @@ struct LoadedChunk {
 }
 
 impl LoadedChunk {
-    async fn load(
+    pub async fn load(
         manifest: TransactionChunk,
         storage: &Arc<dyn BackupStorage>,
         epoch_history: Option<&Arc<EpochHistory>>,
@@ -448,6 +448,17 @@ impl BackupCompactor {
                 .await?;
             new_files.insert(file_handle);
         }
+        for range in
+            metaview.compact_state_snapshot_diff_backups(self.state_snapshot_file_compact_factor)?
+        {
+            let (state_diff_range, file_name) =
+                Metadata::compact_state_snapshot_diff_backup_range(range.to_vec())?;
+            let file_handle = self
+                .storage
+                .save_metadata_lines(&file_name, state_diff_range.as_slice())
+                .await?;
+            new_files.insert(file_handle);
+        }
 
         // Move expired files to the metadata backup folder
         let (to_move, compaction_meta) =
@@ -4,12 +4,15 @@
 
 use crate::{
     backup_types::{
-        epoch_ending::restore::EpochHistoryRestoreController,
-        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        epoch_ending::restore::{EpochHistory, EpochHistoryRestoreController},
+        state_snapshot::{
+            diff_restore::StateSnapshotDiffRestoreController,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
         transaction::restore::TransactionRestoreBatchController,
     },
     metadata,
-    metadata::{cache::MetadataCacheOpt, TransactionBackupMeta},
+    metadata::{cache::MetadataCacheOpt, view::StateSnapshotChain, TransactionBackupMeta},
     metrics::restore::{
         COORDINATOR_FAIL_TS, COORDINATOR_START_TS, COORDINATOR_SUCC_TS, COORDINATOR_TARGET_VERSION,
     },
@@ -158,9 +161,9 @@ impl RestoreCoordinator {
                     // already restored the kv snapshot, no need to restore again
                     None
                 } else {
-                    let snapshot = metadata_view.select_state_snapshot(ver)?;
+                    let snapshot = metadata_view.select_state_snapshot_chain(ver)?;
                     ensure!(
-                        snapshot.is_some() && snapshot.as_ref().unwrap().version == ver,
+                        snapshot.is_some() && snapshot.as_ref().unwrap().version() == ver,
                         "cannot find in-progress state snapshot {}",
                         ver
                     );
@@ -173,36 +176,36 @@ impl RestoreCoordinator {
                     "DB should be empty if no in-progress state snapshot found"
                 );
                 metadata_view
-                    .select_state_snapshot(std::cmp::min(lhs, max_txn_ver))
+                    .select_state_snapshot_chain(std::cmp::min(lhs, max_txn_ver))
                     .expect("Cannot find any snapshot before ledger history start version")
             },
         };
 
         let tree_snapshot = if let Some((latest_tree_version, _)) = latest_tree_version {
-            let snapshot = metadata_view.select_state_snapshot(latest_tree_version)?;
+            let snapshot = metadata_view.select_state_snapshot_chain(latest_tree_version)?;
 
             ensure!(
-                snapshot.is_some() && snapshot.as_ref().unwrap().version == latest_tree_version,
+                snapshot.is_some() && snapshot.as_ref().unwrap().version() == latest_tree_version,
                 "cannot find tree snapshot {}",
                 latest_tree_version
             );
             snapshot.unwrap()
         } else {
             metadata_view
-                .select_state_snapshot(target_version)?
+                .select_state_snapshot_chain(target_version)?
                 .expect("Cannot find tree snapshot before target version")
         };
 
         let do_phase_1 = if let Some(kv_snapshot) = kv_snapshot.as_ref() {
             // if we have a kv snapshot, we need to restore the state between lhs and rs
             // if the version are equal, we don't need to restore phase 1. we can directly restore a snapshot with both tree and KV, and then replay txn till the target_version
-            kv_snapshot.version < tree_snapshot.version
+            kv_snapshot.version() < tree_snapshot.version()
         } else {
             // if we don't have a kv snapshot, we need to restore the state between db_next_version and rs
-            db_next_version < tree_snapshot.version
+            db_next_version < tree_snapshot.version()
         };
         let txn_start_version = if let Some(kv_snapshot) = kv_snapshot.as_ref() {
-            kv_snapshot.version
+            kv_snapshot.version()
         } else {
             db_next_version
         };
@@ -232,26 +235,19 @@ impl RestoreCoordinator {
         if do_phase_1 {
             info!(
                 "Start restoring DB from version {} to tree snapshot version {}",
-                txn_start_version, tree_snapshot.version,
+                txn_start_version,
+                tree_snapshot.version(),
             );
 
             // phase 1.a: restore the kv snapshot
-            if kv_snapshot.is_some() {
-                let kv_snapshot = kv_snapshot.clone().unwrap();
-                info!("Start restoring KV snapshot at {}", kv_snapshot.version);
-
-                StateSnapshotRestoreController::new(
-                    StateSnapshotRestoreOpt {
-                        manifest_handle: kv_snapshot.manifest,
-                        version: kv_snapshot.version,
-                        validate_modules: false,
-                        restore_mode: StateSnapshotRestoreMode::KvOnly,
-                    },
-                    self.global_opt.clone(),
-                    Arc::clone(&self.storage),
+            if let Some(kv_snapshot) = kv_snapshot.as_ref() {
+                info!("Start restoring KV snapshot at {}", kv_snapshot.version());
+
+                self.restore_state_snapshot(
+                    kv_snapshot,
+                    StateSnapshotRestoreMode::KvOnly,
                     epoch_history.clone(),
                 )
-                .run()
                 .await?;
             }
 
@@ -259,7 +255,7 @@ impl RestoreCoordinator {
             let txn_manifests = transaction_backups
                 .iter()
                 .filter(|e| {
-                    e.first_version <= tree_snapshot.version && e.last_version >= db_next_version
+                    e.first_version <= tree_snapshot.version() && e.last_version >= db_next_version
                 })
                 .map(|e| e.manifest.clone())
                 .collect();
@@ -277,11 +273,11 @@ impl RestoreCoordinator {
             // We should replay kv to include the version of tree snapshot so that we can get correct storage usage at that version
             // while restore tree only snapshots
             let kv_replay_version = if let Some(kv_snapshot) = kv_snapshot.as_ref() {
-                kv_snapshot.version + 1
+                kv_snapshot.version() + 1
             } else {
                 db_next_version
             };
-            transaction_restore_opt.target_version = tree_snapshot.version;
+            transaction_restore_opt.target_version = tree_snapshot.version();
             TransactionRestoreBatchController::new(
                 transaction_restore_opt,
                 Arc::clone(&self.storage),
@@ -295,7 +291,7 @@ impl RestoreCoordinator {
             .run()
             .await?;
             // update the expected version for the first phase restore
-            db_next_version = tree_snapshot.version;
+            db_next_version = tree_snapshot.version();
         }
 
         // Phase 2: restore the full tree snapshot and replay till the target version
@@ -318,24 +314,14 @@ impl RestoreCoordinator {
                 };
                 info!(
                     "Start restoring tree snapshot at {} with db_next_version {}",
-                    tree_snapshot.version, db_next_version
+                    tree_snapshot.version(),
+                    db_next_version
                 );
 
-                StateSnapshotRestoreController::new(
-                    StateSnapshotRestoreOpt {
-                        manifest_handle: tree_snapshot.manifest.clone(),
-                        version: tree_snapshot.version,
-                        validate_modules: false,
-                        restore_mode,
-                    },
-                    self.global_opt.clone(),
-                    Arc::clone(&self.storage),
-                    epoch_history.clone(),
-                )
-                .run()
-                .await?;
+                self.restore_state_snapshot(&tree_snapshot, restore_mode, epoch_history.clone())
+                    .await?;
                 replay_version = Some((
-                    tree_snapshot.version + 1,
+                    tree_snapshot.version() + 1,
                     false, /*replay entire txn including update tree and KV*/
                 ));
             }
@@ -374,6 +360,45 @@ impl RestoreCoordinator {
             .unwrap_or_else(|| self.target_version())
     }
 
+    /// Restores a full state snapshot, or a chain of diffs on top of one.
+    async fn restore_state_snapshot(
+        &self,
+        snapshot: &StateSnapshotChain,
+        restore_mode: StateSnapshotRestoreMode,
+        epoch_history: Option<Arc<EpochHistory>>,
+    ) -> Result<()> {
+        let opt = StateSnapshotRestoreOpt {
+            manifest_handle: snapshot.manifest().clone(),
+            version: snapshot.version(),
+            validate_modules: false,
+            restore_mode,
+        };
+        if snapshot.diffs.is_empty() {
+            StateSnapshotRestoreController::new(
+                opt,
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+                epoch_history,
+            )
+            .run()
+            .await
+        } else {
+            info!(
+                base_version = snapshot.base.version,
+                diffs = snapshot.diffs.len(),
+                "Restoring state snapshot from diffs."
+            );
+            StateSnapshotDiffRestoreController::new(
+                opt,
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+                epoch_history,
+            )
+            .run()
+            .await
+        }
+    }
+
     #[allow(dead_code)]
     fn get_actual_target_version(
         &self,
@@ -5,7 +5,10 @@
 use crate::{
     backup_types::{
         epoch_ending::restore::EpochHistoryRestoreController,
-        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        state_snapshot::{
+            diff_restore::StateSnapshotDiffRestoreController,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
         transaction::restore::TransactionRestoreBatchController,
     },
     metadata,
@@ -91,7 +94,7 @@ impl VerifyCoordinator {
         .await?;
         let ver_max = Version::max_value();
         let state_snapshot =
-            metadata_view.select_state_snapshot(self.state_snapshot_before_version)?;
+            metadata_view.select_state_snapshot_chain(self.state_snapshot_before_version)?;
         let transactions =
             metadata_view.select_transaction_backups(self.start_version, self.end_version)?;
         let epoch_endings = metadata_view.select_epoch_ending_backups(ver_max)?;
@@ -121,25 +124,38 @@ impl VerifyCoordinator {
             ))
         };
 
-        if let Some(backup) = state_snapshot {
+        if let Some(snapshot) = state_snapshot {
             info!(
-                epoch = backup.epoch,
-                version = backup.version,
+                base_version = snapshot.base.version,
+                diffs = snapshot.diffs.len(),
+                version = snapshot.version(),
                 "State snapshot selected for verification."
             );
-            StateSnapshotRestoreController::new(
-                StateSnapshotRestoreOpt {
-                    manifest_handle: backup.manifest,
-                    version: backup.version,
-                    validate_modules: self.validate_modules,
-                    restore_mode: StateSnapshotRestoreMode::Default,
-                },
-                global_opt.clone(),
-                Arc::clone(&self.storage),
-                epoch_history.clone(),
-            )
-            .run()
-            .await?;
+            let opt = StateSnapshotRestoreOpt {
+                manifest_handle: snapshot.manifest().clone(),
+                version: snapshot.version(),
+                validate_modules: self.validate_modules,
+                restore_mode: StateSnapshotRestoreMode::Default,
+            };
+            if snapshot.diffs.is_empty() {
+                StateSnapshotRestoreController::new(
+                    opt,
+                    global_opt.clone(),
+                    Arc::clone(&self.storage),
+                    epoch_history.clone(),
+                )
+                .run()
+                .await?;
+            } else {
+                StateSnapshotDiffRestoreController::new(
+                    opt,
+                    global_opt.clone(),
+                    Arc::clone(&self.storage),
+                    epoch_history.clone(),
+                )
+                .run()
+                .await?;
+            }
         }
 
         let txn_manifests = transactions.into_iter().map(|b| b.manifest).collect();
@@ -72,7 +72,7 @@ async fn download_file(
 ) -> Result<()> {
     tokio::io::copy(
         &mut storage_ref
-            .open_for_read(file_handle)
+            .open_metadata_for_read(file_handle)
             .await
             .err_notes(file_handle)?,
         &mut OpenOptions::new()
@@ -211,7 +211,11 @@ pub async fn sync_and_load(
         "Metadata cache loaded.",
     );
 
-    Ok(MetadataView::new(metadata_vec, remote_file_handles))
+    let metadata_view = MetadataView::new(metadata_vec, remote_file_handles);
+    for (manifest, codec) in metadata_view.manifest_codecs() {
+        storage.expect_codec(manifest, codec);
+    }
+    Ok(metadata_view)
 }
 
 trait FileHandleHash {
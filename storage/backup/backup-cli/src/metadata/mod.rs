@@ -5,7 +5,7 @@
 pub mod cache;
 pub mod view;
 
-use crate::storage::{FileHandle, ShellSafeName, TextLine};
+use crate::storage::{codec::BackupCodec, FileHandle, ShellSafeName, TextLine};
 use anyhow::{ensure, Result};
 use aptos_crypto::HashValue;
 use aptos_types::transaction::Version;
@@ -20,6 +20,7 @@ pub(crate) enum Metadata {
     TransactionBackup(TransactionBackupMeta),
     Identity(IdentityMeta),
     CompactionTimestamps(CompactionTimestampsMeta),
+    StateSnapshotDiffBackup(StateSnapshotDiffBackupMeta),
 }
 
 impl Metadata {
@@ -29,6 +30,7 @@ impl Metadata {
         first_version: Version,
         last_version: Version,
         manifest: FileHandle,
+        codec: BackupCodec,
     ) -> Self {
         Self::EpochEndingBackup(EpochEndingBackupMeta {
             first_epoch,
@@ -36,14 +38,39 @@ impl Metadata {
             first_version,
             last_version,
             manifest,
+            codec,
         })
     }
 
-    pub fn new_state_snapshot_backup(epoch: u64, version: Version, manifest: FileHandle) -> Self {
+    pub fn new_state_snapshot_backup(
+        epoch: u64,
+        version: Version,
+        manifest: FileHandle,
+        codec: BackupCodec,
+    ) -> Self {
         Self::StateSnapshotBackup(StateSnapshotBackupMeta {
             epoch,
             version,
             manifest,
+            codec,
+        })
+    }
+
+    pub fn new_state_snapshot_diff_backup(
+        epoch: u64,
+        version: Version,
+        base_version: Version,
+        base_manifest: FileHandle,
+        manifest: FileHandle,
+        codec: BackupCodec,
+    ) -> Self {
+        Self::StateSnapshotDiffBackup(StateSnapshotDiffBackupMeta {
+            epoch,
+            version,
+            base_version,
+            base_manifest,
+            manifest,
+            codec,
         })
     }
 
@@ -51,11 +78,13 @@ impl Metadata {
         first_version: Version,
         last_version: Version,
         manifest: FileHandle,
+        codec: BackupCodec,
     ) -> Self {
         Self::TransactionBackup(TransactionBackupMeta {
             first_version,
             last_version,
             manifest,
+            codec,
         })
     }
 
@@ -112,6 +141,25 @@ impl Metadata {
         Ok((res, name.parse()?))
     }
 
+    pub fn compact_state_snapshot_diff_backup_range(
+        backup_metas: Vec<StateSnapshotDiffBackupMeta>,
+    ) -> Result<(Vec<TextLine>, ShellSafeName)> {
+        ensure!(
+            !backup_metas.is_empty(),
+            "compacting an empty metadata vector"
+        );
+        let name = format!(
+            "state_snapshot_diff_compacted_epoch_{}_{}.meta",
+            backup_metas[0].epoch,
+            backup_metas[backup_metas.len() - 1].epoch
+        );
+        let res: Vec<TextLine> = backup_metas
+            .into_iter()
+            .map(|e| Metadata::StateSnapshotDiffBackup(e).to_text_line())
+            .collect::<Result<_>>()?;
+        Ok((res, name.parse()?))
+    }
+
     pub fn compact_transaction_backup_range(
         backup_metas: Vec<TransactionBackupMeta>,
     ) -> Result<(Vec<TextLine>, ShellSafeName)> {
@@ -163,6 +211,9 @@ impl Metadata {
             Self::CompactionTimestamps(e) => {
                 format!("compaction_timestamps_{}.meta", e.file_compacted_at,)
             },
+            Self::StateSnapshotDiffBackup(s) => {
+                format!("state_snapshot_diff_ver_{}.meta", s.version)
+            },
         }
         .try_into()
         .unwrap()
@@ -180,6 +231,8 @@ pub struct EpochEndingBackupMeta {
     pub first_version: Version,
     pub last_version: Version,
     pub manifest: FileHandle,
+    #[serde(default, skip_serializing_if = "BackupCodec::is_plain")]
+    pub codec: BackupCodec,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
@@ -187,6 +240,21 @@ pub struct StateSnapshotBackupMeta {
     pub epoch: u64,
     pub version: Version,
     pub manifest: FileHandle,
+    #[serde(default, skip_serializing_if = "BackupCodec::is_plain")]
+    pub codec: BackupCodec,
+}
+
+/// A state snapshot stored as the changes from the one in `base_manifest`, which is either a full
+/// snapshot or another diff.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StateSnapshotDiffBackupMeta {
+    pub epoch: u64,
+    pub version: Version,
+    pub base_version: Version,
+    pub base_manifest: FileHandle,
+    pub manifest: FileHandle,
+    #[serde(default, skip_serializing_if = "BackupCodec::is_plain")]
+    pub codec: BackupCodec,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
@@ -194,6 +262,8 @@ pub struct TransactionBackupMeta {
     pub first_version: Version,
     pub last_version: Version,
     pub manifest: FileHandle,
+    #[serde(default, skip_serializing_if = "BackupCodec::is_plain")]
+    pub codec: BackupCodec,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
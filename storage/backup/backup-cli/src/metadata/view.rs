@@ -5,13 +5,14 @@
 use crate::{
     metadata::{
         CompactionTimestampsMeta, EpochEndingBackupMeta, IdentityMeta, Metadata,
-        StateSnapshotBackupMeta, TransactionBackupMeta,
+        StateSnapshotBackupMeta, StateSnapshotDiffBackupMeta, TransactionBackupMeta,
     },
     metrics::backup::COMPACTED_TXN_VERSION,
-    storage::FileHandle,
+    storage::{codec::BackupCodec, FileHandle},
 };
 use anyhow::{anyhow, ensure, Result};
 use aptos_infallible::duration_since_epoch;
+use aptos_logger::prelude::*;
 use aptos_types::transaction::Version;
 use itertools::Itertools;
 use std::{fmt, str::FromStr};
@@ -20,6 +21,7 @@ use std::{fmt, str::FromStr};
 pub struct MetadataView {
     epoch_ending_backups: Vec<EpochEndingBackupMeta>,
     state_snapshot_backups: Vec<StateSnapshotBackupMeta>,
+    state_snapshot_diff_backups: Vec<StateSnapshotDiffBackupMeta>,
     transaction_backups: Vec<TransactionBackupMeta>,
     _identity: Option<IdentityMeta>,
     // The compaction timestamps of the file handles producing this view
@@ -30,6 +32,7 @@ impl MetadataView {
     pub(crate) fn new(metadata_vec: Vec<Metadata>, file_handles: Vec<FileHandle>) -> Self {
         let mut epoch_ending_backups = Vec::new();
         let mut state_snapshot_backups = Vec::new();
+        let mut state_snapshot_diff_backups = Vec::new();
         let mut transaction_backups = Vec::new();
         let mut identity = None;
         let mut compaction_timestamps = Vec::new();
@@ -41,12 +44,15 @@ impl MetadataView {
                 Metadata::TransactionBackup(t) => transaction_backups.push(t),
                 Metadata::Identity(i) => identity = Some(i),
                 Metadata::CompactionTimestamps(t) => compaction_timestamps.push(t),
+                Metadata::StateSnapshotDiffBackup(s) => state_snapshot_diff_backups.push(s),
             }
         }
         epoch_ending_backups.sort_unstable();
         epoch_ending_backups.dedup();
         state_snapshot_backups.sort_unstable();
         state_snapshot_backups.dedup();
+        state_snapshot_diff_backups.sort_unstable();
+        state_snapshot_diff_backups.dedup();
         transaction_backups.sort_unstable();
         transaction_backups.dedup();
 
@@ -72,6 +78,7 @@ impl MetadataView {
         Self {
             epoch_ending_backups,
             state_snapshot_backups,
+            state_snapshot_diff_backups,
             transaction_backups,
             _identity: identity,
             compaction_timestamps: compaction_meta_opt,
@@ -101,6 +108,26 @@ impl MetadataView {
         })
     }
 
+    /// All epoch ending backups, ordered by their first epoch.
+    pub fn epoch_ending_backups(&self) -> &[EpochEndingBackupMeta] {
+        &self.epoch_ending_backups
+    }
+
+    /// All transaction backups, ordered by their first version.
+    pub fn transaction_backups(&self) -> &[TransactionBackupMeta] {
+        &self.transaction_backups
+    }
+
+    /// All full state snapshot backups, ordered by epoch.
+    pub fn state_snapshot_backups(&self) -> &[StateSnapshotBackupMeta] {
+        &self.state_snapshot_backups
+    }
+
+    /// All state snapshot diff backups, ordered by epoch.
+    pub fn state_snapshot_diff_backups(&self) -> &[StateSnapshotDiffBackupMeta] {
+        &self.state_snapshot_diff_backups
+    }
+
     pub fn select_latest_compaction_timestamps(&self) -> Option<CompactionTimestampsMeta> {
         self.compaction_timestamps.clone()
     }
@@ -118,6 +145,65 @@ impl MetadataView {
             .cloned())
     }
 
+    /// Selects the latest state snapshot at or before `target_version`, which can be a chain of
+    /// diffs on top of a full snapshot, as long as every link of the chain is available.
+    pub fn select_state_snapshot_chain(
+        &self,
+        target_version: Version,
+    ) -> Result<Option<StateSnapshotChain>> {
+        let full_snapshot = self.select_state_snapshot(target_version)?;
+        let full_snapshot_version = full_snapshot.as_ref().map(|s| s.version);
+        for diff in self.state_snapshot_diff_backups.iter().sorted().rev() {
+            if diff.version > target_version {
+                continue;
+            }
+            if full_snapshot_version.map_or(false, |v| v >= diff.version) {
+                break;
+            }
+            match self.resolve_state_snapshot_chain(diff) {
+                Some(chain) => return Ok(Some(chain)),
+                None => warn!(
+                    "Base of state snapshot diff at version {} is missing, skipping.",
+                    diff.version,
+                ),
+            }
+        }
+
+        Ok(full_snapshot.map(|base| StateSnapshotChain {
+            base,
+            diffs: Vec::new(),
+        }))
+    }
+
+    fn resolve_state_snapshot_chain(
+        &self,
+        diff: &StateSnapshotDiffBackupMeta,
+    ) -> Option<StateSnapshotChain> {
+        let mut diffs = vec![diff.clone()];
+        loop {
+            let last = diffs.last().unwrap();
+            if last.base_version >= last.version {
+                return None;
+            }
+            if let Some(base) = self
+                .state_snapshot_backups
+                .iter()
+                .find(|s| s.manifest == last.base_manifest && s.version == last.base_version)
+            {
+                diffs.reverse();
+                return Some(StateSnapshotChain {
+                    base: base.clone(),
+                    diffs,
+                });
+            }
+            let next = self
+                .state_snapshot_diff_backups
+                .iter()
+                .find(|d| d.manifest == last.base_manifest && d.version == last.base_version)?;
+            diffs.push(next.clone());
+        }
+    }
+
     pub fn expect_state_snapshot(&self, version: Version) -> Result<StateSnapshotBackupMeta> {
         self.state_snapshot_backups
             .iter()
@@ -235,6 +321,37 @@ impl MetadataView {
         Self::compact_backups(&self.state_snapshot_backups, compaction_cnt)
     }
 
+    pub fn compact_state_snapshot_diff_backups(
+        &mut self,
+        compaction_cnt: usize,
+    ) -> Result<Vec<&[StateSnapshotDiffBackupMeta]>> {
+        Self::compact_backups(&self.state_snapshot_diff_backups, compaction_cnt)
+    }
+
+    /// The manifests of all the backups, with the codec recorded for each backup.
+    pub fn manifest_codecs(&self) -> impl Iterator<Item = (&FileHandle, BackupCodec)> {
+        let epoch_ending = self
+            .epoch_ending_backups
+            .iter()
+            .map(|b| (&b.manifest, b.codec));
+        let state_snapshot = self
+            .state_snapshot_backups
+            .iter()
+            .map(|b| (&b.manifest, b.codec));
+        let state_snapshot_diff = self
+            .state_snapshot_diff_backups
+            .iter()
+            .map(|b| (&b.manifest, b.codec));
+        let transaction = self
+            .transaction_backups
+            .iter()
+            .map(|b| (&b.manifest, b.codec));
+        epoch_ending
+            .chain(state_snapshot)
+            .chain(state_snapshot_diff)
+            .chain(transaction)
+    }
+
     pub fn get_file_handles(&self) -> Vec<FileHandle> {
         self.select_latest_compaction_timestamps()
             .as_ref()
@@ -243,6 +360,25 @@ impl MetadataView {
     }
 }
 
+/// A full state snapshot and the diffs to be applied on top of it, oldest first.
+#[derive(Clone, Debug)]
+pub struct StateSnapshotChain {
+    pub base: StateSnapshotBackupMeta,
+    pub diffs: Vec<StateSnapshotDiffBackupMeta>,
+}
+
+impl StateSnapshotChain {
+    pub fn version(&self) -> Version {
+        self.diffs.last().map_or(self.base.version, |d| d.version)
+    }
+
+    pub fn manifest(&self) -> &FileHandle {
+        self.diffs
+            .last()
+            .map_or(&self.base.manifest, |d| &d.manifest)
+    }
+}
+
 pub struct BackupStorageState {
     pub latest_epoch_ending_epoch: Option<u64>,
     pub latest_state_snapshot_epoch: Option<u64>,
@@ -0,0 +1,628 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    storage::{
+        BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+        TextLine,
+    },
+    utils::error_notes::ErrorNotes,
+};
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
+use anyhow::{ensure, format_err, Result};
+use aptos_compression::zstd::{StreamCompressor, StreamDecompressor};
+use aptos_infallible::RwLock;
+use async_trait::async_trait;
+use clap::Parser;
+use futures::{
+    ready,
+    task::{Context, Poll},
+    Future,
+};
+use rand::random;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+use tokio::{
+    io::{duplex, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf},
+    sync::oneshot,
+    task::JoinHandle,
+};
+
+/// Leads every encoded file, followed by a byte of codec flags. Files without it are read as is,
+/// so backups taken before a codec was configured stay readable, unless an encryption key is
+/// configured.
+const MAGIC: &[u8] = b"\xffAPTBKC\x01";
+const HEADER_SIZE: usize = MAGIC.len() + 1;
+/// The content is compressed as a single zstd frame.
+const ZSTD_COMPRESSED: u8 = 0x4;
+/// The (compressed) content is split into segments, each encrypted with AES-256-GCM.
+const AES_256_GCM_ENCRYPTED: u8 = 0x8;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+/// Size of the encrypted segments, before encryption. All but the last one are full.
+const SEGMENT_SIZE: usize = 1024 * 1024;
+/// Size of the buffer between the writer (or reader) and the background encoding (or decoding).
+const PIPE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// How the files of a backup are encoded on top of their BCS or JSON content. Recorded in the
+/// backup metadata.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
+pub struct BackupCodec {
+    /// Compressed with zstd.
+    pub compressed: bool,
+    /// Encrypted with AES-256-GCM, after being compressed.
+    pub encrypted: bool,
+}
+
+impl BackupCodec {
+    pub fn is_plain(&self) -> bool {
+        !self.compressed && !self.encrypted
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.compressed {
+            flags |= ZSTD_COMPRESSED;
+        }
+        if self.encrypted {
+            flags |= AES_256_GCM_ENCRYPTED;
+        }
+        flags
+    }
+
+    fn from_flags(flags: u8) -> Result<Self> {
+        ensure!(
+            flags & !(ZSTD_COMPRESSED | AES_256_GCM_ENCRYPTED) == 0,
+            "Unknown backup codec flags: {:#x}",
+            flags,
+        );
+        Ok(Self {
+            compressed: flags & ZSTD_COMPRESSED != 0,
+            encrypted: flags & AES_256_GCM_ENCRYPTED != 0,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Parser)]
+pub struct BackupCodecOpt {
+    #[clap(
+        long,
+        help = "Compress every backup file written with zstd, except for the metadata files. \
+        Compressed files are recognized and decompressed when read regardless of this flag."
+    )]
+    pub compress_backup: bool,
+    #[clap(
+        long,
+        value_parser,
+        help = "File holding a hex encoded 32 byte AES-256-GCM key. When given, every backup file \
+        written, except for the metadata files, is encrypted with it, and every backup file read, \
+        except for the metadata files, must be encrypted with it."
+    )]
+    pub backup_encryption_key_file: Option<PathBuf>,
+}
+
+/// A BackupStorage that encodes files written to the wrapped storage with the configured codec,
+/// and decodes files read from it according to their header. Metadata files are left plain so
+/// that they can be listed and indexed without a key; the codec of each backup is recorded in
+/// them instead.
+///
+/// Files read must be encoded with the codec expected for them (see
+/// `BackupStorage::expect_codec()`), and must be encrypted if a key is configured, as otherwise
+/// whoever controls the storage could replace them with unencrypted ones.
+pub struct CodecStorage {
+    inner: Arc<dyn BackupStorage>,
+    codec: BackupCodec,
+    cipher: Option<Aes256Gcm>,
+    expected_codecs: RwLock<HashMap<FileHandle, BackupCodec>>,
+}
+
+impl CodecStorage {
+    pub fn new(
+        inner: Arc<dyn BackupStorage>,
+        compressed: bool,
+        key: Option<&[u8]>,
+    ) -> Result<Self> {
+        let cipher = key
+            .map(|key| {
+                ensure!(
+                    key.len() == KEY_SIZE,
+                    "Backup encryption key must be {} bytes, got {}.",
+                    KEY_SIZE,
+                    key.len(),
+                );
+                Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            inner,
+            codec: BackupCodec {
+                compressed,
+                encrypted: cipher.is_some(),
+            },
+            cipher,
+            expected_codecs: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub async fn new_with_opt(inner: Arc<dyn BackupStorage>, opt: BackupCodecOpt) -> Result<Self> {
+        let key = match &opt.backup_encryption_key_file {
+            Some(path) => Some(Self::load_key(path).await?),
+            None => None,
+        };
+        Self::new(inner, opt.compress_backup, key.as_deref())
+    }
+
+    async fn load_key(path: &Path) -> Result<Vec<u8>> {
+        let key_hex = tokio::fs::read_to_string(path).await.err_notes(path)?;
+        hex::decode(key_hex.trim())
+            .map_err(|e| format_err!("Failed to parse backup encryption key: {}", e))
+    }
+}
+
+#[async_trait]
+impl BackupStorage for CodecStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let (file_handle, file) = self.inner.create_for_write(backup_handle, name).await?;
+        if self.codec.is_plain() {
+            return Ok((file_handle, file));
+        }
+        let writer = EncodingWriter::new(self.codec, self.cipher.clone(), file);
+        Ok((file_handle, Box::new(writer)))
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        let mut file = self.inner.open_for_read(file_handle).await?;
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        (&mut file)
+            .take(HEADER_SIZE as u64)
+            .read_to_end(&mut header)
+            .await?;
+        let has_header = header.len() == HEADER_SIZE && header.starts_with(MAGIC);
+        let codec = if has_header {
+            BackupCodec::from_flags(header[MAGIC.len()]).err_notes(file_handle)?
+        } else {
+            BackupCodec::default()
+        };
+
+        if let Some(expected_codec) = self.expected_codec(file_handle) {
+            ensure!(
+                codec == expected_codec,
+                "File {} is encoded with {:?}, but its backup is recorded with {:?}.",
+                file_handle,
+                codec,
+                expected_codec,
+            );
+        }
+        ensure!(
+            self.cipher.is_none() || codec.encrypted,
+            "File {} is not encrypted, but a backup encryption key is configured.",
+            file_handle,
+        );
+        if !has_header {
+            return Ok(Box::new(Cursor::new(header).chain(file)));
+        }
+
+        let reader =
+            DecodingReader::new(codec, self.cipher.clone(), header, file).err_notes(file_handle)?;
+        Ok(Box::new(reader))
+    }
+
+    async fn open_metadata_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.inner.open_metadata_for_read(file_handle).await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        self.inner.list_metadata_files().await
+    }
+
+    async fn backup_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        self.inner.backup_metadata_file(file_handle).await
+    }
+
+    async fn save_metadata_lines(
+        &self,
+        name: &ShellSafeName,
+        lines: &[TextLine],
+    ) -> Result<FileHandle> {
+        self.inner.save_metadata_lines(name, lines).await
+    }
+
+    fn codec(&self) -> BackupCodec {
+        self.codec
+    }
+
+    fn expect_codec(&self, file_handle: &FileHandleRef, codec: BackupCodec) {
+        self.expected_codecs
+            .write()
+            .entry(file_handle.to_string())
+            .or_insert(codec);
+    }
+
+    fn expected_codec(&self, file_handle: &FileHandleRef) -> Option<BackupCodec> {
+        self.expected_codecs.read().get(file_handle).copied()
+    }
+}
+
+/// Encodes the content of a file as it is written, see `EncodingWriter`.
+struct Encoder {
+    header: Vec<u8>,
+    compressor: Option<StreamCompressor>,
+    cipher: Option<Aes256Gcm>,
+    /// Compressed content not encrypted yet, shorter than a segment.
+    segment: Vec<u8>,
+    segment_index: u64,
+    file: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl Encoder {
+    async fn new(
+        codec: BackupCodec,
+        cipher: Option<Aes256Gcm>,
+        mut file: Box<dyn AsyncWrite + Send + Unpin>,
+    ) -> Result<Self> {
+        let compressor = if codec.compressed {
+            Some(StreamCompressor::new()?)
+        } else {
+            None
+        };
+        let cipher = if codec.encrypted {
+            Some(cipher.ok_or_else(|| format_err!("No backup encryption key configured."))?)
+        } else {
+            None
+        };
+
+        let mut header = MAGIC.to_vec();
+        header.push(codec.flags());
+        file.write_all(&header).await?;
+
+        Ok(Self {
+            header,
+            compressor,
+            cipher,
+            segment: Vec::new(),
+            segment_index: 0,
+            file,
+        })
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        let bytes = match &mut self.compressor {
+            Some(compressor) => compressor.compress(bytes)?,
+            None => bytes.to_vec(),
+        };
+        self.write_compressed(&bytes).await
+    }
+
+    async fn write_compressed(&mut self, bytes: &[u8]) -> Result<()> {
+        match &self.cipher {
+            Some(cipher) => {
+                // Full segments are encrypted right away, so the last one is always shorter.
+                self.segment.extend_from_slice(bytes);
+                while self.segment.len() >= SEGMENT_SIZE {
+                    let rest = self.segment.split_off(SEGMENT_SIZE);
+                    let segment = std::mem::replace(&mut self.segment, rest);
+                    let encrypted =
+                        encrypt_segment(cipher, &self.header, self.segment_index, false, &segment)?;
+                    self.segment_index += 1;
+                    self.file.write_all(&encrypted).await?;
+                }
+            },
+            None => self.file.write_all(bytes).await?,
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<()> {
+        let bytes = match self.compressor.take() {
+            Some(compressor) => compressor.finish()?,
+            None => Vec::new(),
+        };
+        self.write_compressed(&bytes).await?;
+        if let Some(cipher) = &self.cipher {
+            let encrypted = encrypt_segment(
+                cipher,
+                &self.header,
+                self.segment_index,
+                true,
+                &self.segment,
+            )?;
+            self.file.write_all(&encrypted).await?;
+        }
+        self.file.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// Decodes the content of a file as it is read, see `DecodingReader`.
+struct Decoder {
+    header: Vec<u8>,
+    decompressor: Option<StreamDecompressor>,
+    cipher: Option<Aes256Gcm>,
+    file: Box<dyn AsyncRead + Send + Unpin>,
+    output: DuplexStream,
+}
+
+impl Decoder {
+    fn new(
+        codec: BackupCodec,
+        cipher: Option<Aes256Gcm>,
+        header: Vec<u8>,
+        file: Box<dyn AsyncRead + Send + Unpin>,
+        output: DuplexStream,
+    ) -> Result<Self> {
+        let decompressor = if codec.compressed {
+            Some(StreamDecompressor::new()?)
+        } else {
+            None
+        };
+        let cipher = if codec.encrypted {
+            Some(cipher.ok_or_else(|| {
+                format_err!("File is encrypted but no backup encryption key is configured.")
+            })?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            header,
+            decompressor,
+            cipher,
+            file,
+            output,
+        })
+    }
+
+    async fn run(mut self) -> Result<()> {
+        let mut segment_index = 0;
+        loop {
+            let mut buffer = match self.cipher {
+                Some(_) => vec![0; NONCE_SIZE + SEGMENT_SIZE + TAG_SIZE],
+                None => vec![0; PIPE_BUFFER_BYTES],
+            };
+            let len = read_up_to(&mut self.file, &mut buffer).await?;
+            let end_of_file = len < buffer.len();
+            let bytes = match &self.cipher {
+                // Only the last segment is shorter than a full one.
+                Some(cipher) => decrypt_segment(
+                    cipher,
+                    &self.header,
+                    segment_index,
+                    end_of_file,
+                    &buffer[..len],
+                )?,
+                None => {
+                    buffer.truncate(len);
+                    buffer
+                },
+            };
+            self.write_decrypted(&bytes).await?;
+            if end_of_file {
+                break;
+            }
+            segment_index += 1;
+        }
+
+        ensure!(
+            self.decompressor
+                .as_ref()
+                .map_or(true, StreamDecompressor::is_frame_finished),
+            "Compressed file is truncated."
+        );
+        self.output.shutdown().await?;
+        Ok(())
+    }
+
+    async fn write_decrypted(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let bytes = match &mut self.decompressor {
+            Some(decompressor) => decompressor.decompress(bytes)?,
+            None => bytes.to_vec(),
+        };
+        self.output.write_all(&bytes).await?;
+        Ok(())
+    }
+}
+
+/// Binds a segment to its position in the file, so that segments can't be reordered or dropped
+/// without failing the decryption.
+fn segment_aad(header: &[u8], index: u64, last: bool) -> Vec<u8> {
+    [header, &index.to_be_bytes()[..], &[last as u8][..]].concat()
+}
+
+fn encrypt_segment(
+    cipher: &Aes256Gcm,
+    header: &[u8],
+    index: u64,
+    last: bool,
+    segment: &[u8],
+) -> Result<Vec<u8>> {
+    let nonce: [u8; NONCE_SIZE] = random();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload {
+            msg: segment,
+            aad: &segment_aad(header, index, last),
+        })
+        .map_err(|_| format_err!("Failed to encrypt backup file."))?;
+    Ok([&nonce[..], &ciphertext[..]].concat())
+}
+
+fn decrypt_segment(
+    cipher: &Aes256Gcm,
+    header: &[u8],
+    index: u64,
+    last: bool,
+    segment: &[u8],
+) -> Result<Vec<u8>> {
+    ensure!(
+        segment.len() >= NONCE_SIZE + TAG_SIZE,
+        "Encrypted file is truncated."
+    );
+    let (nonce, ciphertext) = segment.split_at(NONCE_SIZE);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload {
+            msg: ciphertext,
+            aad: &segment_aad(header, index, last),
+        })
+        .map_err(|_| format_err!("Failed to decrypt file, wrong key or the file is corrupted."))
+}
+
+/// Reads until the buffer is full or the end of the file, and returns the number of bytes read.
+async fn read_up_to(
+    file: &mut Box<dyn AsyncRead + Send + Unpin>,
+    buffer: &mut [u8],
+) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        let read = file.read(&mut buffer[len..]).await?;
+        if read == 0 {
+            break;
+        }
+        len += read;
+    }
+    Ok(len)
+}
+
+/// Encodes what is written to it and writes it to the wrapped file in the background, a segment
+/// at a time. The file is only complete once the writer is shut down; if the writer is dropped
+/// before that, the wrapped file is dropped without being shut down either.
+struct EncodingWriter {
+    pipe: DuplexStream,
+    finished: Option<oneshot::Sender<()>>,
+    encoding: JoinHandle<Result<()>>,
+}
+
+impl EncodingWriter {
+    fn new(
+        codec: BackupCodec,
+        cipher: Option<Aes256Gcm>,
+        file: Box<dyn AsyncWrite + Send + Unpin>,
+    ) -> Self {
+        let (pipe, mut encoding_pipe) = duplex(PIPE_BUFFER_BYTES);
+        let (finished_tx, finished_rx) = oneshot::channel();
+        let encoding = tokio::spawn(async move {
+            let mut encoder = Encoder::new(codec, cipher, file).await?;
+            let mut buffer = vec![0; PIPE_BUFFER_BYTES];
+            loop {
+                let len = encoding_pipe.read(&mut buffer).await?;
+                if len == 0 {
+                    break;
+                }
+                encoder.write(&buffer[..len]).await?;
+            }
+            finished_rx.await.map_err(|_| {
+                format_err!("Writer dropped before being shut down, file not finished.")
+            })?;
+            encoder.finish().await
+        });
+
+        Self {
+            pipe,
+            finished: Some(finished_tx),
+            encoding,
+        }
+    }
+}
+impl AsyncWrite for EncodingWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.pipe).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.pipe).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(finished) = self.finished.take() {
+            // The encoding fails by itself if it stopped already.
+            let _ = finished.send(());
+        }
+        ready!(Pin::new(&mut self.pipe).poll_shutdown(cx))?;
+
+        Pin::new(&mut self.encoding).poll(cx).map(|res| {
+            res.map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        })
+    }
+}
+
+/// Reads the content of an encoded file, decoded in the background a segment at a time. Fails at
+/// the end of the content if the file could not be decoded entirely.
+struct DecodingReader {
+    pipe: DuplexStream,
+    decoding: Option<JoinHandle<Result<()>>>,
+}
+
+impl DecodingReader {
+    fn new(
+        codec: BackupCodec,
+        cipher: Option<Aes256Gcm>,
+        header: Vec<u8>,
+        file: Box<dyn AsyncRead + Send + Unpin>,
+    ) -> Result<Self> {
+        let (pipe, decoding_pipe) = duplex(PIPE_BUFFER_BYTES);
+        let decoder = Decoder::new(codec, cipher, header, file, decoding_pipe)?;
+        Ok(Self {
+            pipe,
+            decoding: Some(tokio::spawn(decoder.run())),
+        })
+    }
+}
+
+impl AsyncRead for DecodingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut self.pipe).poll_read(cx, buf))?;
+        if buf.filled().len() > filled || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        // The end of the content, which is only valid if the whole file was decoded.
+        let Some(decoding) = self.decoding.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+        let res = ready!(Pin::new(decoding).poll(cx));
+        self.decoding = None;
+        Poll::Ready(
+            res.map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
+        )
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    storage::{
+        local_fs::LocalFs,
+        test_util::{
+            arb_backups, arb_metadata_files, test_save_and_list_metadata_files_impl,
+            test_write_and_read_impl,
+        },
+    },
+    utils::storage_ext::BackupStorageExt,
+};
+use aptos_temppath::TempPath;
+use proptest::prelude::*;
+use std::str::FromStr;
+use tokio::runtime::Runtime;
+
+const KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+
+fn local_fs(tmpdir: &TempPath) -> Arc<dyn BackupStorage> {
+    Arc::new(LocalFs::new(tmpdir.path().to_path_buf()))
+}
+
+async fn write_file(storage: &Arc<dyn BackupStorage>, content: &[u8]) -> FileHandle {
+    write_named_file(storage, "file", content).await
+}
+
+async fn write_named_file(
+    storage: &Arc<dyn BackupStorage>,
+    name: &str,
+    content: &[u8],
+) -> FileHandle {
+    let backup_handle = storage
+        .create_backup(&ShellSafeName::from_str("backup").unwrap())
+        .await
+        .unwrap();
+    let (file_handle, mut file) = storage
+        .create_for_write(&backup_handle, &ShellSafeName::from_str(name).unwrap())
+        .await
+        .unwrap();
+    file.write_all(content).await.unwrap();
+    file.shutdown().await.unwrap();
+    file_handle
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_write_and_read(
+        backups in arb_backups(),
+        compressed in any::<bool>(),
+        encrypted in any::<bool>(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let key = if encrypted { Some(&KEY[..]) } else { None };
+        let store = CodecStorage::new(local_fs(&tmpdir), compressed, key).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_write_and_read_impl(Box::new(store), backups));
+    }
+
+    #[test]
+    fn test_save_list_metadata_files(
+        input in arb_metadata_files(),
+    ) {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let store = CodecStorage::new(local_fs(&tmpdir), true, Some(&KEY[..])).unwrap();
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
+    }
+}
+
+#[test]
+fn test_read_plain_file() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let content = b"written before a codec was configured".to_vec();
+        let file_handle = write_file(&local_fs(&tmpdir), &content).await;
+
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), true, None).unwrap());
+        assert_eq!(storage.read_all(&file_handle).await.unwrap(), content);
+
+        // Plain files could have replaced encrypted ones, so they can't be read with a key.
+        let with_key: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), true, Some(&KEY[..])).unwrap());
+        assert!(with_key.read_all(&file_handle).await.is_err());
+        // Metadata files are never encoded.
+        let mut metadata = String::new();
+        with_key
+            .open_metadata_for_read(&file_handle)
+            .await
+            .unwrap()
+            .read_to_string(&mut metadata)
+            .await
+            .unwrap();
+        assert_eq!(metadata.as_bytes(), &content[..]);
+    });
+}
+
+#[test]
+fn test_read_file_of_unexpected_codec() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let content = vec![42u8; 4096];
+        let compressed = BackupCodec {
+            compressed: true,
+            encrypted: false,
+        };
+        let plain_file = write_file(&local_fs(&tmpdir), &content).await;
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), true, None).unwrap());
+        let compressed_file = write_named_file(&storage, "compressed", &content).await;
+
+        storage.expect_codec(&compressed_file, compressed);
+        assert_eq!(storage.read_all(&compressed_file).await.unwrap(), content);
+        storage.expect_codec(&plain_file, compressed);
+        assert!(storage.read_all(&plain_file).await.is_err());
+
+        // The first expectation recorded is kept.
+        storage.expect_codec(&plain_file, BackupCodec::default());
+        assert_eq!(storage.expected_codec(&plain_file), Some(compressed));
+        assert!(storage.read_all(&plain_file).await.is_err());
+    });
+}
+
+#[test]
+fn test_manifest_codec_applies_to_its_files() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), true, None).unwrap());
+        let plain_file = write_file(&local_fs(&tmpdir), b"plain").await;
+        let manifest = serde_json::json!({ "chunks": [{ "blobs": plain_file }] });
+        let manifest_file =
+            write_named_file(&storage, "manifest", manifest.to_string().as_bytes()).await;
+
+        storage.expect_codec(&manifest_file, storage.codec());
+        let loaded: serde_json::Value = storage.load_json_file(&manifest_file).await.unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(storage.expected_codec(&plain_file), Some(storage.codec()));
+        assert!(storage.read_all(&plain_file).await.is_err());
+    });
+}
+
+#[test]
+fn test_read_encrypted_file() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let tmpdir = TempPath::new();
+        tmpdir.create_as_dir().unwrap();
+        let content = vec![42u8; 4096];
+        let storage: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), true, Some(&KEY[..])).unwrap());
+        assert_eq!(storage.codec(), BackupCodec {
+            compressed: true,
+            encrypted: true,
+        });
+        let file_handle = write_file(&storage, &content).await;
+
+        let raw = local_fs(&tmpdir).read_all(&file_handle).await.unwrap();
+        assert!(raw.starts_with(MAGIC));
+        assert!(raw.len() < content.len());
+
+        // Encrypted files can only be read with the key, whatever codec is configured for writing.
+        let no_key: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), false, None).unwrap());
+        assert!(no_key.read_all(&file_handle).await.is_err());
+        let wrong_key: Arc<dyn BackupStorage> = Arc::new(
+            CodecStorage::new(local_fs(&tmpdir), false, Some(&[8u8; KEY_SIZE][..])).unwrap(),
+        );
+        assert!(wrong_key.read_all(&file_handle).await.is_err());
+        let key_only: Arc<dyn BackupStorage> =
+            Arc::new(CodecStorage::new(local_fs(&tmpdir), false, Some(&KEY[..])).unwrap());
+        assert_eq!(key_only.read_all(&file_handle).await.unwrap(), content);
+    });
+}
+
+#[test]
+fn test_read_file_of_many_segments() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let content: Vec<u8> = (0..3 * SEGMENT_SIZE + 5).map(|_| random::<u8>()).collect();
+        for (compressed, encrypted) in [(true, false), (false, true), (true, true)] {
+            let tmpdir = TempPath::new();
+            tmpdir.create_as_dir().unwrap();
+            let key = if encrypted { Some(&KEY[..]) } else { None };
+            let storage: Arc<dyn BackupStorage> =
+                Arc::new(CodecStorage::new(local_fs(&tmpdir), compressed, key).unwrap());
+            let file_handle = write_file(&storage, &content).await;
+
+            // The header records the codec.
+            let raw = local_fs(&tmpdir).read_all(&file_handle).await.unwrap();
+            assert_eq!(raw[MAGIC.len()], storage.codec().flags());
+            assert_eq!(storage.read_all(&file_handle).await.unwrap(), content);
+        }
+    });
+}
+
+#[test]
+fn test_read_truncated_file() {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let content: Vec<u8> = (0..2 * SEGMENT_SIZE).map(|_| random::<u8>()).collect();
+        for (compressed, encrypted) in [(true, false), (false, true), (true, true)] {
+            let tmpdir = TempPath::new();
+            tmpdir.create_as_dir().unwrap();
+            let key = if encrypted { Some(&KEY[..]) } else { None };
+            let storage: Arc<dyn BackupStorage> =
+                Arc::new(CodecStorage::new(local_fs(&tmpdir), compressed, key).unwrap());
+            let file_handle = write_file(&storage, &content).await;
+
+            // Drop the end of the file, including whole segments.
+            let raw = local_fs(&tmpdir).read_all(&file_handle).await.unwrap();
+            let first_segment_end = HEADER_SIZE + NONCE_SIZE + SEGMENT_SIZE + TAG_SIZE;
+            for len in [raw.len() - 1, first_segment_end] {
+                let truncated_tmpdir = TempPath::new();
+                truncated_tmpdir.create_as_dir().unwrap();
+                let truncated = write_file(&local_fs(&truncated_tmpdir), &raw[..len]).await;
+                let storage: Arc<dyn BackupStorage> = Arc::new(
+                    CodecStorage::new(local_fs(&truncated_tmpdir), compressed, key).unwrap(),
+                );
+                assert!(storage.read_all(&truncated).await.is_err());
+            }
+        }
+    });
+}
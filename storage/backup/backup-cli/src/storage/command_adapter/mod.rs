@@ -36,7 +36,7 @@ pub struct CommandAdapterOpt {
         long = "config",
         help = "Config file for the command adapter backup store."
     )]
-    config: PathBuf,
+    pub config: PathBuf,
 }
 
 impl FromStr for CommandAdapterOpt {
@@ -2,8 +2,10 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod codec;
 pub mod command_adapter;
 pub mod local_fs;
+pub mod s3;
 
 #[cfg(test)]
 mod test_util;
@@ -11,10 +13,13 @@ mod test_util;
 mod tests;
 
 use crate::storage::{
+    codec::{BackupCodec, BackupCodecOpt, CodecStorage},
     command_adapter::{CommandAdapter, CommandAdapterOpt},
     local_fs::{LocalFs, LocalFsOpt},
+    s3::{S3Opt, S3Storage},
 };
 use anyhow::{ensure, Result};
+use aptos_config::config::BackupStorageConfig;
 use async_trait::async_trait;
 use clap::{ArgGroup, Parser};
 use once_cell::sync::Lazy;
@@ -23,7 +28,7 @@ use proptest::prelude::*;
 use regex::Regex;
 #[cfg(test)]
 use std::convert::TryInto;
-use std::{convert::TryFrom, ops::Deref, str::FromStr, sync::Arc};
+use std::{convert::TryFrom, ops::Deref, path::PathBuf, str::FromStr, sync::Arc};
 use tokio::io::{AsyncRead, AsyncWrite};
 
 /// String returned by a specific storage implementation to identify a backup, probably a folder name
@@ -153,6 +158,14 @@ pub trait BackupStorage: Send + Sync {
         &self,
         file_handle: &FileHandleRef,
     ) -> Result<Box<dyn AsyncRead + Send + Unpin>>;
+    /// Open a metadata file (see `list_metadata_files`) for reading. Unlike the files created by
+    /// `create_for_write()`, metadata files are never encoded, see `CodecStorage`.
+    async fn open_metadata_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        self.open_for_read(file_handle).await
+    }
     /// Asks to save a metadata entry and return the File handle of the saved file.
     /// A metadata entry is one line of text.
     /// The backup system doesn't expect a metadata entry to exclusively map to a single file
@@ -185,27 +198,66 @@ pub trait BackupStorage: Send + Sync {
         name: &ShellSafeName,
         lines: &[TextLine],
     ) -> Result<FileHandle>;
+    /// How the files created by `create_for_write()` are encoded, recorded in the metadata of the
+    /// backups they belong to. See `CodecStorage`.
+    fn codec(&self) -> BackupCodec {
+        BackupCodec::default()
+    }
+    /// Records the codec a file is expected to be encoded with, i.e., the one recorded in the
+    /// metadata of the backup it belongs to. Reading the file fails if it's encoded otherwise.
+    /// The first expectation recorded for a file is kept. See `CodecStorage`.
+    fn expect_codec(&self, _file_handle: &FileHandleRef, _codec: BackupCodec) {}
+    /// The codec a file is expected to be encoded with, if recorded by `expect_codec()`.
+    fn expected_codec(&self, _file_handle: &FileHandleRef) -> Option<BackupCodec> {
+        None
+    }
 }
 
 #[derive(Parser)]
 pub enum StorageOpt {
     #[clap(about = "Select the LocalFs backup storage type, which is used mainly for tests.")]
-    LocalFs(LocalFsOpt),
+    LocalFs {
+        #[clap(flatten)]
+        opt: LocalFsOpt,
+        #[clap(flatten)]
+        codec: BackupCodecOpt,
+    },
     #[clap(
         about = "Select the CommandAdapter backup storage type, which reads shell commands with which \
     it communicates with either a local file system or a remote cloud storage. Compression or other \
     fitlers can be added as part of the commands. See a sample config here: \
     https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/command_adapter/sample_configs/"
     )]
-    CommandAdapter(CommandAdapterOpt),
+    CommandAdapter {
+        #[clap(flatten)]
+        opt: CommandAdapterOpt,
+        #[clap(flatten)]
+        codec: BackupCodecOpt,
+    },
+    #[clap(
+        about = "Select the S3 backup storage type, which talks to an S3 compatible object store \
+    directly, uploading and downloading files in parts concurrently. See a sample config here: \
+    https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/s3/sample_configs/"
+    )]
+    S3 {
+        #[clap(flatten)]
+        opt: S3Opt,
+        #[clap(flatten)]
+        codec: BackupCodecOpt,
+    },
 }
 
 impl StorageOpt {
     pub async fn init_storage(self) -> Result<Arc<dyn BackupStorage>> {
-        Ok(match self {
-            StorageOpt::LocalFs(opt) => Arc::new(LocalFs::new_with_opt(opt)),
-            StorageOpt::CommandAdapter(opt) => Arc::new(CommandAdapter::new_with_opt(opt).await?),
-        })
+        let (storage, codec): (Arc<dyn BackupStorage>, _) = match self {
+            StorageOpt::LocalFs { opt, codec } => (Arc::new(LocalFs::new_with_opt(opt)), codec),
+            StorageOpt::CommandAdapter { opt, codec } => {
+                (Arc::new(CommandAdapter::new_with_opt(opt).await?), codec)
+            },
+            StorageOpt::S3 { opt, codec } => (Arc::new(S3Storage::new_with_opt(opt).await?), codec),
+        };
+        // Always wrapped, so that encoded backups are decoded even if no codec is configured.
+        Ok(Arc::new(CodecStorage::new_with_opt(storage, codec).await?))
     }
 }
 
@@ -213,7 +265,7 @@ impl StorageOpt {
 #[clap(group(
     ArgGroup::new("storage")
     .required(true)
-    .args(&["local_fs_dir", "command_adapter_config"]),
+    .args(&["local_fs_dir", "command_adapter_config", "s3_config"]),
 ))]
 pub struct DBToolStorageOpt {
     #[clap(
@@ -229,14 +281,61 @@ pub struct DBToolStorageOpt {
     https://github.com/aptos-labs/aptos-networks/tree/main/testnet/backups "
     )]
     command_adapter_config: Option<CommandAdapterOpt>,
+    #[clap(
+        long,
+        help = "Select the S3 backup storage type, which talks to an S3 compatible object store \
+    directly, uploading and downloading files in parts concurrently. See a sample config here: \
+    https://github.com/aptos-labs/aptos-core/tree/main/storage/backup/backup-cli/src/storage/s3/sample_configs/"
+    )]
+    s3_config: Option<S3Opt>,
+    #[clap(flatten)]
+    codec: BackupCodecOpt,
 }
 
 impl DBToolStorageOpt {
+    /// Selects the backup storage configured in a node config, e.g., for the node to bootstrap from.
+    pub fn from_config(
+        config: &BackupStorageConfig,
+        backup_encryption_key_file: Option<PathBuf>,
+    ) -> Self {
+        let mut opt = Self {
+            local_fs_dir: None,
+            command_adapter_config: None,
+            s3_config: None,
+            codec: BackupCodecOpt {
+                compress_backup: false,
+                backup_encryption_key_file,
+            },
+        };
+        match config {
+            BackupStorageConfig::CommandAdapter(config) => {
+                opt.command_adapter_config = Some(CommandAdapterOpt {
+                    config: config.clone(),
+                })
+            },
+            BackupStorageConfig::LocalFs(dir) => {
+                opt.local_fs_dir = Some(LocalFsOpt { dir: dir.clone() })
+            },
+            BackupStorageConfig::S3(config) => {
+                opt.s3_config = Some(S3Opt {
+                    config: config.clone(),
+                })
+            },
+        }
+        opt
+    }
+
     pub async fn init_storage(self) -> Result<Arc<dyn BackupStorage>> {
-        Ok(if self.local_fs_dir.is_some() {
+        let storage: Arc<dyn BackupStorage> = if self.local_fs_dir.is_some() {
             Arc::new(LocalFs::new_with_opt(self.local_fs_dir.unwrap()))
-        } else {
+        } else if self.command_adapter_config.is_some() {
             Arc::new(CommandAdapter::new_with_opt(self.command_adapter_config.unwrap()).await?)
-        })
+        } else {
+            Arc::new(S3Storage::new_with_opt(self.s3_config.unwrap()).await?)
+        };
+        // Always wrapped, so that encoded backups are decoded even if no codec is configured.
+        Ok(Arc::new(
+            CodecStorage::new_with_opt(storage, self.codec).await?,
+        ))
     }
 }
@@ -0,0 +1,86 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, Result};
+use md5::{Digest, Md5};
+use std::cmp::min;
+
+/// Returns the ETag S3 gives to an object from the MD5 digests of its parts: the digest of the
+/// content for objects uploaded at once, and the digest of the digests of the parts followed by
+/// the number of parts for objects uploaded in multiple parts.
+pub(super) fn md5_etag(part_md5s: &[Vec<u8>], multipart: bool) -> String {
+    if multipart {
+        format!(
+            "{}-{}",
+            hex::encode(Md5::digest(part_md5s.concat())),
+            part_md5s.len()
+        )
+    } else {
+        part_md5s.first().map(hex::encode).unwrap_or_default()
+    }
+}
+
+/// Returns the number of parts of an object uploaded in multiple parts, from its ETag.
+pub(super) fn etag_part_count(etag: &str) -> Option<usize> {
+    etag.trim_matches('"')
+        .split_once('-')
+        .and_then(|(_, part_count)| part_count.parse().ok())
+}
+
+pub(super) fn ensure_etag_matches(etag: &str, expected: &str, key: &str) -> Result<()> {
+    ensure!(
+        etag.trim_matches('"') == expected,
+        "Checksum mismatch for {}: ETag is {}, expected {}.",
+        key,
+        etag,
+        expected,
+    );
+    Ok(())
+}
+
+/// Verifies content read from S3 against the ETag of the object, as it is streamed through.
+pub(super) struct EtagVerifier {
+    key: String,
+    etag: String,
+    // Size of the parts, if the object was uploaded in multiple parts
+    part_size: Option<u64>,
+    part_md5s: Vec<Vec<u8>>,
+    hasher: Md5,
+    bytes_in_part: u64,
+}
+
+impl EtagVerifier {
+    pub fn new(key: String, etag: String, part_size: Option<u64>) -> Self {
+        Self {
+            key,
+            etag,
+            part_size,
+            part_md5s: Vec::new(),
+            hasher: Md5::new(),
+            bytes_in_part: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let part_size = self.part_size.unwrap_or(u64::MAX);
+        while !data.is_empty() {
+            let len = min(data.len() as u64, part_size - self.bytes_in_part) as usize;
+            self.hasher.update(&data[..len]);
+            self.bytes_in_part += len as u64;
+            data = &data[len..];
+
+            if self.bytes_in_part == part_size {
+                self.part_md5s.push(self.hasher.finalize_reset().to_vec());
+                self.bytes_in_part = 0;
+            }
+        }
+    }
+
+    pub fn verify(mut self) -> Result<()> {
+        if self.bytes_in_part > 0 || self.part_md5s.is_empty() {
+            self.part_md5s.push(self.hasher.finalize_reset().to_vec());
+        }
+        let expected = md5_etag(&self.part_md5s, self.part_size.is_some());
+        ensure_etag_matches(&self.etag, &expected, &self.key)
+    }
+}
@@ -0,0 +1,446 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::s3::{
+    config::{S3Config, S3Credentials},
+    signer::{canonical_query, sha256_hex, uri_encode, Signer},
+};
+use anyhow::{bail, ensure, format_err, Result};
+use aptos_logger::prelude::*;
+use bytes::Bytes;
+use md5::{Digest, Md5};
+use reqwest::{header::HeaderMap, Method, StatusCode};
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// What is known about an object in the bucket.
+pub(super) struct ObjectInfo {
+    pub size: u64,
+    pub etag: String,
+}
+
+struct S3Response {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// The outcome of a failed attempt of a request.
+enum RequestError {
+    /// The request may succeed if sent again, e.g. it timed out or the server was overloaded.
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+/// A minimal client of the S3 API, covering what the backup storage needs. Every request is
+/// signed, retried on transient failures, and sent with a checksum of its payload. Requests that
+/// can't be repeated, i.e. POSTs, are not retried if it's unknown whether they reached S3.
+pub(super) struct S3Client {
+    http: reqwest::Client,
+    signer: Signer,
+    endpoint: Url,
+    bucket: String,
+    path_style: bool,
+    max_retries: usize,
+    retry_initial_delay: Duration,
+}
+
+impl S3Client {
+    pub fn new(config: &S3Config, credentials: S3Credentials) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint)?;
+        ensure!(
+            endpoint.host_str().is_some(),
+            "S3 endpoint {} has no host.",
+            config.endpoint
+        );
+
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(config.request_timeout_secs))
+                .build()?,
+            signer: Signer::new(credentials, config.region.clone()),
+            endpoint,
+            bucket: config.bucket.clone(),
+            path_style: config.path_style,
+            max_retries: config.max_retries,
+            retry_initial_delay: Duration::from_millis(config.retry_initial_delay_ms),
+        })
+    }
+
+    /// Returns the size and ETag of an object, or None if it doesn't exist.
+    pub async fn head_object(&self, key: &str) -> Result<Option<ObjectInfo>> {
+        let response = self
+            .send(Method::HEAD, key, &[], vec![], Bytes::new())
+            .await;
+        match response {
+            Ok(response) => Ok(Some(ObjectInfo {
+                size: content_length(&response.headers)?,
+                etag: header(&response.headers, "etag")?,
+            })),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the size of a part of an object uploaded in multiple parts.
+    pub async fn head_part(&self, key: &str, part_number: usize) -> Result<u64> {
+        let part_number = part_number.to_string();
+        let response = self
+            .send(
+                Method::HEAD,
+                key,
+                &[("partNumber", &part_number)],
+                vec![],
+                Bytes::new(),
+            )
+            .await?;
+        content_length(&response.headers)
+    }
+
+    /// Reads the bytes in `[start, end)` of an object, failing if the object was changed and
+    /// doesn't have the expected ETag anymore.
+    pub async fn get_range(&self, key: &str, etag: &str, start: u64, end: u64) -> Result<Bytes> {
+        let headers = vec![
+            ("if-match", etag.to_string()),
+            ("range", format!("bytes={}-{}", start, end - 1)),
+        ];
+        let response = self
+            .send(Method::GET, key, &[], headers, Bytes::new())
+            .await?;
+        ensure!(
+            response.body.len() as u64 == end - start,
+            "Read {} bytes from {}, expected {}.",
+            response.body.len(),
+            key,
+            end - start,
+        );
+        Ok(response.body)
+    }
+
+    /// Uploads an object at once, returning its ETag.
+    pub async fn put_object(&self, key: &str, body: Bytes) -> Result<String> {
+        let headers = vec![("content-md5", base64_md5(&body))];
+        let response = self.send(Method::PUT, key, &[], headers, body).await?;
+        header(&response.headers, "etag")
+    }
+
+    /// Starts a multipart upload, returning its ID.
+    pub async fn create_multipart_upload(&self, key: &str) -> Result<String> {
+        let response = self
+            .send(Method::POST, key, &[("uploads", "")], vec![], Bytes::new())
+            .await?;
+        xml_value(&response.body, "UploadId")
+    }
+
+    /// Uploads a part of a multipart upload, returning its ETag.
+    pub async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: usize,
+        body: Bytes,
+    ) -> Result<String> {
+        let part_number = part_number.to_string();
+        let headers = vec![("content-md5", base64_md5(&body))];
+        let response = self
+            .send(
+                Method::PUT,
+                key,
+                &[("partNumber", &part_number), ("uploadId", upload_id)],
+                headers,
+                body,
+            )
+            .await?;
+        header(&response.headers, "etag")
+    }
+
+    /// Completes a multipart upload from the ETags of its parts, returning the object's ETag.
+    pub async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_etags: &[String],
+    ) -> Result<String> {
+        let parts = part_etags
+            .iter()
+            .enumerate()
+            .map(|(idx, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    idx + 1,
+                    etag
+                )
+            })
+            .collect::<String>();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+        let response = self
+            .send(
+                Method::POST,
+                key,
+                &[("uploadId", upload_id)],
+                vec![],
+                Bytes::from(body),
+            )
+            .await?;
+        xml_value(&response.body, "ETag")
+    }
+
+    pub async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<()> {
+        self.send(
+            Method::DELETE,
+            key,
+            &[("uploadId", upload_id)],
+            vec![],
+            Bytes::new(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the keys of all the objects with the given prefix.
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token));
+            }
+            let response = self
+                .send(Method::GET, "", &query, vec![], Bytes::new())
+                .await?;
+            keys.extend(xml_values(&response.body, "Key"));
+
+            continuation_token = xml_values(&response.body, "NextContinuationToken").pop();
+            if continuation_token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    pub async fn copy_object(&self, source_key: &str, key: &str) -> Result<()> {
+        let headers = vec![(
+            "x-amz-copy-source",
+            format!("/{}/{}", self.bucket, encode_key(source_key)),
+        )];
+        self.send(Method::PUT, key, &[], headers, Bytes::new())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        self.send(Method::DELETE, key, &[], vec![], Bytes::new())
+            .await?;
+        Ok(())
+    }
+
+    fn url(&self, key: &str, query: &[(&str, &str)]) -> Result<Url> {
+        let mut url = self.endpoint.clone();
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        if self.path_style {
+            if key.is_empty() {
+                url.set_path(&format!("{}/{}", base_path, self.bucket));
+            } else {
+                url.set_path(&format!(
+                    "{}/{}/{}",
+                    base_path,
+                    self.bucket,
+                    encode_key(key)
+                ));
+            }
+        } else {
+            let host = format!("{}.{}", self.bucket, url.host_str().unwrap_or_default());
+            url.set_host(Some(&host))?;
+            url.set_path(&format!("{}/{}", base_path, encode_key(key)));
+        }
+        if query.is_empty() {
+            url.set_query(None);
+        } else {
+            url.set_query(Some(&canonical_query(query)));
+        }
+        Ok(url)
+    }
+
+    /// Sends a request, retrying with an exponential backoff until it succeeds or fails with an
+    /// error that can't be recovered from.
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: Vec<(&'static str, String)>,
+        body: Bytes,
+    ) -> Result<S3Response> {
+        let url = self.url(key, query)?;
+        let payload_sha256 = sha256_hex(&body);
+
+        let mut delay = self.retry_initial_delay;
+        let mut retries = 0;
+        loop {
+            let result = self
+                .send_once(&method, &url, &headers, &payload_sha256, body.clone())
+                .await;
+            match result {
+                Ok(response) => return Ok(response),
+                Err(RequestError::Retryable(e)) if retries < self.max_retries => {
+                    warn!(
+                        error = %e,
+                        retries = retries,
+                        "S3 request {} {} failed, retrying in {:?}.",
+                        method,
+                        url,
+                        delay,
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    retries += 1;
+                },
+                Err(RequestError::Retryable(e)) | Err(RequestError::Fatal(e)) => {
+                    return Err(e.context(format!("S3 request {} {} failed", method, url)))
+                },
+            }
+        }
+    }
+
+    async fn send_once(
+        &self,
+        method: &Method,
+        url: &Url,
+        headers: &[(&'static str, String)],
+        payload_sha256: &str,
+        body: Bytes,
+    ) -> Result<S3Response, RequestError> {
+        let signing_headers = self.signer.sign(
+            method.as_str(),
+            url,
+            headers,
+            payload_sha256,
+            chrono::Utc::now(),
+        );
+        let mut request = self.http.request(method.clone(), url.clone()).body(body);
+        for (name, value) in headers.iter().chain(signing_headers.iter()) {
+            request = request.header(*name, value);
+        }
+
+        // The request may have taken effect even if no response was received, e.g. creating a
+        // multipart upload again would leave the first upload behind.
+        let transport_error = |e: reqwest::Error| {
+            if method.is_idempotent() {
+                RequestError::Retryable(e.into())
+            } else {
+                RequestError::Fatal(e.into())
+            }
+        };
+        let response = request.send().await.map_err(transport_error)?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(transport_error)?;
+
+        if !status.is_success() {
+            let error = S3Error {
+                status,
+                code: xml_value(&body, "Code").unwrap_or_default(),
+            };
+            return Err(
+                if status.is_server_error()
+                    || status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::REQUEST_TIMEOUT
+                {
+                    RequestError::Retryable(error.into())
+                } else {
+                    RequestError::Fatal(error.into())
+                },
+            );
+        }
+        // Some operations, like completing a multipart upload, can fail after the server already
+        // responded with a success status. The error is in the body then. Only requests that are
+        // safe to repeat are retried, since the operation may have taken effect regardless, e.g.
+        // completing the upload again would fail as the upload is gone.
+        if method != Method::GET {
+            if let Ok(code) = xml_value(&body, "Code") {
+                let error = S3Error { status, code }.into();
+                return Err(if method.is_idempotent() {
+                    RequestError::Retryable(error)
+                } else {
+                    RequestError::Fatal(error)
+                });
+            }
+        }
+
+        Ok(S3Response { headers, body })
+    }
+}
+
+/// An error returned by the S3 API.
+#[derive(Debug, Error)]
+#[error("S3 responded with {status} {code}")]
+struct S3Error {
+    status: StatusCode,
+    code: String,
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<S3Error>()
+        .map_or(false, |e| e.status == StatusCode::NOT_FOUND)
+}
+
+fn encode_key(key: &str) -> String {
+    key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+fn base64_md5(data: &[u8]) -> String {
+    base64::encode(Md5::digest(data))
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Result<String> {
+    Ok(headers
+        .get(name)
+        .ok_or_else(|| format_err!("Header {} is missing from the S3 response.", name))?
+        .to_str()?
+        .to_string())
+}
+
+fn content_length(headers: &HeaderMap) -> Result<u64> {
+    Ok(header(headers, "content-length")?.parse()?)
+}
+
+/// Returns the text of the first element with the given tag in an XML document.
+fn xml_value(xml: &[u8], tag: &str) -> Result<String> {
+    match xml_values(xml, tag).into_iter().next() {
+        Some(value) => Ok(value),
+        None => bail!("Element {} is missing from the S3 response.", tag),
+    }
+}
+
+/// Returns the text of all the elements with the given tag in an XML document. The responses
+/// of the S3 API are simple enough to not need a full XML parser.
+fn xml_values(xml: &[u8], tag: &str) -> Vec<String> {
+    let xml = String::from_utf8_lossy(xml);
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut values = Vec::new();
+    let mut rest = xml.as_ref();
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        match rest.find(&close) {
+            Some(end) => {
+                values.push(xml_unescape(&rest[..end]));
+                rest = &rest[end + close.len()..];
+            },
+            None => break,
+        }
+    }
+    values
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#34;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,137 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::utils::error_notes::ErrorNotes;
+use anyhow::{ensure, format_err, Result};
+use serde::Deserialize;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// S3 rejects multipart uploads with parts smaller than this, except for the last one.
+const MIN_PART_SIZE_BYTES: usize = 5 * 1024 * 1024;
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    /// Endpoint of the S3 compatible API, e.g. "https://s3.us-west-2.amazonaws.com" or
+    /// "http://localhost:9000".
+    pub endpoint: String,
+    /// Region that requests are signed for. S3 compatible stores usually accept "us-east-1".
+    pub region: String,
+    /// Bucket holding the backups.
+    pub bucket: String,
+    /// Folder in the bucket under which everything is stored, empty to use the root.
+    pub prefix: String,
+    /// Addresses the bucket as "{endpoint}/{bucket}" instead of "{bucket}.{endpoint}". This is
+    /// required by most S3 compatible stores.
+    pub path_style: bool,
+    /// Environment variable holding the access key ID.
+    pub access_key_id_env: String,
+    /// Environment variable holding the secret access key.
+    pub secret_access_key_env: String,
+    /// Environment variable holding the session token of temporary credentials, if set.
+    pub session_token_env: String,
+    /// Size of the parts of multipart uploads. Files smaller than this are uploaded at once.
+    /// S3 requires at least 5MiB, and allows at most 10000 parts per file.
+    pub part_size_bytes: usize,
+    /// Maximum number of parts of a file being uploaded concurrently.
+    pub max_concurrent_part_uploads: usize,
+    /// Size of the ranges in which files are downloaded.
+    pub read_range_bytes: usize,
+    /// Maximum number of ranges of a file being downloaded concurrently.
+    pub max_concurrent_range_reads: usize,
+    /// Verifies files against their ETags when they are uploaded and downloaded. S3 computes
+    /// ETags from the MD5 digests of the content, except for objects encrypted with SSE-KMS or
+    /// SSE-C, in which case this must be disabled.
+    pub verify_etags: bool,
+    /// Number of times a failed request is retried, with an exponential backoff.
+    pub max_retries: usize,
+    /// Delay before the first retry of a failed request.
+    pub retry_initial_delay_ms: u64,
+    /// Timeout of a single request.
+    pub request_timeout_secs: u64,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            region: "us-east-1".to_string(),
+            bucket: String::new(),
+            prefix: String::new(),
+            path_style: false,
+            access_key_id_env: "AWS_ACCESS_KEY_ID".to_string(),
+            secret_access_key_env: "AWS_SECRET_ACCESS_KEY".to_string(),
+            session_token_env: "AWS_SESSION_TOKEN".to_string(),
+            part_size_bytes: 64 * 1024 * 1024,
+            max_concurrent_part_uploads: 4,
+            read_range_bytes: 16 * 1024 * 1024,
+            max_concurrent_range_reads: 8,
+            verify_etags: true,
+            max_retries: 5,
+            retry_initial_delay_ms: 200,
+            request_timeout_secs: 300,
+        }
+    }
+}
+
+impl S3Config {
+    pub async fn load_from_file(path: &Path) -> Result<Self> {
+        let path_str = path.to_str().unwrap_or_default();
+        let mut file = tokio::fs::File::open(path).await.err_notes(path_str)?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).await.err_notes(path_str)?;
+
+        Self::load_from_slice(&content)
+    }
+
+    pub fn load_from_str(content: &str) -> Result<Self> {
+        Self::load_from_slice(content.as_bytes())
+    }
+
+    fn load_from_slice(content: &[u8]) -> Result<Self> {
+        let config: Self = serde_yaml::from_slice(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.endpoint.is_empty(), "S3 endpoint is not set.");
+        ensure!(!self.bucket.is_empty(), "S3 bucket is not set.");
+        ensure!(
+            self.part_size_bytes >= MIN_PART_SIZE_BYTES,
+            "part_size_bytes must be at least {} (5MiB), got {}.",
+            MIN_PART_SIZE_BYTES,
+            self.part_size_bytes,
+        );
+        ensure!(
+            self.read_range_bytes > 0,
+            "read_range_bytes must be positive."
+        );
+        ensure!(
+            self.max_concurrent_part_uploads > 0 && self.max_concurrent_range_reads > 0,
+            "Concurrency limits must be positive."
+        );
+        Ok(())
+    }
+
+    /// Reads the credentials from the configured environment variables.
+    pub fn credentials_from_env(&self) -> Result<S3Credentials> {
+        let read_env = |name: &str| {
+            std::env::var(name)
+                .map_err(|e| format_err!("Failed to read S3 credentials from ${}: {}", name, e))
+        };
+        Ok(S3Credentials {
+            access_key_id: read_env(&self.access_key_id_env)?,
+            secret_access_key: read_env(&self.secret_access_key_env)?,
+            session_token: std::env::var(&self.session_token_env).ok(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
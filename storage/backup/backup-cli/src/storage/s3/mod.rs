@@ -0,0 +1,182 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+mod checksum;
+mod client;
+pub mod config;
+mod reader;
+mod signer;
+mod writer;
+
+#[cfg(test)]
+mod tests;
+
+use crate::storage::{
+    s3::{
+        client::S3Client,
+        config::{S3Config, S3Credentials},
+        reader::open_for_read,
+        writer::S3Writer,
+    },
+    BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    TextLine,
+};
+use anyhow::{ensure, format_err, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
+pub struct S3Opt {
+    #[clap(long = "config", help = "Config file for the S3 backup storage.")]
+    pub config: PathBuf,
+}
+
+impl FromStr for S3Opt {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(S3Opt {
+            config: PathBuf::from(s),
+        })
+    }
+}
+
+/// A BackupStorage that talks to an S3 compatible API directly, laying out files the same way
+/// as `LocalFs` under the configured prefix of the bucket. See `S3Config`.
+pub struct S3Storage {
+    config: S3Config,
+    client: Arc<S3Client>,
+}
+
+impl S3Storage {
+    const METADATA_BACKUP_DIR: &'static str = "metadata_backup";
+    const METADATA_DIR: &'static str = "metadata";
+
+    pub fn new(config: S3Config, credentials: S3Credentials) -> Result<Self> {
+        config.validate()?;
+        let client = Arc::new(S3Client::new(&config, credentials)?);
+
+        Ok(Self { config, client })
+    }
+
+    pub async fn new_with_opt(opt: S3Opt) -> Result<Self> {
+        let config = S3Config::load_from_file(&opt.config).await?;
+        let credentials = config.credentials_from_env()?;
+
+        Self::new(config, credentials)
+    }
+
+    /// Returns the key of the object holding a file.
+    fn key(&self, file_handle: &FileHandleRef) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            file_handle.to_string()
+        } else {
+            format!("{}/{}", prefix, file_handle)
+        }
+    }
+
+    /// Returns the handle of the file held by an object.
+    fn file_handle(&self, key: &str) -> Result<FileHandle> {
+        let prefix = self.key("");
+        key.strip_prefix(&prefix)
+            .map(str::to_string)
+            .ok_or_else(|| format_err!("Key {} is outside of prefix {}.", key, prefix))
+    }
+}
+
+#[async_trait]
+impl BackupStorage for S3Storage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        // There are no folders to create in a bucket.
+        Ok(name.to_string())
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        let file_handle = format!("{}/{}", backup_handle, name.as_ref());
+        let key = self.key(&file_handle);
+        ensure!(
+            self.client.head_object(&key).await?.is_none(),
+            "File {} already exists.",
+            file_handle,
+        );
+
+        let writer = S3Writer::new(
+            self.client.clone(),
+            key,
+            self.config.part_size_bytes,
+            self.config.max_concurrent_part_uploads,
+            self.config.verify_etags,
+        );
+        Ok((file_handle, Box::new(writer)))
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        open_for_read(
+            self.client.clone(),
+            self.key(file_handle),
+            self.config.read_range_bytes,
+            self.config.max_concurrent_range_reads,
+            self.config.verify_etags,
+        )
+        .await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        let prefix = self.key(&format!("{}/", Self::METADATA_DIR));
+        self.client
+            .list_objects(&prefix)
+            .await?
+            .iter()
+            .map(|key| self.file_handle(key))
+            .collect()
+    }
+
+    /// file_handle are expected to be the return results from list_metadata_files
+    /// file_handle is a path with `metadata` in the path, Ex: metadata/epoch_ending_1.meta
+    async fn backup_metadata_file(&self, file_handle: &FileHandleRef) -> Result<()> {
+        let name = Path::new(file_handle)
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| format_err!("cannot extract filename from {}", file_handle))?;
+        let key = self.key(file_handle);
+        let backup_key = self.key(&format!("{}/{}", Self::METADATA_BACKUP_DIR, name));
+
+        // S3 can't move objects, so copy it to the backup folder and delete the original.
+        self.client.copy_object(&key, &backup_key).await?;
+        self.client.delete_object(&key).await
+    }
+
+    async fn save_metadata_lines(
+        &self,
+        name: &ShellSafeName,
+        lines: &[TextLine],
+    ) -> Result<FileHandle> {
+        let content = lines
+            .iter()
+            .map(|e| e.as_ref())
+            .collect::<Vec<&str>>()
+            .join("");
+        let file_handle = format!("{}/{}", Self::METADATA_DIR, name.as_ref());
+        self.client
+            .put_object(&self.key(&file_handle), Bytes::from(content))
+            .await?;
+        Ok(file_handle)
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::s3::{
+    checksum::{etag_part_count, EtagVerifier},
+    client::S3Client,
+};
+use anyhow::{format_err, Result};
+use bytes::Bytes;
+use futures::{stream, stream::BoxStream, StreamExt, TryStreamExt};
+use std::{cmp::min, sync::Arc};
+use tokio::io::AsyncRead;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+type ChunkStream = BoxStream<'static, Result<Bytes>>;
+
+/// Opens an object in S3 for reading. The object is downloaded in ranges, several of which are
+/// read ahead concurrently, and verified against its ETag once it's fully read if `verify_etag`.
+pub(super) async fn open_for_read(
+    client: Arc<S3Client>,
+    key: String,
+    range_size: usize,
+    max_concurrent_range_reads: usize,
+    verify_etag: bool,
+) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+    let info = client
+        .head_object(&key)
+        .await?
+        .ok_or_else(|| format_err!("File {} doesn't exist.", key))?;
+    let verifier = if verify_etag {
+        // Parts are hashed separately in the ETag of objects uploaded in multiple parts.
+        let part_size = match etag_part_count(&info.etag) {
+            Some(_) => Some(client.head_part(&key, 1).await?),
+            None => None,
+        };
+        Some(EtagVerifier::new(key.clone(), info.etag.clone(), part_size))
+    } else {
+        None
+    };
+
+    let size = info.size;
+    let etag = info.etag;
+    let ranges = (0..size)
+        .step_by(range_size)
+        .map(move |start| (start, min(start + range_size as u64, size)));
+    let chunks = stream::iter(ranges)
+        .map(move |(start, end)| {
+            let client = client.clone();
+            let key = key.clone();
+            let etag = etag.clone();
+            tokio::spawn(async move { client.get_range(&key, &etag, start, end).await })
+        })
+        .buffered(max_concurrent_range_reads)
+        .map(|res| res?)
+        .boxed();
+
+    let reader = stream::try_unfold((chunks, verifier), |(chunks, verifier)| {
+        next_verified_chunk(chunks, verifier)
+    })
+    .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
+    .boxed()
+    .into_async_read()
+    .compat();
+    Ok(Box::new(reader))
+}
+
+/// Returns the next chunk of the object after feeding it to the verifier, or verifies the
+/// object once all the chunks are read.
+async fn next_verified_chunk(
+    mut chunks: ChunkStream,
+    mut verifier: Option<EtagVerifier>,
+) -> Result<Option<(Bytes, (ChunkStream, Option<EtagVerifier>))>> {
+    match chunks.try_next().await? {
+        Some(chunk) => {
+            if let Some(verifier) = &mut verifier {
+                verifier.update(&chunk);
+            }
+            Ok(Some((chunk, (chunks, verifier))))
+        },
+        None => {
+            if let Some(verifier) = verifier {
+                verifier.verify()?;
+            }
+            Ok(None)
+        },
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::storage::s3::config::S3Credentials;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2_0_10_6::{Digest, Sha256};
+use url::Url;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Characters that are percent encoded in URIs, everything but the unreserved ones.
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SERVICE: &str = "s3";
+
+/// Encodes a string as a URI component, the way the S3 API expects it.
+pub(super) fn uri_encode(value: &str) -> String {
+    utf8_percent_encode(value, URI_ENCODE_SET).to_string()
+}
+
+/// Builds a canonical query string, with the parameters encoded and sorted.
+pub(super) fn canonical_query(params: &[(&str, &str)]) -> String {
+    let mut encoded = params
+        .iter()
+        .map(|(key, value)| (uri_encode(key), uri_encode(value)))
+        .collect::<Vec<_>>();
+    encoded.sort();
+    encoded
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub(super) fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any size.");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Signs requests with AWS Signature Version 4, which is supported by all S3 compatible APIs.
+/// See https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-header-based-auth.html
+pub(super) struct Signer {
+    credentials: S3Credentials,
+    region: String,
+}
+
+impl Signer {
+    pub fn new(credentials: S3Credentials, region: String) -> Self {
+        Self {
+            credentials,
+            region,
+        }
+    }
+
+    /// Returns the headers to add to a request to sign it. `headers` are the other headers of the
+    /// request that are signed, and the URL must have a canonical query string.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &[(&str, String)],
+        payload_sha256: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<(&'static str, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let mut signed_headers = vec![
+            ("x-amz-content-sha256", payload_sha256.to_string()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(session_token) = &self.credentials.session_token {
+            signed_headers.push(("x-amz-security-token", session_token.clone()));
+        }
+        let mut canonical_headers: Vec<(&str, String)> = signed_headers.clone();
+        canonical_headers.push(("host", host(url)));
+        canonical_headers.extend(
+            headers
+                .iter()
+                .map(|(name, value)| (*name, value.trim().to_string())),
+        );
+        canonical_headers.sort();
+
+        let signed_header_names = canonical_headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            url.path(),
+            url.query().unwrap_or_default(),
+            canonical_headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}\n", name, value))
+                .collect::<String>(),
+            signed_header_names,
+            payload_sha256,
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, SERVICE);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes()),
+        );
+
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let signing_key = [self.region.as_str(), SERVICE, "aws4_request"]
+            .iter()
+            .fold(hmac_sha256(secret.as_bytes(), &date), |key, data| {
+                hmac_sha256(&key, data)
+            });
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+
+        signed_headers.push((
+            "authorization",
+            format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, self.credentials.access_key_id, scope, signed_header_names, signature,
+            ),
+        ));
+        signed_headers
+    }
+}
+
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage::s3::{
+        checksum::{ensure_etag_matches, md5_etag},
+        client::S3Client,
+    },
+    utils::error_notes::ErrorNotes,
+};
+use anyhow::{format_err, Result};
+use aptos_logger::prelude::*;
+use bytes::{Bytes, BytesMut};
+use futures::{
+    ready,
+    stream::{FuturesOrdered, StreamExt},
+    task::{Context, Poll},
+    Future,
+};
+use md5::{Digest, Md5};
+use std::{io, pin::Pin, sync::Arc};
+use tokio::{
+    io::{duplex, AsyncReadExt, AsyncWrite, DuplexStream},
+    sync::oneshot,
+    task::JoinHandle,
+};
+
+/// Size of the buffer between the writer and the background upload.
+const PIPE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Streams what is written to it to an object in S3. The content is cut into parts that are
+/// uploaded concurrently in the background, and the object is only created once the writer is
+/// shut down successfully. If the writer is dropped before that, the upload is aborted.
+pub(super) struct S3Writer {
+    pipe: DuplexStream,
+    finished: Option<oneshot::Sender<()>>,
+    upload: JoinHandle<Result<()>>,
+}
+
+impl S3Writer {
+    pub fn new(
+        client: Arc<S3Client>,
+        key: String,
+        part_size: usize,
+        max_concurrent_part_uploads: usize,
+        verify_etag: bool,
+    ) -> Self {
+        let (pipe, upload_pipe) = duplex(PIPE_BUFFER_BYTES);
+        let (finished_tx, finished_rx) = oneshot::channel();
+        let upload = tokio::spawn(async move {
+            let upload = Upload {
+                client,
+                key,
+                part_size,
+                max_concurrent_part_uploads,
+                verify_etag,
+            };
+            upload
+                .run(upload_pipe, finished_rx)
+                .await
+                .err_notes(&upload.key)
+        });
+
+        Self {
+            pipe,
+            finished: Some(finished_tx),
+            upload,
+        }
+    }
+}
+
+impl AsyncWrite for S3Writer {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.pipe).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.pipe).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(finished) = self.finished.take() {
+            // The upload fails by itself if it stopped already.
+            let _ = finished.send(());
+        }
+        ready!(Pin::new(&mut self.pipe).poll_shutdown(cx))?;
+
+        Pin::new(&mut self.upload).poll(cx).map(|res| {
+            res.map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        })
+    }
+}
+
+struct Upload {
+    client: Arc<S3Client>,
+    key: String,
+    part_size: usize,
+    max_concurrent_part_uploads: usize,
+    verify_etag: bool,
+}
+
+impl Upload {
+    async fn run(&self, mut pipe: DuplexStream, finished: oneshot::Receiver<()>) -> Result<()> {
+        let first_part = self.read_part(&mut pipe).await?;
+        if first_part.len() < self.part_size {
+            // Small enough to be uploaded at once.
+            Self::ensure_finished(finished).await?;
+            let part_md5 = Md5::digest(&first_part).to_vec();
+            let etag = self.client.put_object(&self.key, first_part).await?;
+            return self.verify(&etag, &[part_md5], false);
+        }
+
+        let upload_id = self.client.create_multipart_upload(&self.key).await?;
+        let res = self
+            .upload_parts(&upload_id, first_part, pipe, finished)
+            .await;
+        if res.is_err() {
+            if let Err(e) = self
+                .client
+                .abort_multipart_upload(&self.key, &upload_id)
+                .await
+            {
+                warn!(
+                    error = %e,
+                    "Failed to abort the multipart upload of {}, it will linger until it's cleaned \
+                    up by the bucket lifecycle rules.",
+                    self.key,
+                );
+            }
+        }
+        res
+    }
+
+    async fn upload_parts(
+        &self,
+        upload_id: &str,
+        first_part: Bytes,
+        mut pipe: DuplexStream,
+        finished: oneshot::Receiver<()>,
+    ) -> Result<()> {
+        let mut uploads = FuturesOrdered::new();
+        let mut part_etags = Vec::new();
+        let mut part_md5s = Vec::new();
+        let mut part = first_part;
+        while !part.is_empty() {
+            if uploads.len() >= self.max_concurrent_part_uploads {
+                part_etags.push(Self::next_etag(&mut uploads).await?);
+            }
+
+            part_md5s.push(Md5::digest(&part).to_vec());
+            let client = self.client.clone();
+            let key = self.key.clone();
+            let upload_id = upload_id.to_string();
+            let part_number = part_md5s.len();
+            uploads.push_back(tokio::spawn(async move {
+                client
+                    .upload_part(&key, &upload_id, part_number, part)
+                    .await
+            }));
+
+            part = self.read_part(&mut pipe).await?;
+        }
+        while !uploads.is_empty() {
+            part_etags.push(Self::next_etag(&mut uploads).await?);
+        }
+
+        Self::ensure_finished(finished).await?;
+        let etag = self
+            .client
+            .complete_multipart_upload(&self.key, upload_id, &part_etags)
+            .await?;
+        self.verify(&etag, &part_md5s, true)
+    }
+
+    async fn next_etag(uploads: &mut FuturesOrdered<JoinHandle<Result<String>>>) -> Result<String> {
+        let upload = uploads
+            .next()
+            .await
+            .ok_or_else(|| format_err!("No part upload in progress."))?;
+        upload?
+    }
+
+    /// Reads a part from the pipe. The part is smaller than the part size only at the end.
+    async fn read_part(&self, pipe: &mut DuplexStream) -> Result<Bytes> {
+        let mut part = BytesMut::with_capacity(self.part_size);
+        while part.len() < self.part_size {
+            if pipe.read_buf(&mut part).await? == 0 {
+                break;
+            }
+        }
+        Ok(part.freeze())
+    }
+
+    /// Makes sure the writer was shut down, and not just dropped, before the object is created.
+    async fn ensure_finished(finished: oneshot::Receiver<()>) -> Result<()> {
+        finished
+            .await
+            .map_err(|_| format_err!("Writer dropped before being shut down, upload aborted."))
+    }
+
+    fn verify(&self, etag: &str, part_md5s: &[Vec<u8>], multipart: bool) -> Result<()> {
+        if self.verify_etag {
+            ensure_etag_matches(etag, &md5_etag(part_md5s, multipart), &self.key)?;
+        }
+        Ok(())
+    }
+}
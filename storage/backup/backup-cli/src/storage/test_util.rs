@@ -73,7 +73,7 @@ pub async fn test_save_and_list_metadata_files_impl(
     for file_handle in store.list_metadata_files().await.unwrap() {
         let mut buf = String::new();
         store
-            .open_for_read(&file_handle)
+            .open_metadata_for_read(&file_handle)
             .await
             .unwrap()
             .read_to_string(&mut buf)
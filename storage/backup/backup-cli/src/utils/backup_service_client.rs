@@ -124,6 +124,18 @@ impl BackupServiceClient {
         .await
     }
 
+    pub async fn get_state_snapshot_diff(
+        &self,
+        base_version: Version,
+        version: Version,
+    ) -> Result<impl AsyncRead> {
+        self.get(
+            "state_snapshot_diff",
+            &format!("{}/{}", base_version, version),
+        )
+        .await
+    }
+
     pub async fn get_state_root_proof(&self, version: Version) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         self.get("state_root_proof", &format!("{}", version))
@@ -34,7 +34,16 @@ impl BackupStorageExt for Arc<dyn BackupStorage> {
     }
 
     async fn load_json_file<T: DeserializeOwned>(&self, file_handle: &FileHandleRef) -> Result<T> {
-        Ok(serde_json::from_slice(&self.read_all(file_handle).await?)?)
+        let bytes = self.read_all(file_handle).await?;
+        // The files a manifest refers to belong to the same backup, so they are expected to be
+        // encoded like the manifest.
+        if let Some(codec) = self.expected_codec(file_handle) {
+            let value: serde_json::Value = serde_json::from_slice(&bytes)?;
+            for_each_string(&value, &mut |file_handle| {
+                self.expect_codec(file_handle, codec)
+            });
+        }
+        Ok(serde_json::from_slice(&bytes)?)
     }
 
     async fn create_backup_with_random_suffix(&self, name: &str) -> Result<BackupHandle> {
@@ -42,3 +51,20 @@ impl BackupStorageExt for Arc<dyn BackupStorage> {
             .await
     }
 }
+
+fn for_each_string(value: &serde_json::Value, f: &mut impl FnMut(&str)) {
+    match value {
+        serde_json::Value::String(s) => f(s),
+        serde_json::Value::Array(values) => {
+            for value in values {
+                for_each_string(value, f);
+            }
+        },
+        serde_json::Value::Object(map) => {
+            for value in map.values() {
+                for_each_string(value, f);
+            }
+        },
+        _ => (),
+    }
+}
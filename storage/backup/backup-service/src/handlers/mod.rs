@@ -20,6 +20,7 @@ static STATE_SNAPSHOT: &str = "state_snapshot";
 static STATE_ITEM_COUNT: &str = "state_item_count";
 static STATE_SNAPSHOT_CHUNK: &str = "state_snapshot_chunk";
 static STATE_ROOT_PROOF: &str = "state_root_proof";
+static STATE_SNAPSHOT_DIFF: &str = "state_snapshot_diff";
 static EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
 static TRANSACTIONS: &str = "transactions";
 static TRANSACTION_RANGE_PROOF: &str = "transaction_range_proof";
@@ -87,6 +88,17 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .map(unwrap_or_500)
         .recover(handle_rejection);
 
+    // GET state_snapshot_diff/<base_version>/<version>
+    let bh = backup_handler.clone();
+    let state_snapshot_diff = warp::path!(Version / Version)
+        .map(move |base_version, version| {
+            reply_with_bytes_sender(&bh, STATE_SNAPSHOT_DIFF, move |bh, sender| {
+                bh.get_state_snapshot_diff_iter(base_version, version)?
+                    .try_for_each(|record_res| sender.send_size_prefixed_bcs_bytes(record_res?))
+            })
+        })
+        .recover(handle_rejection);
+
     // GET epoch_ending_ledger_infos/<start_epoch>/<end_epoch>/
     let bh = backup_handler.clone();
     let epoch_ending_ledger_infos = warp::path!(u64 / u64)
@@ -129,6 +141,7 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .or(warp::path(STATE_ITEM_COUNT).and(state_item_count))
         .or(warp::path(STATE_SNAPSHOT_CHUNK).and(state_snapshot_chunk))
         .or(warp::path(STATE_ROOT_PROOF).and(state_root_proof))
+        .or(warp::path(STATE_SNAPSHOT_DIFF).and(state_snapshot_diff))
         .or(warp::path(EPOCH_ENDING_LEDGER_INFOS).and(epoch_ending_ledger_infos))
         .or(warp::path(TRANSACTIONS).and(transactions))
         .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof));
@@ -2,14 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::utils::parse_maxable_u64;
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use aptos_backup_cli::{
     backup_types::{
         epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
-        state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        state_snapshot::{
+            backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+            diff_backup::{StateSnapshotDiffBackupController, StateSnapshotDiffBackupOpt},
+        },
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
     coordinators::{
+        audit::AuditCoordinator,
         backup::{BackupCoordinator, BackupCoordinatorOpt},
         verify::VerifyCoordinator,
     },
@@ -22,7 +26,7 @@ use aptos_backup_cli::{
 };
 use aptos_types::transaction::Version;
 use clap::{Parser, Subcommand};
-use std::{path::PathBuf, sync::Arc};
+use std::{fs, path::PathBuf, sync::Arc};
 
 /// Supports one-time and continuous backup, including querying the backup service and verifying the backup.
 #[derive(Subcommand)]
@@ -41,6 +45,11 @@ pub enum Command {
     Query(OneShotQueryType),
     #[clap(about = "verify the backup through restoring with the backup files")]
     Verify(VerifyOpt),
+    #[clap(
+        about = "Check every backup file against the proofs and the epoch ending LedgerInfos, \
+        without restoring to a DB, and report gaps and corrupted files as JSON."
+    )]
+    Audit(AuditOpt),
 }
 
 #[derive(Parser)]
@@ -98,6 +107,12 @@ enum BackupType {
         #[clap[flatten]]
         storage: DBToolStorageOpt,
     },
+    StateSnapshotDiff {
+        #[clap(flatten)]
+        opt: StateSnapshotDiffBackupOpt,
+        #[clap[flatten]]
+        storage: DBToolStorageOpt,
+    },
     Transaction {
         #[clap(flatten)]
         opt: TransactionBackupOpt,
@@ -165,6 +180,24 @@ pub struct VerifyOpt {
     output_transaction_analysis: Option<PathBuf>,
 }
 
+#[derive(Parser)]
+pub struct AuditOpt {
+    #[clap(flatten)]
+    metadata_cache_opt: MetadataCacheOpt,
+    #[clap(flatten)]
+    trusted_waypoints_opt: TrustedWaypointOpt,
+    #[clap(flatten)]
+    storage: DBToolStorageOpt,
+    #[clap(flatten)]
+    concurrent_downloads: ConcurrentDownloadsOpt,
+    #[clap(
+        long,
+        value_parser,
+        help = "Write the report to this file instead of stdout."
+    )]
+    report_file: Option<PathBuf>,
+}
+
 impl Command {
     pub async fn run(self) -> Result<()> {
         match self {
@@ -193,6 +226,16 @@ impl Command {
                         .run()
                         .await?;
                     },
+                    BackupType::StateSnapshotDiff { opt, storage } => {
+                        StateSnapshotDiffBackupController::new(
+                            opt,
+                            global_opt,
+                            client,
+                            storage.init_storage().await?,
+                        )
+                        .run()
+                        .await?;
+                    },
                     BackupType::Transaction { opt, storage } => {
                         TransactionBackupController::new(
                             opt,
@@ -250,6 +293,27 @@ impl Command {
                 .run()
                 .await?
             },
+            Command::Audit(opt) => {
+                let report = AuditCoordinator::new(
+                    opt.storage.init_storage().await?,
+                    opt.metadata_cache_opt,
+                    opt.trusted_waypoints_opt,
+                    opt.concurrent_downloads.get(),
+                )
+                .run()
+                .await?;
+                let report_json = serde_json::to_string_pretty(&report)?;
+                match opt.report_file {
+                    Some(path) => fs::write(path, report_json)?,
+                    None => println!("{}", report_json),
+                }
+                ensure!(
+                    report.is_clean(),
+                    "Audit found {} gaps and {} corrupted files.",
+                    report.gaps.len(),
+                    report.corrupted_files.len(),
+                );
+            },
         }
         Ok(())
     }
@@ -0,0 +1,419 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err, Result};
+use aptos_db::{db_debugger::checkpoint::CommitProgress, AptosDB};
+use aptos_logger::info;
+use clap::{ArgGroup, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Holds the metadata of a checkpoint, in the checkpoint dir. Its presence marks the checkpoint as
+/// complete, since it's the last thing written.
+const METADATA_FILE_NAME: &str = "checkpoint.json";
+
+/// Creates consistent checkpoints of all the sub-DBs of a DB, and rolls the DB back to them.
+/// The node must be stopped, which is enforced by the RocksDB LOCK files of the sub-DBs. All the
+/// sub-DBs must be under the DB dir, i.e., DBs with `db_path_overrides` aren't supported.
+#[derive(Subcommand)]
+pub enum Command {
+    #[clap(about = "Create a checkpoint of a DB.")]
+    Create(CreateOpt),
+    #[clap(about = "List the checkpoints in a checkpoint dir.")]
+    List(ListOpt),
+    #[clap(about = "Roll a DB back to a checkpoint.")]
+    Restore(RestoreOpt),
+}
+
+#[derive(Parser)]
+pub struct CreateOpt {
+    #[clap(long, value_parser, help = "DB to checkpoint.")]
+    pub db_dir: PathBuf,
+    #[clap(long, value_parser, help = "Dir holding the checkpoints.")]
+    pub checkpoint_dir: PathBuf,
+    #[clap(
+        long,
+        help = "Name of the checkpoint. [Defaults to the DB version and the creation time]"
+    )]
+    pub name: Option<String>,
+    #[clap(long)]
+    pub enable_storage_sharding: bool,
+}
+
+#[derive(Parser)]
+pub struct ListOpt {
+    #[clap(long, value_parser, help = "Dir holding the checkpoints.")]
+    pub checkpoint_dir: PathBuf,
+}
+
+#[derive(Parser)]
+#[clap(group(ArgGroup::new("current_db")
+    .required(true)
+    .args(&["current_db_backup_dir", "opt_out_current_db_backup"]),
+))]
+pub struct RestoreOpt {
+    #[clap(long, value_parser, help = "DB to roll back.")]
+    pub db_dir: PathBuf,
+    #[clap(long, value_parser, help = "Dir holding the checkpoints.")]
+    pub checkpoint_dir: PathBuf,
+    #[clap(long, help = "Name of the checkpoint to roll back to.")]
+    pub name: String,
+    #[clap(
+        long,
+        value_parser,
+        group = "current_db",
+        help = "Where to move the current DB to, on the same filesystem. Anything else in the DB \
+        dir moves with it."
+    )]
+    pub current_db_backup_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        group = "current_db",
+        help = "Delete the current DB instead of moving it."
+    )]
+    pub opt_out_current_db_backup: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CheckpointMetadata {
+    pub created_at_secs: u64,
+    pub enable_storage_sharding: bool,
+    pub commit_progress: CommitProgress,
+}
+
+impl Command {
+    pub fn run(self) -> Result<()> {
+        match self {
+            Command::Create(opt) => {
+                let (name, metadata) = create_checkpoint(
+                    &opt.db_dir,
+                    &opt.checkpoint_dir,
+                    opt.name,
+                    opt.enable_storage_sharding,
+                )?;
+                println!(
+                    "Created checkpoint {} at {:?}.",
+                    name, metadata.commit_progress
+                );
+            },
+            Command::List(opt) => {
+                for (name, metadata) in list_checkpoints(&opt.checkpoint_dir)? {
+                    println!(
+                        "{}: version {}, created at {}s, sharding {}",
+                        name,
+                        metadata.commit_progress.overall_version,
+                        metadata.created_at_secs,
+                        metadata.enable_storage_sharding,
+                    );
+                }
+            },
+            Command::Restore(opt) => {
+                let metadata = restore_checkpoint(
+                    &opt.db_dir,
+                    &opt.checkpoint_dir,
+                    &opt.name,
+                    opt.current_db_backup_dir.as_deref(),
+                )?;
+                println!(
+                    "Rolled back to checkpoint {} at version {}.",
+                    opt.name, metadata.commit_progress.overall_version
+                );
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Checkpoints all the sub-DBs of the DB at `db_dir` into a staging dir, verifies the checkpoint
+/// is consistent and then moves it into `checkpoint_dir`, so that a checkpoint is either complete
+/// or absent.
+pub fn create_checkpoint(
+    db_dir: &Path,
+    checkpoint_dir: &Path,
+    name: Option<String>,
+    enable_storage_sharding: bool,
+) -> Result<(String, CheckpointMetadata)> {
+    if let Some(name) = &name {
+        ensure_valid_name(name)?;
+        ensure!(
+            !checkpoint_dir.join(name).exists(),
+            "Checkpoint {} already exists.",
+            name,
+        );
+    }
+    let created_at_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let staging_dir = checkpoint_dir.join(format!(".staging_{}", created_at_secs));
+    ensure!(!staging_dir.exists(), "{:?} already exists.", staging_dir);
+    fs::create_dir_all(&staging_dir)?;
+
+    let res = (|| -> Result<(String, CheckpointMetadata)> {
+        AptosDB::create_checkpoint(db_dir, &staging_dir, enable_storage_sharding)?;
+        let metadata = CheckpointMetadata {
+            created_at_secs,
+            enable_storage_sharding,
+            commit_progress: CommitProgress::load(&staging_dir, enable_storage_sharding)?,
+        };
+        let name = name.unwrap_or_else(|| {
+            format!(
+                "version_{}_{}",
+                metadata.commit_progress.overall_version, created_at_secs
+            )
+        });
+        fs::write(
+            staging_dir.join(METADATA_FILE_NAME),
+            serde_json::to_vec_pretty(&metadata)?,
+        )?;
+        fs::rename(&staging_dir, checkpoint_dir.join(&name))?;
+        Ok((name, metadata))
+    })();
+    if res.is_err() {
+        let _ = fs::remove_dir_all(&staging_dir);
+    }
+    let (name, metadata) = res?;
+
+    info!(
+        name = name,
+        version = metadata.commit_progress.overall_version,
+        "Created checkpoint."
+    );
+    Ok((name, metadata))
+}
+
+/// Lists the complete checkpoints in `checkpoint_dir`, ordered by name.
+pub fn list_checkpoints(checkpoint_dir: &Path) -> Result<Vec<(String, CheckpointMetadata)>> {
+    let mut checkpoints = Vec::new();
+    for entry in fs::read_dir(checkpoint_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || !entry.path().join(METADATA_FILE_NAME).exists() {
+            continue;
+        }
+        checkpoints.push((name, load_metadata(&entry.path())?));
+    }
+    checkpoints.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+    Ok(checkpoints)
+}
+
+/// Replaces the DB at `db_dir` with a copy of the checkpoint, after verifying both the checkpoint
+/// and the copy. The current DB is moved to `current_db_backup_dir`, or deleted if it's None. The
+/// current DB is only deleted once the copy is in place, and is put back if that fails.
+///
+/// The LOCK files of all the sub-DBs of the current DB are held while swapping, so this fails if
+/// the DB is in use. The current DB must hold all the sub-DBs of the checkpoint, as otherwise some
+/// of them live elsewhere (e.g., with `db_path_overrides`) or the sharding config differs.
+pub fn restore_checkpoint(
+    db_dir: &Path,
+    checkpoint_dir: &Path,
+    name: &str,
+    current_db_backup_dir: Option<&Path>,
+) -> Result<CheckpointMetadata> {
+    ensure_valid_name(name)?;
+    let checkpoint_path = checkpoint_dir.join(name);
+    let metadata = load_metadata(&checkpoint_path)?;
+    verify_commit_progress(&checkpoint_path, &metadata)?;
+
+    // The current DB is moved aside before the copy of the checkpoint is moved into place. If
+    // it's not to be kept, it's moved next to the DB, and deleted once the swap is done.
+    let db_dir_name = db_dir
+        .file_name()
+        .ok_or_else(|| format_err!("Invalid DB dir: {:?}", db_dir))?
+        .to_string_lossy();
+    let replaced_db_dir = match current_db_backup_dir {
+        Some(current_db_backup_dir) => current_db_backup_dir.to_path_buf(),
+        None => db_dir.with_file_name(format!(".{}.replaced", db_dir_name)),
+    };
+    ensure!(
+        !replaced_db_dir.exists(),
+        "{:?} already exists. If it's left behind by an interrupted restore, it may hold the DB.",
+        replaced_db_dir,
+    );
+
+    let has_current_db = db_dir.exists();
+    let _db_lock = if has_current_db {
+        let current_rocksdb_dirs = rocksdb_dirs(db_dir)?;
+        let missing_rocksdb_dirs: Vec<_> = rocksdb_dirs(&checkpoint_path)?
+            .difference(&current_rocksdb_dirs)
+            .cloned()
+            .collect();
+        ensure!(
+            missing_rocksdb_dirs.is_empty(),
+            "{:?} is missing the sub-DBs {:?} of the checkpoint. Sub-DBs outside of the DB dir \
+            (db_path_overrides) aren't supported, and the sharding config must match.",
+            db_dir,
+            missing_rocksdb_dirs,
+        );
+        Some(DbLock::acquire(db_dir, &current_rocksdb_dirs)?)
+    } else {
+        None
+    };
+
+    // Copy the checkpoint next to the DB first, so that the DB can be swapped by renaming.
+    let staging_dir = db_dir.with_file_name(format!(".{}.restoring", db_dir_name));
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+    link_or_copy_dir(&checkpoint_path, &staging_dir)?;
+    verify_commit_progress(&staging_dir, &metadata)?;
+
+    if has_current_db {
+        info!(to = ?replaced_db_dir, "Moving the current DB aside.");
+        fs::rename(db_dir, &replaced_db_dir)?;
+    }
+    if let Err(e) = fs::rename(&staging_dir, db_dir) {
+        if has_current_db {
+            info!(error = ?e, "Failed to move the checkpoint into place, moving the current DB back.");
+            fs::rename(&replaced_db_dir, db_dir)?;
+        }
+        return Err(e.into());
+    }
+    if has_current_db && current_db_backup_dir.is_none() {
+        info!("Deleting the replaced DB.");
+        fs::remove_dir_all(&replaced_db_dir)?;
+    }
+
+    info!(
+        name = name,
+        version = metadata.commit_progress.overall_version,
+        "Restored checkpoint."
+    );
+    Ok(metadata)
+}
+
+/// Holds the LOCK files of RocksDB instances the same way RocksDB does, so that they can't be
+/// opened while it's alive.
+struct DbLock {
+    _lock_files: Vec<File>,
+}
+
+impl DbLock {
+    /// Locks the RocksDB instances at `rocksdb_dirs`, relative to `db_dir`. Fails if any of them
+    /// is locked already, i.e., is open.
+    fn acquire(db_dir: &Path, rocksdb_dirs: &BTreeSet<PathBuf>) -> Result<Self> {
+        let mut lock_files = Vec::with_capacity(rocksdb_dirs.len());
+        for rocksdb_dir in rocksdb_dirs {
+            let lock_path = db_dir.join(rocksdb_dir).join("LOCK");
+            let lock_file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)?;
+            Self::lock_file(&lock_file).map_err(|e| {
+                format_err!(
+                    "Failed to lock {:?}, is the DB in use (e.g., by a running node)? {}",
+                    lock_path,
+                    e
+                )
+            })?;
+            lock_files.push(lock_file);
+        }
+        Ok(Self {
+            _lock_files: lock_files,
+        })
+    }
+
+    /// Takes a write lock on the whole file with fcntl, like RocksDB. On Linux, an open file
+    /// description lock is used, which also conflicts with the locks held by this process.
+    fn lock_file(file: &File) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        const SET_LOCK: libc::c_int = libc::F_OFD_SETLK;
+        #[cfg(not(target_os = "linux"))]
+        const SET_LOCK: libc::c_int = libc::F_SETLK;
+
+        // SAFETY: all zeros is a valid `flock`, and means the whole file (and no pid, as required
+        // for open file description locks).
+        let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+        lock.l_type = libc::F_WRLCK as libc::c_short;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+        // SAFETY: the fd is valid while `file` is borrowed, and `lock` outlives the call.
+        if unsafe { libc::fcntl(file.as_raw_fd(), SET_LOCK, &lock) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// Returns the RocksDB instances under `db_dir` (i.e., the dirs holding a CURRENT file), relative
+/// to `db_dir`.
+fn rocksdb_dirs(db_dir: &Path) -> Result<BTreeSet<PathBuf>> {
+    fn visit(db_dir: &Path, dir: &Path, dirs: &mut BTreeSet<PathBuf>) -> Result<()> {
+        if dir.join("CURRENT").is_file() {
+            dirs.insert(dir.strip_prefix(db_dir)?.to_path_buf());
+        }
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                visit(db_dir, &entry.path(), dirs)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut dirs = BTreeSet::new();
+    visit(db_dir, db_dir, &mut dirs)?;
+    Ok(dirs)
+}
+
+fn ensure_valid_name(name: &str) -> Result<()> {
+    ensure!(
+        !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'),
+        "Invalid checkpoint name: {}",
+        name,
+    );
+    Ok(())
+}
+
+fn load_metadata(checkpoint_path: &Path) -> Result<CheckpointMetadata> {
+    let metadata_path = checkpoint_path.join(METADATA_FILE_NAME);
+    ensure!(
+        metadata_path.exists(),
+        "{:?} is not a complete checkpoint.",
+        checkpoint_path,
+    );
+    Ok(serde_json::from_slice(&fs::read(metadata_path)?)?)
+}
+
+fn verify_commit_progress(db_dir: &Path, metadata: &CheckpointMetadata) -> Result<()> {
+    let commit_progress = CommitProgress::load(db_dir, metadata.enable_storage_sharding)?;
+    ensure!(
+        commit_progress == metadata.commit_progress,
+        "Commit progress of {:?} doesn't match the checkpoint metadata. Expected: {:?}, got: {:?}",
+        db_dir,
+        metadata.commit_progress,
+        commit_progress,
+    );
+    Ok(())
+}
+
+/// Copies `src` to `dst` recursively, hardlinking the immutable SST files. The checkpoint metadata
+/// is left out.
+fn link_or_copy_dir(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            link_or_copy_dir(&src_path, &dst_path)?;
+        } else if entry.file_name() != METADATA_FILE_NAME {
+            // Unlike SST files, the MANIFEST and the WAL are appended to once the DB is opened.
+            let is_sst = src_path.extension() == Some(OsStr::new("sst"));
+            if !is_sst || fs::hard_link(&src_path, &dst_path).is_err() {
+                fs::copy(&src_path, &dst_path)?;
+            }
+        }
+    }
+    Ok(())
+}
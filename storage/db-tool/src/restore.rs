@@ -5,7 +5,10 @@ use anyhow::Result;
 use aptos_backup_cli::{
     backup_types::{
         epoch_ending::restore::{EpochEndingRestoreController, EpochEndingRestoreOpt},
-        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        state_snapshot::{
+            diff_restore::StateSnapshotDiffRestoreController,
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
     coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
@@ -52,6 +55,14 @@ pub enum Oneoff {
         #[clap(flatten)]
         global: GlobalRestoreOpt,
     },
+    StateSnapshotDiff {
+        #[clap(flatten)]
+        storage: DBToolStorageOpt,
+        #[clap(flatten)]
+        opt: StateSnapshotRestoreOpt,
+        #[clap(flatten)]
+        global: GlobalRestoreOpt,
+    },
     Transaction {
         #[clap(flatten)]
         storage: DBToolStorageOpt,
@@ -94,6 +105,20 @@ impl Command {
                         .run()
                         .await?;
                     },
+                    Oneoff::StateSnapshotDiff {
+                        storage,
+                        opt,
+                        global,
+                    } => {
+                        StateSnapshotDiffRestoreController::new(
+                            opt,
+                            global.try_into()?,
+                            storage.init_storage().await?,
+                            None, /* epoch_history */
+                        )
+                        .run()
+                        .await?;
+                    },
                     Oneoff::Transaction {
                         storage,
                         opt,
@@ -47,6 +47,35 @@ fn test_various_cmd_parsing() {
     ]);
 
     run_cmd(&["aptos-db-tool", "backup", "verify", "--local-fs-dir", "."]);
+    run_cmd(&["aptos-db-tool", "backup", "audit", "--local-fs-dir", "."]);
+    run_cmd(&[
+        "aptos-db-tool",
+        "checkpoint",
+        "create",
+        "--db-dir",
+        ".",
+        "--checkpoint-dir",
+        ".",
+    ]);
+    run_cmd(&[
+        "aptos-db-tool",
+        "checkpoint",
+        "list",
+        "--checkpoint-dir",
+        ".",
+    ]);
+    run_cmd(&[
+        "aptos-db-tool",
+        "checkpoint",
+        "restore",
+        "--db-dir",
+        ".",
+        "--checkpoint-dir",
+        ".",
+        "--name",
+        "checkpoint",
+        "--opt-out-current-db-backup",
+    ]);
     run_cmd(&[
         "aptos-db-tool",
         "replay-verify",
@@ -279,6 +308,175 @@ mod dbtool_tests {
         rt.shutdown_timeout(Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_backup_audit() {
+        let db = test_execution_with_storage_impl();
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let (rt, port) = start_local_backup_service(db);
+        let server_addr = format!(" http://localhost:{}", port);
+
+        let backup = |backup: &[&str]| {
+            let mut args = vec![
+                "aptos-db-tool",
+                "backup",
+                "oneoff",
+                "--backup-service-address",
+                server_addr.as_str(),
+            ];
+            args.extend_from_slice(backup);
+            args.extend_from_slice(&["--local-fs-dir", backup_dir.path().to_str().unwrap()]);
+            rt.block_on(DBTool::try_parse_from(args).unwrap().run())
+                .unwrap();
+        };
+        let find_path = |dir: &Path, prefix: &str| {
+            fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .find(|path| {
+                    path.file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .starts_with(prefix)
+                })
+                .unwrap()
+        };
+
+        backup(&["epoch-ending", "--start-epoch", "0", "--end-epoch", "2"]);
+        backup(&["state-snapshot", "--state-snapshot-epoch", "1"]);
+        let state_snapshot_backup = find_path(backup_dir.path(), "state_epoch_1_");
+        let base_manifest = format!(
+            "{}/state.manifest",
+            state_snapshot_backup.file_name().unwrap().to_str().unwrap()
+        );
+        backup(&[
+            "state-snapshot-diff",
+            "--state-snapshot-epoch",
+            "2",
+            "--base-state-manifest",
+            base_manifest.as_str(),
+        ]);
+        backup(&[
+            "transaction",
+            "--start-version",
+            "0",
+            "--num_transactions",
+            "30",
+        ]);
+
+        let report_file = TempPath::new();
+        let audit = || {
+            let metadata_cache_dir = TempPath::new();
+            let result = rt.block_on(
+                DBTool::try_parse_from([
+                    "aptos-db-tool",
+                    "backup",
+                    "audit",
+                    "--metadata-cache-dir",
+                    metadata_cache_dir.path().to_str().unwrap(),
+                    "--local-fs-dir",
+                    backup_dir.path().to_str().unwrap(),
+                    "--report-file",
+                    report_file.path().to_str().unwrap(),
+                ])
+                .unwrap()
+                .run(),
+            );
+            let report: serde_json::Value =
+                serde_json::from_slice(&fs::read(report_file.path()).unwrap()).unwrap();
+            (result, report)
+        };
+        // Truncates the file, returning its original content.
+        let truncate = |path: &Path| {
+            let content = fs::read(path).unwrap();
+            fs::write(path, &content[..content.len() / 2]).unwrap();
+            content
+        };
+        let find_corrupted_file = |report: &serde_json::Value, backup_type: &str| {
+            report["corrupted_files"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|file| file["backup_type"] == backup_type)
+                .unwrap()["file"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let (result, report) = audit();
+        result.unwrap();
+        assert_eq!(report["epoch_ending_backups"], 1);
+        assert_eq!(report["transaction_backups"], 1);
+        assert_eq!(report["state_snapshot_backups"], 1);
+        assert_eq!(report["state_snapshot_diff_backups"], 1);
+        assert!(report["gaps"].as_array().unwrap().is_empty());
+        assert!(report["corrupted_files"].as_array().unwrap().is_empty());
+
+        // Replace the transaction backup with one that skips the first versions, the audit
+        // should report the gap.
+        let metadata_dir = backup_dir.path().join("metadata");
+        let transaction_metadata = find_path(&metadata_dir, "transaction_0-");
+        let transaction_metadata_content = fs::read(&transaction_metadata).unwrap();
+        fs::remove_file(&transaction_metadata).unwrap();
+        backup(&[
+            "transaction",
+            "--start-version",
+            "20",
+            "--num_transactions",
+            "10",
+        ]);
+
+        let (result, report) = audit();
+        assert!(result.is_err());
+        assert!(report["corrupted_files"].as_array().unwrap().is_empty());
+        let gaps = report["gaps"].as_array().unwrap();
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0]["backup_type"], "transaction");
+        assert_eq!(gaps[0]["first"], 0);
+        assert_eq!(gaps[0]["last"], 19);
+        fs::remove_file(find_path(&metadata_dir, "transaction_20-")).unwrap();
+        fs::write(&transaction_metadata, transaction_metadata_content).unwrap();
+
+        // Truncate a state snapshot chunk, the audit should pin it down. The diff on top of
+        // the snapshot can't be verified either.
+        let chunk = state_snapshot_backup.join("0-.chunk");
+        let content = truncate(&chunk);
+
+        let (result, report) = audit();
+        assert!(result.is_err());
+        assert_eq!(report["corrupted_files"].as_array().unwrap().len(), 2);
+        assert!(find_corrupted_file(&report, "state_snapshot").ends_with("0-.chunk"));
+        assert!(find_corrupted_file(&report, "state_snapshot_diff").ends_with("range.proofs"));
+        fs::write(&chunk, content).unwrap();
+
+        // Truncate a state snapshot diff chunk, the audit should pin it down.
+        let chunk = find_path(backup_dir.path(), "state_diff_epoch_2_").join("0.chunk");
+        let content = truncate(&chunk);
+
+        let (result, report) = audit();
+        assert!(result.is_err());
+        assert_eq!(report["corrupted_files"].as_array().unwrap().len(), 1);
+        assert!(find_corrupted_file(&report, "state_snapshot_diff").ends_with("0.chunk"));
+        fs::write(&chunk, content).unwrap();
+
+        // Truncate the transaction chunk, the audit should pin it down.
+        let chunk = find_path(backup_dir.path(), "transaction_0-").join("0-.chunk");
+        truncate(&chunk);
+
+        let (result, report) = audit();
+        assert!(result.is_err());
+        let corrupted_files = report["corrupted_files"].as_array().unwrap();
+        assert_eq!(corrupted_files.len(), 1);
+        assert_eq!(corrupted_files[0]["backup_type"], "transaction");
+        assert!(corrupted_files[0]["file"]
+            .as_str()
+            .unwrap()
+            .ends_with("0-.chunk"));
+        rt.shutdown_timeout(Duration::from_secs(1));
+    }
+
     #[cfg(test)]
     fn db_restore_test_setup(
         start: Version,
@@ -622,6 +820,156 @@ mod dbtool_tests {
         rt.shutdown_timeout(Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_checkpoint_create_and_restore() {
+        use crate::checkpoint::list_checkpoints;
+        use aptos_db::db_debugger::checkpoint::CommitProgress;
+
+        let db_dir = TempPath::new();
+        db_dir.create_as_dir().unwrap();
+        let checkpoint_dir = TempPath::new();
+        checkpoint_dir.create_as_dir().unwrap();
+        let current_db_backup_dir = TempPath::new();
+        let db = test_execution_with_storage_impl_inner(false, db_dir.path());
+        let version = db.get_synced_version().unwrap();
+        drop(db);
+
+        let rt = Runtime::new().unwrap();
+        let run = |args: &[&str]| {
+            rt.block_on(
+                DBTool::try_parse_from([&["aptos-db-tool"][..], args].concat())
+                    .unwrap()
+                    .run(),
+            )
+        };
+        let db_dir_str = db_dir.path().to_str().unwrap();
+        let checkpoint_dir_str = checkpoint_dir.path().to_str().unwrap();
+
+        // Checkpoint the DB
+        run(&[
+            "checkpoint",
+            "create",
+            "--db-dir",
+            db_dir_str,
+            "--checkpoint-dir",
+            checkpoint_dir_str,
+            "--name",
+            "before_upgrade",
+        ])
+        .unwrap();
+        let checkpoints = list_checkpoints(checkpoint_dir.path()).unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        let (name, metadata) = &checkpoints[0];
+        assert_eq!(name, "before_upgrade");
+        assert_eq!(metadata.commit_progress.overall_version, version);
+
+        // Roll the DB back by truncating it, then restore the checkpoint
+        run(&[
+            "debug",
+            "truncate",
+            "--db-dir",
+            db_dir_str,
+            "--target-version",
+            &(version / 2).to_string(),
+            "--opt-out-backup-checkpoint",
+        ])
+        .unwrap();
+        let truncated_version = CommitProgress::load(db_dir.path(), false)
+            .unwrap()
+            .overall_version;
+        assert!(truncated_version < version);
+
+        let restore_args = [
+            "checkpoint",
+            "restore",
+            "--db-dir",
+            db_dir_str,
+            "--checkpoint-dir",
+            checkpoint_dir_str,
+            "--name",
+            "before_upgrade",
+            "--current-db-backup-dir",
+            current_db_backup_dir.path().to_str().unwrap(),
+        ];
+
+        // The DB can't be swapped while it's open, or if a sub-DB is outside of the DB dir
+        let dbs = AptosDB::open_dbs(
+            &StorageDirPaths::from_path(db_dir.path()),
+            RocksdbConfigs::default(),
+            false,
+            0,
+        )
+        .unwrap();
+        assert!(run(&restore_args).is_err());
+        drop(dbs);
+        let state_merkle_db_dir = db_dir.path().join("state_merkle_db");
+        let moved_state_merkle_db_dir = TempPath::new();
+        fs::rename(&state_merkle_db_dir, moved_state_merkle_db_dir.path()).unwrap();
+        assert!(run(&restore_args).is_err());
+        fs::rename(moved_state_merkle_db_dir.path(), &state_merkle_db_dir).unwrap();
+        assert!(!current_db_backup_dir.path().exists());
+        assert_eq!(
+            CommitProgress::load(db_dir.path(), false)
+                .unwrap()
+                .overall_version,
+            truncated_version
+        );
+
+        run(&restore_args).unwrap();
+        assert_eq!(
+            CommitProgress::load(db_dir.path(), false).unwrap(),
+            metadata.commit_progress
+        );
+        assert_eq!(
+            CommitProgress::load(current_db_backup_dir.path(), false)
+                .unwrap()
+                .overall_version,
+            truncated_version
+        );
+
+        // The checkpoint stays intact and can't be overwritten
+        assert_eq!(
+            list_checkpoints(checkpoint_dir.path()).unwrap(),
+            checkpoints
+        );
+        assert!(run(&[
+            "checkpoint",
+            "create",
+            "--db-dir",
+            db_dir_str,
+            "--checkpoint-dir",
+            checkpoint_dir_str,
+            "--name",
+            "before_upgrade",
+        ])
+        .is_err());
+
+        // Restoring without keeping the current DB deletes it, leaving nothing next to the DB
+        run(&[
+            "checkpoint",
+            "restore",
+            "--db-dir",
+            db_dir_str,
+            "--checkpoint-dir",
+            checkpoint_dir_str,
+            "--name",
+            "before_upgrade",
+            "--opt-out-current-db-backup",
+        ])
+        .unwrap();
+        assert_eq!(
+            CommitProgress::load(db_dir.path(), false).unwrap(),
+            metadata.commit_progress
+        );
+        let db_dir_name = db_dir.path().file_name().unwrap().to_string_lossy();
+        for suffix in ["replaced", "restoring"] {
+            assert!(!db_dir
+                .path()
+                .with_file_name(format!(".{}.{}", db_dir_name, suffix))
+                .exists());
+        }
+    }
+
     fn dir_size<P: AsRef<Path>>(path: P) -> u64 {
         let mut size = 0;
 
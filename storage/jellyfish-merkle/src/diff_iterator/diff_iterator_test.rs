@@ -0,0 +1,176 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    diff_iterator::{JellyfishMerkleDiffIterator, LeafChange},
+    mock_tree_store::MockTreeStore,
+    test_helper::{gen_value, ValueBlob},
+    JellyfishMerkleTree,
+};
+use aptos_crypto::HashValue;
+use aptos_types::transaction::Version;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The key, the value and the version of the value of every leaf, at each version of the tree
+type Snapshots = Vec<BTreeMap<HashValue, ((HashValue, ValueBlob), Version)>>;
+
+/// Writes the batches to the tree, one version each, and returns the expected leaves at each
+/// version.
+fn write_batches(
+    db: &MockTreeStore<ValueBlob>,
+    batches: Vec<Vec<(HashValue, Option<(HashValue, ValueBlob)>)>>,
+) -> Snapshots {
+    let tree = JellyfishMerkleTree::new(db);
+    let mut snapshots: Snapshots = vec![];
+    let mut leaves = BTreeMap::new();
+    for (version, batch) in batches.into_iter().enumerate() {
+        let version = version as Version;
+        let value_set = batch
+            .iter()
+            .map(|(key, value)| (*key, value.as_ref()))
+            .collect();
+        let (_root, tree_update_batch) = tree.put_value_set_test(value_set, version).unwrap();
+        db.write_tree_update_batch(tree_update_batch).unwrap();
+
+        for (key, value) in batch {
+            match value {
+                Some(value) => leaves.insert(key, (value, version)),
+                None => leaves.remove(&key),
+            };
+        }
+        snapshots.push(leaves.clone());
+    }
+    snapshots
+}
+
+fn get_changes(
+    db: &Arc<MockTreeStore<ValueBlob>>,
+    base_version: Version,
+    version: Version,
+) -> Vec<(HashValue, bool)> {
+    JellyfishMerkleDiffIterator::new(db.clone(), base_version, version)
+        .unwrap()
+        .map(|change| match change.unwrap() {
+            LeafChange::Updated(leaf) => (leaf.account_key(), true),
+            LeafChange::Deleted(leaf) => (leaf.account_key(), false),
+        })
+        .collect()
+}
+
+fn expected_changes(
+    snapshots: &Snapshots,
+    base_version: Version,
+    version: Version,
+) -> Vec<(HashValue, bool)> {
+    let base = &snapshots[base_version as usize];
+    let new = &snapshots[version as usize];
+    let mut changes: Vec<_> = new
+        .iter()
+        .filter(|(key, leaf)| base.get(key) != Some(leaf))
+        .map(|(key, _)| (*key, true))
+        .chain(
+            base.keys()
+                .filter(|key| !new.contains_key(key))
+                .map(|key| (*key, false)),
+        )
+        .collect();
+    changes.sort();
+    changes
+}
+
+#[test]
+fn test_diff_iterator() {
+    let key = |byte: u8, nibble: u8| {
+        let mut key = [0u8; HashValue::LENGTH];
+        key[0] = byte;
+        key[1] = nibble << 4;
+        HashValue::new(key)
+    };
+    let values: Vec<_> = (0..5).map(|_| Some(gen_value())).collect();
+
+    let db = Arc::new(MockTreeStore::default());
+    let snapshots = write_batches(&db, vec![
+        vec![
+            (key(0x00, 0), values[0].clone()),
+            (key(0x00, 1), values[1].clone()),
+            (key(0x10, 0), values[2].clone()),
+        ],
+        // An update, and an insertion turning a leaf into an internal node
+        vec![
+            (key(0x00, 0), values[3].clone()),
+            (key(0x10, 1), values[4].clone()),
+        ],
+        // A deletion turning an internal node into a leaf
+        vec![(key(0x00, 1), None)],
+        // Deleting everything
+        vec![
+            (key(0x00, 0), None),
+            (key(0x10, 0), None),
+            (key(0x10, 1), None),
+        ],
+    ]);
+
+    assert_eq!(get_changes(&db, 0, 1), vec![
+        (key(0x00, 0), true),
+        (key(0x10, 1), true),
+    ]);
+    assert_eq!(get_changes(&db, 1, 2), vec![(key(0x00, 1), false)]);
+    assert_eq!(get_changes(&db, 0, 2), vec![
+        (key(0x00, 0), true),
+        (key(0x00, 1), false),
+        (key(0x10, 1), true),
+    ]);
+    assert_eq!(get_changes(&db, 0, 3), vec![
+        (key(0x00, 0), false),
+        (key(0x00, 1), false),
+        (key(0x10, 0), false),
+    ]);
+    for base_version in 0..3 {
+        for version in base_version + 1..4 {
+            assert_eq!(
+                get_changes(&db, base_version, version),
+                expected_changes(&snapshots, base_version, version)
+            );
+        }
+    }
+
+    assert!(JellyfishMerkleDiffIterator::new(db, 2, 2).is_err());
+}
+
+#[test]
+fn test_diff_iterator_random() {
+    let mut rng = StdRng::from_seed([1; 32]);
+    // Keys sharing long prefixes, so that leaves are pushed down and pulled up
+    let keys: Vec<_> = (0..64)
+        .map(|_| {
+            let mut key = [0u8; HashValue::LENGTH];
+            key[0] = rng.gen_range(0, 2);
+            key[1] = rng.gen_range(0, 4);
+            key[HashValue::LENGTH - 1] = rng.gen();
+            HashValue::new(key)
+        })
+        .collect();
+    let batches = (0..20)
+        .map(|_| {
+            let mut batch_keys: Vec<_> = keys.choose_multiple(&mut rng, 8).cloned().collect();
+            batch_keys.sort();
+            batch_keys.dedup();
+            batch_keys
+                .into_iter()
+                .map(|key| (key, rng.gen_bool(0.7).then(gen_value)))
+                .collect()
+        })
+        .collect();
+
+    let db = Arc::new(MockTreeStore::default());
+    let snapshots = write_batches(&db, batches);
+    for base_version in 0..19 {
+        for version in base_version + 1..20 {
+            assert_eq!(
+                get_changes(&db, base_version, version),
+                expected_changes(&snapshots, base_version, version)
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module implements `JellyfishMerkleDiffIterator`. Initialized with two versions of the
+//! tree, the iterator generates the leaves that were updated or deleted from the first version to
+//! the second, in the order of their keys, by performing a depth first traversal on both trees at
+//! once. Subtrees shared by both versions are skipped, so the cost is proportional to the changes
+//! rather than the size of the tree.
+
+#[cfg(test)]
+mod diff_iterator_test;
+
+use crate::{
+    node_type::{InternalNode, LeafNode, Node, NodeKey},
+    TreeReader,
+};
+use aptos_storage_interface::{db_ensure as ensure, Result};
+use aptos_types::{nibble::nibble_path::NibblePath, transaction::Version};
+use std::{collections::VecDeque, sync::Arc};
+
+/// A change of a leaf between two versions of the tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LeafChange<K> {
+    /// The leaf was inserted or updated, and this is the leaf in the newer version.
+    Updated(LeafNode<K>),
+    /// The leaf was deleted, and this is the leaf in the older version.
+    Deleted(LeafNode<K>),
+}
+
+/// A subtree at some position of one of the trees, yet to be compared.
+enum Subtree<K> {
+    Empty,
+    Node(NodeKey),
+    /// A leaf that is higher up in this tree, while the other tree has an internal node at this
+    /// position. The leaf is pushed down until it can be compared with the other tree.
+    Leaf(LeafNode<K>),
+}
+
+/// A subtree with its root node loaded.
+enum LoadedSubtree<K> {
+    Empty,
+    Leaf(LeafNode<K>),
+    Internal(NodeKey, InternalNode),
+}
+
+impl<K: crate::Key> LoadedSubtree<K> {
+    fn load<R: TreeReader<K>>(reader: &R, subtree: Subtree<K>) -> Result<Self> {
+        Ok(match subtree {
+            Subtree::Empty => Self::Empty,
+            Subtree::Leaf(leaf) => Self::Leaf(leaf),
+            Subtree::Node(node_key) => match reader.get_node(&node_key)? {
+                Node::Internal(internal_node) => Self::Internal(node_key, internal_node),
+                Node::Leaf(leaf) => Self::Leaf(leaf),
+                Node::Null => Self::Empty,
+            },
+        })
+    }
+
+    /// The depth of the root node, if it's an internal node.
+    fn depth(&self) -> Option<usize> {
+        match self {
+            Self::Internal(node_key, _) => Some(node_key.nibble_path().num_nibbles()),
+            _ => None,
+        }
+    }
+
+    /// Splits the subtree at `depth` into the subtrees of its children.
+    fn into_children(self, depth: usize) -> [Subtree<K>; 16] {
+        let mut children = std::array::from_fn(|_| Subtree::Empty);
+        match self {
+            Self::Empty => (),
+            Self::Leaf(leaf) => {
+                let nibble = NibblePath::new_even(leaf.account_key().to_vec()).get_nibble(depth);
+                children[u8::from(nibble) as usize] = Subtree::Leaf(leaf);
+            },
+            Self::Internal(node_key, internal_node) => {
+                for (nibble, child) in internal_node.children_sorted() {
+                    children[u8::from(*nibble) as usize] =
+                        Subtree::Node(node_key.gen_child_node_key(child.version, *nibble));
+                }
+            },
+        }
+        children
+    }
+}
+
+/// The `JellyfishMerkleDiffIterator` implementation.
+pub struct JellyfishMerkleDiffIterator<R, K> {
+    /// The storage engine from which we can read nodes using node keys.
+    reader: Arc<R>,
+
+    /// The pairs of subtrees of the base and the new tree at the same position that are yet to be
+    /// compared, with the leftmost one on top.
+    stack: Vec<(Subtree<K>, Subtree<K>)>,
+
+    /// The changes found by the last comparison that are yet to be returned.
+    changes: VecDeque<LeafChange<K>>,
+}
+
+impl<R, K> JellyfishMerkleDiffIterator<R, K>
+where
+    R: TreeReader<K>,
+    K: crate::Key,
+{
+    /// Constructs a new iterator over the changes from the tree at `base_version` to the tree at
+    /// `version`. Both trees must still be in the storage, i.e. not pruned.
+    pub fn new(reader: Arc<R>, base_version: Version, version: Version) -> Result<Self> {
+        ensure!(
+            base_version < version,
+            "base_version {} must be smaller than version {}.",
+            base_version,
+            version,
+        );
+        Ok(Self {
+            reader,
+            stack: vec![(
+                Subtree::Node(NodeKey::new_empty_path(base_version)),
+                Subtree::Node(NodeKey::new_empty_path(version)),
+            )],
+            changes: VecDeque::new(),
+        })
+    }
+
+    fn compare(&mut self, base: Subtree<K>, new: Subtree<K>) -> Result<()> {
+        // A node is identified by its key, so the subtrees are the same.
+        if let (Subtree::Node(base_key), Subtree::Node(new_key)) = (&base, &new) {
+            if base_key == new_key {
+                return Ok(());
+            }
+        }
+
+        let base = LoadedSubtree::load(self.reader.as_ref(), base)?;
+        let new = LoadedSubtree::load(self.reader.as_ref(), new)?;
+        match (base, new) {
+            (LoadedSubtree::Leaf(base_leaf), LoadedSubtree::Leaf(new_leaf)) => {
+                if base_leaf.account_key() == new_leaf.account_key() {
+                    if base_leaf.value_hash() != new_leaf.value_hash()
+                        || base_leaf.value_index().1 != new_leaf.value_index().1
+                    {
+                        self.changes.push_back(LeafChange::Updated(new_leaf));
+                    }
+                } else if base_leaf.account_key() < new_leaf.account_key() {
+                    self.changes.push_back(LeafChange::Deleted(base_leaf));
+                    self.changes.push_back(LeafChange::Updated(new_leaf));
+                } else {
+                    self.changes.push_back(LeafChange::Updated(new_leaf));
+                    self.changes.push_back(LeafChange::Deleted(base_leaf));
+                }
+            },
+            (LoadedSubtree::Leaf(base_leaf), LoadedSubtree::Empty) => {
+                self.changes.push_back(LeafChange::Deleted(base_leaf));
+            },
+            (LoadedSubtree::Empty, LoadedSubtree::Leaf(new_leaf)) => {
+                self.changes.push_back(LeafChange::Updated(new_leaf));
+            },
+            (LoadedSubtree::Empty, LoadedSubtree::Empty) => (),
+            (base, new) => {
+                // At least one of the subtrees is an internal node, so compare the children.
+                let depth = base
+                    .depth()
+                    .or_else(|| new.depth())
+                    .expect("One of the subtrees is an internal node.");
+                let children = base
+                    .into_children(depth)
+                    .into_iter()
+                    .zip(new.into_children(depth));
+                for (base_child, new_child) in children.rev() {
+                    if !matches!((&base_child, &new_child), (Subtree::Empty, Subtree::Empty)) {
+                        self.stack.push((base_child, new_child));
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl<R, K> Iterator for JellyfishMerkleDiffIterator<R, K>
+where
+    R: TreeReader<K>,
+    K: crate::Key,
+{
+    type Item = Result<LeafChange<K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(change) = self.changes.pop_front() {
+                return Some(Ok(change));
+            }
+            let (base, new) = self.stack.pop()?;
+            if let Err(err) = self.compare(base, new) {
+                // Stop after an error, as the changes under the failed subtrees are unknown.
+                self.stack.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
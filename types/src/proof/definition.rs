@@ -64,6 +64,23 @@ where
         &self.siblings
     }
 
+    /// Converts the proof of the element at `element_index` into an `AccumulatorRangeProof` of
+    /// the range made of this single element.
+    pub fn to_range_proof(&self, element_index: u64) -> AccumulatorRangeProof<H> {
+        let mut left_siblings = vec![];
+        let mut right_siblings = vec![];
+        let mut index = element_index;
+        for sibling in &self.siblings {
+            if index % 2 == 0 {
+                right_siblings.push(*sibling);
+            } else {
+                left_siblings.push(*sibling);
+            }
+            index /= 2;
+        }
+        AccumulatorRangeProof::new(left_siblings, right_siblings)
+    }
+
     /// Verifies an element whose hash is `element_hash` and version is `element_version` exists in
     /// the accumulator whose root hash is `expected_root_hash` using the provided proof.
     pub fn verify(
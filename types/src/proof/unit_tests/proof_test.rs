@@ -91,6 +91,36 @@ fn test_verify_three_element_accumulator() {
     );
 }
 
+#[test]
+fn test_accumulator_proof_to_range_proof() {
+    let element_hashes: Vec<_> = [&b"hello"[..], b"world", b"!"]
+        .iter()
+        .map(|element| element.test_only_hash())
+        .collect();
+    let internal0_hash =
+        TestAccumulatorInternalNode::new(element_hashes[0], element_hashes[1]).hash();
+    let internal1_hash =
+        TestAccumulatorInternalNode::new(element_hashes[2], *ACCUMULATOR_PLACEHOLDER_HASH).hash();
+    let root_hash = TestAccumulatorInternalNode::new(internal0_hash, internal1_hash).hash();
+
+    let proofs = [
+        TestAccumulatorProof::new(vec![element_hashes[1], internal1_hash]),
+        TestAccumulatorProof::new(vec![element_hashes[0], internal1_hash]),
+        TestAccumulatorProof::new(vec![*ACCUMULATOR_PLACEHOLDER_HASH, internal0_hash]),
+    ];
+    for (index, proof) in proofs.iter().enumerate() {
+        let index = index as u64;
+        let leaf_hashes = &element_hashes[index as usize..=index as usize];
+        let range_proof = proof.to_range_proof(index);
+        range_proof
+            .verify(root_hash, Some(index), leaf_hashes)
+            .unwrap();
+        range_proof
+            .verify(root_hash, Some((index + 1) % 3), leaf_hashes)
+            .unwrap_err();
+    }
+}
+
 #[test]
 fn test_accumulator_proof_max_siblings_leftmost() {
     let element_hash = b"hello".test_only_hash();